@@ -0,0 +1,18 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::error::Result;
+
+/// An output of the station that can be switched on and off, such as a relay
+/// channel.
+pub trait Actuator: Send + Sync {
+    /// Unique id of the actuator across the station.
+    fn id(&self) -> &str;
+
+    /// Energize (`true`) or release (`false`) the actuator.
+    fn set_active(&self, active: bool) -> Result<()>;
+
+    /// Last state applied, actuators start released.
+    fn is_active(&self) -> bool;
+}
@@ -0,0 +1,41 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_config::ConfigHandler;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use tokio::time::Duration;
+
+use crate::gpio::GpioBackend;
+use crate::relay::{RelayBoardSpec, MIST_BOARD, MIST_RELAY};
+use crate::safety::SafetyLimits;
+
+pub static CONFIG: LazyLock<Config> = LazyLock::new(|| ConfigHandler::new(mpl).load());
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// How relay pins are driven
+    pub gpio: GpioBackend,
+    /// Relay boards wired to the station
+    pub boards: Vec<RelayBoardSpec>,
+    /// Interlocks of the relays, keyed by relay id
+    pub safety: HashMap<&'static str, SafetyLimits>,
+}
+
+fn mpl() -> Config {
+    Config {
+        gpio: GpioBackend::Auto,
+        boards: vec![MIST_BOARD],
+        safety: HashMap::from([(
+            MIST_RELAY.id,
+            SafetyLimits {
+                // A stuck mister floods the enclosure
+                max_on_duration: Some(Duration::from_secs(5 * 60)),
+                min_off_time: Some(Duration::from_secs(30)),
+                max_switches_per_hour: Some(60),
+                ..SafetyLimits::default()
+            },
+        )]),
+    }
+}
@@ -0,0 +1,56 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::safety::InterlockReason;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActuatorError {
+    /// No GPIO controller can drive the pin on this machine.
+    GpioUnavailable(String),
+    /// The pin is already claimed, by another process or kernel driver.
+    PinBusy { pin: u8 },
+    /// The process isn't allowed to access the GPIO controller.
+    PermissionDenied(String),
+    /// A safety interlock kept the relay in its current state.
+    InterlockRefused {
+        relay_id: String,
+        reason: InterlockReason,
+    },
+    /// No relay is configured under this id.
+    UnknownRelay(String),
+    /// The relay boards configuration is inconsistent.
+    InvalidConfig(String),
+    /// The level of a claimed pin couldn't be set.
+    Gpio { pin: u8, message: String },
+    /// The actuator service isn't running.
+    ServiceStopped,
+}
+
+impl fmt::Display for ActuatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActuatorError::GpioUnavailable(details) => write!(f, "GPIO unavailable: {details}"),
+            ActuatorError::PinBusy { pin } => write!(f, "GPIO{pin} is already in use"),
+            ActuatorError::PermissionDenied(details) => {
+                write!(f, "Permission denied on GPIO: {details}")
+            }
+            ActuatorError::InterlockRefused { relay_id, reason } => {
+                write!(f, "Relay '{relay_id}' refused by interlock: {reason}")
+            }
+            ActuatorError::UnknownRelay(relay_id) => write!(f, "Unknown relay '{relay_id}'"),
+            ActuatorError::InvalidConfig(details) => write!(f, "Invalid relay config: {details}"),
+            ActuatorError::Gpio { pin, message } => {
+                write!(f, "Failed to drive GPIO{pin}: {message}")
+            }
+            ActuatorError::ServiceStopped => write!(f, "The actuator service is not running"),
+        }
+    }
+}
+
+impl Error for ActuatorError {}
+
+pub type Result<T> = std::result::Result<T, ActuatorError>;
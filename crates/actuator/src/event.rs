@@ -0,0 +1,58 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::relay::RelayState;
+use crate::safety::InterlockReason;
+
+/// Why a relay changed state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchReason {
+    /// Asked through the service handle
+    Command,
+    /// End of a pulse
+    PulseEnded,
+    /// Released after its max on-duration
+    MaxOnDuration,
+    /// Left in its fail-safe state as the service stopped
+    FailSafe,
+}
+
+impl SwitchReason {
+    /// Stable code of the reason, as stored with the actuator events.
+    pub fn code(self) -> &'static str {
+        match self {
+            SwitchReason::Command => "command",
+            SwitchReason::PulseEnded => "pulse_ended",
+            SwitchReason::MaxOnDuration => "max_on_duration",
+            SwitchReason::FailSafe => "fail_safe",
+        }
+    }
+}
+
+/// Events published by the actuator service.
+///
+/// Subscribers get them through a broadcast channel, see
+/// [`crate::services::ActuatorServiceHandle::subscribe`].
+#[derive(Debug, Clone)]
+pub enum ActuatorEvent {
+    /// A relay was switched to `state`.
+    StateChanged {
+        state: RelayState,
+        reason: SwitchReason,
+    },
+    /// An interlock refused to energize a relay.
+    Refused {
+        relay_id: &'static str,
+        reason: InterlockReason,
+    },
+}
+
+impl ActuatorEvent {
+    pub fn relay_id(&self) -> &'static str {
+        match self {
+            ActuatorEvent::StateChanged { state, .. } => state.id,
+            ActuatorEvent::Refused { relay_id, .. } => relay_id,
+        }
+    }
+}
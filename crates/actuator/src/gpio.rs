@@ -0,0 +1,238 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::error::{ActuatorError, Result};
+
+/// Memory mapped GPIO of a Raspberry Pi, used by rppal.
+const RPI_GPIOMEM: &str = "/dev/gpiomem";
+const DEFAULT_GPIO_CHIP: &str = "/dev/gpiochip0";
+
+/// Electrical level of a GPIO line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Level {
+    Low,
+    High,
+}
+
+impl Level {
+    pub fn is_high(self) -> bool {
+        self == Level::High
+    }
+}
+
+impl From<bool> for Level {
+    fn from(high: bool) -> Self {
+        if high {
+            Level::High
+        } else {
+            Level::Low
+        }
+    }
+}
+
+/// A GPIO line configured as an output.
+pub trait OutputPin: Send {
+    fn set_level(&mut self, level: Level) -> Result<()>;
+}
+
+/// How GPIO lines are driven, picked when the relays are opened.
+#[derive(Clone, Debug, Default)]
+pub enum GpioBackend {
+    /// rppal on a Raspberry Pi, then the character device, then simulated.
+    #[default]
+    Auto,
+    /// Raspberry Pi GPIO through rppal.
+    Rppal,
+    /// Linux GPIO character device, e.g. `/dev/gpiochip0`.
+    Cdev { chip: PathBuf },
+    /// No hardware, levels are only recorded.
+    Simulated(SimulatedGpio),
+}
+
+impl GpioBackend {
+    /// Concrete backend of [`GpioBackend::Auto`] on this machine.
+    pub fn resolve(&self) -> GpioBackend {
+        match self {
+            GpioBackend::Auto if RPPAL_AVAILABLE && Path::new(RPI_GPIOMEM).exists() => {
+                GpioBackend::Rppal
+            }
+            GpioBackend::Auto if Path::new(DEFAULT_GPIO_CHIP).exists() => GpioBackend::Cdev {
+                chip: PathBuf::from(DEFAULT_GPIO_CHIP),
+            },
+            GpioBackend::Auto => {
+                tracing::warn!("No GPIO controller found, relays are simulated.");
+                GpioBackend::Simulated(SimulatedGpio::default())
+            }
+            backend => backend.clone(),
+        }
+    }
+
+    /// Claim `pin` (BCM numbering) as an output starting at `level`.
+    pub fn open_output(&self, pin: u8, level: Level) -> Result<Box<dyn OutputPin>> {
+        match self.resolve() {
+            GpioBackend::Auto => unreachable!("resolved above"),
+            GpioBackend::Rppal => rppal_output(pin, level),
+            GpioBackend::Cdev { chip } => cdev_output(&chip, pin, level),
+            GpioBackend::Simulated(gpio) => {
+                let mut output = SimulatedOutput { gpio, pin };
+                output.set_level(level)?;
+                Ok(Box::new(output))
+            }
+        }
+    }
+}
+
+/// Levels of simulated GPIO lines, shared by its clones.
+#[derive(Clone, Debug, Default)]
+pub struct SimulatedGpio {
+    levels: Arc<Mutex<HashMap<u8, Level>>>,
+}
+
+impl SimulatedGpio {
+    /// Level of `pin`, `None` while it was never claimed.
+    pub fn level(&self, pin: u8) -> Option<Level> {
+        self.levels
+            .lock()
+            .expect("simulated GPIO mutex poisoned")
+            .get(&pin)
+            .copied()
+    }
+}
+
+struct SimulatedOutput {
+    gpio: SimulatedGpio,
+    pin: u8,
+}
+
+impl OutputPin for SimulatedOutput {
+    fn set_level(&mut self, level: Level) -> Result<()> {
+        tracing::debug!(pin = self.pin, ?level, "Simulated GPIO set.");
+        self.gpio
+            .levels
+            .lock()
+            .map_err(|error| ActuatorError::Gpio {
+                pin: self.pin,
+                message: format!("Simulated GPIO mutex poisoned: {error}"),
+            })?
+            .insert(self.pin, level);
+
+        Ok(())
+    }
+}
+
+const RPPAL_AVAILABLE: bool = cfg!(all(
+    target_os = "linux",
+    any(target_arch = "arm", target_arch = "aarch64")
+));
+
+#[cfg(all(target_os = "linux", any(target_arch = "arm", target_arch = "aarch64")))]
+fn rppal_output(pin: u8, level: Level) -> Result<Box<dyn OutputPin>> {
+    use rppal::gpio::{Error, Gpio};
+
+    struct RppalOutput(rppal::gpio::OutputPin);
+
+    impl OutputPin for RppalOutput {
+        fn set_level(&mut self, level: Level) -> Result<()> {
+            match level {
+                Level::High => self.0.set_high(),
+                Level::Low => self.0.set_low(),
+            }
+            Ok(())
+        }
+    }
+
+    let rppal_error = |error: Error| match error {
+        Error::PinUsed(pin) => ActuatorError::PinBusy { pin },
+        Error::PermissionDenied(path) => ActuatorError::PermissionDenied(path),
+        Error::Io(error) => io_error(pin, &error),
+        error => ActuatorError::GpioUnavailable(error.to_string()),
+    };
+
+    let gpio = Gpio::new().map_err(rppal_error)?;
+    let pin = gpio.get(pin).map_err(rppal_error)?;
+    let output = match level {
+        Level::High => pin.into_output_high(),
+        Level::Low => pin.into_output_low(),
+    };
+
+    Ok(Box::new(RppalOutput(output)))
+}
+
+#[cfg(not(all(target_os = "linux", any(target_arch = "arm", target_arch = "aarch64"))))]
+fn rppal_output(_pin: u8, _level: Level) -> Result<Box<dyn OutputPin>> {
+    Err(ActuatorError::GpioUnavailable(
+        "rppal GPIO is only available on a Raspberry Pi".to_string(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn cdev_output(chip: &Path, pin: u8, level: Level) -> Result<Box<dyn OutputPin>> {
+    use gpio_cdev::{Chip, LineHandle, LineRequestFlags};
+    use std::error::Error;
+
+    struct CdevOutput {
+        handle: LineHandle,
+        pin: u8,
+    }
+
+    impl OutputPin for CdevOutput {
+        fn set_level(&mut self, level: Level) -> Result<()> {
+            self.handle
+                .set_value(level.is_high().into())
+                .map_err(|error| ActuatorError::Gpio {
+                    pin: self.pin,
+                    message: error.to_string(),
+                })
+        }
+    }
+
+    let cdev_error = |error: gpio_cdev::Error| {
+        if let Some(error) = error.source().and_then(|source| source.downcast_ref()) {
+            return io_error(pin, error);
+        }
+
+        // Failed ioctls only expose their errno through the message
+        let message = error.to_string();
+        if message.contains("EBUSY") {
+            ActuatorError::PinBusy { pin }
+        } else if message.contains("EACCES") || message.contains("EPERM") {
+            ActuatorError::PermissionDenied(message)
+        } else {
+            ActuatorError::GpioUnavailable(format!("{}: {message}", chip.display()))
+        }
+    };
+
+    let mut chip_device = Chip::new(chip).map_err(cdev_error)?;
+    let handle = chip_device
+        .get_line(pin.into())
+        .and_then(|line| line.request(LineRequestFlags::OUTPUT, level.is_high().into(), "arksync"))
+        .map_err(cdev_error)?;
+
+    Ok(Box::new(CdevOutput { handle, pin }))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn cdev_output(chip: &Path, _pin: u8, _level: Level) -> Result<Box<dyn OutputPin>> {
+    Err(ActuatorError::GpioUnavailable(format!(
+        "GPIO character devices such as {} are only available on Linux",
+        chip.display()
+    )))
+}
+
+/// Classify an OS error raised while claiming `pin`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn io_error(pin: u8, error: &io::Error) -> ActuatorError {
+    match error.kind() {
+        io::ErrorKind::PermissionDenied => ActuatorError::PermissionDenied(error.to_string()),
+        io::ErrorKind::ResourceBusy => ActuatorError::PinBusy { pin },
+        _ => ActuatorError::GpioUnavailable(error.to_string()),
+    }
+}
@@ -2,4 +2,14 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+mod actuator;
+mod config;
+pub mod error;
+pub mod event;
+pub mod gpio;
 pub mod relay;
+pub mod safety;
+pub mod services;
+
+pub use actuator::Actuator;
+pub use config::{Config, CONFIG};
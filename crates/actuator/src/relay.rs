@@ -3,39 +3,110 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
-pub const MIST_RELAY: RelaySpec = RelaySpec {
-    id: "mist_relay",
+use crate::error::{ActuatorError, Result};
+use crate::gpio::{GpioBackend, Level, OutputPin};
+use crate::{Actuator, CONFIG};
+
+pub const MIST_BOARD: RelayBoardSpec = RelayBoardSpec {
+    id: "mist_board",
     model: "5V dual-channel relay module",
-    channels: 2,
+    channel_count: 2,
     coil_voltage_vdc: 5.0,
     contact_current_a: 10.0,
     contact_type: "2NO 2NC",
-    gpio_bcm_pin: 17,
-    active_low: true,
+    channels: &[RelayChannelSpec {
+        name: "mist_relay",
+        channel: 1,
+        gpio_bcm_pin: 17,
+        active_low: true,
+    }],
 };
 
+pub const MIST_RELAY: RelaySpec = MIST_BOARD.relay(0);
+
+/// A relay module and the channels wired to the station.
 #[derive(Clone, Copy, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct RelaySpec {
+pub struct RelayBoardSpec {
     pub id: &'static str,
     pub model: &'static str,
-    pub channels: u8,
+    /// Channels on the board, wired or not
+    pub channel_count: u8,
     pub coil_voltage_vdc: f32,
     pub contact_current_a: f32,
     pub contact_type: &'static str,
+    pub channels: &'static [RelayChannelSpec],
+}
+
+/// A wired channel of a relay board.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayChannelSpec {
+    /// Unique across the station, the channel is addressed by it
+    pub name: &'static str,
+    /// Position on the board, from 1
+    pub channel: u8,
     pub gpio_bcm_pin: u8,
     pub active_low: bool,
 }
 
+impl RelayBoardSpec {
+    /// Spec of the `index`-th wired channel.
+    pub const fn relay(&self, index: usize) -> RelaySpec {
+        let channel = self.channels[index];
+
+        RelaySpec {
+            id: channel.name,
+            board: self.id,
+            model: self.model,
+            channel: channel.channel,
+            coil_voltage_vdc: self.coil_voltage_vdc,
+            contact_current_a: self.contact_current_a,
+            contact_type: self.contact_type,
+            gpio_bcm_pin: channel.gpio_bcm_pin,
+            active_low: channel.active_low,
+        }
+    }
+
+    pub fn relays(&self) -> impl Iterator<Item = RelaySpec> + '_ {
+        (0..self.channels.len()).map(|index| self.relay(index))
+    }
+}
+
+/// A single relay channel with the characteristics of its board.
 #[derive(Clone, Copy, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
+pub struct RelaySpec {
+    pub id: &'static str,
+    pub board: &'static str,
+    pub model: &'static str,
+    pub channel: u8,
+    pub coil_voltage_vdc: f32,
+    pub contact_current_a: f32,
+    pub contact_type: &'static str,
+    pub gpio_bcm_pin: u8,
+    pub active_low: bool,
+}
+
+impl RelaySpec {
+    /// Level driving the channel to `active`.
+    pub fn level(&self, active: bool) -> Level {
+        Level::from(active != self.active_low)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct RelayState {
     pub id: &'static str,
     pub gpio_bcm_pin: u8,
     pub active_low: bool,
     pub active: bool,
-    pub level: &'static str,
+    pub level: Level,
 }
 
 impl RelayState {
@@ -45,77 +116,200 @@ impl RelayState {
             gpio_bcm_pin: spec.gpio_bcm_pin,
             active_low: spec.active_low,
             active,
-            level: match (spec.active_low, active) {
-                (true, true) | (false, false) => "low",
-                (true, false) | (false, true) => "high",
-            },
+            level: spec.level(active),
         }
     }
 }
 
-#[cfg(all(target_os = "linux", any(target_arch = "arm", target_arch = "aarch64")))]
+/// Drives one relay channel.
 pub struct RelayDriver {
-    output_pin: std::sync::Mutex<rppal::gpio::OutputPin>,
+    spec: RelaySpec,
+    output_pin: Mutex<Box<dyn OutputPin>>,
+    active: AtomicBool,
 }
 
-#[cfg(all(target_os = "linux", any(target_arch = "arm", target_arch = "aarch64")))]
 impl RelayDriver {
-    pub fn new(spec: RelaySpec) -> Self {
-        use rppal::gpio::Gpio;
+    /// Driver on the configured GPIO backend.
+    pub fn new(spec: RelaySpec) -> Result<Self> {
+        Self::with_backend(spec, &CONFIG.gpio)
+    }
 
-        let gpio = Gpio::new().unwrap_or_else(|error| {
-            panic!("Failed to access Raspberry Pi GPIO controller: {error}");
-        });
+    /// Claim the channel pin on `backend`, the relay starts released.
+    pub fn with_backend(spec: RelaySpec, backend: &GpioBackend) -> Result<Self> {
+        let output_pin = backend.open_output(spec.gpio_bcm_pin, spec.level(false))?;
 
-        let output_pin = gpio
-            .get(spec.gpio_bcm_pin)
-            .unwrap_or_else(|error| panic!("Failed to access GPIO{}: {error}", spec.gpio_bcm_pin))
-            .into_output_high();
+        Ok(Self {
+            spec,
+            output_pin: Mutex::new(output_pin),
+            active: AtomicBool::new(false),
+        })
+    }
 
-        Self {
-            output_pin: std::sync::Mutex::new(output_pin),
-        }
+    pub fn spec(&self) -> RelaySpec {
+        self.spec
     }
 
-    pub fn apply(&self, state: RelayState) -> Result<(), String> {
+    pub fn apply(&self, state: RelayState) -> Result<()> {
         let mut output_pin = self
             .output_pin
             .lock()
-            .map_err(|error| format!("GPIO mutex poisoned: {error}"))?;
+            .map_err(|error| ActuatorError::Gpio {
+                pin: self.spec.gpio_bcm_pin,
+                message: format!("GPIO mutex poisoned: {error}"),
+            })?;
 
-        match state.level {
-            "low" => output_pin.set_low(),
-            "high" => output_pin.set_high(),
-            level => return Err(format!("Unsupported relay level: {level}")),
-        }
+        output_pin.set_level(state.level)?;
+        self.active.store(state.active, Ordering::SeqCst);
 
         Ok(())
     }
 }
 
-#[cfg(not(all(target_os = "linux", any(target_arch = "arm", target_arch = "aarch64"))))]
-pub struct RelayDriver {
-    spec: RelaySpec,
+impl Actuator for RelayDriver {
+    fn id(&self) -> &str {
+        self.spec.id
+    }
+
+    fn set_active(&self, active: bool) -> Result<()> {
+        self.apply(RelayState::new(self.spec, active))
+    }
+
+    fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
 }
 
-#[cfg(not(all(target_os = "linux", any(target_arch = "arm", target_arch = "aarch64"))))]
-impl RelayDriver {
-    pub fn new(spec: RelaySpec) -> Self {
-        log::warn!(
-            "GPIO debug loop is running without Raspberry Pi GPIO access; state changes will only be logged."
-        );
-        Self { spec }
+/// Every wired relay channel of the station, addressed by id.
+#[derive(Clone, Default)]
+pub struct RelayBank {
+    relays: BTreeMap<&'static str, Arc<RelayDriver>>,
+}
+
+impl RelayBank {
+    /// Claim the wired channels of `boards` on `backend`.
+    pub fn open(boards: &[RelayBoardSpec], backend: &GpioBackend) -> Result<Self> {
+        let backend = backend.resolve();
+        let mut relays = BTreeMap::new();
+        let mut pins = BTreeMap::new();
+
+        for spec in boards.iter().flat_map(RelayBoardSpec::relays) {
+            if relays.contains_key(spec.id) {
+                return Err(ActuatorError::InvalidConfig(format!(
+                    "Relay '{}' is declared twice",
+                    spec.id
+                )));
+            }
+            if let Some(other) = pins.insert(spec.gpio_bcm_pin, spec.id) {
+                return Err(ActuatorError::InvalidConfig(format!(
+                    "Relays '{other}' and '{}' share GPIO{}",
+                    spec.id, spec.gpio_bcm_pin
+                )));
+            }
+
+            relays.insert(
+                spec.id,
+                Arc::new(RelayDriver::with_backend(spec, &backend)?),
+            );
+        }
+
+        Ok(Self { relays })
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<RelayDriver>> {
+        self.relays.get(id).cloned()
+    }
+
+    pub fn relays(&self) -> impl Iterator<Item = &Arc<RelayDriver>> {
+        self.relays.values()
     }
 
-    pub fn apply(&self, state: RelayState) -> Result<(), String> {
-        log::debug!(
-            "Simulated relay '{}' on GPIO{} -> {} ({})",
-            state.id,
-            self.spec.gpio_bcm_pin,
-            if state.active { "ON" } else { "OFF" },
-            state.level
+    pub fn specs(&self) -> impl Iterator<Item = RelaySpec> + '_ {
+        self.relays.values().map(|relay| relay.spec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpio::SimulatedGpio;
+
+    const GREENHOUSE_BOARD: RelayBoardSpec = RelayBoardSpec {
+        id: "greenhouse_board",
+        model: "4-channel relay module",
+        channel_count: 4,
+        coil_voltage_vdc: 5.0,
+        contact_current_a: 10.0,
+        contact_type: "SPDT",
+        channels: &[
+            RelayChannelSpec {
+                name: "fan",
+                channel: 1,
+                gpio_bcm_pin: 22,
+                active_low: false,
+            },
+            RelayChannelSpec {
+                name: "heater",
+                channel: 3,
+                gpio_bcm_pin: 23,
+                active_low: true,
+            },
+        ],
+    };
+
+    #[test]
+    fn channels_are_addressable_with_their_own_polarity() {
+        let gpio = SimulatedGpio::default();
+        let bank = RelayBank::open(
+            &[MIST_BOARD, GREENHOUSE_BOARD],
+            &GpioBackend::Simulated(gpio.clone()),
+        )
+        .unwrap();
+
+        // Released on open
+        assert_eq!(gpio.level(22), Some(Level::Low));
+        assert_eq!(gpio.level(23), Some(Level::High));
+
+        bank.get("fan").unwrap().set_active(true).unwrap();
+        bank.get("heater").unwrap().set_active(true).unwrap();
+
+        assert_eq!(gpio.level(22), Some(Level::High));
+        assert_eq!(gpio.level(23), Some(Level::Low));
+        assert!(bank.get("heater").unwrap().is_active());
+        assert!(!bank.get(MIST_RELAY.id).unwrap().is_active());
+        assert_eq!(bank.get("heater").unwrap().spec().channel, 3);
+    }
+
+    #[test]
+    fn shared_pins_are_rejected() {
+        let mut board = GREENHOUSE_BOARD;
+        board.channels = &[RelayChannelSpec {
+            name: "fan",
+            channel: 1,
+            gpio_bcm_pin: MIST_RELAY.gpio_bcm_pin,
+            active_low: false,
+        }];
+
+        let result = RelayBank::open(
+            &[MIST_BOARD, board],
+            &GpioBackend::Simulated(SimulatedGpio::default()),
         );
 
-        Ok(())
+        assert!(matches!(result, Err(ActuatorError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn states_keep_their_camel_case_payload() {
+        let state = RelayState::new(MIST_RELAY, true);
+
+        assert_eq!(
+            serde_json::to_value(state).unwrap(),
+            serde_json::json!({
+                "id": "mist_relay",
+                "gpioBcmPin": 17,
+                "activeLow": true,
+                "active": true,
+                "level": "low",
+            })
+        );
     }
 }
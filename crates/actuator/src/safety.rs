@@ -0,0 +1,225 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use tokio::time::{Duration, Instant};
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// Limits of a relay, a relay without limits can be switched freely.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SafetyLimits {
+    /// The relay is released once on for this long
+    pub max_on_duration: Option<Duration>,
+    /// Rest time before the relay can be energized again
+    pub min_off_time: Option<Duration>,
+    /// Times the relay can be energized over a sliding hour
+    pub max_switches_per_hour: Option<u32>,
+    /// Relays sharing a group are never on together, e.g. heater and chiller
+    pub exclusion_group: Option<&'static str>,
+    /// State left when the service stops or crashes, released by default
+    pub fail_safe_active: bool,
+}
+
+/// Why an interlock refused to energize a relay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterlockReason {
+    MinOffTime { remaining: Duration },
+    SwitchRateExceeded { max_per_hour: u32 },
+    ExclusionGroup { group: String, active_relay: String },
+}
+
+impl InterlockReason {
+    /// Stable code of the reason, without its details.
+    pub fn code(&self) -> &'static str {
+        match self {
+            InterlockReason::MinOffTime { .. } => "min_off_time",
+            InterlockReason::SwitchRateExceeded { .. } => "switch_rate_exceeded",
+            InterlockReason::ExclusionGroup { .. } => "exclusion_group",
+        }
+    }
+}
+
+impl fmt::Display for InterlockReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterlockReason::MinOffTime { remaining } => {
+                write!(f, "minimum off-time not elapsed, {remaining:?} left")
+            }
+            InterlockReason::SwitchRateExceeded { max_per_hour } => {
+                write!(
+                    f,
+                    "already switched on {max_per_hour} times in the last hour"
+                )
+            }
+            InterlockReason::ExclusionGroup {
+                group,
+                active_relay,
+            } => write!(f, "'{active_relay}' of group '{group}' is on"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct RelayHistory {
+    last_off: Option<Instant>,
+    /// Recent switch-ons, oldest first
+    switch_ons: VecDeque<Instant>,
+}
+
+/// Checks the [`SafetyLimits`] of the relays against their history.
+///
+/// Only energizing a relay can be refused, releasing it is always allowed.
+#[derive(Default)]
+pub struct Interlocks {
+    limits: HashMap<&'static str, SafetyLimits>,
+    history: HashMap<&'static str, RelayHistory>,
+}
+
+impl Interlocks {
+    pub fn new(limits: HashMap<&'static str, SafetyLimits>) -> Self {
+        Self {
+            limits,
+            history: HashMap::new(),
+        }
+    }
+
+    pub fn limits(&self, relay_id: &str) -> Option<&SafetyLimits> {
+        self.limits.get(relay_id)
+    }
+
+    /// Whether `relay_id` may be energized at `now`, `is_active` tells the
+    /// current state of the other relays.
+    pub fn check_on(
+        &mut self,
+        relay_id: &str,
+        now: Instant,
+        is_active: impl Fn(&str) -> bool,
+    ) -> Result<(), InterlockReason> {
+        let Some(limits) = self.limits.get(relay_id) else {
+            return Ok(());
+        };
+
+        if let Some(group) = limits.exclusion_group {
+            let active_relay = self.limits.iter().find(|(other, other_limits)| {
+                **other != relay_id
+                    && other_limits.exclusion_group == Some(group)
+                    && is_active(other)
+            });
+            if let Some((active_relay, _)) = active_relay {
+                return Err(InterlockReason::ExclusionGroup {
+                    group: group.to_string(),
+                    active_relay: active_relay.to_string(),
+                });
+            }
+        }
+
+        let Some(history) = self.history.get_mut(relay_id) else {
+            return Ok(());
+        };
+
+        if let (Some(min_off_time), Some(last_off)) = (limits.min_off_time, history.last_off) {
+            let off_for = now.saturating_duration_since(last_off);
+            if off_for < min_off_time {
+                return Err(InterlockReason::MinOffTime {
+                    remaining: min_off_time - off_for,
+                });
+            }
+        }
+
+        if let Some(max_per_hour) = limits.max_switches_per_hour {
+            while history
+                .switch_ons
+                .front()
+                .is_some_and(|switch_on| now.saturating_duration_since(*switch_on) >= HOUR)
+            {
+                history.switch_ons.pop_front();
+            }
+            if history.switch_ons.len() >= max_per_hour as usize {
+                return Err(InterlockReason::SwitchRateExceeded { max_per_hour });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remember that `relay_id` changed state at `now`.
+    pub fn record(&mut self, relay_id: &'static str, active: bool, now: Instant) {
+        let history = self.history.entry(relay_id).or_default();
+        if active {
+            history.switch_ons.push_back(now);
+        } else {
+            history.last_off = Some(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interlocks() -> Interlocks {
+        let mister = SafetyLimits {
+            min_off_time: Some(Duration::from_secs(30)),
+            max_switches_per_hour: Some(2),
+            ..SafetyLimits::default()
+        };
+        let heater = SafetyLimits {
+            exclusion_group: Some("temperature"),
+            ..SafetyLimits::default()
+        };
+        let chiller = heater.clone();
+
+        Interlocks::new(HashMap::from([
+            ("mist_relay", mister),
+            ("heater", heater),
+            ("chiller", chiller),
+        ]))
+    }
+
+    #[test]
+    fn rest_time_and_switch_rate_are_enforced() {
+        let mut interlocks = interlocks();
+        let start = Instant::now();
+        let idle = |_: &str| false;
+
+        interlocks.record("mist_relay", true, start);
+        interlocks.record("mist_relay", false, start + Duration::from_secs(10));
+        assert_eq!(
+            interlocks.check_on("mist_relay", start + Duration::from_secs(20), idle),
+            Err(InterlockReason::MinOffTime {
+                remaining: Duration::from_secs(20)
+            })
+        );
+
+        interlocks.record("mist_relay", true, start + Duration::from_secs(40));
+        interlocks.record("mist_relay", false, start + Duration::from_secs(50));
+        assert_eq!(
+            interlocks.check_on("mist_relay", start + Duration::from_secs(90), idle),
+            Err(InterlockReason::SwitchRateExceeded { max_per_hour: 2 })
+        );
+
+        // The first switch-on left the sliding hour
+        assert!(interlocks
+            .check_on("mist_relay", start + HOUR, idle)
+            .is_ok());
+    }
+
+    #[test]
+    fn relays_of_a_group_exclude_each_other() {
+        let mut interlocks = interlocks();
+
+        assert_eq!(
+            interlocks.check_on("heater", Instant::now(), |relay| relay == "chiller"),
+            Err(InterlockReason::ExclusionGroup {
+                group: "temperature".to_string(),
+                active_relay: "chiller".to_string(),
+            })
+        );
+        assert!(interlocks
+            .check_on("heater", Instant::now(), |relay| relay == "mist_relay")
+            .is_ok());
+    }
+}
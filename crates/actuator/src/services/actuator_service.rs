@@ -0,0 +1,498 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::HashMap;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::{sleep, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::{ActuatorError, Result};
+use crate::event::{ActuatorEvent, SwitchReason};
+use crate::relay::{RelayBank, RelaySpec, RelayState};
+use crate::safety::{Interlocks, SafetyLimits};
+use crate::services::{ActuatorPersistence, ActuatorServiceHandle};
+use crate::Actuator;
+
+/// Events a subscriber can lag behind before missing some.
+const ACTUATOR_EVENTS_CAPACITY: usize = 256;
+
+pub(crate) enum ActuatorServiceCmd {
+    /// Switch a relay, cancelling its pending pulse
+    Switch {
+        relay_id: String,
+        on: bool,
+        respond_to: oneshot::Sender<Result<RelayState>>,
+    },
+    /// Energize a relay, then release it after `duration`
+    Pulse {
+        relay_id: String,
+        duration: Duration,
+        respond_to: oneshot::Sender<Result<RelayState>>,
+    },
+    /// Release a relay at the end of a pulse, unless a later command took over
+    EndPulse { relay_id: &'static str, timer: u64 },
+    /// Release a relay on for its max on-duration, unless switched since
+    MaxOnReached { relay_id: &'static str, timer: u64 },
+    /// Current state of every relay
+    States {
+        respond_to: oneshot::Sender<Vec<(RelaySpec, RelayState)>>,
+    },
+}
+
+/// Owner of the relay drivers, the counterpart of the sensor service.
+///
+/// Relays are only switched through its [`ActuatorServiceHandle`], commands
+/// are applied in order against the safety limits and every change of state
+/// is published.
+pub struct ActuatorService {
+    relays: RelayBank,
+    interlocks: Interlocks,
+    cmd_tx: mpsc::Sender<ActuatorServiceCmd>,
+    cmd_rx: mpsc::Receiver<ActuatorServiceCmd>,
+    events: broadcast::Sender<ActuatorEvent>,
+    persistence: Option<ActuatorPersistence>,
+    /// Timer of the pending pulse of each relay
+    pulses: HashMap<&'static str, u64>,
+    /// Timer of the max on-duration of each energized relay
+    activations: HashMap<&'static str, u64>,
+    next_timer: u64,
+}
+
+impl ActuatorService {
+    pub fn new(relays: RelayBank) -> Self {
+        let (cmd_tx, cmd_rx) = mpsc::channel(100);
+        let (events, _) = broadcast::channel(ACTUATOR_EVENTS_CAPACITY);
+
+        Self {
+            relays,
+            interlocks: Interlocks::default(),
+            cmd_tx,
+            cmd_rx,
+            events,
+            persistence: None,
+            pulses: HashMap::new(),
+            activations: HashMap::new(),
+            next_timer: 0,
+        }
+    }
+
+    /// Handle to talk to the service once it runs.
+    pub fn handle(&self) -> ActuatorServiceHandle {
+        ActuatorServiceHandle::new(self.cmd_tx.clone(), self.events.clone())
+    }
+
+    /// Enforce `limits`, keyed by relay id.
+    pub fn with_safety_limits(mut self, limits: HashMap<&'static str, SafetyLimits>) -> Self {
+        self.interlocks = Interlocks::new(limits);
+        self
+    }
+
+    /// Persist the relays and their states while the service runs.
+    pub fn with_persistence(mut self, persistence: ActuatorPersistence) -> Self {
+        self.persistence = Some(persistence);
+        self
+    }
+
+    /// Apply commands until `shutdown` is cancelled, then leave every relay
+    /// in its fail-safe state.
+    ///
+    /// The fail-safe states are also applied if the service panics or its
+    /// task is aborted.
+    pub async fn run(mut self, shutdown: CancellationToken) {
+        // Stopped after the fail-safe states so they are kept
+        let persisted = CancellationToken::new();
+        let persistence = self.persistence.take().map(|persistence| {
+            let events = self.events.subscribe();
+            persistence.run(self.relays.specs().collect(), events, persisted.clone())
+        });
+        tracing::info!(
+            relays = self.relays.relays().count(),
+            "Actuator service started."
+        );
+
+        let main_loop = async move {
+            let _fail_safe = FailSafeGuard {
+                relays: self.relays.clone(),
+                states: self.fail_safe_states(),
+            };
+
+            loop {
+                tokio::select! {
+                    Some(cmd) = self.cmd_rx.recv() => self.handle_cmd(cmd),
+                    _ = shutdown.cancelled() => break,
+                }
+            }
+
+            self.apply_fail_safe();
+            persisted.cancel();
+        };
+
+        tokio::join!(main_loop, async move {
+            if let Some(persistence) = persistence {
+                persistence.await;
+            }
+        });
+
+        tracing::info!("Actuator service stopped.");
+    }
+
+    fn handle_cmd(&mut self, cmd: ActuatorServiceCmd) {
+        match cmd {
+            ActuatorServiceCmd::Switch {
+                relay_id,
+                on,
+                respond_to,
+            } => {
+                self.pulses.remove(relay_id.as_str());
+                let _ = respond_to.send(self.switch(&relay_id, on, SwitchReason::Command));
+            }
+
+            ActuatorServiceCmd::Pulse {
+                relay_id,
+                duration,
+                respond_to,
+            } => {
+                let result = self.switch(&relay_id, true, SwitchReason::Command);
+                if let Ok(state) = &result {
+                    let timer = self.next_timer();
+                    self.pulses.insert(state.id, timer);
+                    self.schedule(
+                        duration,
+                        ActuatorServiceCmd::EndPulse {
+                            relay_id: state.id,
+                            timer,
+                        },
+                    );
+                }
+                let _ = respond_to.send(result);
+            }
+
+            ActuatorServiceCmd::EndPulse { relay_id, timer } => {
+                if self.pulses.get(relay_id) != Some(&timer) {
+                    return;
+                }
+                self.pulses.remove(relay_id);
+                self.release(relay_id, SwitchReason::PulseEnded);
+            }
+
+            ActuatorServiceCmd::MaxOnReached { relay_id, timer } => {
+                if self.activations.get(relay_id) != Some(&timer) {
+                    return;
+                }
+                tracing::warn!(relay_id, "Relay reached its max on-duration, releasing it.");
+                self.pulses.remove(relay_id);
+                self.release(relay_id, SwitchReason::MaxOnDuration);
+            }
+
+            ActuatorServiceCmd::States { respond_to } => {
+                let states = self
+                    .relays
+                    .relays()
+                    .map(|relay| {
+                        (
+                            relay.spec(),
+                            RelayState::new(relay.spec(), relay.is_active()),
+                        )
+                    })
+                    .collect();
+                let _ = respond_to.send(states);
+            }
+        }
+    }
+
+    /// Drive a relay and publish its state if it changed.
+    ///
+    /// Energizing a relay goes through the interlocks, except to reach its
+    /// fail-safe state.
+    fn switch(&mut self, relay_id: &str, on: bool, reason: SwitchReason) -> Result<RelayState> {
+        let relay = self
+            .relays
+            .get(relay_id)
+            .ok_or_else(|| ActuatorError::UnknownRelay(relay_id.to_string()))?;
+        let spec = relay.spec();
+        let was_active = relay.is_active();
+        let now = Instant::now();
+
+        if on && !was_active && reason != SwitchReason::FailSafe {
+            let relays = &self.relays;
+            let is_active = |other: &str| relays.get(other).is_some_and(|relay| relay.is_active());
+
+            if let Err(refusal) = self.interlocks.check_on(spec.id, now, is_active) {
+                tracing::warn!(relay_id = spec.id, %refusal, "Relay refused to switch ON.");
+                let _ = self.events.send(ActuatorEvent::Refused {
+                    relay_id: spec.id,
+                    reason: refusal.clone(),
+                });
+                return Err(ActuatorError::InterlockRefused {
+                    relay_id: spec.id.to_string(),
+                    reason: refusal,
+                });
+            }
+        }
+
+        relay.set_active(on)?;
+        let state = RelayState::new(spec, on);
+        if was_active == on {
+            return Ok(state);
+        }
+
+        self.interlocks.record(spec.id, on, now);
+        self.activations.remove(spec.id);
+        let max_on_duration = self
+            .interlocks
+            .limits(spec.id)
+            .and_then(|limits| limits.max_on_duration);
+        if let (true, Some(max_on_duration)) = (on, max_on_duration) {
+            let timer = self.next_timer();
+            self.activations.insert(spec.id, timer);
+            self.schedule(
+                max_on_duration,
+                ActuatorServiceCmd::MaxOnReached {
+                    relay_id: spec.id,
+                    timer,
+                },
+            );
+        }
+
+        tracing::info!(
+            relay_id = state.id,
+            on,
+            level = ?state.level,
+            reason = reason.code(),
+            "Relay switched."
+        );
+        // No receiver is fine, nobody is listening yet
+        let _ = self
+            .events
+            .send(ActuatorEvent::StateChanged { state, reason });
+
+        Ok(state)
+    }
+
+    fn release(&mut self, relay_id: &'static str, reason: SwitchReason) {
+        if let Err(error) = self.switch(relay_id, false, reason) {
+            tracing::error!(relay_id, %error, "Failed to release the relay.");
+        }
+    }
+
+    fn next_timer(&mut self) -> u64 {
+        self.next_timer += 1;
+        self.next_timer
+    }
+
+    /// Send `cmd` to the service after `delay`.
+    fn schedule(&self, delay: Duration, cmd: ActuatorServiceCmd) {
+        let cmd_tx = self.cmd_tx.clone();
+        tokio::spawn(async move {
+            sleep(delay).await;
+            let _ = cmd_tx.send(cmd).await;
+        });
+    }
+
+    fn fail_safe_states(&self) -> HashMap<&'static str, bool> {
+        self.relays
+            .specs()
+            .map(|spec| {
+                let active = self
+                    .interlocks
+                    .limits(spec.id)
+                    .is_some_and(|limits| limits.fail_safe_active);
+                (spec.id, active)
+            })
+            .collect()
+    }
+
+    /// Leave the relays in their fail-safe state, published like any switch.
+    fn apply_fail_safe(&mut self) {
+        self.pulses.clear();
+        self.activations.clear();
+
+        for (relay_id, active) in self.fail_safe_states() {
+            if let Err(error) = self.switch(relay_id, active, SwitchReason::FailSafe) {
+                tracing::error!(
+                    relay_id,
+                    %error,
+                    "Failed to leave the relay in its fail-safe state."
+                );
+            }
+        }
+    }
+}
+
+/// Drives the relays to their fail-safe state when dropped, the last resort
+/// when the service stops without reaching [`ActuatorService::apply_fail_safe`].
+struct FailSafeGuard {
+    relays: RelayBank,
+    states: HashMap<&'static str, bool>,
+}
+
+impl Drop for FailSafeGuard {
+    fn drop(&mut self) {
+        for (relay_id, active) in &self.states {
+            let Some(relay) = self.relays.get(relay_id) else {
+                continue;
+            };
+            if relay.is_active() == *active {
+                continue;
+            }
+
+            tracing::warn!(
+                relay_id,
+                "Actuator service interrupted, relay left in its fail-safe state."
+            );
+            if let Err(error) = relay.set_active(*active) {
+                tracing::error!(
+                    relay_id,
+                    %error,
+                    "Failed to leave the relay in its fail-safe state."
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpio::{GpioBackend, Level, SimulatedGpio};
+    use crate::relay::{MIST_BOARD, MIST_RELAY};
+    use crate::safety::InterlockReason;
+
+    fn service(gpio: &SimulatedGpio) -> ActuatorService {
+        let relays = RelayBank::open(&[MIST_BOARD], &GpioBackend::Simulated(gpio.clone())).unwrap();
+        ActuatorService::new(relays)
+    }
+
+    fn limits(limits: SafetyLimits) -> HashMap<&'static str, SafetyLimits> {
+        HashMap::from([(MIST_RELAY.id, limits)])
+    }
+
+    async fn is_active(handle: &ActuatorServiceHandle) -> bool {
+        handle.states().await.unwrap()[0].1.active
+    }
+
+    #[tokio::test]
+    async fn changes_are_published_and_relays_released_on_shutdown() {
+        let gpio = SimulatedGpio::default();
+        let service = service(&gpio);
+        let handle = service.handle();
+        let mut events = handle.subscribe();
+        let shutdown = CancellationToken::new();
+        let task = tokio::spawn(service.run(shutdown.clone()));
+
+        handle.turn_on(MIST_RELAY.id).await.unwrap();
+        // Already on, nothing to publish
+        handle.turn_on(MIST_RELAY.id).await.unwrap();
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            ActuatorEvent::StateChanged { state, reason: SwitchReason::Command } if state.active
+        ));
+        assert_eq!(gpio.level(MIST_RELAY.gpio_bcm_pin), Some(Level::Low));
+        assert_eq!(
+            handle.turn_on("unknown").await,
+            Err(ActuatorError::UnknownRelay("unknown".to_string()))
+        );
+
+        shutdown.cancel();
+        task.await.unwrap();
+
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            ActuatorEvent::StateChanged { state, reason: SwitchReason::FailSafe } if !state.active
+        ));
+        assert_eq!(gpio.level(MIST_RELAY.gpio_bcm_pin), Some(Level::High));
+        assert_eq!(
+            handle.turn_off(MIST_RELAY.id).await,
+            Err(ActuatorError::ServiceStopped)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_pulse_releases_the_relay_unless_superseded() {
+        let gpio = SimulatedGpio::default();
+        let service = service(&gpio);
+        let handle = service.handle();
+        let shutdown = CancellationToken::new();
+        tokio::spawn(service.run(shutdown.clone()));
+
+        handle
+            .pulse(MIST_RELAY.id, Duration::from_secs(5))
+            .await
+            .unwrap();
+        sleep(Duration::from_secs(4)).await;
+        assert!(is_active(&handle).await);
+        sleep(Duration::from_secs(2)).await;
+        assert!(!is_active(&handle).await);
+
+        // Turned on manually during the pulse, it stays on
+        handle
+            .pulse(MIST_RELAY.id, Duration::from_secs(5))
+            .await
+            .unwrap();
+        handle.turn_on(MIST_RELAY.id).await.unwrap();
+        sleep(Duration::from_secs(6)).await;
+        assert!(is_active(&handle).await);
+
+        shutdown.cancel();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn limits_cut_long_activations_and_refuse_early_restarts() {
+        let gpio = SimulatedGpio::default();
+        let service = service(&gpio).with_safety_limits(limits(SafetyLimits {
+            max_on_duration: Some(Duration::from_secs(60)),
+            min_off_time: Some(Duration::from_secs(30)),
+            ..SafetyLimits::default()
+        }));
+        let handle = service.handle();
+        let mut events = handle.subscribe();
+        let shutdown = CancellationToken::new();
+        tokio::spawn(service.run(shutdown.clone()));
+
+        handle.turn_on(MIST_RELAY.id).await.unwrap();
+        sleep(Duration::from_secs(61)).await;
+        assert!(!is_active(&handle).await);
+
+        let refused = handle.turn_on(MIST_RELAY.id).await;
+        assert!(matches!(
+            refused,
+            Err(ActuatorError::InterlockRefused {
+                reason: InterlockReason::MinOffTime { .. },
+                ..
+            })
+        ));
+
+        let _ = events.recv().await.unwrap();
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            ActuatorEvent::StateChanged {
+                reason: SwitchReason::MaxOnDuration,
+                ..
+            }
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            ActuatorEvent::Refused { relay_id, .. } if relay_id == MIST_RELAY.id
+        ));
+
+        sleep(Duration::from_secs(30)).await;
+        handle.turn_on(MIST_RELAY.id).await.unwrap();
+
+        shutdown.cancel();
+    }
+
+    #[tokio::test]
+    async fn an_aborted_service_leaves_the_fail_safe_states() {
+        let gpio = SimulatedGpio::default();
+        let service = service(&gpio);
+        let handle = service.handle();
+        let task = tokio::spawn(service.run(CancellationToken::new()));
+
+        handle.turn_on(MIST_RELAY.id).await.unwrap();
+        task.abort();
+        assert!(task.await.unwrap_err().is_cancelled());
+
+        assert_eq!(gpio.level(MIST_RELAY.gpio_bcm_pin), Some(Level::High));
+    }
+}
@@ -0,0 +1,92 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::Duration;
+
+use crate::error::{ActuatorError, Result};
+use crate::event::ActuatorEvent;
+use crate::relay::{RelaySpec, RelayState};
+use crate::services::actuator_service::ActuatorServiceCmd;
+
+/// Cheap, cloneable access to a running [`super::ActuatorService`].
+#[derive(Clone)]
+pub struct ActuatorServiceHandle {
+    cmd_tx: mpsc::Sender<ActuatorServiceCmd>,
+    events: broadcast::Sender<ActuatorEvent>,
+}
+
+impl ActuatorServiceHandle {
+    pub(crate) fn new(
+        cmd_tx: mpsc::Sender<ActuatorServiceCmd>,
+        events: broadcast::Sender<ActuatorEvent>,
+    ) -> Self {
+        Self { cmd_tx, events }
+    }
+
+    /// Receive the relay state changes published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<ActuatorEvent> {
+        self.events.subscribe()
+    }
+
+    /// State of every relay, `None` once the service stopped.
+    pub async fn states(&self) -> Option<Vec<(RelaySpec, RelayState)>> {
+        let (respond_to, rx) = oneshot::channel();
+        self.cmd_tx
+            .send(ActuatorServiceCmd::States { respond_to })
+            .await
+            .ok()?;
+
+        rx.await.ok()
+    }
+
+    pub async fn turn_on(&self, relay_id: &str) -> Result<RelayState> {
+        self.switch(relay_id, true).await
+    }
+
+    pub async fn turn_off(&self, relay_id: &str) -> Result<RelayState> {
+        self.switch(relay_id, false).await
+    }
+
+    /// Switch a relay, a pulse in progress on it is cancelled.
+    pub async fn switch(&self, relay_id: &str, on: bool) -> Result<RelayState> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(
+            ActuatorServiceCmd::Switch {
+                relay_id: relay_id.to_string(),
+                on,
+                respond_to,
+            },
+            rx,
+        )
+        .await
+    }
+
+    /// Energize a relay for `duration`, e.g. a mist burst.
+    pub async fn pulse(&self, relay_id: &str, duration: Duration) -> Result<RelayState> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(
+            ActuatorServiceCmd::Pulse {
+                relay_id: relay_id.to_string(),
+                duration,
+                respond_to,
+            },
+            rx,
+        )
+        .await
+    }
+
+    async fn request(
+        &self,
+        cmd: ActuatorServiceCmd,
+        rx: oneshot::Receiver<Result<RelayState>>,
+    ) -> Result<RelayState> {
+        self.cmd_tx
+            .send(cmd)
+            .await
+            .map_err(|_| ActuatorError::ServiceStopped)?;
+
+        rx.await.map_err(|_| ActuatorError::ServiceStopped)?
+    }
+}
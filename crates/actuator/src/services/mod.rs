@@ -0,0 +1,11 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+mod actuator_service;
+mod handle;
+mod persistence;
+
+pub use actuator_service::ActuatorService;
+pub use handle::ActuatorServiceHandle;
+pub use persistence::{actuator_record, refusal_details, ActuatorPersistence};
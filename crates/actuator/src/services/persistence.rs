@@ -0,0 +1,165 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::{ActuatorEventRecord, ActuatorRecord, ActuatorStateRecord};
+use arksync_db::ActuatorStore;
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::event::{ActuatorEvent, SwitchReason};
+use crate::relay::{RelaySpec, RelayState};
+use crate::safety::InterlockReason;
+
+/// Subscriber writing the relays and their states to Postgres.
+///
+/// Relays are upserted in the `actuators` table when the service starts,
+/// each switch updates their row and is appended to `actuator_events` with
+/// its reason, as are refused switches.
+pub struct ActuatorPersistence {
+    store: ActuatorStore<'static>,
+    station_knot_id: Uuid,
+    /// Row id of each relay
+    ids: HashMap<&'static str, Uuid>,
+}
+
+impl ActuatorPersistence {
+    pub fn new(store: ActuatorStore<'static>, station_knot_id: Uuid) -> Self {
+        Self {
+            store,
+            station_knot_id,
+            ids: HashMap::new(),
+        }
+    }
+
+    pub async fn run(
+        mut self,
+        relays: Vec<RelaySpec>,
+        mut events: broadcast::Receiver<ActuatorEvent>,
+        shutdown: CancellationToken,
+    ) {
+        for spec in relays {
+            match self
+                .store
+                .upsert(&actuator_record(spec, self.station_knot_id))
+                .await
+            {
+                Ok(id) => {
+                    self.ids.insert(spec.id, id);
+                }
+                Err(err) => tracing::error!(
+                    relay_id = spec.id,
+                    error = format_args!("{err:#}"),
+                    "Failed to register the relay."
+                ),
+            }
+        }
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => self.persist(event).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Actuator persistence lagging behind, actuator events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = shutdown.cancelled() => {
+                    // Keep the fail-safe states applied on shutdown
+                    while let Ok(event) = events.try_recv() {
+                        self.persist(event).await;
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn persist(&self, event: ActuatorEvent) {
+        let Some(id) = self.ids.get(event.relay_id()) else {
+            return;
+        };
+
+        match event {
+            ActuatorEvent::StateChanged { state, reason } => {
+                self.persist_state(*id, state, reason).await;
+            }
+            ActuatorEvent::Refused { relay_id, reason } => {
+                let record = ActuatorEventRecord {
+                    actuator_id: *id,
+                    // Only switching on is ever refused
+                    active: true,
+                    refused: true,
+                    reason: reason.code().to_string(),
+                    details: refusal_details(&reason),
+                    occurred_at: Utc::now(),
+                };
+                if let Err(err) = self.store.record_refusal(&record).await {
+                    tracing::error!(
+                        relay_id,
+                        error = format_args!("{err:#}"),
+                        "Failed to persist the refusal of the relay."
+                    );
+                }
+            }
+        }
+    }
+
+    async fn persist_state(&self, id: Uuid, state: RelayState, reason: SwitchReason) {
+        let record = ActuatorStateRecord {
+            active: state.active,
+            state_since: Utc::now(),
+        };
+        if let Err(err) = self.store.record_state(id, &record, reason.code()).await {
+            tracing::error!(
+                relay_id = state.id,
+                error = format_args!("{err:#}"),
+                "Failed to persist the state of the relay."
+            );
+        }
+    }
+}
+
+/// `details` of a refused switch in `actuator_events`.
+pub fn refusal_details(reason: &InterlockReason) -> Value {
+    match reason {
+        InterlockReason::MinOffTime { remaining } => {
+            json!({ "remaining_ms": remaining.as_millis() as u64 })
+        }
+        InterlockReason::SwitchRateExceeded { max_per_hour } => {
+            json!({ "max_per_hour": max_per_hour })
+        }
+        InterlockReason::ExclusionGroup {
+            group,
+            active_relay,
+        } => json!({ "group": group, "active_relay": active_relay }),
+    }
+}
+
+/// Row of a relay of the knot `station_knot_id`, as written in `actuators`.
+pub fn actuator_record(spec: RelaySpec, station_knot_id: Uuid) -> ActuatorRecord {
+    ActuatorRecord {
+        station_knot_id,
+        // The channel of the board, the pin may be rewired
+        hardware_uid: Some(format!("relay:{}:{}", spec.board, spec.channel)),
+        name: spec.id.to_string(),
+        kind: "relay".to_string(),
+        protocol: "gpio".to_string(),
+        connection: json!({
+            "board": spec.board,
+            "model": spec.model,
+            "channel": spec.channel,
+            "gpio_bcm_pin": spec.gpio_bcm_pin,
+            "active_low": spec.active_low,
+        }),
+        // Relays are released when claimed
+        state: ActuatorStateRecord {
+            active: false,
+            state_since: Utc::now(),
+        },
+    }
+}
@@ -0,0 +1,134 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::{AlertRecord, AlertSeverity};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl From<Severity> for AlertSeverity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Info => AlertSeverity::Info,
+            Severity::Warning => AlertSeverity::Warning,
+            Severity::Critical => AlertSeverity::Critical,
+        }
+    }
+}
+
+impl From<AlertSeverity> for Severity {
+    fn from(severity: AlertSeverity) -> Self {
+        match severity {
+            AlertSeverity::Info => Severity::Info,
+            AlertSeverity::Warning => Severity::Warning,
+            AlertSeverity::Critical => Severity::Critical,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    Firing,
+    /// Someone is on it, the alert stays open until its condition clears
+    Acknowledged,
+    Resolved,
+}
+
+impl AlertStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AlertStatus::Firing => "firing",
+            AlertStatus::Acknowledged => "acknowledged",
+            AlertStatus::Resolved => "resolved",
+        }
+    }
+}
+
+/// An alert raised by a rule for one sensor.
+///
+/// A rule raises at most one open alert per sensor, the alert resolves by
+/// itself once the condition clears.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub id: Uuid,
+    pub rule_id: Uuid,
+    pub rule_name: String,
+    pub sensor_id: Uuid,
+    pub severity: Severity,
+    pub message: String,
+    /// Value that raised the alert, if the condition has one
+    pub value: Option<f64>,
+    pub raised_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub acknowledged_by: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+impl Alert {
+    pub fn status(&self) -> AlertStatus {
+        if self.resolved_at.is_some() {
+            AlertStatus::Resolved
+        } else if self.acknowledged_at.is_some() {
+            AlertStatus::Acknowledged
+        } else {
+            AlertStatus::Firing
+        }
+    }
+}
+
+impl From<&Alert> for AlertRecord {
+    fn from(alert: &Alert) -> Self {
+        Self {
+            id: alert.id,
+            rule_id: alert.rule_id,
+            rule_name: alert.rule_name.clone(),
+            sensor_id: alert.sensor_id,
+            severity: alert.severity.into(),
+            message: alert.message.clone(),
+            value: alert.value,
+            raised_at: alert.raised_at,
+            acknowledged_at: alert.acknowledged_at,
+            acknowledged_by: alert.acknowledged_by.clone(),
+            resolved_at: alert.resolved_at,
+        }
+    }
+}
+
+impl From<AlertRecord> for Alert {
+    fn from(record: AlertRecord) -> Self {
+        Self {
+            id: record.id,
+            rule_id: record.rule_id,
+            rule_name: record.rule_name,
+            sensor_id: record.sensor_id,
+            severity: record.severity.into(),
+            message: record.message,
+            value: record.value,
+            raised_at: record.raised_at,
+            acknowledged_at: record.acknowledged_at,
+            acknowledged_by: record.acknowledged_by,
+            resolved_at: record.resolved_at,
+        }
+    }
+}
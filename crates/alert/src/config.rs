@@ -0,0 +1,57 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_config::ConfigHandler;
+use arksync_sensor::sensor::SensorState;
+use std::sync::LazyLock;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::alert::Severity;
+use crate::notify::ChannelConfig;
+use crate::rule::{AlertRule, Condition};
+
+pub static CONFIG: LazyLock<Config> = LazyLock::new(|| ConfigHandler::new(mpl).load());
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Ids must stay the same across restarts, open alerts are matched back
+    /// to their rule
+    pub rules: Vec<AlertRule>,
+    /// Where the alerts are sent, none by default, the alerts only show in
+    /// the app
+    pub channels: Vec<ChannelConfig>,
+}
+
+fn mpl() -> Config {
+    Config {
+        rules: vec![
+            AlertRule {
+                id: Uuid::from_u128(0x0a1e_0001),
+                name: "Sensor unreachable".to_string(),
+                sensor_id: None,
+                kind: None,
+                condition: Condition::State {
+                    states: vec![SensorState::Unreachable],
+                },
+                severity: Severity::Critical,
+                for_duration: Duration::from_secs(5 * 60),
+                enabled: true,
+            },
+            AlertRule {
+                id: Uuid::from_u128(0x0a1e_0002),
+                name: "Stale sensor data".to_string(),
+                sensor_id: None,
+                kind: None,
+                condition: Condition::Stale {
+                    after: Duration::from_secs(10 * 60),
+                },
+                severity: Severity::Warning,
+                for_duration: Duration::ZERO,
+                enabled: true,
+            },
+        ],
+        channels: Vec::new(),
+    }
+}
@@ -0,0 +1,431 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_sensor::event::SensorEvent;
+use arksync_sensor::sensor::{SensorInfo, SensorKind, SensorName, SensorState};
+use chrono::Utc;
+use std::collections::HashMap;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::alert::Alert;
+use crate::error::{AlertError, Result};
+use crate::event::AlertEvent;
+use crate::rule::{AlertRule, Condition};
+
+/// What the engine knows of a sensor.
+#[derive(Debug)]
+struct SensorView {
+    name: String,
+    kind: SensorKind,
+    state: SensorState,
+    /// Last measurement and when it was taken
+    last: Option<(f64, Instant)>,
+    /// Change per minute between the last two measurements
+    rate: Option<f64>,
+    /// Last measurement, or when the sensor was first seen
+    last_activity: Instant,
+}
+
+impl SensorView {
+    fn new(info: &SensorInfo, now: Instant) -> Self {
+        Self {
+            name: display_name(info),
+            kind: info.kind,
+            state: info.state,
+            last: None,
+            rate: None,
+            last_activity: now,
+        }
+    }
+}
+
+fn display_name(info: &SensorInfo) -> String {
+    match &info.name {
+        SensorName::Named(name) => name.clone(),
+        SensorName::Unnamed => format!("{} sensor {}", info.kind.as_str(), info.id),
+    }
+}
+
+/// Condition of a rule met by a sensor.
+struct Breach {
+    value: Option<f64>,
+    message: String,
+}
+
+impl Condition {
+    fn check(&self, sensor: &SensorView, now: Instant) -> Option<Breach> {
+        match self {
+            Condition::Threshold { above, below } => {
+                let (value, _) = sensor.last?;
+                let message = if above.is_some_and(|above| value > above) {
+                    format!(
+                        "{}: {value:.2} above {}",
+                        sensor.name,
+                        above.unwrap_or_default()
+                    )
+                } else if below.is_some_and(|below| value < below) {
+                    format!(
+                        "{}: {value:.2} below {}",
+                        sensor.name,
+                        below.unwrap_or_default()
+                    )
+                } else {
+                    return None;
+                };
+                Some(Breach {
+                    value: Some(value),
+                    message,
+                })
+            }
+            Condition::RateOfChange { max_per_minute } => {
+                let rate = sensor.rate.filter(|rate| rate.abs() > *max_per_minute)?;
+                Some(Breach {
+                    value: Some(rate),
+                    message: format!(
+                        "{}: changing by {rate:+.2}/min, faster than {max_per_minute}/min",
+                        sensor.name
+                    ),
+                })
+            }
+            Condition::Stale { after } => {
+                let silence = now.saturating_duration_since(sensor.last_activity);
+                (silence >= *after).then(|| Breach {
+                    value: None,
+                    message: format!(
+                        "{}: no measurement for {} s",
+                        sensor.name,
+                        silence.as_secs()
+                    ),
+                })
+            }
+            Condition::State { states } => states.contains(&sensor.state).then(|| Breach {
+                value: None,
+                message: format!("{} is {}", sensor.name, sensor.state.as_str()),
+            }),
+        }
+    }
+}
+
+/// Evaluates the alert rules against the sensor events.
+///
+/// Alerts are deduplicated per rule and sensor: while one is open, the rule
+/// raises nothing more for that sensor. It is resolved as soon as its
+/// condition clears, acknowledged or not.
+#[derive(Debug)]
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    sensors: HashMap<Uuid, SensorView>,
+    /// Since when a condition holds, by rule and sensor, before it raises
+    pending: HashMap<(Uuid, Uuid), Instant>,
+    open: HashMap<(Uuid, Uuid), Alert>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules,
+            sensors: HashMap::new(),
+            pending: HashMap::new(),
+            open: HashMap::new(),
+        }
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Alerts still open, oldest first.
+    pub fn open(&self) -> Vec<Alert> {
+        let mut open: Vec<_> = self.open.values().cloned().collect();
+        open.sort_by_key(|alert| alert.raised_at);
+        open
+    }
+
+    /// Take back the alerts left open by a previous run. They resolve once
+    /// their sensor shows up healthy, or right away when their rule is gone.
+    pub fn restore(&mut self, alerts: Vec<Alert>) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+        for alert in alerts {
+            if self.rules.iter().any(|rule| rule.id == alert.rule_id) {
+                self.open.insert((alert.rule_id, alert.sensor_id), alert);
+            } else {
+                events.push(resolve(alert));
+            }
+        }
+        events
+    }
+
+    pub fn acknowledge(&mut self, id: Uuid, by: &str) -> Result<AlertEvent> {
+        let alert = self
+            .open
+            .values_mut()
+            .find(|alert| alert.id == id)
+            .ok_or(AlertError::UnknownAlert(id))?;
+
+        if alert.acknowledged_at.is_none() {
+            alert.acknowledged_at = Some(Utc::now());
+            alert.acknowledged_by = Some(by.to_string());
+        }
+        Ok(AlertEvent::Acknowledged(alert.clone()))
+    }
+
+    pub fn observe(&mut self, event: &SensorEvent, now: Instant) -> Vec<AlertEvent> {
+        let sensor_id = event.sensor_id();
+        match event {
+            SensorEvent::Discovered(info)
+            | SensorEvent::StateChanged { info, .. }
+            | SensorEvent::Renamed(info) => {
+                let sensor = self
+                    .sensors
+                    .entry(sensor_id)
+                    .or_insert_with(|| SensorView::new(info, now));
+                sensor.name = display_name(info);
+                sensor.state = info.state;
+            }
+            SensorEvent::Measurement(measurement) => {
+                let sensor = self.sensors.entry(sensor_id).or_insert_with(|| SensorView {
+                    name: format!("{} sensor {sensor_id}", measurement.kind.as_str()),
+                    kind: measurement.kind,
+                    state: SensorState::Active,
+                    last: None,
+                    rate: None,
+                    last_activity: now,
+                });
+                if let Some((last, at)) = sensor.last {
+                    let minutes = now.saturating_duration_since(at).as_secs_f64() / 60.0;
+                    if minutes > 0.0 {
+                        sensor.rate = Some((measurement.value - last) / minutes);
+                    }
+                }
+                sensor.last = Some((measurement.value, now));
+                sensor.last_activity = now;
+            }
+            SensorEvent::Removed(_) => return self.forget(sensor_id),
+            SensorEvent::ReadCompleted(_) => return Vec::new(),
+        }
+
+        self.evaluate(sensor_id, now)
+    }
+
+    /// Conditions that depend on time, stale data and durations.
+    pub fn tick(&mut self, now: Instant) -> Vec<AlertEvent> {
+        let sensor_ids: Vec<_> = self.sensors.keys().copied().collect();
+        sensor_ids
+            .into_iter()
+            .flat_map(|sensor_id| self.evaluate(sensor_id, now))
+            .collect()
+    }
+
+    fn evaluate(&mut self, sensor_id: Uuid, now: Instant) -> Vec<AlertEvent> {
+        let Some(sensor) = self.sensors.get(&sensor_id) else {
+            return Vec::new();
+        };
+
+        let mut events = Vec::new();
+        for rule in &self.rules {
+            if !rule.covers(sensor_id, sensor.kind) {
+                continue;
+            }
+
+            let key = (rule.id, sensor_id);
+            let Some(breach) = rule.condition.check(sensor, now) else {
+                self.pending.remove(&key);
+                if let Some(alert) = self.open.remove(&key) {
+                    events.push(resolve(alert));
+                }
+                continue;
+            };
+            if self.open.contains_key(&key) {
+                continue;
+            }
+
+            let since = *self.pending.entry(key).or_insert(now);
+            if now.saturating_duration_since(since) < rule.for_duration {
+                continue;
+            }
+
+            self.pending.remove(&key);
+            let alert = Alert {
+                id: Uuid::new_v4(),
+                rule_id: rule.id,
+                rule_name: rule.name.clone(),
+                sensor_id,
+                severity: rule.severity,
+                message: breach.message,
+                value: breach.value,
+                raised_at: Utc::now(),
+                acknowledged_at: None,
+                acknowledged_by: None,
+                resolved_at: None,
+            };
+            self.open.insert(key, alert.clone());
+            events.push(AlertEvent::Raised(alert));
+        }
+
+        events
+    }
+
+    /// The sensor left the registry, its alerts have nothing left to watch.
+    fn forget(&mut self, sensor_id: Uuid) -> Vec<AlertEvent> {
+        self.sensors.remove(&sensor_id);
+        self.pending.retain(|(_, id), _| *id != sensor_id);
+
+        let keys: Vec<_> = self
+            .open
+            .keys()
+            .filter(|(_, id)| *id == sensor_id)
+            .copied()
+            .collect();
+        keys.into_iter()
+            .filter_map(|key| self.open.remove(&key))
+            .map(resolve)
+            .collect()
+    }
+}
+
+fn resolve(mut alert: Alert) -> AlertEvent {
+    alert.resolved_at = Some(Utc::now());
+    AlertEvent::Resolved(alert)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arksync_sensor::event::Measurement;
+    use arksync_sensor::i2c_bus::I2cConnection;
+    use arksync_sensor::sensor::{SensorConnection, SensorStateReason};
+    use tokio::time::Duration;
+
+    use crate::alert::{AlertStatus, Severity};
+
+    const SENSOR: Uuid = Uuid::from_u128(1);
+
+    fn rule(condition: Condition, for_duration: Duration) -> AlertRule {
+        AlertRule {
+            id: Uuid::from_u128(10),
+            name: "rule".to_string(),
+            sensor_id: Some(SENSOR),
+            kind: None,
+            condition,
+            severity: Severity::Critical,
+            for_duration,
+            enabled: true,
+        }
+    }
+
+    fn measurement(value: f64) -> SensorEvent {
+        SensorEvent::Measurement(Measurement {
+            sensor_id: SENSOR,
+            kind: SensorKind::Temperature,
+            value,
+            time: Utc::now(),
+        })
+    }
+
+    fn raised(events: &[AlertEvent]) -> usize {
+        events
+            .iter()
+            .filter(|event| matches!(event, AlertEvent::Raised(_)))
+            .count()
+    }
+
+    #[test]
+    fn a_threshold_alert_is_raised_once_and_resolves() {
+        let threshold = Condition::Threshold {
+            above: Some(30.0),
+            below: None,
+        };
+        let mut engine = AlertEngine::new(vec![rule(threshold, Duration::ZERO)]);
+        let now = Instant::now();
+
+        assert_eq!(raised(&engine.observe(&measurement(31.0), now)), 1);
+        assert_eq!(raised(&engine.observe(&measurement(32.0), now)), 0);
+
+        let id = engine.open()[0].id;
+        let acknowledged = engine.acknowledge(id, "admin").unwrap();
+        assert_eq!(acknowledged.alert().status(), AlertStatus::Acknowledged);
+
+        let events = engine.observe(&measurement(29.0), now);
+        assert!(matches!(&events[..], [AlertEvent::Resolved(alert)] if alert.id == id));
+        assert!(engine.open().is_empty());
+        assert_eq!(
+            engine.acknowledge(id, "admin"),
+            Err(AlertError::UnknownAlert(id))
+        );
+    }
+
+    #[test]
+    fn the_rate_of_change_is_per_minute() {
+        let rate = Condition::RateOfChange {
+            max_per_minute: 1.0,
+        };
+        let mut engine = AlertEngine::new(vec![rule(rate, Duration::ZERO)]);
+        let now = Instant::now();
+
+        engine.observe(&measurement(20.0), now);
+        let slow = now + Duration::from_secs(60);
+        assert_eq!(raised(&engine.observe(&measurement(20.5), slow)), 0);
+        let fast = slow + Duration::from_secs(30);
+        assert_eq!(raised(&engine.observe(&measurement(19.5), fast)), 1);
+        assert_eq!(engine.open()[0].value, Some(-2.0));
+    }
+
+    #[test]
+    fn stale_data_and_states_wait_for_their_duration() {
+        let unreachable = Condition::State {
+            states: vec![SensorState::Unreachable],
+        };
+        let mut state_rule = rule(unreachable, Duration::from_secs(300));
+        state_rule.id = Uuid::from_u128(11);
+        let stale = rule(
+            Condition::Stale {
+                after: Duration::from_secs(600),
+            },
+            Duration::ZERO,
+        );
+        let mut engine = AlertEngine::new(vec![state_rule, stale]);
+        let now = Instant::now();
+        let at = |secs| now + Duration::from_secs(secs);
+
+        engine.observe(&measurement(20.0), now);
+        let mut info = SensorInfo {
+            id: SENSOR,
+            hardware_uid: String::new(),
+            kind: SensorKind::Temperature,
+            firmware: 0.0,
+            name: SensorName::Named("RTD".to_string()),
+            state: SensorState::Unreachable,
+            state_reason: SensorStateReason::NoRecentActivity,
+            state_since: Utc::now(),
+            last_activity: Utc::now(),
+            consecutive_failures: 3,
+            connection: SensorConnection::I2c(I2cConnection {
+                bus: 1,
+                address: 102,
+            }),
+        };
+        let unreachable = SensorEvent::StateChanged {
+            previous: SensorState::Active,
+            info: info.clone(),
+        };
+        assert_eq!(raised(&engine.observe(&unreachable, at(1))), 0);
+        assert_eq!(raised(&engine.tick(at(300))), 0);
+        let events = engine.tick(at(301));
+        assert!(
+            matches!(&events[..], [AlertEvent::Raised(alert)] if alert.message == "RTD is unreachable")
+        );
+
+        assert_eq!(raised(&engine.tick(at(600))), 1);
+
+        info.state = SensorState::Active;
+        let recovered = SensorEvent::StateChanged {
+            previous: SensorState::Unreachable,
+            info,
+        };
+        engine.observe(&recovered, at(601));
+        engine.observe(&measurement(20.0), at(602));
+        assert!(engine.open().is_empty());
+    }
+}
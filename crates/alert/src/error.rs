@@ -0,0 +1,35 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::error::Error;
+use std::fmt;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlertError {
+    /// The rule can never fire or always fires, e.g. an empty threshold.
+    InvalidRule(String),
+    /// No open alert with this id, it may be resolved already.
+    UnknownAlert(Uuid),
+    /// The notification channel can't be set up, e.g. a broken template.
+    InvalidChannel(String),
+    ServiceStopped,
+}
+
+impl fmt::Display for AlertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertError::InvalidRule(details) => write!(f, "Invalid alert rule: {details}"),
+            AlertError::UnknownAlert(id) => write!(f, "No open alert {id}"),
+            AlertError::InvalidChannel(details) => {
+                write!(f, "Invalid notification channel: {details}")
+            }
+            AlertError::ServiceStopped => write!(f, "Alert service stopped"),
+        }
+    }
+}
+
+impl Error for AlertError {}
+
+pub type Result<T> = std::result::Result<T, AlertError>;
@@ -0,0 +1,27 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use serde::Serialize;
+
+use crate::alert::Alert;
+
+/// Events published by the alert service, with the alert as it is after
+/// the change.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "alert", rename_all = "camelCase")]
+pub enum AlertEvent {
+    Raised(Alert),
+    Acknowledged(Alert),
+    Resolved(Alert),
+}
+
+impl AlertEvent {
+    pub fn alert(&self) -> &Alert {
+        match self {
+            AlertEvent::Raised(alert)
+            | AlertEvent::Acknowledged(alert)
+            | AlertEvent::Resolved(alert) => alert,
+        }
+    }
+}
@@ -0,0 +1,14 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+pub mod alert;
+mod config;
+pub mod engine;
+pub mod error;
+pub mod event;
+pub mod notify;
+pub mod rule;
+pub mod services;
+
+pub use config::{Config, CONFIG};
@@ -0,0 +1,139 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use std::time::Duration;
+
+use crate::alert::Severity;
+use crate::error::{AlertError, Result};
+use crate::notify::{CommandConfig, PushConfig, SmtpConfig, Template, WebhookConfig};
+
+pub const DEFAULT_TITLE: &str = "[{{severity}}] {{rule}} {{event}}";
+pub const DEFAULT_BODY: &str = "{{message}}\n\nSensor {{sensor_id}}, raised at {{raised_at}}.";
+
+#[derive(Clone, Debug)]
+pub enum TransportConfig {
+    Email(SmtpConfig),
+    Webhook(WebhookConfig),
+    Push(PushConfig),
+    Command(CommandConfig),
+}
+
+/// At most `max` notifications every `per`, the others are dropped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max: u32,
+    pub per: Duration,
+}
+
+/// Daily period when only the alerts at `bypass` severity or above go out,
+/// e.g. 22:00 to 07:00 letting through the critical ones.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    /// Ends the next day when before `start`
+    pub end: NaiveTime,
+    pub time_zone: Tz,
+    pub bypass: Severity,
+}
+
+impl QuietHours {
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let time = at.with_timezone(&self.time_zone).time();
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// Whether an alert of `severity` is kept quiet at `at`.
+    pub fn holds_back(&self, severity: Severity, at: DateTime<Utc>) -> bool {
+        severity < self.bypass && self.contains(at)
+    }
+}
+
+/// Where and when to send the alerts.
+#[derive(Clone, Debug)]
+pub struct ChannelConfig {
+    /// Names the channel in the logs
+    pub name: String,
+    pub transport: TransportConfig,
+    /// Title, or subject, see [`Template`] for the placeholders
+    pub title: String,
+    pub body: String,
+    /// Alerts below are not sent on this channel
+    pub min_severity: Severity,
+    pub rate_limit: Option<RateLimit>,
+    pub quiet_hours: Option<QuietHours>,
+    /// Send only the alerts still firing, unacknowledged, after this delay,
+    /// e.g. a phone call once the email went unanswered for 15 minutes
+    pub escalate_after: Option<Duration>,
+    /// Also tell when an alert sent on this channel resolves
+    pub send_resolved: bool,
+}
+
+impl ChannelConfig {
+    /// Templates of the title and body, fails on an unknown placeholder.
+    pub fn templates(&self) -> Result<(Template, Template)> {
+        Ok((Template::parse(&self.title)?, Template::parse(&self.body)?))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |details: &str| {
+            Err(AlertError::InvalidChannel(format!(
+                "'{}': {details}",
+                self.name
+            )))
+        };
+
+        if self
+            .rate_limit
+            .is_some_and(|limit| limit.max == 0 || limit.per.is_zero())
+        {
+            return invalid("the rate limit lets nothing through");
+        }
+        if self
+            .quiet_hours
+            .is_some_and(|quiet_hours| quiet_hours.start == quiet_hours.end)
+        {
+            return invalid("the quiet hours start and end at the same time");
+        }
+        match &self.transport {
+            TransportConfig::Email(smtp) if smtp.to.is_empty() => {
+                return invalid("the email has no recipient")
+            }
+            TransportConfig::Command(command) if command.program.as_os_str().is_empty() => {
+                return invalid("no command to run")
+            }
+            _ => {}
+        }
+
+        self.templates().map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn quiet_hours_span_midnight() {
+        let night = QuietHours {
+            start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            time_zone: chrono_tz::Europe::Paris,
+            bypass: Severity::Critical,
+        };
+        // 23:30 and 12:00 in Paris, in summer
+        let late = Utc.with_ymd_and_hms(2026, 7, 1, 21, 30, 0).unwrap();
+        let noon = Utc.with_ymd_and_hms(2026, 7, 1, 10, 0, 0).unwrap();
+
+        assert!(night.holds_back(Severity::Warning, late));
+        assert!(!night.holds_back(Severity::Critical, late));
+        assert!(!night.holds_back(Severity::Warning, noon));
+    }
+}
@@ -0,0 +1,99 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+use crate::notify::{Notification, Notifier, NotifyError};
+
+#[derive(Clone, Debug)]
+pub struct CommandConfig {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    /// The command is killed past this delay
+    pub timeout: Duration,
+}
+
+/// Runs a local command for every notification, e.g. a script driving a
+/// buzzer or a GSM modem.
+///
+/// The body is written on its standard input, the rest is in `ARKSYNC_ALERT_*`
+/// environment variables.
+pub struct CommandNotifier {
+    config: CommandConfig,
+}
+
+impl CommandNotifier {
+    pub fn new(config: CommandConfig) -> Self {
+        Self { config }
+    }
+
+    async fn run(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let alert = &notification.alert;
+        let mut child = Command::new(&self.config.program)
+            .args(&self.config.args)
+            .env("ARKSYNC_ALERT_EVENT", notification.reason.as_str())
+            .env("ARKSYNC_ALERT_TITLE", &notification.title)
+            .env("ARKSYNC_ALERT_ID", alert.id.to_string())
+            .env("ARKSYNC_ALERT_RULE", &alert.rule_name)
+            .env("ARKSYNC_ALERT_SEVERITY", alert.severity.as_str())
+            .env("ARKSYNC_ALERT_SENSOR_ID", alert.sensor_id.to_string())
+            .env(
+                "ARKSYNC_ALERT_VALUE",
+                alert
+                    .value
+                    .map(|value| value.to_string())
+                    .unwrap_or_default(),
+            )
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| {
+                NotifyError(format!(
+                    "can't run {}: {err}",
+                    self.config.program.display()
+                ))
+            })?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            // A command ignoring its input closes it early, that's fine
+            let _ = stdin.write_all(notification.body.as_bytes()).await;
+        }
+
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|err| NotifyError(err.to_string()))?;
+        if !output.status.success() {
+            return Err(NotifyError(format!(
+                "{} exited with {}: {}",
+                self.config.program.display(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Notifier for CommandNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        timeout(self.config.timeout, self.run(notification))
+            .await
+            .map_err(|_| {
+                NotifyError(format!(
+                    "{} timed out after {:?}",
+                    self.config.program.display(),
+                    self.config.timeout
+                ))
+            })?
+    }
+}
@@ -0,0 +1,351 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::alert::Alert;
+use crate::error::Result;
+use crate::event::AlertEvent;
+use crate::notify::{ChannelConfig, Notification, Reason, Template};
+
+struct Channel {
+    config: ChannelConfig,
+    title: Template,
+    body: Template,
+    /// When the notifications within the rate limit window went out
+    sent: VecDeque<Instant>,
+}
+
+impl Channel {
+    /// Take a slot in the rate limit, false when none is left.
+    fn acquire(&mut self, now: Instant) -> bool {
+        let Some(limit) = self.config.rate_limit else {
+            return true;
+        };
+
+        while self
+            .sent
+            .front()
+            .is_some_and(|sent| now.saturating_duration_since(*sent) >= limit.per)
+        {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= limit.max as usize {
+            return false;
+        }
+        self.sent.push_back(now);
+        true
+    }
+}
+
+struct Escalation {
+    alert: Alert,
+    channel: usize,
+    due: Instant,
+}
+
+/// A notification to send on the channel at `channel` in the configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delivery {
+    pub channel: usize,
+    pub notification: Notification,
+}
+
+/// Decides which channels hear about the alert events, without sending
+/// anything itself.
+pub struct Dispatcher {
+    channels: Vec<Channel>,
+    escalations: Vec<Escalation>,
+    /// Channels told about each open alert, to tell them it resolved
+    notified: HashMap<Uuid, Vec<usize>>,
+}
+
+impl Dispatcher {
+    /// Fails if a channel is invalid.
+    pub fn new(channels: Vec<ChannelConfig>) -> Result<Self> {
+        let channels = channels
+            .into_iter()
+            .map(|config| {
+                config.validate()?;
+                let (title, body) = config.templates()?;
+                Ok(Channel {
+                    config,
+                    title,
+                    body,
+                    sent: VecDeque::new(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            channels,
+            escalations: Vec::new(),
+            notified: HashMap::new(),
+        })
+    }
+
+    pub fn channel(&self, index: usize) -> &ChannelConfig {
+        &self.channels[index].config
+    }
+
+    pub fn dispatch(
+        &mut self,
+        event: &AlertEvent,
+        now: Instant,
+        at: DateTime<Utc>,
+    ) -> Vec<Delivery> {
+        let mut deliveries = Vec::new();
+
+        match event {
+            AlertEvent::Raised(alert) => {
+                for index in 0..self.channels.len() {
+                    let config = &self.channels[index].config;
+                    if alert.severity < config.min_severity {
+                        continue;
+                    }
+                    match config.escalate_after {
+                        Some(after) => self.escalations.push(Escalation {
+                            alert: alert.clone(),
+                            channel: index,
+                            due: now + after,
+                        }),
+                        None => {
+                            deliveries.extend(self.notify(index, Reason::Raised, alert, now, at))
+                        }
+                    }
+                }
+            }
+            // Someone is on it, no need to wake anyone else
+            AlertEvent::Acknowledged(alert) => {
+                self.escalations
+                    .retain(|escalation| escalation.alert.id != alert.id);
+            }
+            AlertEvent::Resolved(alert) => {
+                self.escalations
+                    .retain(|escalation| escalation.alert.id != alert.id);
+                for index in self.notified.remove(&alert.id).unwrap_or_default() {
+                    if self.channels[index].config.send_resolved {
+                        deliveries.extend(self.notify(index, Reason::Resolved, alert, now, at));
+                    }
+                }
+            }
+        }
+
+        deliveries
+    }
+
+    /// Escalate the alerts left firing past the delay of their channel.
+    pub fn tick(&mut self, now: Instant, at: DateTime<Utc>) -> Vec<Delivery> {
+        let (due, pending) = std::mem::take(&mut self.escalations)
+            .into_iter()
+            .partition::<Vec<_>, _>(|escalation| escalation.due <= now);
+        self.escalations = pending;
+
+        due.into_iter()
+            .filter_map(|escalation| {
+                self.notify(
+                    escalation.channel,
+                    Reason::Escalated,
+                    &escalation.alert,
+                    now,
+                    at,
+                )
+            })
+            .collect()
+    }
+
+    fn notify(
+        &mut self,
+        index: usize,
+        reason: Reason,
+        alert: &Alert,
+        now: Instant,
+        at: DateTime<Utc>,
+    ) -> Option<Delivery> {
+        let channel = &mut self.channels[index];
+        if channel
+            .config
+            .quiet_hours
+            .is_some_and(|quiet_hours| quiet_hours.holds_back(alert.severity, at))
+        {
+            tracing::debug!(
+                rule = alert.rule_name,
+                channel = channel.config.name,
+                "Alert held back by the quiet hours."
+            );
+            return None;
+        }
+        if !channel.acquire(now) {
+            tracing::warn!(
+                rule = alert.rule_name,
+                channel = channel.config.name,
+                "Alert dropped, the rate limit is reached."
+            );
+            return None;
+        }
+
+        let notification = Notification {
+            reason,
+            title: channel.title.render(reason, alert),
+            body: channel.body.render(reason, alert),
+            alert: alert.clone(),
+        };
+        if reason != Reason::Resolved {
+            self.notified.entry(alert.id).or_default().push(index);
+        }
+
+        Some(Delivery {
+            channel: index,
+            notification,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveTime, TimeZone};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use crate::alert::Severity;
+    use crate::notify::{
+        CommandConfig, QuietHours, RateLimit, TransportConfig, DEFAULT_BODY, DEFAULT_TITLE,
+    };
+
+    fn channel(name: &str) -> ChannelConfig {
+        ChannelConfig {
+            name: name.to_string(),
+            transport: TransportConfig::Command(CommandConfig {
+                program: PathBuf::from("true"),
+                args: Vec::new(),
+                timeout: Duration::from_secs(1),
+            }),
+            title: DEFAULT_TITLE.to_string(),
+            body: DEFAULT_BODY.to_string(),
+            min_severity: Severity::Info,
+            rate_limit: None,
+            quiet_hours: None,
+            escalate_after: None,
+            send_resolved: false,
+        }
+    }
+
+    fn alert(severity: Severity) -> Alert {
+        Alert {
+            id: Uuid::new_v4(),
+            rule_id: Uuid::new_v4(),
+            rule_name: "Heater failure".to_string(),
+            sensor_id: Uuid::new_v4(),
+            severity,
+            message: "water at 18.00".to_string(),
+            value: Some(18.0),
+            raised_at: Utc::now(),
+            acknowledged_at: None,
+            acknowledged_by: None,
+            resolved_at: None,
+        }
+    }
+
+    fn noon() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 7, 1, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn channels_filter_by_severity_and_rate_limit() {
+        let email = ChannelConfig {
+            rate_limit: Some(RateLimit {
+                max: 2,
+                per: Duration::from_secs(60),
+            }),
+            ..channel("email")
+        };
+        let pager = ChannelConfig {
+            min_severity: Severity::Critical,
+            ..channel("pager")
+        };
+        let mut dispatcher = Dispatcher::new(vec![email, pager]).unwrap();
+        let now = Instant::now();
+
+        let raise = |dispatcher: &mut Dispatcher, severity, now| {
+            dispatcher
+                .dispatch(&AlertEvent::Raised(alert(severity)), now, noon())
+                .into_iter()
+                .map(|delivery| delivery.channel)
+                .collect::<Vec<usize>>()
+        };
+        assert_eq!(raise(&mut dispatcher, Severity::Critical, now), vec![0, 1]);
+        assert_eq!(raise(&mut dispatcher, Severity::Warning, now), vec![0]);
+        assert_eq!(
+            raise(&mut dispatcher, Severity::Warning, now),
+            Vec::<usize>::new()
+        );
+        let later = now + Duration::from_secs(60);
+        assert_eq!(raise(&mut dispatcher, Severity::Warning, later), vec![0]);
+    }
+
+    #[test]
+    fn quiet_hours_hold_back_the_minor_alerts() {
+        let quiet = ChannelConfig {
+            quiet_hours: Some(QuietHours {
+                start: NaiveTime::from_hms_opt(11, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(13, 0, 0).unwrap(),
+                time_zone: chrono_tz::UTC,
+                bypass: Severity::Critical,
+            }),
+            ..channel("quiet")
+        };
+        let mut dispatcher = Dispatcher::new(vec![quiet]).unwrap();
+        let now = Instant::now();
+
+        let warning = AlertEvent::Raised(alert(Severity::Warning));
+        assert!(dispatcher.dispatch(&warning, now, noon()).is_empty());
+        let critical = AlertEvent::Raised(alert(Severity::Critical));
+        assert_eq!(dispatcher.dispatch(&critical, now, noon()).len(), 1);
+    }
+
+    #[test]
+    fn unacknowledged_alerts_escalate_then_resolve() {
+        let email = ChannelConfig {
+            send_resolved: true,
+            ..channel("email")
+        };
+        let phone = ChannelConfig {
+            escalate_after: Some(Duration::from_secs(15 * 60)),
+            ..channel("phone")
+        };
+        let mut dispatcher = Dispatcher::new(vec![email, phone]).unwrap();
+        let now = Instant::now();
+
+        let acknowledged = alert(Severity::Critical);
+        dispatcher.dispatch(&AlertEvent::Raised(acknowledged.clone()), now, noon());
+        dispatcher.dispatch(&AlertEvent::Acknowledged(acknowledged), now, noon());
+
+        let ignored = alert(Severity::Critical);
+        let deliveries = dispatcher.dispatch(&AlertEvent::Raised(ignored.clone()), now, noon());
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].notification.reason, Reason::Raised);
+        assert_eq!(
+            deliveries[0].notification.title,
+            "[critical] Heater failure raised"
+        );
+
+        assert!(dispatcher
+            .tick(now + Duration::from_secs(14 * 60), noon())
+            .is_empty());
+        let deliveries = dispatcher.tick(now + Duration::from_secs(15 * 60), noon());
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].channel, 1);
+        assert_eq!(deliveries[0].notification.reason, Reason::Escalated);
+
+        // Only the channel asking for it hears about the resolution
+        let deliveries = dispatcher.dispatch(&AlertEvent::Resolved(ignored), now, noon());
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].channel, 0);
+        assert_eq!(deliveries[0].notification.reason, Reason::Resolved);
+    }
+}
@@ -0,0 +1,262 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use lettre::message::header::{ContentType, HeaderName, HeaderValue};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::time::Duration;
+use tokio::time::timeout;
+
+use crate::notify::{Notification, Notifier, NotifyError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// Plain text, only for a relay on the same host or network
+    None,
+    /// Upgraded with `STARTTLS`, usually on port 587
+    StartTls,
+    /// TLS from the start, usually on port 465
+    Tls,
+}
+
+#[derive(Clone, Debug)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    /// Logs in when set
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    /// Whole exchange with the server, the mail is given up past it
+    pub timeout: Duration,
+}
+
+/// Sends the notifications by email through an SMTP server.
+///
+/// Certificates are checked against the system trust store.
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    host: String,
+    timeout: Duration,
+}
+
+impl EmailNotifier {
+    pub fn new(config: SmtpConfig) -> Result<Self, NotifyError> {
+        let builder = match config.security {
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+            }
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                    .map_err(|err| NotifyError(format!("TLS setup failed: {err}")))?
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)
+                .map_err(|err| NotifyError(format!("TLS setup failed: {err}")))?,
+        };
+        let mut builder = builder.port(config.port).timeout(Some(config.timeout));
+        if let Some(username) = &config.username {
+            let password = config.password.clone().unwrap_or_default();
+            builder = builder.credentials(Credentials::new(username.clone(), password));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: mailbox(&config.from)?,
+            to: config
+                .to
+                .iter()
+                .map(|to| mailbox(to))
+                .collect::<Result<_, _>>()?,
+            host: config.host,
+            timeout: config.timeout,
+        })
+    }
+
+    fn message(&self, notification: &Notification) -> Result<Message, NotifyError> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(single_line(&notification.title))
+            .header(ContentType::TEXT_PLAIN)
+            .raw_header(HeaderValue::new(
+                HeaderName::new_from_ascii_str("X-ArkSync-Alert"),
+                notification.alert.id.to_string(),
+            ));
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+
+        builder
+            .body(notification.body.clone())
+            .map_err(|err| NotifyError(format!("invalid mail: {err}")))
+    }
+}
+
+impl Notifier for EmailNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let message = self.message(notification)?;
+        timeout(self.timeout, self.transport.send(message))
+            .await
+            .map_err(|_| {
+                NotifyError(format!(
+                    "no answer from {} after {:?}",
+                    self.host, self.timeout
+                ))
+            })?
+            .map_err(|err| NotifyError(err.to_string()))?;
+
+        Ok(())
+    }
+}
+
+fn mailbox(address: &str) -> Result<Mailbox, NotifyError> {
+    address
+        .parse()
+        .map_err(|err| NotifyError(format!("invalid address '{address}': {err}")))
+}
+
+/// A rendered value put on one line, line breaks would start new headers.
+fn single_line(value: &str) -> String {
+    value
+        .split(['\r', '\n'])
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+
+    use crate::alert::{Alert, Severity};
+    use crate::notify::Reason;
+
+    /// Accepts one mail, returns the commands and the mail received.
+    async fn smtp_server(listener: TcpListener) -> (Vec<String>, String) {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        let mut commands = Vec::new();
+        let mut mail = String::new();
+
+        stream
+            .get_mut()
+            .write_all(b"220 stand-in\r\n")
+            .await
+            .unwrap();
+        loop {
+            let mut line = String::new();
+            if stream.read_line(&mut line).await.unwrap() == 0 {
+                break;
+            }
+            let command = line.trim_end().to_string();
+            let reply: &[u8] = if command.starts_with("EHLO") {
+                b"250-stand-in\r\n250 AUTH PLAIN\r\n"
+            } else if command.starts_with("AUTH") {
+                b"235 ok\r\n"
+            } else if command == "DATA" {
+                b"354 go ahead\r\n"
+            } else if command == "QUIT" {
+                b"221 bye\r\n"
+            } else {
+                b"250 ok\r\n"
+            };
+            commands.push(command.clone());
+            stream.get_mut().write_all(reply).await.unwrap();
+
+            if command == "DATA" {
+                loop {
+                    let mut line = String::new();
+                    stream.read_line(&mut line).await.unwrap();
+                    if line == ".\r\n" {
+                        break;
+                    }
+                    mail.push_str(&line);
+                }
+                stream.get_mut().write_all(b"250 queued\r\n").await.unwrap();
+            }
+        }
+
+        (commands, mail)
+    }
+
+    /// Sends `title` and `body` through a stand-in server.
+    async fn send(title: &str, body: &str) -> (Vec<String>, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(smtp_server(listener));
+
+        let notifier = EmailNotifier::new(SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::None,
+            username: Some("station".to_string()),
+            password: Some("secret".to_string()),
+            from: "station@arksync.local".to_string(),
+            to: vec!["keeper@example.com".to_string()],
+            timeout: Duration::from_secs(5),
+        })
+        .unwrap();
+        let notification = Notification {
+            reason: Reason::Raised,
+            title: title.to_string(),
+            body: body.to_string(),
+            alert: Alert {
+                id: Uuid::new_v4(),
+                rule_id: Uuid::new_v4(),
+                rule_name: "Heater failure".to_string(),
+                sensor_id: Uuid::new_v4(),
+                severity: Severity::Critical,
+                message: "Water at 18 °C".to_string(),
+                value: Some(18.0),
+                raised_at: Utc::now(),
+                acknowledged_at: None,
+                acknowledged_by: None,
+                resolved_at: None,
+            },
+        };
+        notifier.send(&notification).await.unwrap();
+
+        server.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn mails_go_through_the_smtp_server() {
+        let (commands, mail) = send("Heater failure", "Water at 18 C\n.\nCheck the heater.").await;
+
+        assert!(commands[0].starts_with("EHLO "));
+        // `\0station\0secret`
+        assert_eq!(commands[1], "AUTH PLAIN AHN0YXRpb24Ac2VjcmV0");
+        assert_eq!(commands[2], "MAIL FROM:<station@arksync.local>");
+        assert_eq!(commands[3], "RCPT TO:<keeper@example.com>");
+        assert_eq!(commands[4], "DATA");
+        assert!(mail.contains("Subject: Heater failure\r\n"));
+        assert!(mail.contains("\r\nWater at 18 C\r\n..\r\nCheck the heater.\r\n"));
+    }
+
+    #[tokio::test]
+    async fn line_breaks_cant_add_headers() {
+        let (_, mail) = send("Heater\r\nBcc: intruder@example.com", "Check the heater.").await;
+
+        let (headers, _) = mail.split_once("\r\n\r\n").unwrap();
+        assert!(!headers.lines().any(|line| line.starts_with("Bcc:")));
+        assert!(headers.contains("Subject: Heater Bcc: intruder@example.com\r\n"));
+    }
+
+    #[test]
+    fn header_values_stay_on_one_line() {
+        assert_eq!(single_line("Heater"), "Heater");
+        assert_eq!(
+            single_line("Heater\r\nBcc: intruder@example.com\n"),
+            "Heater Bcc: intruder@example.com"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+mod channel;
+mod command;
+mod dispatcher;
+mod email;
+mod push;
+mod template;
+mod webhook;
+
+pub use channel::{
+    ChannelConfig, QuietHours, RateLimit, TransportConfig, DEFAULT_BODY, DEFAULT_TITLE,
+};
+pub use command::{CommandConfig, CommandNotifier};
+pub use dispatcher::{Delivery, Dispatcher};
+pub use email::{EmailNotifier, SmtpConfig, SmtpSecurity};
+pub use push::{PushConfig, PushNotifier, PushService};
+pub use template::Template;
+pub use webhook::{WebhookConfig, WebhookNotifier};
+
+use reqwest::Response;
+use serde::Serialize;
+use std::fmt;
+use std::future::Future;
+
+use crate::alert::Alert;
+
+/// Why a channel is notified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    Raised,
+    /// Still firing and unacknowledged after the delay of the channel
+    Escalated,
+    Resolved,
+}
+
+impl Reason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Reason::Raised => "raised",
+            Reason::Escalated => "escalated",
+            Reason::Resolved => "resolved",
+        }
+    }
+}
+
+/// A notification rendered for one channel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notification {
+    pub reason: Reason,
+    pub title: String,
+    pub body: String,
+    pub alert: Alert,
+}
+
+#[derive(Debug)]
+pub struct NotifyError(pub String);
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Notification failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for NotifyError {}
+
+/// Delivers the notifications of a channel.
+pub trait Notifier: Send + Sync {
+    fn send(
+        &self,
+        notification: &Notification,
+    ) -> impl Future<Output = Result<(), NotifyError>> + Send;
+}
+
+/// One of the built-in notifiers.
+pub enum Transport {
+    /// Boxed, the SMTP transport is much larger than the other notifiers
+    Email(Box<EmailNotifier>),
+    Webhook(WebhookNotifier),
+    Push(PushNotifier),
+    Command(CommandNotifier),
+}
+
+impl Transport {
+    pub fn new(config: &TransportConfig) -> Result<Self, NotifyError> {
+        let transport = match config {
+            TransportConfig::Email(config) => {
+                Transport::Email(Box::new(EmailNotifier::new(config.clone())?))
+            }
+            TransportConfig::Webhook(config) => {
+                Transport::Webhook(WebhookNotifier::new(config.clone())?)
+            }
+            TransportConfig::Push(config) => Transport::Push(PushNotifier::new(config.clone())?),
+            TransportConfig::Command(config) => {
+                Transport::Command(CommandNotifier::new(config.clone()))
+            }
+        };
+        Ok(transport)
+    }
+}
+
+impl Notifier for Transport {
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        match self {
+            Transport::Email(notifier) => notifier.send(notification).await,
+            Transport::Webhook(notifier) => notifier.send(notification).await,
+            Transport::Push(notifier) => notifier.send(notification).await,
+            Transport::Command(notifier) => notifier.send(notification).await,
+        }
+    }
+}
+
+/// Turn an HTTP answer other than a success into an error.
+async fn check_response(response: Response) -> Result<(), NotifyError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    Err(NotifyError(format!("server answered {status}: {body}")))
+}
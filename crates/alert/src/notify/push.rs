@@ -0,0 +1,102 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use reqwest::Client;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::alert::Severity;
+use crate::notify::{check_response, Notification, Notifier, NotifyError, Reason};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PushService {
+    /// ntfy, messages published to a topic
+    Ntfy { topic: String },
+    /// Gotify, messages posted for an application
+    Gotify,
+}
+
+#[derive(Clone, Debug)]
+pub struct PushConfig {
+    /// Base url of the server, e.g. `https://ntfy.sh`
+    pub url: String,
+    pub service: PushService,
+    /// ntfy access token, or Gotify application token
+    pub token: Option<String>,
+}
+
+/// Pushes the notifications to phones through a ntfy or Gotify server.
+pub struct PushNotifier {
+    client: Client,
+    config: PushConfig,
+}
+
+impl PushNotifier {
+    pub fn new(config: PushConfig) -> Result<Self, NotifyError> {
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|err| NotifyError(err.to_string()))?;
+
+        Ok(Self { client, config })
+    }
+}
+
+impl Notifier for PushNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let url = self.config.url.trim_end_matches('/');
+        let severity = notification.alert.severity;
+        let resolved = notification.reason == Reason::Resolved;
+
+        let request = match &self.config.service {
+            // Published as JSON, headers can't hold a non-ASCII title
+            PushService::Ntfy { topic } => {
+                let priority = match severity {
+                    _ if resolved => 3,
+                    Severity::Critical => 5,
+                    Severity::Warning => 4,
+                    Severity::Info => 3,
+                };
+                let request = self.client.post(url).json(&json!({
+                    "topic": topic,
+                    "title": notification.title,
+                    "message": notification.body,
+                    "priority": priority,
+                    "tags": [severity.as_str(), notification.reason.as_str()],
+                }));
+                match &self.config.token {
+                    Some(token) => request.bearer_auth(token),
+                    None => request,
+                }
+            }
+            PushService::Gotify => {
+                let priority = match severity {
+                    _ if resolved => 2,
+                    Severity::Critical => 8,
+                    Severity::Warning => 5,
+                    Severity::Info => 2,
+                };
+                self.client
+                    .post(format!("{url}/message"))
+                    .header(
+                        "X-Gotify-Key",
+                        self.config.token.as_deref().unwrap_or_default(),
+                    )
+                    .json(&json!({
+                        "title": notification.title,
+                        "message": notification.body,
+                        "priority": priority,
+                    }))
+            }
+        };
+
+        let response = request
+            .send()
+            .await
+            .map_err(|err| NotifyError(err.to_string()))?;
+        check_response(response).await
+    }
+}
@@ -0,0 +1,153 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::alert::Alert;
+use crate::error::{AlertError, Result};
+use crate::notify::Reason;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Event,
+    Rule,
+    Severity,
+    Status,
+    Message,
+    Value,
+    SensorId,
+    AlertId,
+    RaisedAt,
+    AcknowledgedBy,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        let field = match name {
+            "event" => Field::Event,
+            "rule" => Field::Rule,
+            "severity" => Field::Severity,
+            "status" => Field::Status,
+            "message" => Field::Message,
+            "value" => Field::Value,
+            "sensor_id" => Field::SensorId,
+            "alert_id" => Field::AlertId,
+            "raised_at" => Field::RaisedAt,
+            "acknowledged_by" => Field::AcknowledgedBy,
+            _ => return None,
+        };
+        Some(field)
+    }
+
+    fn render(self, reason: Reason, alert: &Alert) -> String {
+        match self {
+            Field::Event => reason.as_str().to_string(),
+            Field::Rule => alert.rule_name.clone(),
+            Field::Severity => alert.severity.as_str().to_string(),
+            Field::Status => alert.status().as_str().to_string(),
+            Field::Message => alert.message.clone(),
+            Field::Value => alert
+                .value
+                .map(|value| format!("{value:.2}"))
+                .unwrap_or_default(),
+            Field::SensorId => alert.sensor_id.to_string(),
+            Field::AlertId => alert.id.to_string(),
+            Field::RaisedAt => alert.raised_at.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+            Field::AcknowledgedBy => alert.acknowledged_by.clone().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+/// Text with `{{field}}` placeholders filled from the alert, e.g.
+/// `[{{severity}}] {{rule}}: {{message}}`.
+///
+/// The fields are `event`, `rule`, `severity`, `status`, `message`, `value`,
+/// `sensor_id`, `alert_id`, `raised_at` and `acknowledged_by`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let Some(end) = rest[start..].find("}}") else {
+                return Err(AlertError::InvalidChannel(format!(
+                    "unclosed placeholder in template '{template}'"
+                )));
+            };
+            let name = rest[start + 2..start + end].trim();
+            let field = Field::parse(name).ok_or_else(|| {
+                AlertError::InvalidChannel(format!("unknown placeholder '{name}' in template"))
+            })?;
+            parts.push(Part::Field(field));
+            rest = &rest[start + end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+
+        Ok(Self { parts })
+    }
+
+    pub fn render(&self, reason: Reason, alert: &Alert) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Field(field) => field.render(reason, alert),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::Severity;
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    #[test]
+    fn placeholders_are_filled_from_the_alert() {
+        let alert = Alert {
+            id: Uuid::nil(),
+            rule_id: Uuid::nil(),
+            rule_name: "Water too hot".to_string(),
+            sensor_id: Uuid::nil(),
+            severity: Severity::Critical,
+            message: "31.50 above 30".to_string(),
+            value: Some(31.5),
+            raised_at: Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap(),
+            acknowledged_at: None,
+            acknowledged_by: None,
+            resolved_at: None,
+        };
+
+        let template = Template::parse("[{{severity}}] {{ rule }} {{event}}: {{value}}").unwrap();
+        assert_eq!(
+            template.render(Reason::Escalated, &alert),
+            "[critical] Water too hot escalated: 31.50"
+        );
+        assert_eq!(
+            Template::parse("at {{raised_at}}")
+                .unwrap()
+                .render(Reason::Raised, &alert),
+            "at 2026-01-02 03:04:05 UTC"
+        );
+
+        assert!(Template::parse("{{unknown}}").is_err());
+        assert!(Template::parse("{{rule").is_err());
+    }
+}
@@ -0,0 +1,64 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use reqwest::Client;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::alert::Alert;
+use crate::notify::{check_response, Notification, Notifier, NotifyError, Reason};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Sent with every request, e.g. an `Authorization`
+    pub headers: Vec<(String, String)>,
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    event: Reason,
+    title: &'a str,
+    body: &'a str,
+    alert: &'a Alert,
+}
+
+/// Posts the notifications as JSON, the alert included.
+pub struct WebhookNotifier {
+    client: Client,
+    config: WebhookConfig,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookConfig) -> Result<Self, NotifyError> {
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|err| NotifyError(err.to_string()))?;
+
+        Ok(Self { client, config })
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let mut request = self.client.post(&self.config.url).json(&Payload {
+            event: notification.reason,
+            title: &notification.title,
+            body: &notification.body,
+            alert: &notification.alert,
+        });
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|err| NotifyError(err.to_string()))?;
+        check_response(response).await
+    }
+}
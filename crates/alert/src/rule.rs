@@ -0,0 +1,115 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_sensor::sensor::{SensorKind, SensorState};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::alert::Severity;
+use crate::error::{AlertError, Result};
+
+/// What makes a sensor alert.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// The last measurement is out of bounds
+    Threshold {
+        above: Option<f64>,
+        below: Option<f64>,
+    },
+    /// The value moves faster than `max_per_minute` between two
+    /// measurements, either way
+    RateOfChange { max_per_minute: f64 },
+    /// No measurement for `after`
+    Stale { after: Duration },
+    /// The sensor is in one of `states`
+    State { states: Vec<SensorState> },
+}
+
+/// Raises alerts for the sensors it covers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlertRule {
+    pub id: Uuid,
+    pub name: String,
+    /// Sensor covered, every sensor when empty
+    pub sensor_id: Option<Uuid>,
+    /// Kind of sensors covered, every kind when empty
+    pub kind: Option<SensorKind>,
+    pub condition: Condition,
+    pub severity: Severity,
+    /// How long the condition holds before the alert is raised, e.g. an RTD
+    /// `Unreachable` for more than 5 minutes
+    pub for_duration: Duration,
+    pub enabled: bool,
+}
+
+impl AlertRule {
+    pub fn covers(&self, sensor_id: Uuid, kind: SensorKind) -> bool {
+        self.enabled
+            && self.sensor_id.is_none_or(|id| id == sensor_id)
+            && self.kind.is_none_or(|covered| covered == kind)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let invalid = match &self.condition {
+            Condition::Threshold { above, below } => match (above, below) {
+                (None, None) => Some("the threshold has no bound"),
+                (Some(above), Some(below)) if below > above => {
+                    Some("the threshold bounds overlap, it always fires")
+                }
+                _ => None,
+            },
+            Condition::RateOfChange { max_per_minute } if *max_per_minute <= 0.0 => {
+                Some("the rate of change must be positive")
+            }
+            Condition::Stale { after } if after.is_zero() => Some("the stale delay can't be zero"),
+            Condition::State { states } if states.is_empty() => Some("no state to alert on"),
+            _ => None,
+        };
+
+        match invalid {
+            Some(details) => Err(AlertError::InvalidRule(format!(
+                "'{}': {details}",
+                self.name
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_that_never_or_always_fire_are_invalid() {
+        let mut rule = AlertRule {
+            id: Uuid::nil(),
+            name: "water temperature".to_string(),
+            sensor_id: None,
+            kind: Some(SensorKind::Temperature),
+            condition: Condition::Threshold {
+                above: None,
+                below: None,
+            },
+            severity: Severity::Warning,
+            for_duration: Duration::ZERO,
+            enabled: true,
+        };
+        assert!(rule.validate().is_err());
+
+        rule.condition = Condition::Threshold {
+            above: Some(20.0),
+            below: Some(22.0),
+        };
+        assert!(rule.validate().is_err());
+
+        rule.condition = Condition::Threshold {
+            above: Some(28.0),
+            below: Some(22.0),
+        };
+        assert!(rule.validate().is_ok());
+        assert!(rule.covers(Uuid::new_v4(), SensorKind::Temperature));
+        assert!(!rule.covers(Uuid::new_v4(), SensorKind::Ph));
+    }
+}
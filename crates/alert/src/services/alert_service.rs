@@ -0,0 +1,296 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::AlertRecord;
+use arksync_db::AlertStore;
+use arksync_sensor::event::SensorEvent;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{interval, Duration, Instant, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::alert::{Alert, Severity};
+use crate::engine::AlertEngine;
+use crate::error::Result;
+use crate::event::AlertEvent;
+use crate::rule::AlertRule;
+use crate::services::AlertServiceHandle;
+
+/// Wait between two evaluations of the time-based conditions.
+const TICK: Duration = Duration::from_secs(5);
+
+pub(crate) enum AlertServiceCmd {
+    Rules {
+        respond_to: oneshot::Sender<Vec<AlertRule>>,
+    },
+    Open {
+        respond_to: oneshot::Sender<Vec<Alert>>,
+    },
+    Acknowledge {
+        id: Uuid,
+        by: String,
+        respond_to: oneshot::Sender<Result<Alert>>,
+    },
+}
+
+/// Raises alerts from the sensor events and keeps their history.
+pub struct AlertService {
+    engine: AlertEngine,
+    sensors: Option<broadcast::Receiver<SensorEvent>>,
+    store: Option<AlertStore<'static>>,
+    events: broadcast::Sender<AlertEvent>,
+    cmd_tx: mpsc::Sender<AlertServiceCmd>,
+    cmd_rx: mpsc::Receiver<AlertServiceCmd>,
+}
+
+impl AlertService {
+    /// Fails if a rule is invalid.
+    pub fn new(rules: Vec<AlertRule>) -> Result<Self> {
+        for rule in &rules {
+            rule.validate()?;
+        }
+        let (cmd_tx, cmd_rx) = mpsc::channel(100);
+        let (events, _) = broadcast::channel(256);
+
+        Ok(Self {
+            engine: AlertEngine::new(rules),
+            sensors: None,
+            store: None,
+            events,
+            cmd_tx,
+            cmd_rx,
+        })
+    }
+
+    /// Handle to talk to the service once it runs.
+    pub fn handle(&self) -> AlertServiceHandle {
+        AlertServiceHandle::new(self.cmd_tx.clone(), self.events.clone())
+    }
+
+    /// Sensor events to watch, without them only the open alerts can be
+    /// listed and acknowledged.
+    pub fn with_sensors(mut self, sensors: broadcast::Receiver<SensorEvent>) -> Self {
+        self.sensors = Some(sensors);
+        self
+    }
+
+    /// Keep the alerts in Postgres, the open ones are taken back on start.
+    pub fn with_store(mut self, store: AlertStore<'static>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Evaluate the rules until `shutdown` is cancelled.
+    pub async fn run(mut self, shutdown: CancellationToken) {
+        self.load().await;
+        tracing::info!(rules = self.engine.rules().len(), "Alert service started.");
+
+        let mut tick = interval(TICK);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                event = next_event(&mut self.sensors) => match event {
+                    Ok(event) => {
+                        let events = self.engine.observe(&event, Instant::now());
+                        self.publish(events).await;
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Alert service lagging behind, sensor events lost.");
+                    }
+                    Err(RecvError::Closed) => self.sensors = None,
+                },
+                Some(cmd) = self.cmd_rx.recv() => self.handle_cmd(cmd).await,
+                _ = tick.tick() => {
+                    let events = self.engine.tick(Instant::now());
+                    self.publish(events).await;
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+
+        tracing::info!("Alert service stopped.");
+    }
+
+    async fn load(&mut self) {
+        let Some(store) = self.store else {
+            return;
+        };
+
+        match store.open().await {
+            Ok(records) => {
+                let alerts = records.into_iter().map(Alert::from).collect();
+                let events = self.engine.restore(alerts);
+                self.publish(events).await;
+            }
+            Err(err) => tracing::error!(
+                error = format_args!("{err:#}"),
+                "Failed to load the open alerts."
+            ),
+        }
+    }
+
+    async fn handle_cmd(&mut self, cmd: AlertServiceCmd) {
+        match cmd {
+            AlertServiceCmd::Rules { respond_to } => {
+                let _ = respond_to.send(self.engine.rules().to_vec());
+            }
+            AlertServiceCmd::Open { respond_to } => {
+                let _ = respond_to.send(self.engine.open());
+            }
+            AlertServiceCmd::Acknowledge { id, by, respond_to } => {
+                let result = match self.engine.acknowledge(id, &by) {
+                    Ok(event) => {
+                        let alert = event.alert().clone();
+                        self.publish(vec![event]).await;
+                        Ok(alert)
+                    }
+                    Err(err) => Err(err),
+                };
+                let _ = respond_to.send(result);
+            }
+        }
+    }
+
+    /// Record the changes and send them to the subscribers.
+    async fn publish(&self, events: Vec<AlertEvent>) {
+        for event in events {
+            match &event {
+                // The level of an event is fixed, hence one per severity
+                AlertEvent::Raised(alert) => match alert.severity {
+                    Severity::Critical => {
+                        tracing::error!(rule = alert.rule_name, "Alert raised: {}", alert.message)
+                    }
+                    Severity::Warning => {
+                        tracing::warn!(rule = alert.rule_name, "Alert raised: {}", alert.message)
+                    }
+                    Severity::Info => {
+                        tracing::info!(rule = alert.rule_name, "Alert raised: {}", alert.message)
+                    }
+                },
+                AlertEvent::Acknowledged(alert) => tracing::info!(
+                    rule = alert.rule_name,
+                    by = alert.acknowledged_by.as_deref().unwrap_or_default(),
+                    "Alert acknowledged."
+                ),
+                AlertEvent::Resolved(alert) => {
+                    tracing::info!(rule = alert.rule_name, "Alert resolved: {}", alert.message)
+                }
+            }
+            self.record(&event).await;
+            let _ = self.events.send(event);
+        }
+    }
+
+    async fn record(&self, event: &AlertEvent) {
+        let Some(store) = self.store else {
+            return;
+        };
+
+        let result = match event {
+            AlertEvent::Raised(alert) => store.insert(&AlertRecord::from(alert)).await,
+            AlertEvent::Acknowledged(alert) => match alert.acknowledged_at {
+                Some(at) => store
+                    .acknowledge(
+                        alert.id,
+                        alert.acknowledged_by.as_deref().unwrap_or_default(),
+                        at,
+                    )
+                    .await
+                    .map(|_| ()),
+                None => Ok(()),
+            },
+            AlertEvent::Resolved(alert) => match alert.resolved_at {
+                Some(at) => store.resolve(alert.id, at).await,
+                None => Ok(()),
+            },
+        };
+
+        if let Err(err) = result {
+            tracing::error!(
+                alert_id = %event.alert().id,
+                error = format_args!("{err:#}"),
+                "Failed to record an alert."
+            );
+        }
+    }
+}
+
+/// Next sensor event, never ready without sensors.
+async fn next_event(
+    sensors: &mut Option<broadcast::Receiver<SensorEvent>>,
+) -> std::result::Result<SensorEvent, RecvError> {
+    match sensors {
+        Some(sensors) => sensors.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arksync_sensor::event::Measurement;
+    use arksync_sensor::sensor::SensorKind;
+    use chrono::Utc;
+
+    use crate::alert::AlertStatus;
+    use crate::error::AlertError;
+    use crate::rule::Condition;
+
+    #[tokio::test]
+    async fn alerts_are_published_and_acknowledged() {
+        let rule = AlertRule {
+            id: Uuid::new_v4(),
+            name: "Water too hot".to_string(),
+            sensor_id: None,
+            kind: Some(SensorKind::Temperature),
+            condition: Condition::Threshold {
+                above: Some(30.0),
+                below: None,
+            },
+            severity: Severity::Critical,
+            for_duration: Duration::ZERO,
+            enabled: true,
+        };
+        let (sensors, _) = broadcast::channel(16);
+        let service = AlertService::new(vec![rule])
+            .unwrap()
+            .with_sensors(sensors.subscribe());
+        let handle = service.handle();
+        let mut events = handle.subscribe();
+        let shutdown = CancellationToken::new();
+        let task = tokio::spawn(service.run(shutdown.clone()));
+
+        sensors
+            .send(SensorEvent::Measurement(Measurement {
+                sensor_id: Uuid::new_v4(),
+                kind: SensorKind::Temperature,
+                value: 31.5,
+                time: Utc::now(),
+            }))
+            .unwrap();
+        let AlertEvent::Raised(alert) = events.recv().await.unwrap() else {
+            panic!("an alert should be raised");
+        };
+        assert_eq!(handle.open().await.unwrap(), vec![alert.clone()]);
+
+        let acknowledged = handle.acknowledge(alert.id, "admin").await.unwrap();
+        assert_eq!(acknowledged.status(), AlertStatus::Acknowledged);
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            AlertEvent::Acknowledged(alert) if alert.acknowledged_by.as_deref() == Some("admin")
+        ));
+
+        let unknown = Uuid::new_v4();
+        assert_eq!(
+            handle.acknowledge(unknown, "admin").await,
+            Err(AlertError::UnknownAlert(unknown))
+        );
+
+        shutdown.cancel();
+        task.await.unwrap();
+        assert_eq!(handle.open().await, Err(AlertError::ServiceStopped));
+    }
+}
@@ -0,0 +1,68 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use tokio::sync::{broadcast, mpsc, oneshot};
+use uuid::Uuid;
+
+use crate::alert::Alert;
+use crate::error::{AlertError, Result};
+use crate::event::AlertEvent;
+use crate::rule::AlertRule;
+use crate::services::alert_service::AlertServiceCmd;
+
+#[derive(Clone)]
+pub struct AlertServiceHandle {
+    cmd_tx: mpsc::Sender<AlertServiceCmd>,
+    events: broadcast::Sender<AlertEvent>,
+}
+
+impl AlertServiceHandle {
+    pub(crate) fn new(
+        cmd_tx: mpsc::Sender<AlertServiceCmd>,
+        events: broadcast::Sender<AlertEvent>,
+    ) -> Self {
+        Self { cmd_tx, events }
+    }
+
+    /// Receive the alerts as they are raised, acknowledged and resolved.
+    pub fn subscribe(&self) -> broadcast::Receiver<AlertEvent> {
+        self.events.subscribe()
+    }
+
+    pub async fn rules(&self) -> Result<Vec<AlertRule>> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(AlertServiceCmd::Rules { respond_to }, rx)
+            .await
+    }
+
+    /// Alerts not resolved yet, oldest first.
+    pub async fn open(&self) -> Result<Vec<Alert>> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(AlertServiceCmd::Open { respond_to }, rx).await
+    }
+
+    /// Mark an open alert as taken care of by `by`, it stays open until its
+    /// condition clears.
+    pub async fn acknowledge(&self, id: Uuid, by: &str) -> Result<Alert> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(
+            AlertServiceCmd::Acknowledge {
+                id,
+                by: by.to_string(),
+                respond_to,
+            },
+            rx,
+        )
+        .await?
+    }
+
+    async fn request<T>(&self, cmd: AlertServiceCmd, rx: oneshot::Receiver<T>) -> Result<T> {
+        self.cmd_tx
+            .send(cmd)
+            .await
+            .map_err(|_| AlertError::ServiceStopped)?;
+
+        rx.await.map_err(|_| AlertError::ServiceStopped)
+    }
+}
@@ -0,0 +1,11 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+mod alert_service;
+mod handle;
+mod notification_service;
+
+pub use alert_service::AlertService;
+pub use handle::AlertServiceHandle;
+pub use notification_service::NotificationService;
@@ -0,0 +1,123 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::Utc;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinSet;
+use tokio::time::{interval, timeout, Duration, Instant, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::{AlertError, Result};
+use crate::event::AlertEvent;
+use crate::notify::{ChannelConfig, Delivery, Dispatcher, Notifier, Transport};
+
+/// Wait between two checks of the pending escalations.
+const TICK: Duration = Duration::from_secs(1);
+/// Notifications still going out on shutdown get this long to finish.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sends the alert events to the notification channels.
+pub struct NotificationService {
+    dispatcher: Dispatcher,
+    transports: Vec<Arc<Transport>>,
+    alerts: broadcast::Receiver<AlertEvent>,
+}
+
+impl NotificationService {
+    /// Fails if a channel is invalid or its transport can't be set up.
+    pub fn new(
+        channels: Vec<ChannelConfig>,
+        alerts: broadcast::Receiver<AlertEvent>,
+    ) -> Result<Self> {
+        let transports = channels
+            .iter()
+            .map(|channel| {
+                Transport::new(&channel.transport)
+                    .map(Arc::new)
+                    .map_err(|err| AlertError::InvalidChannel(format!("'{}': {err}", channel.name)))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            dispatcher: Dispatcher::new(channels)?,
+            transports,
+            alerts,
+        })
+    }
+
+    /// Notify until `shutdown` is cancelled or the alert service stops.
+    pub async fn run(mut self, shutdown: CancellationToken) {
+        tracing::info!(
+            channels = self.transports.len(),
+            "Notification service started."
+        );
+
+        let mut deliveries = JoinSet::new();
+        let mut tick = interval(TICK);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                event = self.alerts.recv() => match event {
+                    Ok(event) => {
+                        let pending = self.dispatcher.dispatch(&event, Instant::now(), Utc::now());
+                        self.send(pending, &mut deliveries);
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Notification service lagging behind, alert events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = tick.tick() => {
+                    let pending = self.dispatcher.tick(Instant::now(), Utc::now());
+                    self.send(pending, &mut deliveries);
+                }
+                Some(Err(err)) = deliveries.join_next() => {
+                    tracing::error!(error = %err, "Notification task failed.");
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+
+        let drained = timeout(DRAIN_TIMEOUT, async {
+            while deliveries.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            tracing::warn!("Notifications still in flight dropped on shutdown.");
+        }
+        tracing::info!("Notification service stopped.");
+    }
+
+    /// Send in the background, a slow server doesn't hold back the others.
+    fn send(&self, pending: Vec<Delivery>, deliveries: &mut JoinSet<()>) {
+        for Delivery {
+            channel,
+            notification,
+        } in pending
+        {
+            let transport = self.transports[channel].clone();
+            let name = self.dispatcher.channel(channel).name.clone();
+
+            deliveries.spawn(async move {
+                let alert = &notification.alert;
+                match transport.send(&notification).await {
+                    Ok(()) => tracing::info!(
+                        rule = alert.rule_name,
+                        reason = notification.reason.as_str(),
+                        channel = name,
+                        "Alert notification sent."
+                    ),
+                    Err(err) => tracing::error!(
+                        rule = alert.rule_name,
+                        channel = name,
+                        error = %err,
+                        "Failed to send an alert notification."
+                    ),
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,170 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_alert::alert::{Alert, Severity};
+use arksync_alert::event::AlertEvent;
+use arksync_alert::notify::{
+    ChannelConfig, CommandConfig, PushConfig, PushService, TransportConfig, WebhookConfig,
+    DEFAULT_BODY, DEFAULT_TITLE,
+};
+use arksync_alert::services::NotificationService;
+use chrono::Utc;
+use serde_json::json;
+use std::path::PathBuf;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+use wiremock::matchers::{body_partial_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn channel(name: &str, transport: TransportConfig) -> ChannelConfig {
+    ChannelConfig {
+        name: name.to_string(),
+        transport,
+        title: DEFAULT_TITLE.to_string(),
+        body: DEFAULT_BODY.to_string(),
+        min_severity: Severity::Warning,
+        rate_limit: None,
+        quiet_hours: None,
+        escalate_after: None,
+        send_resolved: false,
+    }
+}
+
+fn heater_failure() -> Alert {
+    Alert {
+        id: Uuid::new_v4(),
+        rule_id: Uuid::new_v4(),
+        rule_name: "Heater failure".to_string(),
+        sensor_id: Uuid::new_v4(),
+        severity: Severity::Critical,
+        message: "water at 18.00, below 22".to_string(),
+        value: Some(18.0),
+        raised_at: Utc::now(),
+        acknowledged_at: None,
+        acknowledged_by: None,
+        resolved_at: None,
+    }
+}
+
+/// Raise an alert and run the service until `delivered`, or fail after a few
+/// seconds.
+async fn raise_until(channels: Vec<ChannelConfig>, delivered: impl AsyncFn() -> bool) {
+    let (alerts, _) = broadcast::channel(16);
+    let service = NotificationService::new(channels, alerts.subscribe()).unwrap();
+    let shutdown = CancellationToken::new();
+    let task = tokio::spawn(service.run(shutdown.clone()));
+
+    alerts.send(AlertEvent::Raised(heater_failure())).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !delivered().await {
+        assert!(Instant::now() < deadline, "notification not delivered");
+        sleep(Duration::from_millis(20)).await;
+    }
+
+    shutdown.cancel();
+    task.await.unwrap();
+}
+
+async fn received(server: &MockServer, count: usize) -> bool {
+    server
+        .received_requests()
+        .await
+        .is_some_and(|requests| requests.len() >= count)
+}
+
+#[tokio::test]
+async fn webhooks_receive_the_alert_as_json() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/hooks/arksync"))
+        .and(header("x-token", "secret"))
+        .and(body_partial_json(json!({
+            "event": "raised",
+            "title": "[critical] Heater failure raised",
+            "alert": { "ruleName": "Heater failure", "severity": "critical" },
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let webhook = channel(
+        "webhook",
+        TransportConfig::Webhook(WebhookConfig {
+            url: format!("{}/hooks/arksync", server.uri()),
+            headers: vec![("X-Token".to_string(), "secret".to_string())],
+        }),
+    );
+    raise_until(vec![webhook], async || received(&server, 1).await).await;
+}
+
+#[tokio::test]
+async fn push_servers_receive_a_prioritised_message() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .and(header("authorization", "Bearer tk_ntfy"))
+        .and(body_partial_json(json!({
+            "topic": "arksync",
+            "title": "[critical] Heater failure raised",
+            "priority": 5,
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/message"))
+        .and(header("x-gotify-key", "gotify-app"))
+        .and(body_partial_json(json!({ "priority": 8 })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let ntfy = channel(
+        "ntfy",
+        TransportConfig::Push(PushConfig {
+            url: server.uri(),
+            service: PushService::Ntfy {
+                topic: "arksync".to_string(),
+            },
+            token: Some("tk_ntfy".to_string()),
+        }),
+    );
+    let gotify = channel(
+        "gotify",
+        TransportConfig::Push(PushConfig {
+            url: format!("{}/", server.uri()),
+            service: PushService::Gotify,
+            token: Some("gotify-app".to_string()),
+        }),
+    );
+    raise_until(vec![ntfy, gotify], async || received(&server, 2).await).await;
+}
+
+#[tokio::test]
+async fn commands_get_the_alert_on_stdin_and_in_their_environment() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("alert.txt");
+    let hook = channel(
+        "hook",
+        TransportConfig::Command(CommandConfig {
+            program: PathBuf::from("sh"),
+            args: vec![
+                "-c".to_string(),
+                r#"{ cat; echo; echo "$ARKSYNC_ALERT_SEVERITY $ARKSYNC_ALERT_VALUE"; } > "$0.tmp" && mv "$0.tmp" "$0""#.to_string(),
+                output.display().to_string(),
+            ],
+            timeout: Duration::from_secs(5),
+        }),
+    );
+
+    raise_until(vec![hook], async || output.exists()).await;
+    let written = std::fs::read_to_string(&output).unwrap();
+    assert!(written.starts_with("water at 18.00, below 22\n"));
+    assert!(written.ends_with("critical 18\n"));
+}
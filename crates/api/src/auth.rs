@@ -0,0 +1,53 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_users::application::use_cases::AuthenticateUseCase;
+use arksync_users::domain::User;
+use axum::extract::FromRequestParts;
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+
+use crate::{ApiError, ApiState};
+
+/// User behind the `Authorization: Bearer <token>` header of a request.
+pub struct AuthUser {
+    pub user: User,
+    pub token: String,
+}
+
+impl AuthUser {
+    pub async fn authenticate(state: &ApiState, token: &str) -> Result<Self, ApiError> {
+        let user = AuthenticateUseCase::new(&state.pool)
+            .execute(token)
+            .await
+            .map_err(ApiError::internal)?
+            .ok_or_else(ApiError::unauthorized)?;
+
+        Ok(Self {
+            user,
+            token: token.to_string(),
+        })
+    }
+}
+
+impl FromRequestParts<ApiState> for AuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &ApiState) -> Result<Self, ApiError> {
+        let token = bearer_token(&parts.headers).ok_or_else(ApiError::unauthorized)?;
+
+        Self::authenticate(state, token).await
+    }
+}
+
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
@@ -0,0 +1,28 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_config::ConfigHandler;
+use std::net::SocketAddr;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+pub static CONFIG: LazyLock<Config> = LazyLock::new(|| ConfigHandler::new(mpl).load());
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// `sk hub` serves the API of its sensors, relays and schedules
+    pub enabled: bool,
+    /// Address of the HTTP server
+    pub listen: SocketAddr,
+    /// How long a session stays valid after logging in
+    pub session_ttl: Duration,
+}
+
+fn mpl() -> Config {
+    Config {
+        enabled: false,
+        listen: SocketAddr::from(([0, 0, 0, 0], 8080)),
+        session_ttl: Duration::from_secs(7 * 24 * 3600),
+    }
+}
@@ -0,0 +1,18 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+mod auth;
+mod config;
+mod error;
+mod openapi;
+pub mod routes;
+mod server;
+mod state;
+
+pub use auth::AuthUser;
+pub use config::{Config, CONFIG};
+pub use error::ApiError;
+pub use openapi::ApiDoc;
+pub use server::{router, serve};
+pub use state::ApiState;
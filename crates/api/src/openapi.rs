@@ -0,0 +1,67 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::routes::{alerts, control, events, relays, schedules, sensors, sessions, users};
+
+/// OpenAPI description of the API, served on `/api/openapi.json`.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "ArkSync API"),
+    paths(
+        sessions::log_in,
+        sessions::log_out,
+        sensors::list_sensors,
+        sensors::get_sensor,
+        sensors::rename_sensor,
+        sensors::read_sensor,
+        sensors::sensor_history,
+        relays::list_relays,
+        relays::set_relay,
+        schedules::list_schedules,
+        schedules::create_schedule,
+        schedules::update_schedule,
+        schedules::delete_schedule,
+        schedules::preview_schedules,
+        control::list_control_rules,
+        control::pid_terms,
+        control::set_setpoint,
+        control::set_mode,
+        alerts::list_alerts,
+        alerts::alert_history,
+        alerts::acknowledge_alert,
+        users::list_users,
+        users::create_user,
+        users::current_user,
+        users::delete_user,
+        events::live_events,
+    ),
+    components(schemas(events::LiveEvent)),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "sessions", description = "Log in and out"),
+        (name = "sensors", description = "Sensors of the station and their history"),
+        (name = "relays", description = "Relays of the station"),
+        (name = "schedules", description = "Time-based rules driving the relays"),
+        (name = "control", description = "Rules driving the relays from the measurements"),
+        (name = "alerts", description = "Alerts raised on the sensors"),
+        (name = "users", description = "Users allowed on the station"),
+        (name = "events", description = "Live measurements, sensor changes, PID terms and alerts"),
+    )
+)]
+pub struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
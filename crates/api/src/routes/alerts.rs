@@ -0,0 +1,170 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_alert::alert::Alert;
+use arksync_alert::error::AlertError;
+use arksync_alert::services::AlertServiceHandle;
+use arksync_db::AlertStore;
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::error::ErrorBody;
+use crate::{ApiError, ApiState, AuthUser};
+
+const DEFAULT_HISTORY_LIMIT: u32 = 100;
+const MAX_HISTORY_LIMIT: u32 = 1000;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertDto {
+    pub id: Uuid,
+    pub rule_id: Uuid,
+    pub rule_name: String,
+    pub sensor_id: Uuid,
+    /// `info`, `warning` or `critical`
+    pub severity: String,
+    /// `firing`, `acknowledged` or `resolved`
+    pub status: String,
+    pub message: String,
+    pub value: Option<f64>,
+    pub raised_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub acknowledged_by: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+impl From<&Alert> for AlertDto {
+    fn from(alert: &Alert) -> Self {
+        Self {
+            id: alert.id,
+            rule_id: alert.rule_id,
+            rule_name: alert.rule_name.clone(),
+            sensor_id: alert.sensor_id,
+            severity: alert.severity.as_str().to_string(),
+            status: alert.status().as_str().to_string(),
+            message: alert.message.clone(),
+            value: alert.value,
+            raised_at: alert.raised_at,
+            acknowledged_at: alert.acknowledged_at,
+            acknowledged_by: alert.acknowledged_by.clone(),
+            resolved_at: alert.resolved_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AlertHistoryParams {
+    /// Alerts raised before this time, now by default
+    pub before: Option<DateTime<Utc>>,
+    /// 100 by default, 1000 at most
+    pub limit: Option<u32>,
+}
+
+/// Alerts not resolved yet, oldest first.
+#[utoipa::path(
+    get,
+    path = "/api/alerts",
+    tag = "alerts",
+    responses(
+        (status = 200, body = [AlertDto]),
+        (status = 401, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list_alerts(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<AlertDto>>, ApiError> {
+    let alerts = alerts(&state)?.open().await.map_err(alert_error)?;
+
+    Ok(Json(alerts.iter().map(AlertDto::from).collect()))
+}
+
+/// Alerts resolved or not, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/alerts/history",
+    tag = "alerts",
+    params(AlertHistoryParams),
+    responses(
+        (status = 200, body = [AlertDto]),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn alert_history(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Query(params): Query<AlertHistoryParams>,
+) -> Result<Json<Vec<AlertDto>>, ApiError> {
+    let limit = params.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    if limit == 0 || limit > MAX_HISTORY_LIMIT {
+        return Err(ApiError::bad_request(format!(
+            "The history holds 1 to {MAX_HISTORY_LIMIT} alerts"
+        )));
+    }
+
+    let records = AlertStore::new(&state.pool)
+        .history(params.before.unwrap_or_else(Utc::now), i64::from(limit))
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(
+        records
+            .into_iter()
+            .map(|record| AlertDto::from(&Alert::from(record)))
+            .collect(),
+    ))
+}
+
+/// Take an alert in charge, it stays open until its condition clears.
+#[utoipa::path(
+    post,
+    path = "/api/alerts/{id}/acknowledge",
+    tag = "alerts",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 200, body = AlertDto),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn acknowledge_alert(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AlertDto>, ApiError> {
+    let alert = alerts(&state)?
+        .acknowledge(id, auth.user.username())
+        .await
+        .map_err(alert_error)?;
+
+    Ok(Json(AlertDto::from(&alert)))
+}
+
+fn alerts(state: &ApiState) -> Result<&AlertServiceHandle, ApiError> {
+    state
+        .alerts
+        .as_ref()
+        .ok_or_else(ApiError::alerts_unavailable)
+}
+
+fn alert_error(err: AlertError) -> ApiError {
+    match err {
+        AlertError::UnknownAlert(_) => ApiError::not_found(err.to_string()),
+        AlertError::InvalidRule(_) | AlertError::InvalidChannel(_) => {
+            ApiError::bad_request(err.to_string())
+        }
+        AlertError::ServiceStopped => ApiError::alerts_unavailable(),
+    }
+}
@@ -0,0 +1,250 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_control::error::ControlError;
+use arksync_control::pid::{Mode, PidTerms};
+use arksync_control::rule::{ControlRule, Strategy};
+use arksync_control::services::ControlServiceHandle;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::error::ErrorBody;
+use crate::{ApiError, ApiState, AuthUser};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlMode {
+    Auto,
+    /// The operator sets the duty cycle
+    Manual,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlRuleDto {
+    pub id: Uuid,
+    pub name: String,
+    pub sensor_id: Uuid,
+    pub relay_id: String,
+    /// `hysteresis` or `pid`, setpoints and modes only apply to `pid`
+    pub strategy: String,
+    /// Configured setpoint of a PID rule
+    pub setpoint: Option<f64>,
+}
+
+impl From<&ControlRule> for ControlRuleDto {
+    fn from(rule: &ControlRule) -> Self {
+        let (strategy, setpoint) = match rule.strategy {
+            Strategy::Hysteresis(_) => ("hysteresis", None),
+            Strategy::Pid(settings) => ("pid", Some(settings.setpoint)),
+        };
+
+        Self {
+            id: rule.id,
+            name: rule.name.clone(),
+            sensor_id: rule.sensor_id,
+            relay_id: rule.relay_id.clone(),
+            strategy: strategy.to_string(),
+            setpoint,
+        }
+    }
+}
+
+/// Terms of the last update of a PID loop.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PidTermsDto {
+    /// Setpoint in use, on its way to the target while ramping
+    pub setpoint: f64,
+    pub measurement: f64,
+    pub error: f64,
+    pub proportional: f64,
+    pub integral: f64,
+    pub derivative: f64,
+    /// Duty cycle applied, between 0 and 1
+    pub output: f64,
+    pub mode: ControlMode,
+}
+
+impl From<PidTerms> for PidTermsDto {
+    fn from(terms: PidTerms) -> Self {
+        Self {
+            setpoint: terms.setpoint,
+            measurement: terms.measurement,
+            error: terms.error,
+            proportional: terms.proportional,
+            integral: terms.integral,
+            derivative: terms.derivative,
+            output: terms.output,
+            mode: match terms.mode {
+                Mode::Auto => ControlMode::Auto,
+                Mode::Manual { .. } => ControlMode::Manual,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetpointRequest {
+    pub setpoint: f64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ModeRequest {
+    pub mode: ControlMode,
+    /// Duty cycle between 0 and 1, required in manual
+    pub output: Option<f64>,
+}
+
+impl ModeRequest {
+    fn into_mode(self) -> Result<Mode, ApiError> {
+        match (self.mode, self.output) {
+            (ControlMode::Auto, _) => Ok(Mode::Auto),
+            (ControlMode::Manual, Some(output)) if (0.0..=1.0).contains(&output) => {
+                Ok(Mode::Manual { output })
+            }
+            (ControlMode::Manual, _) => Err(ApiError::bad_request(
+                "The manual mode takes an output between 0 and 1",
+            )),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/control/rules",
+    tag = "control",
+    responses(
+        (status = 200, body = [ControlRuleDto]),
+        (status = 401, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list_control_rules(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<ControlRuleDto>>, ApiError> {
+    let rules = control(&state)?.rules().await.map_err(control_error)?;
+
+    Ok(Json(rules.iter().map(ControlRuleDto::from).collect()))
+}
+
+/// Terms of the last update of a PID rule, `null` before its first
+/// measurement.
+///
+/// The following updates are streamed on `/api/events`.
+#[utoipa::path(
+    get,
+    path = "/api/control/rules/{id}/terms",
+    tag = "control",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 200, body = Option<PidTermsDto>),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn pid_terms(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Option<PidTermsDto>>, ApiError> {
+    let terms = control(&state)?
+        .pid_terms(id)
+        .await
+        .map_err(control_error)?;
+
+    Ok(Json(terms.map(PidTermsDto::from)))
+}
+
+/// Move the setpoint of a PID rule, along its ramp.
+#[utoipa::path(
+    put,
+    path = "/api/control/rules/{id}/setpoint",
+    tag = "control",
+    params(("id" = Uuid, Path)),
+    request_body = SetpointRequest,
+    responses(
+        (status = 204),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn set_setpoint(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SetpointRequest>,
+) -> Result<StatusCode, ApiError> {
+    if !request.setpoint.is_finite() {
+        return Err(ApiError::bad_request("The setpoint must be a number"));
+    }
+
+    control(&state)?
+        .set_setpoint(id, request.setpoint)
+        .await
+        .map_err(control_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Switch a PID rule between manual and auto, without a bump.
+#[utoipa::path(
+    put,
+    path = "/api/control/rules/{id}/mode",
+    tag = "control",
+    params(("id" = Uuid, Path)),
+    request_body = ModeRequest,
+    responses(
+        (status = 204),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn set_mode(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ModeRequest>,
+) -> Result<StatusCode, ApiError> {
+    let mode = request.into_mode()?;
+
+    control(&state)?
+        .set_mode(id, mode)
+        .await
+        .map_err(control_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn control(state: &ApiState) -> Result<&ControlServiceHandle, ApiError> {
+    state
+        .control
+        .as_ref()
+        .ok_or_else(ApiError::control_unavailable)
+}
+
+fn control_error(err: ControlError) -> ApiError {
+    match err {
+        ControlError::InvalidRule(_) | ControlError::NotPid(_) => {
+            ApiError::bad_request(err.to_string())
+        }
+        ControlError::UnknownRule(_) => ApiError::not_found(err.to_string()),
+        ControlError::ServiceStopped => ApiError::control_unavailable(),
+    }
+}
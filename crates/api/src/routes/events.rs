@@ -0,0 +1,194 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_alert::event::AlertEvent;
+use arksync_control::event::ControlEvent;
+use arksync_sensor::event::SensorEvent;
+use axum::extract::ws::rejection::WebSocketUpgradeRejection;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::auth::bearer_token;
+use crate::error::ErrorBody;
+use crate::routes::alerts::AlertDto;
+use crate::routes::control::PidTermsDto;
+use crate::routes::sensors::SensorDto;
+use crate::{ApiError, ApiState, AuthUser};
+
+/// Message of the live stream.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum LiveEvent {
+    Measurement {
+        sensor_id: Uuid,
+        kind: String,
+        value: f64,
+        time: DateTime<Utc>,
+    },
+    /// A sensor joined the registry, changed state or got renamed.
+    Sensor {
+        sensor: SensorDto,
+    },
+    SensorRemoved {
+        sensor_id: Uuid,
+    },
+    /// An alert was raised, acknowledged or resolved, see its status.
+    Alert {
+        alert: AlertDto,
+    },
+    /// A PID rule took a measurement in.
+    PidTerms {
+        rule_id: Uuid,
+        terms: PidTermsDto,
+    },
+}
+
+impl LiveEvent {
+    pub fn from_event(event: &SensorEvent) -> Option<Self> {
+        match event {
+            SensorEvent::Measurement(measurement) => Some(LiveEvent::Measurement {
+                sensor_id: measurement.sensor_id,
+                kind: measurement.kind.as_str().to_string(),
+                value: measurement.value,
+                time: measurement.time,
+            }),
+            SensorEvent::Discovered(info)
+            | SensorEvent::StateChanged { info, .. }
+            | SensorEvent::Renamed(info) => Some(LiveEvent::Sensor {
+                sensor: SensorDto::from(info),
+            }),
+            SensorEvent::Removed(info) => Some(LiveEvent::SensorRemoved { sensor_id: info.id }),
+            SensorEvent::ReadCompleted(_) => None,
+        }
+    }
+
+    pub fn from_alert(event: &AlertEvent) -> Self {
+        LiveEvent::Alert {
+            alert: AlertDto::from(event.alert()),
+        }
+    }
+
+    pub fn from_control(event: &ControlEvent) -> Option<Self> {
+        match event {
+            ControlEvent::PidUpdated { rule_id, terms } => Some(LiveEvent::PidTerms {
+                rule_id: *rule_id,
+                terms: PidTermsDto::from(*terms),
+            }),
+            ControlEvent::RelaySwitched { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct LiveEventsParams {
+    /// Session token, for clients that can't set headers on a WebSocket
+    pub access_token: Option<String>,
+}
+
+/// Stream of live measurements, sensor changes, PID terms and alerts.
+///
+/// Each WebSocket text message is a `LiveEvent` as JSON.
+#[utoipa::path(
+    get,
+    path = "/api/events",
+    tag = "events",
+    params(LiveEventsParams),
+    responses(
+        (status = 101, description = "Switching to the event stream"),
+        (status = 401, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn live_events(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Query(params): Query<LiveEventsParams>,
+    upgrade: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Result<Response, ApiError> {
+    let token = bearer_token(&headers)
+        .or(params.access_token.as_deref())
+        .ok_or_else(ApiError::unauthorized)?;
+    AuthUser::authenticate(&state, token).await?;
+    // Checked after the session so anonymous clients learn nothing more
+    let upgrade =
+        upgrade.map_err(|rejection| ApiError::new(rejection.status(), rejection.body_text()))?;
+
+    Ok(upgrade.on_upgrade(move |socket| stream(socket, state)))
+}
+
+async fn stream(mut socket: WebSocket, state: ApiState) {
+    let mut events = state.sensors.subscribe();
+    let mut alerts = state.alerts.as_ref().map(|alerts| alerts.subscribe());
+    let mut control = state.control.as_ref().map(|control| control.subscribe());
+
+    loop {
+        let live = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => LiveEvent::from_event(&event),
+                // A slow client misses events, it is not disconnected
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            event = next_event(&mut alerts) => match event {
+                Ok(event) => Some(LiveEvent::from_alert(&event)),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => {
+                    alerts = None;
+                    continue;
+                }
+            },
+            event = next_event(&mut control) => match event {
+                Ok(event) => LiveEvent::from_control(&event),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => {
+                    control = None;
+                    continue;
+                }
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => None,
+            },
+            _ = state.shutdown.cancelled() => {
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+        };
+
+        let Some(live) = live else {
+            continue;
+        };
+        let payload = match serde_json::to_string(&live) {
+            Ok(payload) => payload,
+            Err(err) => {
+                tracing::error!(error = %err, "Failed to encode a live event.");
+                continue;
+            }
+        };
+        if socket.send(Message::Text(payload.into())).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Next event of an optional service, never ready without it.
+async fn next_event<T: Clone>(events: &mut Option<broadcast::Receiver<T>>) -> Result<T, RecvError> {
+    match events {
+        Some(events) => events.recv().await,
+        None => std::future::pending().await,
+    }
+}
@@ -0,0 +1,12 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+pub mod alerts;
+pub mod control;
+pub mod events;
+pub mod relays;
+pub mod schedules;
+pub mod sensors;
+pub mod sessions;
+pub mod users;
@@ -0,0 +1,128 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::error::ActuatorError;
+use arksync_actuator::relay::{RelaySpec, RelayState};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::error::ErrorBody;
+use crate::{ApiError, ApiState, AuthUser};
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayDto {
+    pub id: String,
+    pub model: String,
+    pub gpio_bcm_pin: u8,
+    pub active_low: bool,
+    /// Energized
+    pub active: bool,
+}
+
+impl RelayDto {
+    fn new(spec: RelaySpec, state: RelayState) -> Self {
+        Self {
+            id: spec.id.to_string(),
+            model: spec.model.to_string(),
+            gpio_bcm_pin: spec.gpio_bcm_pin,
+            active_low: spec.active_low,
+            active: state.active,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetRelayRequest {
+    pub on: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/relays",
+    tag = "relays",
+    responses(
+        (status = 200, body = [RelayDto]),
+        (status = 401, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list_relays(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<RelayDto>>, ApiError> {
+    let states = state
+        .relays
+        .states()
+        .await
+        .ok_or_else(ApiError::relays_unavailable)?;
+
+    Ok(Json(
+        states
+            .into_iter()
+            .map(|(spec, state)| RelayDto::new(spec, state))
+            .collect(),
+    ))
+}
+
+/// Switch a relay on or off.
+#[utoipa::path(
+    put,
+    path = "/api/relays/{id}",
+    tag = "relays",
+    params(("id" = String, Path, example = "mist_relay")),
+    request_body = SetRelayRequest,
+    responses(
+        (status = 200, body = RelayDto),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 409, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn set_relay(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+    Json(request): Json<SetRelayRequest>,
+) -> Result<Json<RelayDto>, ApiError> {
+    let relay_state = state
+        .relays
+        .switch(&id, request.on)
+        .await
+        .map_err(actuator_error)?;
+
+    let spec = state
+        .relays
+        .states()
+        .await
+        .ok_or_else(ApiError::relays_unavailable)?
+        .into_iter()
+        .map(|(spec, _)| spec)
+        .find(|spec| spec.id == relay_state.id)
+        .ok_or_else(|| ApiError::not_found(format!("Unknown relay '{id}'")))?;
+
+    Ok(Json(RelayDto::new(spec, relay_state)))
+}
+
+/// Refusals are the client's to handle, hardware failures are ours.
+fn actuator_error(err: ActuatorError) -> ApiError {
+    match err {
+        ActuatorError::UnknownRelay(_) => ApiError::not_found(err.to_string()),
+        ActuatorError::InterlockRefused { .. } => {
+            ApiError::new(StatusCode::CONFLICT, err.to_string())
+        }
+        ActuatorError::ServiceStopped => ApiError::relays_unavailable(),
+        ActuatorError::GpioUnavailable(_)
+        | ActuatorError::PinBusy { .. }
+        | ActuatorError::PermissionDenied(_)
+        | ActuatorError::InvalidConfig(_)
+        | ActuatorError::Gpio { .. } => ApiError::internal(eyre::Report::new(err)),
+    }
+}
@@ -0,0 +1,304 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_scheduler::error::SchedulerError;
+use arksync_scheduler::plan::Occurrence;
+use arksync_scheduler::rule::ScheduleRule;
+use arksync_scheduler::services::SchedulerServiceHandle;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+use std::time::Duration;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::error::ErrorBody;
+use crate::{ApiError, ApiState, AuthUser};
+
+const DEFAULT_PREVIEW_HOURS: u32 = 24;
+const MAX_PREVIEW_HOURS: u32 = 7 * 24;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRuleDto {
+    pub id: Uuid,
+    pub name: String,
+    pub relay_id: String,
+    #[schema(value_type = Object)]
+    pub trigger: Value,
+    #[schema(value_type = Object)]
+    pub action: Value,
+    pub time_zone: String,
+    pub priority: i32,
+    pub enabled: bool,
+}
+
+impl From<&ScheduleRule> for ScheduleRuleDto {
+    fn from(rule: &ScheduleRule) -> Self {
+        Self {
+            id: rule.id,
+            name: rule.name.clone(),
+            relay_id: rule.relay_id.clone(),
+            trigger: serde_json::to_value(&rule.trigger).unwrap_or_default(),
+            action: serde_json::to_value(rule.action).unwrap_or_default(),
+            time_zone: rule.time_zone.name().to_string(),
+            priority: rule.priority,
+            enabled: rule.enabled,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRuleRequest {
+    pub name: String,
+    pub relay_id: String,
+    /// `cron`, `interval` or `solar`, e.g.
+    /// `{"type": "interval", "every_secs": 7200, "start": "08:00:00", "end": "20:00:00"}`
+    #[schema(value_type = Object)]
+    pub trigger: Value,
+    /// `turn_on`, `turn_off` or `pulse`, e.g. `{"type": "pulse", "duration_secs": 30}`
+    #[schema(value_type = Object)]
+    pub action: Value,
+    /// IANA time zone, `UTC` by default
+    pub time_zone: Option<String>,
+    /// Wins overlaps over lower priorities, 0 by default
+    #[serde(default)]
+    pub priority: i32,
+    /// Enabled by default
+    pub enabled: Option<bool>,
+}
+
+impl ScheduleRuleRequest {
+    fn into_rule(self, id: Uuid) -> Result<ScheduleRule, ApiError> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err(ApiError::bad_request("The rule name can't be empty"));
+        }
+
+        let time_zone = match self.time_zone.as_deref() {
+            Some(time_zone) => Tz::from_str(time_zone)
+                .map_err(|_| ApiError::bad_request(format!("Unknown time zone '{time_zone}'")))?,
+            None => Tz::UTC,
+        };
+
+        Ok(ScheduleRule {
+            id,
+            name: name.to_string(),
+            relay_id: self.relay_id,
+            trigger: serde_json::from_value(self.trigger)
+                .map_err(|err| ApiError::bad_request(format!("Invalid trigger: {err}")))?,
+            action: serde_json::from_value(self.action)
+                .map_err(|err| ApiError::bad_request(format!("Invalid action: {err}")))?,
+            time_zone,
+            priority: self.priority,
+            enabled: self.enabled.unwrap_or(true),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OccurrenceDto {
+    pub rule_id: Uuid,
+    pub relay_id: String,
+    pub at: DateTime<Utc>,
+    #[schema(value_type = Object)]
+    pub action: Value,
+    /// Rule holding the relay at that time, the occurrence is skipped
+    pub superseded_by: Option<Uuid>,
+}
+
+impl From<Occurrence> for OccurrenceDto {
+    fn from(occurrence: Occurrence) -> Self {
+        Self {
+            rule_id: occurrence.rule_id,
+            relay_id: occurrence.relay_id,
+            at: occurrence.at,
+            action: serde_json::to_value(occurrence.action).unwrap_or_default(),
+            superseded_by: occurrence.superseded_by,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PreviewParams {
+    /// 24 by default, a week at most
+    pub hours: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/schedules",
+    tag = "schedules",
+    responses(
+        (status = 200, body = [ScheduleRuleDto]),
+        (status = 401, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list_schedules(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<ScheduleRuleDto>>, ApiError> {
+    let rules = scheduler(&state)?.rules().await.map_err(scheduler_error)?;
+
+    Ok(Json(rules.iter().map(ScheduleRuleDto::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/schedules",
+    tag = "schedules",
+    request_body = ScheduleRuleRequest,
+    responses(
+        (status = 201, body = ScheduleRuleDto),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn create_schedule(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Json(request): Json<ScheduleRuleRequest>,
+) -> Result<(StatusCode, Json<ScheduleRuleDto>), ApiError> {
+    let rule = request.into_rule(Uuid::new_v4())?;
+    let rule = scheduler(&state)?
+        .save(rule)
+        .await
+        .map_err(scheduler_error)?;
+
+    Ok((StatusCode::CREATED, Json(ScheduleRuleDto::from(&rule))))
+}
+
+/// Replace the definition of a rule.
+#[utoipa::path(
+    put,
+    path = "/api/schedules/{id}",
+    tag = "schedules",
+    params(("id" = Uuid, Path)),
+    request_body = ScheduleRuleRequest,
+    responses(
+        (status = 200, body = ScheduleRuleDto),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn update_schedule(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ScheduleRuleRequest>,
+) -> Result<Json<ScheduleRuleDto>, ApiError> {
+    let scheduler = scheduler(&state)?;
+    let known = scheduler
+        .rules()
+        .await
+        .map_err(scheduler_error)?
+        .iter()
+        .any(|rule| rule.id == id);
+    if !known {
+        return Err(scheduler_error(SchedulerError::UnknownRule(id)));
+    }
+
+    let rule = scheduler
+        .save(request.into_rule(id)?)
+        .await
+        .map_err(scheduler_error)?;
+
+    Ok(Json(ScheduleRuleDto::from(&rule)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/schedules/{id}",
+    tag = "schedules",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 204),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn delete_schedule(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    scheduler(&state)?
+        .remove(id)
+        .await
+        .map_err(scheduler_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// What the rules will do from now on, overlaps resolved.
+#[utoipa::path(
+    get,
+    path = "/api/schedules/preview",
+    tag = "schedules",
+    params(PreviewParams),
+    responses(
+        (status = 200, body = [OccurrenceDto]),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn preview_schedules(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Query(params): Query<PreviewParams>,
+) -> Result<Json<Vec<OccurrenceDto>>, ApiError> {
+    let hours = params.hours.unwrap_or(DEFAULT_PREVIEW_HOURS);
+    if hours == 0 || hours > MAX_PREVIEW_HOURS {
+        return Err(ApiError::bad_request(format!(
+            "The preview covers 1 to {MAX_PREVIEW_HOURS} hours"
+        )));
+    }
+
+    let occurrences = scheduler(&state)?
+        .preview(Duration::from_secs(u64::from(hours) * 3600))
+        .await
+        .map_err(scheduler_error)?;
+
+    Ok(Json(
+        occurrences.into_iter().map(OccurrenceDto::from).collect(),
+    ))
+}
+
+fn scheduler(state: &ApiState) -> Result<&SchedulerServiceHandle, ApiError> {
+    state
+        .scheduler
+        .as_ref()
+        .ok_or_else(ApiError::scheduler_unavailable)
+}
+
+fn scheduler_error(err: SchedulerError) -> ApiError {
+    match err {
+        SchedulerError::InvalidRule(_) | SchedulerError::UnknownRelay(_) => {
+            ApiError::bad_request(err.to_string())
+        }
+        SchedulerError::UnknownRule(_) => ApiError::not_found(err.to_string()),
+        SchedulerError::RelaysUnavailable => ApiError::relays_unavailable(),
+        SchedulerError::ServiceStopped => ApiError::scheduler_unavailable(),
+        SchedulerError::Store(_) => ApiError::internal(eyre::Report::new(err)),
+    }
+}
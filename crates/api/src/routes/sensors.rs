@@ -0,0 +1,296 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::{
+    History, HistoryQuery, Resolution, RollupResolution, SensorKind as KindRecord,
+};
+use arksync_db::MeasurementStore;
+use arksync_sensor::error::SensorError;
+use arksync_sensor::sensor::{SensorInfo, SensorName};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::error::ErrorBody;
+use crate::{ApiError, ApiState, AuthUser};
+
+const DEFAULT_MAX_POINTS: u32 = 500;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SensorDto {
+    pub id: Uuid,
+    pub hardware_uid: String,
+    /// `temperature`, `ph`, `ec`, `humidity`, `co2` or `custom`
+    pub kind: String,
+    pub firmware: f64,
+    pub name: Option<String>,
+    /// `active`, `degraded`, `initializing`, `unplugged` or `unreachable`
+    pub state: String,
+    pub state_reason: String,
+    pub state_since: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    pub consecutive_failures: u32,
+}
+
+impl From<&SensorInfo> for SensorDto {
+    fn from(info: &SensorInfo) -> Self {
+        Self {
+            id: info.id,
+            hardware_uid: info.hardware_uid.clone(),
+            kind: info.kind.as_str().to_string(),
+            firmware: info.firmware,
+            name: match &info.name {
+                SensorName::Named(name) => Some(name.clone()),
+                SensorName::Unnamed => None,
+            },
+            state: info.state.as_str().to_string(),
+            state_reason: info.state_reason.code().to_string(),
+            state_since: info.state_since,
+            last_activity: info.last_activity,
+            consecutive_failures: info.consecutive_failures,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RenameSensorRequest {
+    /// 1 to 16 ASCII characters, without spaces or commas
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadingDto {
+    pub value: f64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct HistoryParams {
+    #[param(value_type = String, example = "temperature")]
+    pub kind: KindRecord,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Upper bound on the number of points, 500 by default
+    pub max_points: Option<u32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPointDto {
+    pub time: DateTime<Utc>,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HistoryDto {
+    /// `raw`, `one_minute`, `fifteen_minutes`, `one_hour` or `one_day`
+    pub resolution: String,
+    pub points: Vec<HistoryPointDto>,
+}
+
+impl From<History> for HistoryDto {
+    fn from(history: History) -> Self {
+        let resolution = match history.resolution {
+            Resolution::Raw => "raw",
+            Resolution::Rollup(RollupResolution::OneMinute) => "one_minute",
+            Resolution::Rollup(RollupResolution::FifteenMinutes) => "fifteen_minutes",
+            Resolution::Rollup(RollupResolution::OneHour) => "one_hour",
+            Resolution::Rollup(RollupResolution::OneDay) => "one_day",
+        };
+
+        Self {
+            resolution: resolution.to_string(),
+            points: history
+                .points
+                .into_iter()
+                .map(|point| HistoryPointDto {
+                    time: point.time,
+                    min: point.min,
+                    max: point.max,
+                    avg: point.avg,
+                    count: point.count,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Sensors of the registry.
+#[utoipa::path(
+    get,
+    path = "/api/sensors",
+    tag = "sensors",
+    responses(
+        (status = 200, body = [SensorDto]),
+        (status = 401, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list_sensors(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<SensorDto>>, ApiError> {
+    let sensors = state
+        .sensors
+        .all_sensors()
+        .await
+        .ok_or_else(ApiError::sensors_unavailable)?;
+
+    let mut sensors: Vec<SensorDto> = sensors
+        .values()
+        .map(|sensor| SensorDto::from(&sensor.info()))
+        .collect();
+    sensors.sort_by(|a, b| a.hardware_uid.cmp(&b.hardware_uid));
+
+    Ok(Json(sensors))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sensors/{id}",
+    tag = "sensors",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 200, body = SensorDto),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn get_sensor(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SensorDto>, ApiError> {
+    let info = require_sensor(&state, id).await?;
+
+    Ok(Json(SensorDto::from(&info)))
+}
+
+/// Store a new name on the sensor board.
+#[utoipa::path(
+    put,
+    path = "/api/sensors/{id}/name",
+    tag = "sensors",
+    params(("id" = Uuid, Path)),
+    request_body = RenameSensorRequest,
+    responses(
+        (status = 200, body = SensorDto),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 502, description = "The board didn't accept the name", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn rename_sensor(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<RenameSensorRequest>,
+) -> Result<Json<SensorDto>, ApiError> {
+    require_sensor(&state, id).await?;
+
+    let info = state
+        .sensors
+        .rename_sensor(id, request.name)
+        .await
+        .map_err(sensor_error)?;
+
+    Ok(Json(SensorDto::from(&info)))
+}
+
+/// Read a sensor now, outside of its regular ticks.
+#[utoipa::path(
+    post,
+    path = "/api/sensors/{id}/read",
+    tag = "sensors",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 200, body = ReadingDto),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 502, description = "The board didn't answer", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn read_sensor(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReadingDto>, ApiError> {
+    require_sensor(&state, id).await?;
+
+    let value = state.sensors.read_sensor(id).await.map_err(sensor_error)?;
+
+    Ok(Json(ReadingDto { value }))
+}
+
+/// Measurements of a sensor, downsampled to at most about `maxPoints`.
+#[utoipa::path(
+    get,
+    path = "/api/sensors/{id}/history",
+    tag = "sensors",
+    params(("id" = Uuid, Path), HistoryParams),
+    responses(
+        (status = 200, body = HistoryDto),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn sensor_history(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<HistoryParams>,
+) -> Result<Json<HistoryDto>, ApiError> {
+    if params.from >= params.to {
+        return Err(ApiError::bad_request(format!(
+            "Invalid history window: {} is not before {}",
+            params.from, params.to
+        )));
+    }
+
+    let query = HistoryQuery {
+        sensor_id: id,
+        kind: params.kind,
+        from: params.from,
+        to: params.to,
+        max_points: params.max_points.unwrap_or(DEFAULT_MAX_POINTS).max(1),
+    };
+    let history = MeasurementStore::new(&state.pool)
+        .history(&query)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(HistoryDto::from(history)))
+}
+
+async fn require_sensor(state: &ApiState, id: Uuid) -> Result<SensorInfo, ApiError> {
+    state
+        .sensors
+        .find_sensor(id)
+        .await
+        .map(|sensor| sensor.info())
+        .ok_or_else(|| ApiError::not_found(format!("Unknown sensor {id}")))
+}
+
+/// Rejected input is the client's fault, anything else comes from the board.
+fn sensor_error(err: SensorError) -> ApiError {
+    match err {
+        SensorError::Message(message) => ApiError::bad_request(message),
+        SensorError::Source(err) => ApiError::new(StatusCode::BAD_GATEWAY, err.to_string()),
+    }
+}
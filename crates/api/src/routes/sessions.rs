@@ -0,0 +1,88 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_users::application::use_cases::{LogIn, LogInUseCase, LogOutUseCase};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::error::ErrorBody;
+use crate::routes::users::UserDto;
+use crate::{ApiError, ApiState, AuthUser};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LogInRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionDto {
+    /// Bearer token to send in the `Authorization` header
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub user: UserDto,
+}
+
+/// Open a session.
+#[utoipa::path(
+    post,
+    path = "/api/sessions",
+    tag = "sessions",
+    request_body = LogInRequest,
+    responses(
+        (status = 201, body = SessionDto),
+        (status = 401, description = "Unknown user or wrong password", body = ErrorBody),
+    )
+)]
+pub async fn log_in(
+    State(state): State<ApiState>,
+    Json(request): Json<LogInRequest>,
+) -> Result<(StatusCode, Json<SessionDto>), ApiError> {
+    let (user, session) = LogInUseCase::new(&state.pool)
+        .execute(LogIn {
+            username: request.username,
+            password: request.password,
+            ttl: state.session_ttl,
+        })
+        .await
+        .map_err(ApiError::internal)?
+        .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "Invalid credentials"))?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(SessionDto {
+            token: session.token().as_str().to_string(),
+            expires_at: session.expires_at(),
+            user: UserDto::from(&user),
+        }),
+    ))
+}
+
+/// Close the session of the request.
+#[utoipa::path(
+    delete,
+    path = "/api/sessions",
+    tag = "sessions",
+    responses(
+        (status = 204),
+        (status = 401, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn log_out(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<StatusCode, ApiError> {
+    LogOutUseCase::new(&state.pool)
+        .execute(&auth.token)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,186 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_users::application::use_cases::{
+    CreateUser, CreateUserUseCase, DeleteUserUseCase, ListUsersUseCase,
+};
+use arksync_users::domain::User;
+use arksync_users::stores::UserStore;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth::bearer_token;
+use crate::error::ErrorBody;
+use crate::{ApiError, ApiState, AuthUser};
+
+const MIN_PASSWORD_LEN: usize = 8;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserDto {
+    pub id: Uuid,
+    pub username: String,
+}
+
+impl From<&User> for UserDto {
+    fn from(user: &User) -> Self {
+        Self {
+            id: user.id(),
+            username: user.username().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateUserRequest {
+    pub username: String,
+    /// At least 8 characters
+    pub password: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users",
+    tag = "users",
+    responses(
+        (status = 200, body = [UserDto]),
+        (status = 401, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list_users(
+    _auth: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<UserDto>>, ApiError> {
+    let users = ListUsersUseCase::new(&state.pool)
+        .execute()
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(users.iter().map(UserDto::from).collect()))
+}
+
+/// Create a user.
+///
+/// The first user of a station can be created without a session, every other
+/// one needs a logged in user.
+#[utoipa::path(
+    post,
+    path = "/api/users",
+    tag = "users",
+    request_body = CreateUserRequest,
+    responses(
+        (status = 201, body = UserDto),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+    ),
+    security(("bearer" = []), ())
+)]
+pub async fn create_user(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateUserRequest>,
+) -> Result<(StatusCode, Json<UserDto>), ApiError> {
+    let existing = UserStore::new(&state.pool)
+        .count()
+        .await
+        .map_err(ApiError::internal)?;
+    let first = existing == 0;
+    if !first {
+        let token = bearer_token(&headers).ok_or_else(ApiError::unauthorized)?;
+        AuthUser::authenticate(&state, token).await?;
+    }
+
+    let username = request.username.trim();
+    if username.is_empty() {
+        return Err(ApiError::bad_request("The username can't be empty"));
+    }
+    if request.password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(ApiError::bad_request(format!(
+            "The password needs at least {MIN_PASSWORD_LEN} characters"
+        )));
+    }
+    if UserStore::new(&state.pool)
+        .find_by_username(username)
+        .await
+        .map_err(ApiError::internal)?
+        .is_some()
+    {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("User '{username}' already exists"),
+        ));
+    }
+
+    let cmd = CreateUser {
+        username: username.to_string(),
+        password: request.password,
+    };
+    let use_case = CreateUserUseCase::new(&state.pool);
+    let user = if first {
+        // Another request may have created the first user meanwhile
+        use_case
+            .execute_first(cmd)
+            .await
+            .map_err(ApiError::internal)?
+            .ok_or_else(ApiError::unauthorized)?
+    } else {
+        use_case.execute(cmd).await.map_err(ApiError::internal)?
+    };
+
+    Ok((StatusCode::CREATED, Json(UserDto::from(&user))))
+}
+
+/// User of the session.
+#[utoipa::path(
+    get,
+    path = "/api/users/me",
+    tag = "users",
+    responses(
+        (status = 200, body = UserDto),
+        (status = 401, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn current_user(auth: AuthUser) -> Json<UserDto> {
+    Json(UserDto::from(&auth.user))
+}
+
+/// Delete a user and close its sessions.
+#[utoipa::path(
+    delete,
+    path = "/api/users/{id}",
+    tag = "users",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 204),
+        (status = 400, description = "Deleting the user of the session", body = ErrorBody),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn delete_user(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if auth.user.id() == id {
+        return Err(ApiError::bad_request("You can't delete your own user"));
+    }
+
+    let deleted = DeleteUserUseCase::new(&state.pool)
+        .execute(id)
+        .await
+        .map_err(ApiError::internal)?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::not_found(format!("Unknown user {id}")))
+    }
+}
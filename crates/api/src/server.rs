@@ -0,0 +1,80 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use axum::routing::{delete, get, post, put};
+use axum::{Json, Router};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use utoipa::OpenApi;
+
+use crate::routes::{alerts, control, events, relays, schedules, sensors, sessions, users};
+use crate::{ApiDoc, ApiState};
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/api/openapi.json", get(openapi))
+        .route(
+            "/api/sessions",
+            post(sessions::log_in).delete(sessions::log_out),
+        )
+        .route("/api/sensors", get(sensors::list_sensors))
+        .route("/api/sensors/{id}", get(sensors::get_sensor))
+        .route("/api/sensors/{id}/name", put(sensors::rename_sensor))
+        .route("/api/sensors/{id}/read", post(sensors::read_sensor))
+        .route("/api/sensors/{id}/history", get(sensors::sensor_history))
+        .route("/api/relays", get(relays::list_relays))
+        .route("/api/alerts", get(alerts::list_alerts))
+        .route("/api/alerts/history", get(alerts::alert_history))
+        .route(
+            "/api/alerts/{id}/acknowledge",
+            post(alerts::acknowledge_alert),
+        )
+        .route("/api/relays/{id}", put(relays::set_relay))
+        .route("/api/control/rules", get(control::list_control_rules))
+        .route("/api/control/rules/{id}/terms", get(control::pid_terms))
+        .route(
+            "/api/control/rules/{id}/setpoint",
+            put(control::set_setpoint),
+        )
+        .route("/api/control/rules/{id}/mode", put(control::set_mode))
+        .route(
+            "/api/schedules",
+            get(schedules::list_schedules).post(schedules::create_schedule),
+        )
+        .route("/api/schedules/preview", get(schedules::preview_schedules))
+        .route(
+            "/api/schedules/{id}",
+            put(schedules::update_schedule).delete(schedules::delete_schedule),
+        )
+        .route(
+            "/api/users",
+            get(users::list_users).post(users::create_user),
+        )
+        .route("/api/users/me", get(users::current_user))
+        .route("/api/users/{id}", delete(users::delete_user))
+        .route("/api/events", get(events::live_events))
+        .with_state(state)
+}
+
+/// Serve the API on `listener` until `shutdown` is cancelled.
+///
+/// Live event streams are closed on shutdown.
+pub async fn serve(
+    listener: TcpListener,
+    mut state: ApiState,
+    shutdown: CancellationToken,
+) -> std::io::Result<()> {
+    if let Ok(addr) = listener.local_addr() {
+        tracing::info!(%addr, "API listening.");
+    }
+    state.shutdown = shutdown.clone();
+
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await
+}
+
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
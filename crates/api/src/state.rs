@@ -0,0 +1,66 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::services::ActuatorServiceHandle;
+use arksync_alert::services::AlertServiceHandle;
+use arksync_control::services::ControlServiceHandle;
+use arksync_scheduler::services::SchedulerServiceHandle;
+use arksync_sensor::services::SensorServiceHandle;
+use chrono::TimeDelta;
+use sqlx::PgPool;
+use tokio_util::sync::CancellationToken;
+
+use crate::CONFIG;
+
+/// What the handlers share.
+#[derive(Clone)]
+pub struct ApiState {
+    pub(crate) pool: PgPool,
+    pub(crate) sensors: SensorServiceHandle,
+    pub(crate) relays: ActuatorServiceHandle,
+    /// Schedule routes answer 503 without it
+    pub(crate) scheduler: Option<SchedulerServiceHandle>,
+    /// Alert routes answer 503 without it, the history still reads Postgres
+    pub(crate) alerts: Option<AlertServiceHandle>,
+    /// Control routes answer 503 without it
+    pub(crate) control: Option<ControlServiceHandle>,
+    pub(crate) session_ttl: TimeDelta,
+    /// Cancelled when the server stops, closes the live streams
+    pub(crate) shutdown: CancellationToken,
+}
+
+impl ApiState {
+    pub fn new(pool: PgPool, sensors: SensorServiceHandle, relays: ActuatorServiceHandle) -> Self {
+        Self {
+            pool,
+            sensors,
+            relays,
+            scheduler: None,
+            alerts: None,
+            control: None,
+            session_ttl: TimeDelta::from_std(CONFIG.session_ttl).unwrap_or(TimeDelta::days(7)),
+            shutdown: CancellationToken::new(),
+        }
+    }
+
+    pub fn with_scheduler(mut self, scheduler: SchedulerServiceHandle) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    pub fn with_alerts(mut self, alerts: AlertServiceHandle) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    pub fn with_control(mut self, control: ControlServiceHandle) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    pub fn with_session_ttl(mut self, session_ttl: TimeDelta) -> Self {
+        self.session_ttl = session_ttl;
+        self
+    }
+}
@@ -0,0 +1,85 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::relay::RelayBank;
+use arksync_actuator::services::ActuatorService;
+use arksync_api::{serve, ApiState};
+use arksync_sensor::services::SensorService;
+use serde_json::Value;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+async fn start() -> (
+    SocketAddr,
+    CancellationToken,
+    JoinHandle<std::io::Result<()>>,
+) {
+    // The pool connects lazily, nothing here reaches the database
+    let state = ApiState::new(
+        arksync_db::pool().clone(),
+        SensorService::new().handle(),
+        ActuatorService::new(RelayBank::default()).handle(),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let shutdown = CancellationToken::new();
+    let server = tokio::spawn(serve(listener, state, shutdown.clone()));
+
+    (addr, shutdown, server)
+}
+
+#[tokio::test]
+async fn openapi_description_is_public() {
+    let (addr, shutdown, server) = start().await;
+
+    let openapi: Value = reqwest::get(format!("http://{addr}/api/openapi.json"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert!(openapi["paths"]["/api/sensors/{id}/history"]["get"].is_object());
+    assert!(openapi["paths"]["/api/relays/{id}"]["put"].is_object());
+    assert!(openapi["paths"]["/api/schedules/preview"]["get"].is_object());
+    assert!(openapi["paths"]["/api/alerts/{id}/acknowledge"]["post"].is_object());
+    assert!(openapi["paths"]["/api/control/rules/{id}/mode"]["put"].is_object());
+    assert_eq!(
+        openapi["components"]["securitySchemes"]["bearer"]["scheme"],
+        "bearer"
+    );
+    assert!(openapi["components"]["schemas"]["LiveEvent"].is_object());
+
+    shutdown.cancel();
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn requests_without_a_session_are_rejected() {
+    let (addr, shutdown, server) = start().await;
+    let client = reqwest::Client::new();
+
+    for path in [
+        "/api/sensors",
+        "/api/relays",
+        "/api/schedules/preview",
+        "/api/alerts",
+        "/api/alerts/history",
+        "/api/control/rules",
+        "/api/users/me",
+        "/api/events",
+    ] {
+        let response = client
+            .get(format!("http://{addr}{path}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401, "{path}");
+    }
+
+    shutdown.cancel();
+    server.await.unwrap().unwrap();
+}
@@ -5,6 +5,7 @@
 use clap::{Parser, Subcommand};
 
 mod db;
+mod station;
 
 #[derive(Debug, Parser)]
 #[command(name = "sk")]
@@ -18,6 +19,10 @@ struct Cli {
 enum Command {
     #[command(subcommand)]
     Db(db::DbCommand),
+    /// Run this Pi as a remote knot of a hub, without the app
+    Knot,
+    /// Accept the remote knots of this station, without the app
+    Hub,
 }
 
 #[tokio::main]
@@ -26,5 +31,7 @@ async fn main() -> eyre::Result<()> {
 
     match cli.command {
         Command::Db(cmd) => cmd.exec().await,
+        Command::Knot => station::run_knot().await,
+        Command::Hub => station::run_hub().await,
     }
 }
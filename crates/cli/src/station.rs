@@ -0,0 +1,296 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::relay::RelayBank;
+use arksync_actuator::services::{ActuatorPersistence, ActuatorService, ActuatorServiceHandle};
+use arksync_api::ApiState;
+use arksync_control::services::ControlService;
+use arksync_db::{pool, ActuatorStore, MeasurementStore, ScheduleStore, SensorStore};
+use arksync_scheduler::services::SchedulerService;
+use arksync_sensor::event::SensorEvent;
+use arksync_sensor::identity::IdentityResolver;
+use arksync_sensor::services::{
+    MeasurementRecorder, SensorPersistence, SensorService, SensorServiceHandle,
+};
+use arksync_sync::auth::PairingKey;
+use arksync_sync::services::{HubService, KnotAgent, KnotPersistence};
+use arksync_sync::CONFIG;
+use arksync_telemetry::mqtt::{
+    self, HomeAssistantDiscovery, RumqttPublisher, StationCommands, Topics,
+};
+use arksync_telemetry::{InfluxExporter, MqttBridge, QueueWriter, StationMetrics, TelemetryQueue};
+use std::future::Future;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing_subscriber::EnvFilter;
+
+/// Run the sensors, relays and control rules of a remote knot headless,
+/// reporting to its hub until interrupted.
+pub async fn run_knot() -> eyre::Result<()> {
+    init_tracing();
+    let key = PairingKey::new(&CONFIG.pairing_key)?;
+    let shutdown = cancel_on_ctrl_c();
+
+    let sensors = SensorService::new();
+    let sensor_events = sensors.handle().subscribe();
+    let relays = ActuatorService::new(RelayBank::open(
+        &arksync_actuator::CONFIG.boards,
+        &arksync_actuator::CONFIG.gpio,
+    )?)
+    .with_safety_limits(arksync_actuator::CONFIG.safety.clone());
+    let control = ControlService::new(
+        relays.handle(),
+        arksync_control::CONFIG.rules.clone(),
+        &arksync_actuator::CONFIG.safety,
+    )?;
+    let control_events = sensors.handle().subscribe();
+    let agent = KnotAgent::new(&CONFIG.knot, key)?
+        .with_sensors(sensor_events)
+        .with_relays(relays.handle());
+    let queue = open_queue()?;
+    let telemetry = export_telemetry(
+        queue.clone(),
+        &sensors.handle(),
+        &relays.handle(),
+        shutdown.clone(),
+    );
+    let metrics = serve_metrics(
+        sensors.handle().subscribe(),
+        relays.handle(),
+        queue,
+        shutdown.clone(),
+    );
+
+    let (.., exported, metrics) = tokio::join!(
+        sensors.run(shutdown.clone()),
+        relays.run(shutdown.clone()),
+        control.run(control_events, shutdown.clone()),
+        agent.run(shutdown),
+        telemetry,
+        metrics,
+    );
+
+    exported?;
+    metrics
+}
+
+/// Run the sensors, relays, schedules and control rules of the local hub and
+/// accept its remote knots until interrupted.
+///
+/// With the API enabled, they are also served over HTTP, e.g. on a Pi without
+/// a screen.
+pub async fn run_hub() -> eyre::Result<()> {
+    init_tracing();
+    let key = PairingKey::new(&CONFIG.pairing_key)?;
+    let shutdown = cancel_on_ctrl_c();
+
+    arksync_db::run().await?;
+    let local = arksync_db::register_local_hub().await?;
+    let hub = HubService::bind(&CONFIG.hub, key)
+        .await?
+        .with_persistence(KnotPersistence::new(pool(), local.station_hub_id));
+
+    let sensors = SensorService::new()
+        .with_identity_resolver(IdentityResolver::new(Some(SensorStore::new(pool()))))
+        .with_persistence(SensorPersistence::new(SensorStore::new(pool()), local.id))
+        .with_measurement_recorder(MeasurementRecorder::new(MeasurementStore::new(pool())));
+    let relays = ActuatorService::new(RelayBank::open(
+        &arksync_actuator::CONFIG.boards,
+        &arksync_actuator::CONFIG.gpio,
+    )?)
+    .with_safety_limits(arksync_actuator::CONFIG.safety.clone())
+    .with_persistence(ActuatorPersistence::new(
+        ActuatorStore::new(pool()),
+        local.id,
+    ));
+    let scheduler = SchedulerService::new(relays.handle(), arksync_scheduler::CONFIG.location)
+        .with_store(ScheduleStore::new(pool()));
+    let control = ControlService::new(
+        relays.handle(),
+        arksync_control::CONFIG.rules.clone(),
+        &arksync_actuator::CONFIG.safety,
+    )?;
+    let control_events = sensors.handle().subscribe();
+    let api = serve_api(
+        ApiState::new(pool().clone(), sensors.handle(), relays.handle())
+            .with_scheduler(scheduler.handle())
+            .with_control(control.handle()),
+        shutdown.clone(),
+    );
+    let queue = open_queue()?;
+    let telemetry = export_telemetry(
+        queue.clone(),
+        &sensors.handle(),
+        &relays.handle(),
+        shutdown.clone(),
+    );
+    let metrics = serve_metrics(
+        sensors.handle().subscribe(),
+        relays.handle(),
+        queue,
+        shutdown.clone(),
+    );
+
+    let (.., served, exported, metrics) = tokio::join!(
+        hub.run(shutdown.clone()),
+        sensors.run(shutdown.clone()),
+        relays.run(shutdown.clone()),
+        scheduler.run(shutdown.clone()),
+        control.run(control_events, shutdown.clone()),
+        api,
+        telemetry,
+        metrics,
+    );
+
+    served?;
+    exported?;
+    metrics
+}
+
+/// Serve the API until `shutdown`, if enabled.
+async fn serve_api(state: ApiState, shutdown: CancellationToken) -> eyre::Result<()> {
+    let config = &arksync_api::CONFIG;
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let listener = TcpListener::bind(config.listen).await?;
+    Ok(arksync_api::serve(listener, state, shutdown).await?)
+}
+
+/// The telemetry queue, opened only when an exporter reads it.
+fn open_queue() -> eyre::Result<Option<TelemetryQueue>> {
+    let config = &arksync_telemetry::CONFIG;
+    if !config.influx.enabled && !config.mqtt.enabled {
+        return Ok(None);
+    }
+
+    Ok(Some(TelemetryQueue::open(&config.queue)?))
+}
+
+/// Append the sensor events to the queue and run the enabled exporters on it
+/// until `shutdown`: InfluxDB, and the MQTT bridge driving the relays.
+///
+/// Called before the sensor service runs so that no event is missed.
+fn export_telemetry(
+    queue: Option<TelemetryQueue>,
+    sensors: &SensorServiceHandle,
+    relays: &ActuatorServiceHandle,
+    shutdown: CancellationToken,
+) -> impl Future<Output = eyre::Result<()>> {
+    let config = &arksync_telemetry::CONFIG;
+    let sensor_events = sensors.subscribe();
+    let bridge_events = sensors.subscribe();
+    let relay_events = relays.subscribe();
+    let commands = StationCommands::new(sensors.clone()).with_relays(relays.clone());
+    let relays = relays.clone();
+
+    async move {
+        let Some(queue) = queue else {
+            return Ok(());
+        };
+
+        let influx = async {
+            if !config.influx.enabled {
+                return eyre::Ok(());
+            }
+
+            let exporter = InfluxExporter::new(&config.influx)?;
+            let consumer = queue.consumer(InfluxExporter::CONSUMER).await?;
+            exporter.run(consumer, shutdown.clone()).await;
+            Ok(())
+        };
+
+        let bridge = async {
+            if !config.mqtt.enabled {
+                return eyre::Ok(());
+            }
+
+            let topics = Topics::new(&config.mqtt.topic_prefix, &config.mqtt.knot);
+            let (publisher, transport) = mqtt::connect(&config.mqtt, &topics);
+            let states = relays.states().await.unwrap_or_default();
+            let mut bridge =
+                MqttBridge::new(publisher, commands, topics).with_relays(states, relay_events);
+            if let Some(prefix) = &config.mqtt.discovery_prefix {
+                bridge = bridge.with_discovery(HomeAssistantDiscovery::new(prefix.clone()));
+            }
+            let consumer = queue
+                .consumer(MqttBridge::<RumqttPublisher, StationCommands>::CONSUMER)
+                .await?;
+            bridge
+                .run(transport, bridge_events, consumer, shutdown.clone())
+                .await;
+            Ok(())
+        };
+
+        let (_, influx, bridge) = tokio::join!(
+            QueueWriter::new(queue.clone()).run(sensor_events, shutdown.clone()),
+            influx,
+            bridge,
+        );
+
+        influx.and(bridge)
+    }
+}
+
+/// Serve the metrics of the sensors, relays and telemetry queue until
+/// `shutdown`, if enabled.
+///
+/// `sensor_events` must be subscribed before the sensor service runs so that
+/// no sensor is missed.
+async fn serve_metrics(
+    sensor_events: broadcast::Receiver<SensorEvent>,
+    relays: ActuatorServiceHandle,
+    queue: Option<TelemetryQueue>,
+    shutdown: CancellationToken,
+) -> eyre::Result<()> {
+    let config = &arksync_telemetry::CONFIG.metrics;
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let listener = TcpListener::bind(config.listen).await?;
+    let relay_events = relays.subscribe();
+    let states = relays.states().await.unwrap_or_default();
+    let mut metrics = StationMetrics::new().with_relays(states.into_iter().map(|(_, state)| state));
+    if let Some(queue) = queue {
+        metrics = metrics.with_queue(queue);
+    }
+
+    let (.., served) = tokio::join!(
+        metrics.clone().run(sensor_events, shutdown.clone()),
+        metrics.clone().run_relays(relay_events, shutdown.clone()),
+        arksync_telemetry::metrics::serve(listener, metrics, shutdown),
+    );
+
+    Ok(served?)
+}
+
+/// Levels are set with `RUST_LOG`, e.g. `RUST_LOG=arksync_sync=debug`, and
+/// `ARKSYNC_LOG_FORMAT=json` logs one JSON object per line.
+fn init_tracing() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+
+    if std::env::var("ARKSYNC_LOG_FORMAT").is_ok_and(|format| format == "json") {
+        subscriber.json().with_current_span(true).init();
+    } else {
+        subscriber.init();
+    }
+}
+
+fn cancel_on_ctrl_c() -> CancellationToken {
+    let shutdown = CancellationToken::new();
+
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            let _ = tokio::signal::ctrl_c().await;
+            shutdown.cancel();
+        }
+    });
+
+    shutdown
+}
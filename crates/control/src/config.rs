@@ -0,0 +1,20 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_config::ConfigHandler;
+use std::sync::LazyLock;
+
+use crate::rule::ControlRule;
+
+pub static CONFIG: LazyLock<Config> = LazyLock::new(|| ConfigHandler::new(mpl).load());
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Control loops of the station, none until sensors are bound to relays
+    pub rules: Vec<ControlRule>,
+}
+
+fn mpl() -> Config {
+    Config { rules: Vec::new() }
+}
@@ -0,0 +1,357 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::safety::SafetyLimits;
+use arksync_sensor::sensor::SensorState;
+use tokio::time::{Duration, Instant};
+
+use crate::error::{ControlError, Result};
+use crate::pid::{Mode, Pid, PidTerms};
+use crate::proportioning::{TimeProportioning, Window};
+use crate::rule::{ControlRule, FaultBehaviour, Strategy};
+
+/// Wait after a refused switch before asking again.
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// PID state of a rule.
+#[derive(Debug)]
+struct PidOutput {
+    pid: Pid,
+    output: TimeProportioning,
+    window: Option<Window>,
+    last_measurement: Option<Instant>,
+}
+
+/// Control loop of one rule, fed with the sensor and the relay states.
+///
+/// It only decides: the returned commands are applied by the
+/// [`crate::services::ControlService`], which reports back the relay
+/// switches, whoever made them.
+#[derive(Debug)]
+pub struct Controller {
+    rule: ControlRule,
+    /// Limits of the relay, enforced by the actuator service, the PID
+    /// windows are shaped to fit them
+    limits: Option<SafetyLimits>,
+    pid: Option<PidOutput>,
+    relay_on: bool,
+    last_switch: Option<Instant>,
+    /// State the rule asks for, `None` leaves the relay as it is
+    demand: Option<bool>,
+    sensor_fault: bool,
+    retry_at: Option<Instant>,
+}
+
+impl Controller {
+    pub fn new(rule: ControlRule, limits: Option<SafetyLimits>) -> Self {
+        let pid = match rule.strategy {
+            Strategy::Pid(settings) => Some(PidOutput {
+                pid: Pid::new(
+                    settings.tuning,
+                    settings.direction,
+                    settings.setpoint,
+                    settings.ramp_per_minute.map(|ramp| ramp / 60.0),
+                ),
+                output: settings.output,
+                window: None,
+                last_measurement: None,
+            }),
+            Strategy::Hysteresis(_) => None,
+        };
+
+        Self {
+            rule,
+            limits,
+            pid,
+            relay_on: false,
+            last_switch: None,
+            demand: None,
+            sensor_fault: false,
+            retry_at: None,
+        }
+    }
+
+    pub fn rule(&self) -> &ControlRule {
+        &self.rule
+    }
+
+    /// Terms of the last PID update, `None` for other strategies.
+    pub fn pid_terms(&self) -> Option<PidTerms> {
+        self.pid.as_ref().and_then(|pid| pid.pid.terms())
+    }
+
+    /// Move the PID setpoint, along its ramp.
+    pub fn set_setpoint(&mut self, setpoint: f64) -> Result<()> {
+        self.pid_mut()?.pid.set_target(setpoint);
+        Ok(())
+    }
+
+    /// Switch the PID between manual and auto, the next window applies it.
+    pub fn set_mode(&mut self, mode: Mode) -> Result<()> {
+        self.pid_mut()?.pid.set_mode(mode);
+        Ok(())
+    }
+
+    /// The relay was switched, by this rule or anyone else.
+    pub fn relay_switched(&mut self, on: bool, now: Instant) {
+        if self.relay_on != on {
+            self.relay_on = on;
+            self.last_switch = Some(now);
+        }
+        self.retry_at = None;
+    }
+
+    /// The relay refused the last command, ask again later.
+    pub fn refused(&mut self, now: Instant) {
+        self.retry_at = Some(now + RETRY_DELAY);
+    }
+
+    /// A valid measurement of the sensor, ignored while it is faulty.
+    pub fn measurement(&mut self, value: f64, now: Instant) -> Option<bool> {
+        if !self.sensor_fault {
+            match (&mut self.pid, self.rule.strategy) {
+                (Some(pid), _) => {
+                    let elapsed = pid
+                        .last_measurement
+                        .map_or(Duration::ZERO, |last| now.duration_since(last));
+                    pid.pid.update(value, elapsed);
+                    pid.last_measurement = Some(now);
+                }
+                (None, Strategy::Hysteresis(hysteresis)) => {
+                    if let Some(demand) = hysteresis.demand(value) {
+                        self.demand = Some(demand);
+                    }
+                }
+                (None, Strategy::Pid(_)) => {}
+            }
+        }
+        self.command(now)
+    }
+
+    /// The sensor moved to `state`, `None` once it left the registry.
+    pub fn sensor_state(&mut self, state: Option<SensorState>, now: Instant) -> Option<bool> {
+        let fault = matches!(
+            state,
+            None | Some(SensorState::Degraded | SensorState::Unreachable | SensorState::Unplugged)
+        );
+        if fault == self.sensor_fault {
+            return self.command(now);
+        }
+
+        self.sensor_fault = fault;
+        self.demand = match (fault, self.rule.on_sensor_fault) {
+            (true, FaultBehaviour::Hold) => None,
+            (true, FaultBehaviour::FailSafeOff) => Some(false),
+            (true, FaultBehaviour::FailSafeOn) => Some(true),
+            // Wait for the next measurement
+            (false, _) => None,
+        };
+        if let Some(pid) = &mut self.pid {
+            pid.window = None;
+            pid.last_measurement = None;
+            pid.pid.restart();
+        }
+        if fault {
+            tracing::warn!(
+                rule_id = %self.rule.id,
+                relay_id = self.rule.relay_id,
+                on_sensor_fault = ?self.rule.on_sensor_fault,
+                "Control rule sensor faulty."
+            );
+        }
+        self.command(now)
+    }
+
+    /// Commands held back by the minimum times, and the PID windows, become
+    /// due with time.
+    pub fn tick(&mut self, now: Instant) -> Option<bool> {
+        self.command(now)
+    }
+
+    /// Switch to ask for now, if any.
+    fn command(&mut self, now: Instant) -> Option<bool> {
+        if !self.sensor_fault {
+            self.window_demand(now);
+        }
+
+        let demand = self.demand.filter(|demand| *demand != self.relay_on)?;
+        if self.retry_at.is_some_and(|retry_at| now < retry_at) {
+            return None;
+        }
+
+        // The fail-safe states don't wait for the minimum times
+        if self.sensor_fault {
+            return Some(demand);
+        }
+
+        let min_time = if self.relay_on {
+            self.rule.min_on_time
+        } else {
+            self.rule.min_off_time
+        };
+        let elapsed = self
+            .last_switch
+            .is_none_or(|last_switch| now.duration_since(last_switch) >= min_time);
+
+        elapsed.then_some(demand)
+    }
+
+    /// Follow the PID output through its windows, a new window starts with
+    /// the duty of the last update.
+    fn window_demand(&mut self, now: Instant) {
+        let Some(pid) = &mut self.pid else {
+            return;
+        };
+
+        let window = pid.output.window;
+        let demand = pid.window.and_then(|current| current.demand(window, now));
+        self.demand = match demand {
+            Some(demand) => Some(demand),
+            None => {
+                let duty = match pid.pid.mode() {
+                    Mode::Manual { output } => Some(output),
+                    Mode::Auto => pid.pid.terms().map(|terms| terms.output),
+                };
+                duty.map(|duty| {
+                    let current = Window {
+                        started_at: now,
+                        on_time: pid.output.on_time(duty, self.limits.as_ref()),
+                    };
+                    pid.window = Some(current);
+                    !current.on_time.is_zero()
+                })
+            }
+        };
+    }
+
+    fn pid_mut(&mut self) -> Result<&mut PidOutput> {
+        self.pid.as_mut().ok_or(ControlError::NotPid(self.rule.id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    use crate::pid::{Direction, PidTuning};
+    use crate::rule::{Hysteresis, PidSettings};
+
+    fn rule(strategy: Strategy, on_sensor_fault: FaultBehaviour) -> ControlRule {
+        ControlRule {
+            id: Uuid::nil(),
+            name: "heater".to_string(),
+            sensor_id: Uuid::nil(),
+            relay_id: "heater_relay".to_string(),
+            strategy,
+            min_on_time: Duration::from_secs(60),
+            min_off_time: Duration::from_secs(120),
+            on_sensor_fault,
+        }
+    }
+
+    fn heater(on_sensor_fault: FaultBehaviour) -> Controller {
+        let hysteresis = Hysteresis::Raise {
+            on_below: 24.0,
+            off_above: 25.0,
+        };
+        Controller::new(
+            rule(Strategy::Hysteresis(hysteresis), on_sensor_fault),
+            None,
+        )
+    }
+
+    #[test]
+    fn the_relay_keeps_its_state_inside_the_band() {
+        let mut heater = heater(FaultBehaviour::Hold);
+        let now = Instant::now();
+
+        assert_eq!(heater.measurement(24.5, now), None);
+        assert_eq!(heater.measurement(23.9, now), Some(true));
+        heater.relay_switched(true, now);
+
+        let later = now + Duration::from_secs(300);
+        assert_eq!(heater.measurement(24.5, later), None);
+        assert_eq!(heater.measurement(25.1, later), Some(false));
+    }
+
+    #[test]
+    fn minimum_times_delay_the_commands() {
+        let mut heater = heater(FaultBehaviour::Hold);
+        let now = Instant::now();
+
+        heater.relay_switched(true, now);
+        assert_eq!(heater.measurement(26.0, now), None);
+        assert_eq!(heater.tick(now + Duration::from_secs(59)), None);
+        assert_eq!(heater.tick(now + Duration::from_secs(60)), Some(false));
+
+        let off = now + Duration::from_secs(60);
+        heater.relay_switched(false, off);
+        assert_eq!(heater.measurement(20.0, off), None);
+        assert_eq!(heater.tick(off + Duration::from_secs(120)), Some(true));
+    }
+
+    #[test]
+    fn faulty_sensors_apply_the_fault_behaviour() {
+        let now = Instant::now();
+
+        let mut held = heater(FaultBehaviour::Hold);
+        held.relay_switched(true, now);
+        assert_eq!(held.sensor_state(Some(SensorState::Unreachable), now), None);
+
+        let mut released = heater(FaultBehaviour::FailSafeOff);
+        released.relay_switched(true, now);
+        // Right away, regardless of the minimum on time
+        assert_eq!(
+            released.sensor_state(Some(SensorState::Degraded), now),
+            Some(false)
+        );
+        released.relay_switched(false, now);
+        // Measurements wait for the sensor to recover
+        assert_eq!(released.measurement(20.0, now), None);
+        assert_eq!(released.sensor_state(Some(SensorState::Active), now), None);
+
+        let mut energized = heater(FaultBehaviour::FailSafeOn);
+        assert_eq!(energized.sensor_state(None, now), Some(true));
+    }
+
+    #[test]
+    fn a_pid_duty_is_spread_over_its_window() {
+        let settings = PidSettings {
+            setpoint: 25.0,
+            tuning: PidTuning {
+                kp: 0.4,
+                ki: 0.0,
+                kd: 0.0,
+                derivative_filter: Duration::ZERO,
+            },
+            direction: Direction::Raise,
+            ramp_per_minute: None,
+            output: TimeProportioning {
+                window: Duration::from_secs(60),
+                min_pulse: Duration::from_secs(1),
+            },
+        };
+        let mut pid = rule(Strategy::Pid(settings), FaultBehaviour::Hold);
+        pid.min_on_time = Duration::ZERO;
+        pid.min_off_time = Duration::ZERO;
+        let mut pid = Controller::new(pid, None);
+        let now = Instant::now();
+        let at = |secs| now + Duration::from_secs(secs);
+
+        // 40 % of the window
+        assert_eq!(pid.measurement(24.0, now), Some(true));
+        assert_eq!(pid.pid_terms().unwrap().output, 0.4);
+        pid.relay_switched(true, now);
+        assert_eq!(pid.tick(at(23)), None);
+        assert_eq!(pid.tick(at(24)), Some(false));
+        pid.relay_switched(false, at(24));
+
+        // Manual output from the next window
+        pid.set_mode(Mode::Manual { output: 1.0 }).unwrap();
+        assert_eq!(pid.tick(at(59)), None);
+        assert_eq!(pid.tick(at(60)), Some(true));
+        assert!(heater(FaultBehaviour::Hold).set_setpoint(20.0).is_err());
+    }
+}
@@ -0,0 +1,32 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::error::Error;
+use std::fmt;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlError {
+    /// The rule can't drive its relay, e.g. an inverted hysteresis band.
+    InvalidRule(String),
+    UnknownRule(Uuid),
+    /// Setpoints and modes only apply to PID rules.
+    NotPid(Uuid),
+    ServiceStopped,
+}
+
+impl fmt::Display for ControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControlError::InvalidRule(details) => write!(f, "Invalid control rule: {details}"),
+            ControlError::UnknownRule(id) => write!(f, "Unknown control rule {id}"),
+            ControlError::NotPid(id) => write!(f, "Control rule {id} is not a PID loop"),
+            ControlError::ServiceStopped => write!(f, "Control service stopped"),
+        }
+    }
+}
+
+impl Error for ControlError {}
+
+pub type Result<T> = std::result::Result<T, ControlError>;
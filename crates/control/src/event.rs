@@ -0,0 +1,26 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::pid::PidTerms;
+
+/// Events published by the control service.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ControlEvent {
+    /// A PID loop took a measurement in, with its terms for charting.
+    PidUpdated { rule_id: Uuid, terms: PidTerms },
+    /// A rule switched its relay.
+    RelaySwitched {
+        rule_id: Uuid,
+        relay_id: String,
+        on: bool,
+    },
+}
@@ -0,0 +1,265 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Gains of a PID loop, the output is a duty cycle between 0 and 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PidTuning {
+    /// Duty per unit of error
+    pub kp: f64,
+    /// Duty per unit of error and second
+    pub ki: f64,
+    /// Duty per unit of error change per second
+    pub kd: f64,
+    /// Time constant of the low-pass filter on the derivative, it keeps the
+    /// sensor noise away from the output
+    pub derivative_filter: Duration,
+}
+
+/// What energizing the relay does to the measured value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// e.g. a heater or a CO₂ valve
+    #[default]
+    Raise,
+    /// e.g. a chiller or an extractor
+    Lower,
+}
+
+impl Direction {
+    fn sign(self) -> f64 {
+        match self {
+            Direction::Raise => 1.0,
+            Direction::Lower => -1.0,
+        }
+    }
+}
+
+/// Who sets the output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Mode {
+    #[default]
+    Auto,
+    /// Fixed duty set by the operator, the loop keeps tracking it to take
+    /// over without a bump.
+    Manual { output: f64 },
+}
+
+/// Internal state of a PID update, for charting and tuning.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PidTerms {
+    /// Setpoint in use, on its way to the target while ramping
+    pub setpoint: f64,
+    pub measurement: f64,
+    pub error: f64,
+    pub proportional: f64,
+    pub integral: f64,
+    pub derivative: f64,
+    /// Duty cycle applied, between 0 and 1
+    pub output: f64,
+    pub mode: Mode,
+}
+
+/// PID loop with anti-windup, a filtered derivative, setpoint ramps and
+/// bumpless manual/auto transfer.
+///
+/// The derivative acts on the measurement, so a setpoint change doesn't
+/// kick the output.
+#[derive(Clone, Debug)]
+pub struct Pid {
+    tuning: PidTuning,
+    direction: Direction,
+    target: f64,
+    /// Setpoint change per second, the setpoint jumps to the target without
+    ramp: Option<f64>,
+    setpoint: Option<f64>,
+    integral: f64,
+    derivative: f64,
+    last_measurement: Option<f64>,
+    mode: Mode,
+    terms: Option<PidTerms>,
+}
+
+impl Pid {
+    pub fn new(tuning: PidTuning, direction: Direction, target: f64, ramp: Option<f64>) -> Self {
+        Self {
+            tuning,
+            direction,
+            target,
+            ramp,
+            setpoint: None,
+            integral: 0.0,
+            derivative: 0.0,
+            last_measurement: None,
+            mode: Mode::Auto,
+            terms: None,
+        }
+    }
+
+    pub fn target(&self) -> f64 {
+        self.target
+    }
+
+    /// Move the setpoint to `target`, along the ramp if any.
+    pub fn set_target(&mut self, target: f64) {
+        self.target = target;
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Switch between manual and auto, the output continues from where it is.
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = match mode {
+            Mode::Manual { output } => Mode::Manual {
+                output: output.clamp(0.0, 1.0),
+            },
+            Mode::Auto => Mode::Auto,
+        };
+    }
+
+    /// Terms of the last update.
+    pub fn terms(&self) -> Option<PidTerms> {
+        self.terms
+    }
+
+    /// Forget the last measurement, e.g. after a sensor fault, so the gap
+    /// doesn't show up as a derivative spike. The integral is kept.
+    pub fn restart(&mut self) {
+        self.last_measurement = None;
+        self.derivative = 0.0;
+    }
+
+    /// Update the loop with a measurement taken `elapsed` after the previous
+    /// one.
+    pub fn update(&mut self, measurement: f64, elapsed: Duration) -> PidTerms {
+        let dt = elapsed.as_secs_f64();
+        let sign = self.direction.sign();
+
+        let setpoint = match (self.setpoint, self.ramp) {
+            (Some(setpoint), Some(ramp)) => {
+                let step = ramp * dt;
+                setpoint + (self.target - setpoint).clamp(-step, step)
+            }
+            // Ramps start from where the process is
+            (None, Some(_)) => measurement,
+            (_, None) => self.target,
+        };
+        self.setpoint = Some(setpoint);
+
+        let error = sign * (setpoint - measurement);
+        let proportional = self.tuning.kp * error;
+
+        if let Some(last) = self.last_measurement.filter(|_| dt > 0.0) {
+            let raw = -sign * self.tuning.kd * (measurement - last) / dt;
+            let filter = self.tuning.derivative_filter.as_secs_f64();
+            self.derivative += dt / (filter + dt) * (raw - self.derivative);
+        }
+        self.last_measurement = Some(measurement);
+
+        let output = match self.mode {
+            Mode::Manual { output } => {
+                // Tracking: the integral takes the value that gives the
+                // manual output back when switching to auto
+                self.integral = (output - proportional - self.derivative).clamp(0.0, 1.0);
+                output
+            }
+            Mode::Auto => {
+                let integral = self.integral + self.tuning.ki * error * dt;
+                let unclamped = proportional + integral + self.derivative;
+                // Anti-windup: the integral stops growing while it pushes
+                // a saturated output further
+                let winding = (unclamped > 1.0 && error > 0.0) || (unclamped < 0.0 && error < 0.0);
+                if !winding {
+                    self.integral = integral.clamp(0.0, 1.0);
+                }
+                (proportional + self.integral + self.derivative).clamp(0.0, 1.0)
+            }
+        };
+
+        let terms = PidTerms {
+            setpoint,
+            measurement,
+            error,
+            proportional,
+            integral: self.integral,
+            derivative: self.derivative,
+            output,
+            mode: self.mode,
+        };
+        self.terms = Some(terms);
+        terms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn heater(ramp: Option<f64>) -> Pid {
+        let tuning = PidTuning {
+            kp: 0.2,
+            ki: 0.01,
+            kd: 0.0,
+            derivative_filter: Duration::ZERO,
+        };
+        Pid::new(tuning, Direction::Raise, 25.0, ramp)
+    }
+
+    #[test]
+    fn the_integral_doesnt_wind_up_while_saturated() {
+        let mut pid = heater(None);
+
+        for _ in 0..600 {
+            assert_eq!(pid.update(15.0, SECOND).output, 1.0);
+        }
+        assert!(pid.terms().unwrap().integral < 0.01);
+
+        // Above the setpoint the output drops right away
+        assert_eq!(pid.update(26.0, SECOND).output, 0.0);
+    }
+
+    #[test]
+    fn the_derivative_is_filtered() {
+        let tuning = PidTuning {
+            kp: 0.0,
+            ki: 0.0,
+            kd: 1.0,
+            derivative_filter: Duration::from_secs(9),
+        };
+        let mut pid = Pid::new(tuning, Direction::Lower, 25.0, None);
+        pid.update(25.0, SECOND);
+
+        // Rising by 1 °C/s, a tenth of it goes through the filter at first
+        let terms = pid.update(26.0, SECOND);
+        assert!((terms.derivative - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn the_setpoint_ramps_from_the_measurement() {
+        let mut pid = heater(Some(0.1));
+
+        assert_eq!(pid.update(20.0, SECOND).setpoint, 20.0);
+        assert!((pid.update(20.0, 10 * SECOND).setpoint - 21.0).abs() < 1e-9);
+        assert_eq!(pid.update(20.0, 100 * SECOND).setpoint, 25.0);
+    }
+
+    #[test]
+    fn switching_to_auto_is_bumpless() {
+        let mut pid = heater(None);
+        pid.set_mode(Mode::Manual { output: 0.4 });
+        assert_eq!(pid.update(24.5, SECOND).output, 0.4);
+
+        pid.set_mode(Mode::Auto);
+        let output = pid.update(24.5, SECOND).output;
+        assert!((output - 0.4).abs() < 0.01);
+    }
+}
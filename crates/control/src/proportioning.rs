@@ -0,0 +1,137 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::safety::SafetyLimits;
+use tokio::time::{Duration, Instant};
+
+use crate::error::{ControlError, Result};
+
+/// Turns a duty cycle into on/off periods over a fixed window, e.g. 40 % of
+/// a 60 s window keeps the relay on for 24 s then off for 36 s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeProportioning {
+    pub window: Duration,
+    /// Shorter on or off periods are skipped, they wear the relay for
+    /// nothing
+    pub min_pulse: Duration,
+}
+
+impl TimeProportioning {
+    /// Check the window against the limits of the relay it drives.
+    pub fn validate(&self, limits: Option<&SafetyLimits>) -> Result<()> {
+        if self.window.is_zero() || self.min_pulse * 2 > self.window {
+            return Err(ControlError::InvalidRule(format!(
+                "a {:?} window can't hold two {:?} pulses",
+                self.window, self.min_pulse
+            )));
+        }
+
+        let Some(limits) = limits else {
+            return Ok(());
+        };
+        if let Some(max_switches) = limits.max_switches_per_hour {
+            let windows_per_hour =
+                Duration::from_secs(3600).as_secs_f64() / self.window.as_secs_f64();
+            if windows_per_hour > f64::from(max_switches) {
+                return Err(ControlError::InvalidRule(format!(
+                    "a {:?} window switches the relay more than {max_switches} times per hour",
+                    self.window
+                )));
+            }
+        }
+        if limits
+            .min_off_time
+            .is_some_and(|min_off_time| min_off_time >= self.window)
+        {
+            return Err(ControlError::InvalidRule(format!(
+                "a {:?} window is shorter than the minimum off-time of the relay",
+                self.window
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Time on within a window for `duty`, within the relay `limits`.
+    pub fn on_time(&self, duty: f64, limits: Option<&SafetyLimits>) -> Duration {
+        let mut on = self.window.mul_f64(duty.clamp(0.0, 1.0));
+        if let Some(limits) = limits {
+            if let Some(max_on) = limits.max_on_duration {
+                on = on.min(max_on);
+            }
+            // A partial window must leave the relay rest long enough
+            if let Some(min_off_time) = limits.min_off_time.filter(|_| on < self.window) {
+                on = on.min(self.window.saturating_sub(min_off_time));
+            }
+        }
+
+        if on < self.min_pulse {
+            Duration::ZERO
+        } else if self.window - on < self.min_pulse {
+            self.window
+        } else {
+            on
+        }
+    }
+}
+
+/// Current window of a time-proportioned output.
+#[derive(Clone, Copy, Debug)]
+pub struct Window {
+    pub started_at: Instant,
+    pub on_time: Duration,
+}
+
+impl Window {
+    /// Whether the relay is on at `now`, `None` once the window is over.
+    pub fn demand(&self, window: Duration, now: Instant) -> Option<bool> {
+        let elapsed = now.saturating_duration_since(self.started_at);
+        (elapsed < window).then_some(elapsed < self.on_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: TimeProportioning = TimeProportioning {
+        window: Duration::from_secs(60),
+        min_pulse: Duration::from_secs(2),
+    };
+
+    #[test]
+    fn the_duty_cycle_splits_the_window() {
+        assert_eq!(MINUTE.on_time(0.4, None), Duration::from_secs(24));
+        assert_eq!(MINUTE.on_time(0.01, None), Duration::ZERO);
+        assert_eq!(MINUTE.on_time(0.99, None), MINUTE.window);
+
+        let window = Window {
+            started_at: Instant::now(),
+            on_time: Duration::from_secs(24),
+        };
+        let at = |secs| window.started_at + Duration::from_secs(secs);
+        assert_eq!(window.demand(MINUTE.window, at(23)), Some(true));
+        assert_eq!(window.demand(MINUTE.window, at(24)), Some(false));
+        assert_eq!(window.demand(MINUTE.window, at(60)), None);
+    }
+
+    #[test]
+    fn the_safety_limits_bound_the_window() {
+        let limits = SafetyLimits {
+            max_on_duration: Some(Duration::from_secs(40)),
+            min_off_time: Some(Duration::from_secs(30)),
+            max_switches_per_hour: Some(60),
+            ..SafetyLimits::default()
+        };
+
+        assert!(MINUTE.validate(Some(&limits)).is_ok());
+        assert_eq!(MINUTE.on_time(0.9, Some(&limits)), Duration::from_secs(30));
+
+        let short = TimeProportioning {
+            window: Duration::from_secs(30),
+            ..MINUTE
+        };
+        assert!(short.validate(Some(&limits)).is_err());
+    }
+}
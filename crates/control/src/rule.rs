@@ -0,0 +1,158 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::safety::SafetyLimits;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::error::{ControlError, Result};
+use crate::pid::{Direction, PidTuning};
+use crate::proportioning::TimeProportioning;
+
+/// On/off control around a band, the relay keeps its state inside it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hysteresis {
+    /// Energizing raises the value, e.g. a heater on below 24 °C and off
+    /// above 25 °C.
+    Raise { on_below: f64, off_above: f64 },
+    /// Energizing lowers the value, e.g. a fan on above 28 °C and off below
+    /// 26 °C.
+    Lower { on_above: f64, off_below: f64 },
+}
+
+impl Hysteresis {
+    fn validate(self) -> std::result::Result<(), &'static str> {
+        let inverted = match self {
+            Hysteresis::Raise {
+                on_below,
+                off_above,
+            } => on_below > off_above,
+            Hysteresis::Lower {
+                on_above,
+                off_below,
+            } => off_below > on_above,
+        };
+
+        if inverted {
+            Err("the hysteresis band is inverted")
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Whether the relay should be on for `value`, `None` inside the band.
+    pub fn demand(self, value: f64) -> Option<bool> {
+        match self {
+            Hysteresis::Raise {
+                on_below,
+                off_above,
+            } => {
+                if value < on_below {
+                    Some(true)
+                } else if value > off_above {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            Hysteresis::Lower {
+                on_above,
+                off_below,
+            } => {
+                if value > on_above {
+                    Some(true)
+                } else if value < off_below {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// PID loop driving a relay through time-proportional windows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PidSettings {
+    pub setpoint: f64,
+    pub tuning: PidTuning,
+    pub direction: Direction,
+    /// Setpoint change per minute, a new setpoint applies at once without
+    pub ramp_per_minute: Option<f64>,
+    pub output: TimeProportioning,
+}
+
+/// How a rule turns the measurements into relay states.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    /// Bang-bang, for slow processes that tolerate some swing
+    Hysteresis(Hysteresis),
+    Pid(PidSettings),
+}
+
+/// What the relay does while its sensor can't be trusted, `Degraded`,
+/// `Unreachable` or gone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FaultBehaviour {
+    /// Keep the relay as it is
+    #[default]
+    Hold,
+    /// Release the relay, e.g. a heater
+    FailSafeOff,
+    /// Energize the relay, e.g. an aerator
+    FailSafeOn,
+}
+
+/// Binds the measurements of a sensor to a relay.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ControlRule {
+    pub id: Uuid,
+    pub name: String,
+    pub sensor_id: Uuid,
+    pub relay_id: String,
+    pub strategy: Strategy,
+    /// Shortest time on before the rule releases the relay
+    pub min_on_time: Duration,
+    /// Shortest time off before the rule energizes the relay again
+    pub min_off_time: Duration,
+    pub on_sensor_fault: FaultBehaviour,
+}
+
+impl ControlRule {
+    /// Check the rule, and its output against the `limits` of the relay.
+    pub fn validate(&self, limits: Option<&SafetyLimits>) -> Result<()> {
+        let pid = match self.strategy {
+            Strategy::Hysteresis(hysteresis) => {
+                return hysteresis.validate().map_err(|details| {
+                    ControlError::InvalidRule(format!("'{}': {details}", self.name))
+                });
+            }
+            Strategy::Pid(pid) => pid,
+        };
+
+        let tuning = pid.tuning;
+        if [tuning.kp, tuning.ki, tuning.kd]
+            .iter()
+            .any(|gain| !gain.is_finite() || *gain < 0.0)
+        {
+            return Err(ControlError::InvalidRule(format!(
+                "'{}': the PID gains must be positive",
+                self.name
+            )));
+        }
+        if pid.ramp_per_minute.is_some_and(|ramp| ramp <= 0.0) {
+            return Err(ControlError::InvalidRule(format!(
+                "'{}': the setpoint ramp must be positive",
+                self.name
+            )));
+        }
+
+        pid.output.validate(limits).map_err(|err| match err {
+            ControlError::InvalidRule(details) => {
+                ControlError::InvalidRule(format!("'{}': {details}", self.name))
+            }
+            err => err,
+        })
+    }
+}
@@ -0,0 +1,301 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::error::ActuatorError;
+use arksync_actuator::event::ActuatorEvent;
+use arksync_actuator::safety::SafetyLimits;
+use arksync_actuator::services::ActuatorServiceHandle;
+use arksync_sensor::event::SensorEvent;
+use std::collections::{HashMap, HashSet};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{interval, Duration, Instant, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::controller::Controller;
+use crate::error::{ControlError, Result};
+use crate::event::ControlEvent;
+use crate::pid::{Mode, PidTerms};
+use crate::rule::{ControlRule, Strategy};
+use crate::services::ControlServiceHandle;
+
+/// Period of the re-evaluation of the rules between two measurements.
+const TICK: Duration = Duration::from_secs(1);
+
+pub(crate) enum ControlServiceCmd {
+    Rules {
+        respond_to: oneshot::Sender<Vec<ControlRule>>,
+    },
+    PidTerms {
+        rule_id: Uuid,
+        respond_to: oneshot::Sender<Result<Option<PidTerms>>>,
+    },
+    SetSetpoint {
+        rule_id: Uuid,
+        setpoint: f64,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    SetMode {
+        rule_id: Uuid,
+        mode: Mode,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+}
+
+/// Runs the control rules, from the sensor events to the actuator service.
+pub struct ControlService {
+    relays: ActuatorServiceHandle,
+    controllers: Vec<Controller>,
+    events: broadcast::Sender<ControlEvent>,
+    cmd_tx: mpsc::Sender<ControlServiceCmd>,
+    cmd_rx: mpsc::Receiver<ControlServiceCmd>,
+}
+
+impl ControlService {
+    /// Fails if a rule is invalid or two rules drive the same relay.
+    ///
+    /// The `limits` of the relays are the ones of the actuator service, the
+    /// PID windows are checked and shaped against them.
+    pub fn new(
+        relays: ActuatorServiceHandle,
+        rules: Vec<ControlRule>,
+        limits: &HashMap<&'static str, SafetyLimits>,
+    ) -> Result<Self> {
+        let mut driven = HashSet::new();
+        for rule in &rules {
+            rule.validate(limits.get(rule.relay_id.as_str()))?;
+            if !driven.insert(rule.relay_id.clone()) {
+                return Err(ControlError::InvalidRule(format!(
+                    "relay '{}' is driven by more than one rule",
+                    rule.relay_id
+                )));
+            }
+        }
+
+        let (cmd_tx, cmd_rx) = mpsc::channel(100);
+        let (events, _) = broadcast::channel(256);
+        let controllers = rules
+            .into_iter()
+            .map(|rule| {
+                let limits = limits.get(rule.relay_id.as_str()).cloned();
+                Controller::new(rule, limits)
+            })
+            .collect();
+
+        Ok(Self {
+            relays,
+            controllers,
+            events,
+            cmd_tx,
+            cmd_rx,
+        })
+    }
+
+    /// Handle to talk to the service once it runs.
+    pub fn handle(&self) -> ControlServiceHandle {
+        ControlServiceHandle::new(self.cmd_tx.clone(), self.events.clone())
+    }
+
+    /// Control the relays from `sensors` until `shutdown` is cancelled.
+    ///
+    /// The relays are left as they are on shutdown, the actuator service
+    /// owns their fail-safe state.
+    pub async fn run(
+        mut self,
+        mut sensors: broadcast::Receiver<SensorEvent>,
+        shutdown: CancellationToken,
+    ) {
+        let mut relay_events = self.relays.subscribe();
+        if let Some(states) = self.relays.states().await {
+            let now = Instant::now();
+            for (_, state) in states {
+                self.relay_switched(state.id, state.active, now);
+            }
+        }
+        tracing::info!(rules = self.controllers.len(), "Control service started.");
+
+        let mut tick = interval(TICK);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                event = sensors.recv() => match event {
+                    Ok(event) => self.observe(&event).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Control service lagging behind, sensor events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                event = relay_events.recv() => match event {
+                    Ok(ActuatorEvent::StateChanged { state, .. }) => {
+                        self.relay_switched(state.id, state.active, Instant::now());
+                    }
+                    Ok(ActuatorEvent::Refused { .. }) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Control service lagging behind, actuator events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                Some(cmd) = self.cmd_rx.recv() => self.handle_cmd(cmd),
+                _ = tick.tick() => self.tick().await,
+                _ = shutdown.cancelled() => break,
+            }
+        }
+
+        tracing::info!("Control service stopped.");
+    }
+
+    async fn observe(&mut self, event: &SensorEvent) {
+        let sensor_id = event.sensor_id();
+        let now = Instant::now();
+
+        for index in 0..self.controllers.len() {
+            let controller = &mut self.controllers[index];
+            if controller.rule().sensor_id != sensor_id {
+                continue;
+            }
+
+            let command = match event {
+                SensorEvent::Measurement(measurement) => {
+                    let command = controller.measurement(measurement.value, now);
+                    if let Some(terms) = controller.pid_terms() {
+                        let _ = self.events.send(ControlEvent::PidUpdated {
+                            rule_id: controller.rule().id,
+                            terms,
+                        });
+                    }
+                    command
+                }
+                SensorEvent::Discovered(info) | SensorEvent::StateChanged { info, .. } => {
+                    controller.sensor_state(Some(info.state), now)
+                }
+                SensorEvent::Removed(_) => controller.sensor_state(None, now),
+                SensorEvent::Renamed(_) | SensorEvent::ReadCompleted(_) => None,
+            };
+
+            if let Some(on) = command {
+                self.apply(index, on).await;
+            }
+        }
+    }
+
+    fn handle_cmd(&mut self, cmd: ControlServiceCmd) {
+        match cmd {
+            ControlServiceCmd::Rules { respond_to } => {
+                let rules = self
+                    .controllers
+                    .iter()
+                    .map(|controller| controller.rule().clone())
+                    .collect();
+                let _ = respond_to.send(rules);
+            }
+            ControlServiceCmd::PidTerms {
+                rule_id,
+                respond_to,
+            } => {
+                let terms = self.controller(rule_id).and_then(|controller| {
+                    let terms = controller.pid_terms();
+                    match controller.rule().strategy {
+                        Strategy::Pid(_) => Ok(terms),
+                        Strategy::Hysteresis(_) => Err(ControlError::NotPid(rule_id)),
+                    }
+                });
+                let _ = respond_to.send(terms);
+            }
+            ControlServiceCmd::SetSetpoint {
+                rule_id,
+                setpoint,
+                respond_to,
+            } => {
+                let result = self
+                    .controller(rule_id)
+                    .and_then(|controller| controller.set_setpoint(setpoint));
+                if result.is_ok() {
+                    tracing::info!(%rule_id, setpoint, "Control rule setpoint moved.");
+                }
+                let _ = respond_to.send(result);
+            }
+            ControlServiceCmd::SetMode {
+                rule_id,
+                mode,
+                respond_to,
+            } => {
+                let result = self
+                    .controller(rule_id)
+                    .and_then(|controller| controller.set_mode(mode));
+                if result.is_ok() {
+                    tracing::info!(%rule_id, ?mode, "Control rule mode changed.");
+                }
+                let _ = respond_to.send(result);
+            }
+        }
+    }
+
+    fn controller(&mut self, rule_id: Uuid) -> Result<&mut Controller> {
+        self.controllers
+            .iter_mut()
+            .find(|controller| controller.rule().id == rule_id)
+            .ok_or(ControlError::UnknownRule(rule_id))
+    }
+
+    async fn tick(&mut self) {
+        let now = Instant::now();
+        for index in 0..self.controllers.len() {
+            if let Some(on) = self.controllers[index].tick(now) {
+                self.apply(index, on).await;
+            }
+        }
+    }
+
+    async fn apply(&mut self, index: usize, on: bool) {
+        let controller = &mut self.controllers[index];
+        let rule_id = controller.rule().id;
+        let relay_id = controller.rule().relay_id.clone();
+
+        match self.relays.switch(&relay_id, on).await {
+            Ok(state) => {
+                tracing::info!(
+                    %rule_id,
+                    rule = controller.rule().name,
+                    relay_id,
+                    on,
+                    "Control rule switched its relay."
+                );
+                controller.relay_switched(state.active, Instant::now());
+                let _ = self.events.send(ControlEvent::RelaySwitched {
+                    rule_id,
+                    relay_id,
+                    on: state.active,
+                });
+            }
+            Err(err @ ActuatorError::InterlockRefused { .. }) => {
+                tracing::warn!(
+                    %rule_id,
+                    relay_id,
+                    error = %err,
+                    "Control rule refused by the relay interlocks."
+                );
+                controller.refused(Instant::now());
+            }
+            Err(err) => {
+                tracing::error!(
+                    %rule_id,
+                    relay_id,
+                    error = %err,
+                    "Control rule failed to switch its relay."
+                );
+                controller.refused(Instant::now());
+            }
+        }
+    }
+
+    fn relay_switched(&mut self, relay_id: &str, on: bool, now: Instant) {
+        for controller in &mut self.controllers {
+            if controller.rule().relay_id == relay_id {
+                controller.relay_switched(on, now);
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use tokio::sync::{broadcast, mpsc, oneshot};
+use uuid::Uuid;
+
+use crate::error::{ControlError, Result};
+use crate::event::ControlEvent;
+use crate::pid::{Mode, PidTerms};
+use crate::rule::ControlRule;
+use crate::services::control_service::ControlServiceCmd;
+
+#[derive(Clone)]
+pub struct ControlServiceHandle {
+    cmd_tx: mpsc::Sender<ControlServiceCmd>,
+    events: broadcast::Sender<ControlEvent>,
+}
+
+impl ControlServiceHandle {
+    pub(crate) fn new(
+        cmd_tx: mpsc::Sender<ControlServiceCmd>,
+        events: broadcast::Sender<ControlEvent>,
+    ) -> Self {
+        Self { cmd_tx, events }
+    }
+
+    /// Receive the PID terms and the switches of the rules.
+    pub fn subscribe(&self) -> broadcast::Receiver<ControlEvent> {
+        self.events.subscribe()
+    }
+
+    pub async fn rules(&self) -> Result<Vec<ControlRule>> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(ControlServiceCmd::Rules { respond_to }, rx)
+            .await
+    }
+
+    /// Terms of the last update of a PID rule, `None` before its first
+    /// measurement.
+    pub async fn pid_terms(&self, rule_id: Uuid) -> Result<Option<PidTerms>> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(
+            ControlServiceCmd::PidTerms {
+                rule_id,
+                respond_to,
+            },
+            rx,
+        )
+        .await?
+    }
+
+    /// Move the setpoint of a PID rule, along its ramp.
+    pub async fn set_setpoint(&self, rule_id: Uuid, setpoint: f64) -> Result<()> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(
+            ControlServiceCmd::SetSetpoint {
+                rule_id,
+                setpoint,
+                respond_to,
+            },
+            rx,
+        )
+        .await?
+    }
+
+    /// Switch a PID rule between manual and auto, without a bump.
+    pub async fn set_mode(&self, rule_id: Uuid, mode: Mode) -> Result<()> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(
+            ControlServiceCmd::SetMode {
+                rule_id,
+                mode,
+                respond_to,
+            },
+            rx,
+        )
+        .await?
+    }
+
+    async fn request<T>(&self, cmd: ControlServiceCmd, rx: oneshot::Receiver<T>) -> Result<T> {
+        self.cmd_tx
+            .send(cmd)
+            .await
+            .map_err(|_| ControlError::ServiceStopped)?;
+
+        rx.await.map_err(|_| ControlError::ServiceStopped)
+    }
+}
@@ -0,0 +1,74 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Stand-ins to run control rules without boards, relays are simulated by
+//! the actuator crate with [`arksync_actuator::gpio::SimulatedGpio`].
+
+use arksync_sensor::event::{Measurement, SensorEvent};
+use arksync_sensor::i2c_bus::I2cConnection;
+use arksync_sensor::sensor::{
+    SensorConnection, SensorInfo, SensorKind, SensorName, SensorState, SensorStateReason,
+};
+use chrono::Utc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Sensor publishing events the way the sensor service does.
+pub struct SimulatedSensor {
+    info: SensorInfo,
+    events: broadcast::Sender<SensorEvent>,
+}
+
+impl SimulatedSensor {
+    pub fn new(kind: SensorKind) -> Self {
+        let (events, _) = broadcast::channel(64);
+
+        Self {
+            info: SensorInfo {
+                id: Uuid::new_v4(),
+                hardware_uid: "simulated".to_string(),
+                kind,
+                firmware: 0.0,
+                name: SensorName::Unnamed,
+                state: SensorState::Active,
+                state_reason: SensorStateReason::MeasurementOk,
+                state_since: Utc::now(),
+                last_activity: Utc::now(),
+                consecutive_failures: 0,
+                connection: SensorConnection::I2c(I2cConnection { bus: 0, address: 0 }),
+            },
+            events,
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.info.id
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SensorEvent> {
+        self.events.subscribe()
+    }
+
+    /// Publish a valid measurement.
+    pub fn measure(&self, value: f64) {
+        let _ = self.events.send(SensorEvent::Measurement(Measurement {
+            sensor_id: self.info.id,
+            kind: self.info.kind,
+            value,
+            time: Utc::now(),
+        }));
+    }
+
+    /// Move the sensor to `state` and publish the change.
+    pub fn set_state(&mut self, state: SensorState) {
+        let previous = self.info.state;
+        self.info.state = state;
+        self.info.state_since = Utc::now();
+
+        let _ = self.events.send(SensorEvent::StateChanged {
+            previous,
+            info: self.info.clone(),
+        });
+    }
+}
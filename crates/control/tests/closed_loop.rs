@@ -0,0 +1,96 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::event::ActuatorEvent;
+use arksync_actuator::gpio::{GpioBackend, SimulatedGpio};
+use arksync_actuator::relay::{RelayBank, MIST_BOARD, MIST_RELAY};
+use arksync_actuator::services::ActuatorService;
+use arksync_control::rule::{ControlRule, FaultBehaviour, Hysteresis, Strategy};
+use arksync_control::services::ControlService;
+use arksync_control::simulation::SimulatedSensor;
+use arksync_sensor::sensor::{SensorKind, SensorState};
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+use tokio::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+async fn next_state(events: &mut broadcast::Receiver<ActuatorEvent>) -> bool {
+    loop {
+        if let ActuatorEvent::StateChanged { state, .. } = events.recv().await.unwrap() {
+            return state.active;
+        }
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_heater_follows_its_sensor() {
+    let relays = RelayBank::open(
+        &[MIST_BOARD],
+        &GpioBackend::Simulated(SimulatedGpio::default()),
+    )
+    .unwrap();
+    let actuators = ActuatorService::new(relays);
+    let relays = actuators.handle();
+    let mut events = relays.subscribe();
+
+    let mut sensor = SimulatedSensor::new(SensorKind::Temperature);
+    let rule = ControlRule {
+        id: Uuid::new_v4(),
+        name: "heater".to_string(),
+        sensor_id: sensor.id(),
+        relay_id: MIST_RELAY.id.to_string(),
+        strategy: Strategy::Hysteresis(Hysteresis::Raise {
+            on_below: 24.0,
+            off_above: 25.0,
+        }),
+        min_on_time: Duration::from_secs(60),
+        min_off_time: Duration::from_secs(60),
+        on_sensor_fault: FaultBehaviour::FailSafeOff,
+    };
+    let control = ControlService::new(relays.clone(), vec![rule], &HashMap::new()).unwrap();
+
+    let shutdown = CancellationToken::new();
+    let actuators = tokio::spawn(actuators.run(shutdown.clone()));
+    let control = tokio::spawn(control.run(sensor.subscribe(), shutdown.clone()));
+
+    sensor.measure(23.0);
+    assert!(next_state(&mut events).await);
+
+    let on = Instant::now();
+    sensor.measure(24.5);
+    sensor.measure(26.0);
+    assert!(!next_state(&mut events).await);
+    assert!(on.elapsed() >= Duration::from_secs(60));
+
+    // Back on after the minimum off time, then released by the fault
+    sensor.measure(20.0);
+    assert!(next_state(&mut events).await);
+    sensor.set_state(SensorState::Unreachable);
+    assert!(!next_state(&mut events).await);
+
+    shutdown.cancel();
+    control.await.unwrap();
+    actuators.await.unwrap();
+}
+
+#[test]
+fn a_relay_is_driven_by_one_rule() {
+    let relays = ActuatorService::new(RelayBank::default()).handle();
+    let rule = ControlRule {
+        id: Uuid::new_v4(),
+        name: "fan".to_string(),
+        sensor_id: Uuid::new_v4(),
+        relay_id: MIST_RELAY.id.to_string(),
+        strategy: Strategy::Hysteresis(Hysteresis::Lower {
+            on_above: 28.0,
+            off_below: 26.0,
+        }),
+        min_on_time: Duration::ZERO,
+        min_off_time: Duration::ZERO,
+        on_sensor_fault: FaultBehaviour::Hold,
+    };
+
+    assert!(ControlService::new(relays, vec![rule.clone(), rule], &HashMap::new()).is_err());
+}
@@ -15,6 +15,10 @@ pub struct Config {
     pub pg_user: String,
     pub pg_password: String,
     pub pg_max_connections: u32,
+    /// Measurements older than this are dropped with their partition
+    pub measurements_retention_days: u32,
+    /// Name of the hub when first registered
+    pub station_name: String,
 }
 
 fn mpl() -> Config {
@@ -25,5 +29,7 @@ fn mpl() -> Config {
         pg_user: "admin".to_string(),
         pg_password: "admin".to_string(),
         pg_max_connections: 5,
+        measurements_retention_days: 365,
+        station_name: "ArkSync".to_string(),
     }
 }
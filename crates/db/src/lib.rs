@@ -7,16 +7,36 @@ pub mod migrations;
 mod postgres;
 mod postgres_reset;
 mod postgres_setup;
+mod station;
+pub mod stores;
 
 pub use config::{Config, CONFIG};
 pub use migrations::{Migrator, MplMigrator};
-pub use postgres::{connect_db, pool, PG_POOL};
+pub use postgres::{connect_db, is_transient, pool, PG_POOL};
 pub use postgres_reset::reset_public_schema;
 pub use postgres_setup::setup;
+pub use station::{local_hardware_uid, register_local_hub};
+pub use stores::{
+    ActuatorStore, AlertStore, MeasurementStore, ScheduleStore, SensorStore, StationStore,
+};
 
+#[tracing::instrument(
+    name = "db_setup",
+    skip_all,
+    fields(host = %CONFIG.pg_host, database = %CONFIG.pg_db)
+)]
 pub async fn run() -> eyre::Result<()> {
     setup().await?;
     MplMigrator::run().await?;
+    MeasurementStore::new(pool())
+        .set_retention(CONFIG.measurements_retention_days)
+        .await?;
+    tracing::info!(
+        host = %CONFIG.pg_host,
+        database = %CONFIG.pg_db,
+        retention_days = CONFIG.measurements_retention_days,
+        "Database ready."
+    );
 
     Ok(())
 }
@@ -0,0 +1,17 @@
+-- This Source Code Form is subject to the terms of the Mozilla Public
+-- License, v. 2.0. If a copy of the MPL was not distributed with this
+-- file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+create table sensor_events (
+    id bigint generated always as identity primary key,
+    sensor_id uuid not null references sensors(id),
+    previous_status sensor_status,
+    status sensor_status not null,
+    state_reason text not null,
+    state_reason_details jsonb not null default '{}'::jsonb,
+    consecutive_failures integer not null default 0 check (consecutive_failures >= 0),
+    occurred_at timestamptz not null default now()
+);
+
+create index sensor_events_sensor_id_occurred_at_idx
+on sensor_events (sensor_id, occurred_at desc);
@@ -0,0 +1,29 @@
+-- This Source Code Form is subject to the terms of the Mozilla Public
+-- License, v. 2.0. If a copy of the MPL was not distributed with this
+-- file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+create table measurements (
+    sensor_id uuid not null references sensors(id),
+    kind sensor_kind not null,
+    time timestamptz not null,
+    value double precision not null,
+    primary key (sensor_id, kind, time)
+) partition by range (time);
+
+create index measurements_time_idx
+on measurements (time);
+
+-- Daily partitions, a week created ahead. The pg_partman background worker
+-- runs the maintenance (new partitions and retention).
+select create_parent(
+    p_parent_table := 'public.measurements',
+    p_control := 'time',
+    p_interval := '1 day',
+    p_premake := 7
+);
+
+-- Default retention, overridden at startup from the db configuration
+update part_config
+set retention = '365 days',
+    retention_keep_table = false
+where parent_table = 'public.measurements';
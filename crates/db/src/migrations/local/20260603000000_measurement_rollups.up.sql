@@ -0,0 +1,24 @@
+-- This Source Code Form is subject to the terms of the Mozilla Public
+-- License, v. 2.0. If a copy of the MPL was not distributed with this
+-- file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+create type rollup_resolution as enum (
+    'one_minute',
+    'fifteen_minutes',
+    'one_hour',
+    'one_day'
+);
+
+-- Aggregates maintained incrementally on each measurement batch. The average
+-- is derived from sum and count so buckets can be merged.
+create table measurement_rollups (
+    sensor_id uuid not null references sensors(id),
+    kind sensor_kind not null,
+    resolution rollup_resolution not null,
+    bucket timestamptz not null,
+    min double precision not null,
+    max double precision not null,
+    sum double precision not null,
+    count bigint not null check (count > 0),
+    primary key (sensor_id, kind, resolution, bucket)
+);
@@ -0,0 +1,13 @@
+-- This Source Code Form is subject to the terms of the Mozilla Public
+-- License, v. 2.0. If a copy of the MPL was not distributed with this
+-- file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+-- Sessions opened through the API. Only a hash of the bearer token is stored.
+create table user_sessions (
+    token_hash bytea primary key,
+    user_id uuid not null references users(id),
+    created_at timestamptz not null default now(),
+    expires_at timestamptz not null
+);
+
+create index user_sessions_user_id_idx on user_sessions (user_id);
@@ -0,0 +1,9 @@
+-- This Source Code Form is subject to the terms of the Mozilla Public
+-- License, v. 2.0. If a copy of the MPL was not distributed with this
+-- file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+drop table if exists actuator_events;
+
+alter table actuators
+    drop column if exists state_since,
+    drop column if exists active;
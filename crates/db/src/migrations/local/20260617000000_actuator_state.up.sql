@@ -0,0 +1,17 @@
+-- This Source Code Form is subject to the terms of the Mozilla Public
+-- License, v. 2.0. If a copy of the MPL was not distributed with this
+-- file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+alter table actuators
+    add column active boolean not null default false,
+    add column state_since timestamptz not null default now();
+
+create table actuator_events (
+    id bigint generated always as identity primary key,
+    actuator_id uuid not null references actuators(id),
+    active boolean not null,
+    occurred_at timestamptz not null default now()
+);
+
+create index actuator_events_actuator_id_occurred_at_idx
+on actuator_events (actuator_id, occurred_at desc);
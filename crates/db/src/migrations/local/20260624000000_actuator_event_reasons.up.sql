@@ -0,0 +1,10 @@
+-- This Source Code Form is subject to the terms of the Mozilla Public
+-- License, v. 2.0. If a copy of the MPL was not distributed with this
+-- file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+-- Refused switches are logged with the switches, `active` is then the
+-- requested state.
+alter table actuator_events
+    add column refused boolean not null default false,
+    add column reason text not null default 'command',
+    add column details jsonb not null default '{}'::jsonb;
@@ -0,0 +1,28 @@
+-- This Source Code Form is subject to the terms of the Mozilla Public
+-- License, v. 2.0. If a copy of the MPL was not distributed with this
+-- file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+create table schedule_rules (
+    id uuid primary key default gen_random_uuid(),
+    name text not null,
+    relay_id text not null,
+    trigger jsonb not null,
+    action jsonb not null,
+    time_zone text not null default 'UTC',
+    priority integer not null default 0,
+    enabled boolean not null default true,
+    -- Last occurrence fired, never fired again after a restart
+    last_fired_at timestamptz,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now(),
+    deleted_at timestamptz
+);
+
+create index schedule_rules_relay_id_idx
+on schedule_rules (relay_id)
+where deleted_at is null;
+
+create trigger schedule_rules_set_updated_at
+before update on schedule_rules
+for each row
+execute function set_updated_at();
@@ -0,0 +1,34 @@
+-- This Source Code Form is subject to the terms of the Mozilla Public
+-- License, v. 2.0. If a copy of the MPL was not distributed with this
+-- file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+create type alert_severity as enum (
+    'info',
+    'warning',
+    'critical'
+);
+
+-- Alerts raised by the alert rules, open until resolved. Sensors aren't
+-- referenced: an alert outlives a forgotten sensor.
+create table alerts (
+    id uuid primary key,
+    rule_id uuid not null,
+    rule_name text not null,
+    sensor_id uuid not null,
+    severity alert_severity not null,
+    message text not null,
+    value double precision,
+    raised_at timestamptz not null,
+    acknowledged_at timestamptz,
+    acknowledged_by text,
+    resolved_at timestamptz,
+    check (acknowledged_at is null or acknowledged_by is not null)
+);
+
+-- At most one open alert per rule and sensor
+create unique index alerts_open_idx
+on alerts (rule_id, sensor_id)
+where resolved_at is null;
+
+create index alerts_raised_at_idx
+on alerts (raised_at desc);
@@ -0,0 +1,76 @@
+-- This Source Code Form is subject to the terms of the Mozilla Public
+-- License, v. 2.0. If a copy of the MPL was not distributed with this
+-- file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+create or replace function register_local_hub_as_knot()
+returns trigger
+language plpgsql
+as $$
+begin
+    insert into station_knots (
+        station_hub_id,
+        name,
+        hardware_uid,
+        role,
+        status
+    )
+    values (
+        new.id,
+        new.name,
+        new.hardware_uid,
+        'local_hub',
+        'active'
+    );
+
+    return new;
+end;
+$$;
+
+-- Hubs registered before the first user was created go to the oldest user.
+-- Without any user they are removed together with everything under them.
+update station_hubs
+set user_id = (
+    select id from users where deleted_at is null order by created_at limit 1
+)
+where user_id is null;
+
+create temporary table orphaned_knots on commit drop as
+select station_knots.id
+from station_knots
+join station_hubs on station_hubs.id = station_knots.station_hub_id
+where station_hubs.user_id is null;
+
+delete from measurement_rollups
+where sensor_id in (
+    select id from sensors where station_knot_id in (select id from orphaned_knots)
+);
+
+delete from measurements
+where sensor_id in (
+    select id from sensors where station_knot_id in (select id from orphaned_knots)
+);
+
+delete from sensor_events
+where sensor_id in (
+    select id from sensors where station_knot_id in (select id from orphaned_knots)
+);
+
+delete from sensors
+where station_knot_id in (select id from orphaned_knots);
+
+delete from actuator_events
+where actuator_id in (
+    select id from actuators where station_knot_id in (select id from orphaned_knots)
+);
+
+delete from actuators
+where station_knot_id in (select id from orphaned_knots);
+
+delete from station_knots
+where id in (select id from orphaned_knots);
+
+delete from station_hubs
+where user_id is null;
+
+alter table station_hubs
+    alter column user_id set not null;
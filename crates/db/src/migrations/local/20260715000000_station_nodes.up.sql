@@ -0,0 +1,31 @@
+-- This Source Code Form is subject to the terms of the Mozilla Public
+-- License, v. 2.0. If a copy of the MPL was not distributed with this
+-- file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+-- The local hub registers itself on first boot, before any user exists
+alter table station_hubs
+    alter column user_id drop not null;
+
+create or replace function register_local_hub_as_knot()
+returns trigger
+language plpgsql
+as $$
+begin
+    insert into station_knots (
+        station_hub_id,
+        name,
+        hardware_uid,
+        role,
+        status
+    )
+    values (
+        new.id,
+        new.name,
+        new.hardware_uid,
+        'local_hub',
+        'awake'
+    );
+
+    return new;
+end;
+$$;
@@ -8,6 +8,13 @@ pub struct MplMigrator;
 
 impl Migrator for MplMigrator {
     async fn run() -> Result<(), sqlx::migrate::MigrateError> {
-        sqlx::migrate!("./src/migrations/local").run(pool()).await
+        let migrator = sqlx::migrate!("./src/migrations/local");
+        migrator.run(pool()).await?;
+        tracing::debug!(
+            migrations = migrator.iter().count(),
+            "Database migrations applied."
+        );
+
+        Ok(())
     }
 }
@@ -14,15 +14,58 @@ pub fn pool() -> &'static PgPool {
 }
 
 pub fn connect_db(database_name: &str) -> PgPool {
+    tracing::debug!(
+        host = %CONFIG.pg_host,
+        port = CONFIG.pg_port,
+        database = database_name,
+        max_connections = CONFIG.pg_max_connections,
+        "Creating the Postgres pool."
+    );
     PgPoolOptions::new()
         .max_connections(CONFIG.pg_max_connections)
         .connect_lazy(&database_url(database_name))
         .expect("postgres database url should be valid")
 }
 
+/// Whether a store error may go away on its own, e.g. while the server
+/// restarts, as opposed to a statement Postgres will refuse every time.
+///
+/// Errors that don't come from sqlx are considered transient.
+pub fn is_transient(err: &eyre::Report) -> bool {
+    match err.downcast_ref::<sqlx::Error>() {
+        // Connection, resources, operator intervention, system errors and
+        // failed transactions (serialization, deadlock)
+        Some(sqlx::Error::Database(err)) => err
+            .code()
+            .is_some_and(|code| matches!(code.get(..2), Some("08" | "40" | "53" | "57" | "58"))),
+        Some(
+            sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::Protocol(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed,
+        ) => true,
+        Some(_) => false,
+        None => true,
+    }
+}
+
 fn database_url(database_name: &str) -> String {
     format!(
         "postgres://{}:{}@{}:{}/{}",
         CONFIG.pg_user, CONFIG.pg_password, CONFIG.pg_host, CONFIG.pg_port, database_name
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_connection_errors_are_transient() {
+        assert!(is_transient(&sqlx::Error::PoolTimedOut.into()));
+        assert!(is_transient(&eyre::eyre!("Unknown failure")));
+        assert!(!is_transient(&sqlx::Error::RowNotFound.into()));
+    }
+}
@@ -0,0 +1,109 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use eyre::{eyre, Result};
+use std::fs;
+
+use crate::stores::{StationKnotRecord, StationStore};
+use crate::{pool, CONFIG};
+
+const DEVICE_TREE_SERIAL: &str = "/sys/firmware/devicetree/base/serial-number";
+const CPU_INFO: &str = "/proc/cpuinfo";
+const MACHINE_ID: &str = "/etc/machine-id";
+
+/// Hardware uid of this machine, stable across reinstalls on a Pi.
+///
+/// The board serial is read from the device tree, or from `/proc/cpuinfo` on
+/// older kernels. Other machines, e.g. during development, fall back on their
+/// machine id.
+pub fn local_hardware_uid() -> Result<String> {
+    let serial = fs::read_to_string(DEVICE_TREE_SERIAL)
+        .ok()
+        .and_then(|serial| device_tree_serial(&serial))
+        .or_else(|| {
+            fs::read_to_string(CPU_INFO)
+                .ok()
+                .and_then(|cpu_info| cpu_info_serial(&cpu_info))
+        });
+    if let Some(serial) = serial {
+        return Ok(format!("rpi:{serial}"));
+    }
+
+    let machine_id = fs::read_to_string(MACHINE_ID)
+        .map_err(|err| eyre!("No board serial nor machine id to identify the station: {err}"))?;
+    match machine_id.trim() {
+        "" => Err(eyre!("Empty machine id, cannot identify the station.")),
+        machine_id => Ok(format!("machine:{machine_id}")),
+    }
+}
+
+/// Register the hub of this Pi on first boot and return its local knot.
+#[tracing::instrument(name = "station_registration", skip_all)]
+pub async fn register_local_hub() -> Result<StationKnotRecord> {
+    let hardware_uid = local_hardware_uid()?;
+    let knot = StationStore::new(pool())
+        .register_local_hub(&CONFIG.station_name, &hardware_uid)
+        .await?;
+    tracing::info!(
+        knot_id = %knot.id,
+        hub_id = %knot.station_hub_id,
+        hardware_uid,
+        "Local hub registered."
+    );
+
+    Ok(knot)
+}
+
+fn device_tree_serial(serial: &str) -> Option<String> {
+    normalize_serial(serial.trim_end_matches('\0'))
+}
+
+fn cpu_info_serial(cpu_info: &str) -> Option<String> {
+    cpu_info
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim() == "Serial")
+        .and_then(|(_, serial)| normalize_serial(serial))
+}
+
+/// Serials without their leading zeros, absent when only made of zeros as
+/// on boards that don't expose one.
+fn normalize_serial(serial: &str) -> Option<String> {
+    let serial = serial.trim().trim_start_matches('0').to_ascii_lowercase();
+    (!serial.is_empty() && serial.chars().all(|c| c.is_ascii_hexdigit())).then_some(serial)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_serial_of_the_device_tree() {
+        assert_eq!(
+            device_tree_serial("100000002a3b4c5d\0"),
+            Some("100000002a3b4c5d".to_string())
+        );
+        assert_eq!(
+            device_tree_serial("00000000E1A2B3C4\0"),
+            Some("e1a2b3c4".to_string())
+        );
+    }
+
+    #[test]
+    fn reads_the_serial_of_cpu_info() {
+        let cpu_info = "processor\t: 0\nHardware\t: BCM2835\nRevision\t: c03111\nSerial\t\t: 100000002a3b4c5d\nModel\t\t: Raspberry Pi 4 Model B Rev 1.1\n";
+
+        assert_eq!(
+            cpu_info_serial(cpu_info),
+            Some("100000002a3b4c5d".to_string())
+        );
+    }
+
+    #[test]
+    fn ignores_missing_serials() {
+        assert_eq!(cpu_info_serial("Serial\t\t: 0000000000000000\n"), None);
+        assert_eq!(cpu_info_serial("processor\t: 0\n"), None);
+        assert_eq!(device_tree_serial("\0"), None);
+    }
+}
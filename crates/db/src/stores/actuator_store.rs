@@ -0,0 +1,160 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use eyre::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::stores::{
+    ActuatorEventRecord, ActuatorRecord, ActuatorStateRecord, ActuatorSummaryRecord,
+};
+
+#[derive(Clone, Copy)]
+pub struct ActuatorStore<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> ActuatorStore<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert a configured actuator or refresh the row of a known one, found
+    /// by its hardware uid, and return its id.
+    ///
+    /// The name is only set on insert, it may have been changed since by the
+    /// operator.
+    pub async fn upsert(&self, actuator: &ActuatorRecord) -> Result<Uuid> {
+        let id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO actuators (
+                station_knot_id, hardware_uid, name, kind, protocol, connection,
+                active, state_since
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (hardware_uid) WHERE hardware_uid IS NOT NULL AND deleted_at IS NULL
+            DO UPDATE SET
+                station_knot_id = excluded.station_knot_id,
+                kind = excluded.kind,
+                protocol = excluded.protocol,
+                connection = excluded.connection,
+                status = 'active',
+                active = excluded.active,
+                state_since = excluded.state_since
+            RETURNING id
+            "#,
+        )
+        .bind(actuator.station_knot_id)
+        .bind(&actuator.hardware_uid)
+        .bind(&actuator.name)
+        .bind(&actuator.kind)
+        .bind(&actuator.protocol)
+        .bind(&actuator.connection)
+        .bind(actuator.state.active)
+        .bind(actuator.state.state_since)
+        .fetch_one(self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Actuators not removed, by name.
+    pub async fn list(&self) -> Result<Vec<ActuatorSummaryRecord>> {
+        let records = sqlx::query_as::<_, ActuatorSummaryRecord>(
+            r#"
+            SELECT id, station_knot_id, hardware_uid, name, kind, protocol, active, state_since
+            FROM actuators
+            WHERE deleted_at IS NULL
+            ORDER BY name, id
+            "#,
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    /// Update the state of an actuator and append the switch to the event
+    /// log with its `reason`, atomically.
+    pub async fn record_state(
+        &self,
+        id: Uuid,
+        state: &ActuatorStateRecord,
+        reason: &str,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            UPDATE actuators
+            SET active = $2,
+                state_since = $3
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(state.active)
+        .bind(state.state_since)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO actuator_events (actuator_id, active, reason, occurred_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(id)
+        .bind(state.active)
+        .bind(reason)
+        .bind(state.state_since)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Append a refused switch to the event log, the actuator row is left
+    /// untouched.
+    pub async fn record_refusal(&self, event: &ActuatorEventRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO actuator_events (
+                actuator_id, active, refused, reason, details, occurred_at
+            )
+            VALUES ($1, $2, true, $3, $4, $5)
+            "#,
+        )
+        .bind(event.actuator_id)
+        .bind(event.active)
+        .bind(&event.reason)
+        .bind(&event.details)
+        .bind(event.occurred_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Events of an actuator, most recent first.
+    pub async fn events(&self, actuator_id: Uuid, limit: i64) -> Result<Vec<ActuatorEventRecord>> {
+        let events = sqlx::query_as::<_, ActuatorEventRecord>(
+            r#"
+            SELECT actuator_id, active, refused, reason, details, occurred_at
+            FROM actuator_events
+            WHERE actuator_id = $1
+            ORDER BY occurred_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(actuator_id)
+        .bind(limit)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(events)
+    }
+}
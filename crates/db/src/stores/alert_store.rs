@@ -0,0 +1,118 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::{DateTime, Utc};
+use eyre::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::stores::AlertRecord;
+
+#[derive(Clone, Copy)]
+pub struct AlertStore<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> AlertStore<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn insert(&self, alert: &AlertRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO alerts (
+                id, rule_id, rule_name, sensor_id, severity, message, value, raised_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(alert.id)
+        .bind(alert.rule_id)
+        .bind(&alert.rule_name)
+        .bind(alert.sensor_id)
+        .bind(alert.severity)
+        .bind(&alert.message)
+        .bind(alert.value)
+        .bind(alert.raised_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns whether the alert was open and not acknowledged yet.
+    pub async fn acknowledge(&self, id: Uuid, by: &str, at: DateTime<Utc>) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE alerts
+            SET acknowledged_at = $3, acknowledged_by = $2
+            WHERE id = $1
+            AND acknowledged_at IS NULL
+            AND resolved_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(by)
+        .bind(at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn resolve(&self, id: Uuid, at: DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE alerts
+            SET resolved_at = $2
+            WHERE id = $1
+            AND resolved_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Alerts not resolved yet, oldest first.
+    pub async fn open(&self) -> Result<Vec<AlertRecord>> {
+        let alerts = sqlx::query_as::<_, AlertRecord>(
+            r#"
+            SELECT id, rule_id, rule_name, sensor_id, severity, message, value, raised_at,
+                acknowledged_at, acknowledged_by, resolved_at
+            FROM alerts
+            WHERE resolved_at IS NULL
+            ORDER BY raised_at
+            "#,
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(alerts)
+    }
+
+    /// Alerts raised before `before`, resolved or not, newest first.
+    pub async fn history(&self, before: DateTime<Utc>, limit: i64) -> Result<Vec<AlertRecord>> {
+        let alerts = sqlx::query_as::<_, AlertRecord>(
+            r#"
+            SELECT id, rule_id, rule_name, sensor_id, severity, message, value, raised_at,
+                acknowledged_at, acknowledged_by, resolved_at
+            FROM alerts
+            WHERE raised_at < $1
+            ORDER BY raised_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(before)
+        .bind(limit)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(alerts)
+    }
+}
@@ -0,0 +1,298 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "station_knot_role", rename_all = "snake_case")]
+pub enum StationKnotRole {
+    /// The Pi running the hub, registered with it
+    LocalHub,
+    RemoteKnot,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "station_knot_status", rename_all = "snake_case")]
+pub enum StationKnotStatus {
+    Awake,
+    Sleeping,
+    Unreachable,
+}
+
+/// A row of `station_hubs`, the station a set of knots report to.
+#[derive(Clone, Debug, PartialEq, FromRow)]
+pub struct StationHubRecord {
+    pub id: Uuid,
+    /// Empty until the station is claimed by a user
+    pub user_id: Option<Uuid>,
+    pub name: String,
+    pub hardware_uid: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A row of `station_knots`, a Pi with its sensors and actuators.
+#[derive(Clone, Debug, PartialEq, FromRow)]
+pub struct StationKnotRecord {
+    pub id: Uuid,
+    pub station_hub_id: Uuid,
+    pub name: String,
+    pub hardware_uid: String,
+    pub role: StationKnotRole,
+    pub status: StationKnotStatus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "sensor_status", rename_all = "snake_case")]
+pub enum SensorStatus {
+    Active,
+    Degraded,
+    Initializing,
+    Unplugged,
+    Unreachable,
+}
+
+impl SensorStatus {
+    /// Name of the `sensor_status` value.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SensorStatus::Active => "active",
+            SensorStatus::Degraded => "degraded",
+            SensorStatus::Initializing => "initializing",
+            SensorStatus::Unplugged => "unplugged",
+            SensorStatus::Unreachable => "unreachable",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "sensor_kind", rename_all = "snake_case")]
+pub enum SensorKind {
+    Temperature,
+    Ph,
+    Ec,
+    Humidity,
+    Co2,
+    Custom,
+}
+
+impl SensorKind {
+    /// Name of the `sensor_kind` value.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SensorKind::Temperature => "temperature",
+            SensorKind::Ph => "ph",
+            SensorKind::Ec => "ec",
+            SensorKind::Humidity => "humidity",
+            SensorKind::Co2 => "co2",
+            SensorKind::Custom => "custom",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "sensor_driver", rename_all = "snake_case")]
+pub enum SensorDriver {
+    AtlasScientificEzo,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "sensor_protocol", rename_all = "snake_case")]
+pub enum SensorProtocol {
+    Uart,
+    I2c,
+}
+
+/// The persistent identity of a sensor: its id and the hardware signal it was
+/// last recognized with.
+#[derive(Clone, Debug, FromRow)]
+pub struct SensorIdentityRecord {
+    pub id: Uuid,
+    pub hardware_uid: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct SensorRecord {
+    pub id: Uuid,
+    pub station_knot_id: Uuid,
+    pub hardware_uid: Option<String>,
+    pub name: String,
+    pub kind: SensorKind,
+    pub driver: SensorDriver,
+    pub protocol: SensorProtocol,
+    pub connection: Value,
+    pub firmware: Option<f64>,
+    #[sqlx(flatten)]
+    pub state: SensorStateRecord,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct MeasurementRecord {
+    pub sensor_id: Uuid,
+    pub kind: SensorKind,
+    pub time: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// Bucket width of the `measurement_rollups` table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "rollup_resolution", rename_all = "snake_case")]
+pub enum RollupResolution {
+    OneMinute,
+    FifteenMinutes,
+    OneHour,
+    OneDay,
+}
+
+/// One point of a measurement history, either a raw measurement or a rollup
+/// bucket. A raw measurement has the same min, max and avg and a count of 1.
+#[derive(Clone, Debug, PartialEq, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPoint {
+    pub time: DateTime<Utc>,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub count: i64,
+}
+
+/// The part of a sensor row that moves with its state machine.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct SensorStateRecord {
+    pub status: SensorStatus,
+    pub state_reason: String,
+    pub state_reason_details: Value,
+    pub state_since: DateTime<Utc>,
+    pub last_activity_at: DateTime<Utc>,
+    pub consecutive_failures: i32,
+}
+
+/// A row of the `sensor_events` audit log.
+#[derive(Clone, Debug, FromRow)]
+pub struct SensorEventRecord {
+    pub sensor_id: Uuid,
+    pub previous_status: Option<SensorStatus>,
+    pub status: SensorStatus,
+    pub state_reason: String,
+    pub state_reason_details: Value,
+    pub consecutive_failures: i32,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl SensorEventRecord {
+    pub fn new(
+        sensor_id: Uuid,
+        previous_status: Option<SensorStatus>,
+        state: &SensorStateRecord,
+    ) -> Self {
+        Self {
+            sensor_id,
+            previous_status,
+            status: state.status,
+            state_reason: state.state_reason.clone(),
+            state_reason_details: state.state_reason_details.clone(),
+            consecutive_failures: state.consecutive_failures,
+            occurred_at: state.state_since,
+        }
+    }
+}
+
+/// A row of the `actuators` table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct ActuatorRecord {
+    pub station_knot_id: Uuid,
+    pub hardware_uid: Option<String>,
+    pub name: String,
+    /// What the actuator is, e.g. `relay`
+    pub kind: String,
+    /// How it's driven, e.g. `gpio`
+    pub protocol: String,
+    pub connection: Value,
+    #[sqlx(flatten)]
+    pub state: ActuatorStateRecord,
+}
+
+/// The part of an actuator row that moves when it's switched.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct ActuatorStateRecord {
+    pub active: bool,
+    pub state_since: DateTime<Utc>,
+}
+
+/// An actuator as listed, without its connection details.
+#[derive(Clone, Debug, PartialEq, FromRow)]
+pub struct ActuatorSummaryRecord {
+    pub id: Uuid,
+    pub station_knot_id: Uuid,
+    pub hardware_uid: Option<String>,
+    pub name: String,
+    pub kind: String,
+    pub protocol: String,
+    pub active: bool,
+    pub state_since: DateTime<Utc>,
+}
+
+/// A row of the `actuator_events` log.
+#[derive(Clone, Debug, PartialEq, FromRow)]
+pub struct ActuatorEventRecord {
+    pub actuator_id: Uuid,
+    /// The requested state when `refused`
+    pub active: bool,
+    pub refused: bool,
+    /// Code of the switch or refusal reason, e.g. `max_on_duration`
+    pub reason: String,
+    pub details: Value,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// A row of `schedule_rules`, the trigger and action kept as tagged JSON.
+#[derive(Clone, Debug, PartialEq, FromRow)]
+pub struct ScheduleRuleRecord {
+    pub id: Uuid,
+    pub name: String,
+    pub relay_id: String,
+    pub trigger: Value,
+    pub action: Value,
+    pub time_zone: String,
+    pub priority: i32,
+    pub enabled: bool,
+    pub last_fired_at: Option<DateTime<Utc>>,
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "alert_severity", rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// A row of `alerts`, open while `resolved_at` is empty.
+#[derive(Clone, Debug, PartialEq, FromRow)]
+pub struct AlertRecord {
+    pub id: Uuid,
+    pub rule_id: Uuid,
+    pub rule_name: String,
+    pub sensor_id: Uuid,
+    pub severity: AlertSeverity,
+    pub message: String,
+    pub value: Option<f64>,
+    pub raised_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub acknowledged_by: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
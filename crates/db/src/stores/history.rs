@@ -0,0 +1,118 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::stores::{HistoryPoint, RollupResolution, SensorKind};
+
+/// Shortest period between two raw measurements of a sensor, used to estimate
+/// how many raw points a window holds.
+const RAW_SAMPLE_PERIOD: TimeDelta = TimeDelta::seconds(1);
+
+/// Resolutions from the finest to the coarsest.
+const ROLLUP_RESOLUTIONS: [RollupResolution; 4] = [
+    RollupResolution::OneMinute,
+    RollupResolution::FifteenMinutes,
+    RollupResolution::OneHour,
+    RollupResolution::OneDay,
+];
+
+impl RollupResolution {
+    pub fn width(self) -> TimeDelta {
+        match self {
+            RollupResolution::OneMinute => TimeDelta::minutes(1),
+            RollupResolution::FifteenMinutes => TimeDelta::minutes(15),
+            RollupResolution::OneHour => TimeDelta::hours(1),
+            RollupResolution::OneDay => TimeDelta::days(1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "resolution")]
+pub enum Resolution {
+    Raw,
+    Rollup(RollupResolution),
+}
+
+impl Resolution {
+    /// The finest resolution keeping `window` under `max_points`.
+    ///
+    /// Falls back to daily buckets when even those exceed the budget, a long
+    /// window is better served slightly over budget than not at all.
+    pub fn for_window(window: TimeDelta, max_points: u32) -> Self {
+        let max_points = i64::from(max_points.max(1));
+        let points = |width: TimeDelta| window.num_seconds() / width.num_seconds();
+
+        if points(RAW_SAMPLE_PERIOD) <= max_points {
+            return Resolution::Raw;
+        }
+
+        ROLLUP_RESOLUTIONS
+            .into_iter()
+            .find(|resolution| points(resolution.width()) <= max_points)
+            .map_or(
+                Resolution::Rollup(RollupResolution::OneDay),
+                Resolution::Rollup,
+            )
+    }
+}
+
+/// Measurements of a sensor in `[from, to)`, with at most about `max_points`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryQuery {
+    pub sensor_id: Uuid,
+    pub kind: SensorKind,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub max_points: u32,
+}
+
+impl HistoryQuery {
+    pub fn resolution(&self) -> Resolution {
+        Resolution::for_window(self.to - self.from, self.max_points)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct History {
+    pub resolution: Resolution,
+    pub points: Vec<HistoryPoint>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolution_follows_the_point_budget() {
+        let cases = [
+            (TimeDelta::minutes(10), Resolution::Raw),
+            (
+                TimeDelta::hours(6),
+                Resolution::Rollup(RollupResolution::OneMinute),
+            ),
+            (
+                TimeDelta::days(7),
+                Resolution::Rollup(RollupResolution::FifteenMinutes),
+            ),
+            (
+                TimeDelta::days(30),
+                Resolution::Rollup(RollupResolution::OneHour),
+            ),
+            (
+                TimeDelta::days(3650),
+                Resolution::Rollup(RollupResolution::OneDay),
+            ),
+        ];
+
+        for (window, expected) in cases {
+            assert_eq!(Resolution::for_window(window, 1000), expected, "{window}");
+        }
+    }
+}
@@ -0,0 +1,209 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::{DateTime, Utc};
+use eyre::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::stores::{
+    History, HistoryPoint, HistoryQuery, MeasurementRecord, Resolution, SensorKind,
+};
+
+#[derive(Clone, Copy)]
+pub struct MeasurementStore<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> MeasurementStore<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert a batch of measurements in a single statement.
+    ///
+    /// Measurements already stored (same sensor, kind and time) are skipped so
+    /// a batch can safely be retried. The rollups are updated in the same
+    /// statement from the inserted measurements only, a retried batch isn't
+    /// counted twice.
+    pub async fn insert_batch(&self, measurements: &[MeasurementRecord]) -> Result<u64> {
+        if measurements.is_empty() {
+            return Ok(0);
+        }
+
+        let mut sensor_ids = Vec::with_capacity(measurements.len());
+        let mut kinds = Vec::with_capacity(measurements.len());
+        let mut times = Vec::with_capacity(measurements.len());
+        let mut values = Vec::with_capacity(measurements.len());
+
+        for measurement in measurements {
+            sensor_ids.push(measurement.sensor_id);
+            kinds.push(measurement.kind);
+            times.push(measurement.time);
+            values.push(measurement.value);
+        }
+
+        let inserted = sqlx::query_scalar::<_, i64>(
+            r#"
+            WITH inserted AS (
+                INSERT INTO measurements (sensor_id, kind, time, value)
+                SELECT * FROM UNNEST($1::uuid[], $2::sensor_kind[], $3::timestamptz[], $4::float8[])
+                ON CONFLICT DO NOTHING
+                RETURNING sensor_id, kind, time, value
+            ),
+            resolutions (resolution, width) AS (
+                VALUES
+                    ('one_minute'::rollup_resolution, interval '1 minute'),
+                    ('fifteen_minutes', interval '15 minutes'),
+                    ('one_hour', interval '1 hour'),
+                    ('one_day', interval '1 day')
+            ),
+            rollups AS (
+                INSERT INTO measurement_rollups (sensor_id, kind, resolution, bucket, min, max, sum, count)
+                SELECT
+                    inserted.sensor_id,
+                    inserted.kind,
+                    resolutions.resolution,
+                    date_bin(resolutions.width, inserted.time, timestamptz '2000-01-01 00:00:00+00'),
+                    min(inserted.value),
+                    max(inserted.value),
+                    sum(inserted.value),
+                    count(*)
+                FROM inserted CROSS JOIN resolutions
+                GROUP BY 1, 2, 3, 4
+                ON CONFLICT (sensor_id, kind, resolution, bucket) DO UPDATE SET
+                    min = least(measurement_rollups.min, excluded.min),
+                    max = greatest(measurement_rollups.max, excluded.max),
+                    sum = measurement_rollups.sum + excluded.sum,
+                    count = measurement_rollups.count + excluded.count
+            )
+            SELECT count(*) FROM inserted
+            "#,
+        )
+        .bind(sensor_ids)
+        .bind(kinds)
+        .bind(times)
+        .bind(values)
+        .fetch_one(self.pool)
+        .await?;
+
+        Ok(u64::try_from(inserted)?)
+    }
+
+    /// Latest measurement of each of the given sensors, sensors without any
+    /// are left out.
+    pub async fn latest(&self, sensor_ids: &[Uuid]) -> Result<Vec<MeasurementRecord>> {
+        let records = sqlx::query_as::<_, MeasurementRecord>(
+            r#"
+            SELECT DISTINCT ON (sensor_id) sensor_id, kind, time, value
+            FROM measurements
+            WHERE sensor_id = ANY($1)
+            ORDER BY sensor_id, time DESC
+            "#,
+        )
+        .bind(sensor_ids)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    /// Measurements of a sensor in `[from, to)`, oldest first.
+    pub async fn range(
+        &self,
+        sensor_id: Uuid,
+        kind: SensorKind,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<MeasurementRecord>> {
+        let records = sqlx::query_as::<_, MeasurementRecord>(
+            r#"
+            SELECT sensor_id, kind, time, value
+            FROM measurements
+            WHERE sensor_id = $1
+            AND kind = $2
+            AND time >= $3
+            AND time < $4
+            ORDER BY time
+            "#,
+        )
+        .bind(sensor_id)
+        .bind(kind)
+        .bind(from)
+        .bind(to)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    /// History of a sensor at the finest resolution fitting the point budget
+    /// of the query, oldest first.
+    pub async fn history(&self, query: &HistoryQuery) -> Result<History> {
+        let resolution = query.resolution();
+
+        let points = match resolution {
+            Resolution::Raw => {
+                sqlx::query_as::<_, HistoryPoint>(
+                    r#"
+                    SELECT time, value AS min, value AS max, value AS avg, 1::int8 AS count
+                    FROM measurements
+                    WHERE sensor_id = $1
+                    AND kind = $2
+                    AND time >= $3
+                    AND time < $4
+                    ORDER BY time
+                    "#,
+                )
+                .bind(query.sensor_id)
+                .bind(query.kind)
+                .bind(query.from)
+                .bind(query.to)
+                .fetch_all(self.pool)
+                .await?
+            }
+            Resolution::Rollup(rollup) => {
+                sqlx::query_as::<_, HistoryPoint>(
+                    r#"
+                    SELECT bucket AS time, min, max, sum / count AS avg, count
+                    FROM measurement_rollups
+                    WHERE sensor_id = $1
+                    AND kind = $2
+                    AND resolution = $3
+                    AND bucket >= date_bin($4, $5, timestamptz '2000-01-01 00:00:00+00')
+                    AND bucket < $6
+                    ORDER BY bucket
+                    "#,
+                )
+                .bind(query.sensor_id)
+                .bind(query.kind)
+                .bind(rollup)
+                .bind(rollup.width())
+                .bind(query.from)
+                .bind(query.to)
+                .fetch_all(self.pool)
+                .await?
+            }
+        };
+
+        Ok(History { resolution, points })
+    }
+
+    /// Drop partitions older than `days`, applied by the partman maintenance.
+    pub async fn set_retention(&self, days: u32) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE part_config
+            SET retention = make_interval(days => $1),
+                retention_keep_table = false
+            WHERE parent_table = 'public.measurements'
+            "#,
+        )
+        .bind(i32::try_from(days)?)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+mod actuator_store;
+mod alert_store;
+mod definitions;
+mod history;
+mod measurement_store;
+mod schedule_store;
+mod sensor_store;
+mod station_store;
+
+pub use actuator_store::ActuatorStore;
+pub use alert_store::AlertStore;
+pub use definitions::*;
+pub use history::{History, HistoryQuery, Resolution};
+pub use measurement_store::MeasurementStore;
+pub use schedule_store::ScheduleStore;
+pub use sensor_store::SensorStore;
+pub use station_store::StationStore;
@@ -0,0 +1,112 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::{DateTime, Utc};
+use eyre::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::stores::ScheduleRuleRecord;
+
+#[derive(Clone, Copy)]
+pub struct ScheduleStore<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> ScheduleStore<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Live rules, oldest first.
+    pub async fn rules(&self) -> Result<Vec<ScheduleRuleRecord>> {
+        let rules = sqlx::query_as::<_, ScheduleRuleRecord>(
+            r#"
+            SELECT id, name, relay_id, trigger, action, time_zone, priority, enabled,
+                last_fired_at
+            FROM schedule_rules
+            WHERE deleted_at IS NULL
+            ORDER BY created_at
+            "#,
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(rules)
+    }
+
+    /// Insert a rule or replace the definition of a known one.
+    ///
+    /// The last firing is kept, a rule edited right after it fired doesn't
+    /// fire again for the same time.
+    pub async fn upsert(&self, rule: &ScheduleRuleRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO schedule_rules (
+                id, name, relay_id, trigger, action, time_zone, priority, enabled
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (id) DO UPDATE SET
+                name = excluded.name,
+                relay_id = excluded.relay_id,
+                trigger = excluded.trigger,
+                action = excluded.action,
+                time_zone = excluded.time_zone,
+                priority = excluded.priority,
+                enabled = excluded.enabled,
+                deleted_at = NULL
+            "#,
+        )
+        .bind(rule.id)
+        .bind(&rule.name)
+        .bind(&rule.relay_id)
+        .bind(&rule.trigger)
+        .bind(&rule.action)
+        .bind(&rule.time_zone)
+        .bind(rule.priority)
+        .bind(rule.enabled)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Soft delete a rule, returns whether it existed.
+    pub async fn delete(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE schedule_rules
+            SET deleted_at = now()
+            WHERE id = $1
+            AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record that the occurrence of a rule at `at` fires.
+    ///
+    /// Returns `false` if that occurrence, or a later one, already fired: the
+    /// caller must not fire it again.
+    pub async fn claim_firing(&self, id: Uuid, at: DateTime<Utc>) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE schedule_rules
+            SET last_fired_at = $2
+            WHERE id = $1
+            AND (last_fired_at IS NULL OR last_fired_at < $2)
+            "#,
+        )
+        .bind(id)
+        .bind(at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
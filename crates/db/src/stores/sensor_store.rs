@@ -0,0 +1,278 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use eyre::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::stores::{
+    SensorEventRecord, SensorIdentityRecord, SensorRecord, SensorStateRecord, SensorStatus,
+};
+
+#[derive(Clone, Copy)]
+pub struct SensorStore<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> SensorStore<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Find the live sensors known under any of the given hardware uids.
+    pub async fn find_by_hardware_uids(
+        &self,
+        hardware_uids: &[String],
+    ) -> Result<Vec<SensorIdentityRecord>> {
+        let records = sqlx::query_as::<_, SensorIdentityRecord>(
+            r#"
+            SELECT id, hardware_uid
+            FROM sensors
+            WHERE hardware_uid = ANY($1)
+            AND deleted_at IS NULL
+            "#,
+        )
+        .bind(hardware_uids)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    /// Sensors not forgotten, by name.
+    pub async fn list(&self) -> Result<Vec<SensorRecord>> {
+        let records = sqlx::query_as::<_, SensorRecord>(
+            r#"
+            SELECT id, station_knot_id, hardware_uid, name, kind, driver, protocol,
+                connection, firmware, status, state_reason, state_reason_details,
+                state_since, last_activity_at, consecutive_failures
+            FROM sensors
+            WHERE deleted_at IS NULL
+            ORDER BY name, id
+            "#,
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    /// Soft delete a sensor, its history is kept but its hardware uid is
+    /// released: the probe gets a new id the next time it is plugged.
+    ///
+    /// Returns whether the sensor was known.
+    pub async fn forget(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE sensors
+            SET deleted_at = now()
+            WHERE id = $1
+            AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Move a sensor to a new hardware uid, e.g. when a stronger identity
+    /// signal becomes available for the same probe.
+    pub async fn update_hardware_uid(&self, id: Uuid, hardware_uid: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE sensors
+            SET hardware_uid = $2
+            WHERE id = $1
+            AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(hardware_uid)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Insert a discovered sensor or refresh the row of a known one.
+    ///
+    /// The name is only set on insert, it may have been changed since by the
+    /// operator.
+    pub async fn upsert(&self, sensor: &SensorRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO sensors (
+                id, station_knot_id, hardware_uid, name, kind, driver, protocol,
+                connection, firmware, status, state_reason, state_reason_details,
+                state_since, last_activity_at, consecutive_failures
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            ON CONFLICT (id) DO UPDATE SET
+                station_knot_id = excluded.station_knot_id,
+                hardware_uid = excluded.hardware_uid,
+                kind = excluded.kind,
+                driver = excluded.driver,
+                protocol = excluded.protocol,
+                connection = excluded.connection,
+                firmware = excluded.firmware,
+                status = excluded.status,
+                state_reason = excluded.state_reason,
+                state_reason_details = excluded.state_reason_details,
+                state_since = excluded.state_since,
+                last_activity_at = excluded.last_activity_at,
+                consecutive_failures = excluded.consecutive_failures,
+                deleted_at = NULL
+            "#,
+        )
+        .bind(sensor.id)
+        .bind(sensor.station_knot_id)
+        .bind(&sensor.hardware_uid)
+        .bind(&sensor.name)
+        .bind(sensor.kind)
+        .bind(sensor.driver)
+        .bind(sensor.protocol)
+        .bind(&sensor.connection)
+        .bind(sensor.firmware)
+        .bind(sensor.state.status)
+        .bind(&sensor.state.state_reason)
+        .bind(&sensor.state.state_reason_details)
+        .bind(sensor.state.state_since)
+        .bind(sensor.state.last_activity_at)
+        .bind(sensor.state.consecutive_failures)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Rename a sensor along with the hardware uid its new name gives it, so
+    /// the probe resolves to the same row after a restart.
+    pub async fn rename(&self, id: Uuid, name: &str, hardware_uid: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE sensors
+            SET name = $2, hardware_uid = $3
+            WHERE id = $1
+            AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(hardware_uid)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Update the state of a sensor and append the transition to the event
+    /// log, atomically.
+    pub async fn record_transition(
+        &self,
+        id: Uuid,
+        previous_status: Option<SensorStatus>,
+        state: &SensorStateRecord,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        Self::write_state(&mut *tx, id, state).await?;
+        Self::insert_event(
+            &mut *tx,
+            &SensorEventRecord::new(id, previous_status, state),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Update the state of a sensor without logging an event, for changes
+    /// that keep its status such as activity and failure counts.
+    pub async fn update_state(&self, id: Uuid, state: &SensorStateRecord) -> Result<()> {
+        Self::write_state(self.pool, id, state).await
+    }
+
+    pub async fn append_event(&self, event: &SensorEventRecord) -> Result<()> {
+        Self::insert_event(self.pool, event).await
+    }
+
+    /// Events of a sensor, most recent first.
+    pub async fn events(&self, sensor_id: Uuid, limit: i64) -> Result<Vec<SensorEventRecord>> {
+        let events = sqlx::query_as::<_, SensorEventRecord>(
+            r#"
+            SELECT sensor_id, previous_status, status, state_reason, state_reason_details,
+                consecutive_failures, occurred_at
+            FROM sensor_events
+            WHERE sensor_id = $1
+            ORDER BY occurred_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(sensor_id)
+        .bind(limit)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    async fn write_state(
+        executor: impl sqlx::PgExecutor<'_>,
+        id: Uuid,
+        state: &SensorStateRecord,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE sensors
+            SET status = $2,
+                state_reason = $3,
+                state_reason_details = $4,
+                state_since = $5,
+                last_activity_at = $6,
+                consecutive_failures = $7
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(state.status)
+        .bind(&state.state_reason)
+        .bind(&state.state_reason_details)
+        .bind(state.state_since)
+        .bind(state.last_activity_at)
+        .bind(state.consecutive_failures)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_event(
+        executor: impl sqlx::PgExecutor<'_>,
+        event: &SensorEventRecord,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO sensor_events (
+                sensor_id, previous_status, status, state_reason, state_reason_details,
+                consecutive_failures, occurred_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(event.sensor_id)
+        .bind(event.previous_status)
+        .bind(event.status)
+        .bind(&event.state_reason)
+        .bind(&event.state_reason_details)
+        .bind(event.consecutive_failures)
+        .bind(event.occurred_at)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use eyre::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::stores::{StationHubRecord, StationKnotRecord, StationKnotStatus};
+
+/// The station hubs and the knots reporting to them.
+#[derive(Clone, Copy)]
+pub struct StationStore<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> StationStore<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Register the hub running on this Pi, once, and return its local knot
+    /// marked awake.
+    ///
+    /// The local knot is created with the hub by a trigger. The name is only
+    /// set on insert, it may have been changed since by the operator.
+    pub async fn register_local_hub(
+        &self,
+        name: &str,
+        hardware_uid: &str,
+    ) -> Result<StationKnotRecord> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO station_hubs (name, hardware_uid)
+            VALUES ($1, $2)
+            ON CONFLICT (hardware_uid) WHERE deleted_at IS NULL
+            DO NOTHING
+            "#,
+        )
+        .bind(name)
+        .bind(hardware_uid)
+        .execute(&mut *tx)
+        .await?;
+
+        let knot = sqlx::query_as::<_, StationKnotRecord>(
+            r#"
+            UPDATE station_knots k
+            SET status = 'awake'
+            FROM station_hubs h
+            WHERE h.id = k.station_hub_id
+            AND h.hardware_uid = $1
+            AND h.deleted_at IS NULL
+            AND k.role = 'local_hub'
+            AND k.deleted_at IS NULL
+            RETURNING k.id, k.station_hub_id, k.name, k.hardware_uid, k.role, k.status
+            "#,
+        )
+        .bind(hardware_uid)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(knot)
+    }
+
+    /// Register a remote knot with a hub, or mark a known one awake, and
+    /// return it.
+    ///
+    /// Returns `None` when the hardware uid is the one of a hub, e.g. a knot
+    /// running on the Pi of its hub without a hardware uid of its own.
+    pub async fn register_remote_knot(
+        &self,
+        station_hub_id: Uuid,
+        name: &str,
+        hardware_uid: &str,
+    ) -> Result<Option<StationKnotRecord>> {
+        let record = sqlx::query_as::<_, StationKnotRecord>(
+            r#"
+            INSERT INTO station_knots (station_hub_id, name, hardware_uid, role, status)
+            VALUES ($1, $2, $3, 'remote_knot', 'awake')
+            ON CONFLICT (hardware_uid) WHERE deleted_at IS NULL
+            DO UPDATE SET
+                station_hub_id = excluded.station_hub_id,
+                status = 'awake'
+            WHERE station_knots.role = 'remote_knot'
+            RETURNING id, station_hub_id, name, hardware_uid, role, status
+            "#,
+        )
+        .bind(station_hub_id)
+        .bind(name)
+        .bind(hardware_uid)
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(record)
+    }
+
+    /// Mark the remote knots of a hub unreachable until they connect again,
+    /// e.g. when the hub starts.
+    pub async fn mark_remote_knots_unreachable(&self, station_hub_id: Uuid) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE station_knots
+            SET status = 'unreachable'
+            WHERE station_hub_id = $1
+            AND role = 'remote_knot'
+            AND status = 'awake'
+            AND deleted_at IS NULL
+            "#,
+        )
+        .bind(station_hub_id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn hubs(&self) -> Result<Vec<StationHubRecord>> {
+        let records = sqlx::query_as::<_, StationHubRecord>(
+            r#"
+            SELECT id, user_id, name, hardware_uid, created_at
+            FROM station_hubs
+            WHERE deleted_at IS NULL
+            ORDER BY created_at
+            "#,
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    /// Knots of every hub, the local ones first.
+    pub async fn knots(&self) -> Result<Vec<StationKnotRecord>> {
+        let records = sqlx::query_as::<_, StationKnotRecord>(
+            r#"
+            SELECT id, station_hub_id, name, hardware_uid, role, status
+            FROM station_knots
+            WHERE deleted_at IS NULL
+            ORDER BY station_hub_id, role, name
+            "#,
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    pub async fn knot(&self, id: Uuid) -> Result<Option<StationKnotRecord>> {
+        let record = sqlx::query_as::<_, StationKnotRecord>(
+            r#"
+            SELECT id, station_hub_id, name, hardware_uid, role, status
+            FROM station_knots
+            WHERE id = $1
+            AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(record)
+    }
+
+    /// Rename a knot, returns whether it was known.
+    pub async fn rename_knot(&self, id: Uuid, name: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE station_knots
+            SET name = $2
+            WHERE id = $1
+            AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn set_knot_status(&self, id: Uuid, status: StationKnotStatus) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE station_knots
+            SET status = $2
+            WHERE id = $1
+            AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(status)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_config::ConfigHandler;
+use std::sync::LazyLock;
+
+use crate::solar::Location;
+
+pub static CONFIG: LazyLock<Config> = LazyLock::new(|| ConfigHandler::new(mpl).load());
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Where the station stands, for the sunrise and sunset rules
+    pub location: Location,
+}
+
+fn mpl() -> Config {
+    Config {
+        location: Location {
+            latitude: 48.8566,
+            longitude: 2.3522,
+        },
+    }
+}
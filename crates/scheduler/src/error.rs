@@ -0,0 +1,42 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::error::Error;
+use std::fmt;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchedulerError {
+    /// The rule can't be planned, e.g. a malformed cron expression.
+    InvalidRule(String),
+    /// The rule drives a relay the actuator service doesn't know.
+    UnknownRelay(String),
+    /// No rule is saved under this id.
+    UnknownRule(Uuid),
+    /// The rules couldn't be written to Postgres.
+    Store(String),
+    /// The actuator service driving the relays isn't running.
+    RelaysUnavailable,
+    /// The scheduler service isn't running.
+    ServiceStopped,
+}
+
+impl fmt::Display for SchedulerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchedulerError::InvalidRule(details) => write!(f, "Invalid schedule rule: {details}"),
+            SchedulerError::UnknownRelay(relay_id) => write!(f, "Unknown relay '{relay_id}'"),
+            SchedulerError::UnknownRule(id) => write!(f, "Unknown schedule rule {id}"),
+            SchedulerError::Store(details) => write!(f, "Failed to store the schedule: {details}"),
+            SchedulerError::RelaysUnavailable => {
+                write!(f, "The actuator service is not running")
+            }
+            SchedulerError::ServiceStopped => write!(f, "The scheduler service is not running"),
+        }
+    }
+}
+
+impl Error for SchedulerError {}
+
+pub type Result<T> = std::result::Result<T, SchedulerError>;
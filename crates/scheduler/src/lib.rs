@@ -0,0 +1,12 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+mod config;
+pub mod error;
+pub mod plan;
+pub mod rule;
+pub mod services;
+pub mod solar;
+
+pub use config::{Config, CONFIG};
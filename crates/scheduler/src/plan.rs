@@ -0,0 +1,220 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Occurrences of the rules and how overlaps between them are resolved.
+//!
+//! A rule holds its relay while its pulse runs. An occurrence on a held
+//! relay, or at the same instant as an accepted one, is superseded unless
+//! its priority is strictly higher. It then takes the relay over, the
+//! actuator service cancels the pending end of the previous pulse.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::rule::{RuleAction, ScheduleRule};
+use crate::solar::Location;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Occurrence {
+    pub rule_id: Uuid,
+    pub relay_id: String,
+    pub at: DateTime<Utc>,
+    pub action: RuleAction,
+    pub priority: i32,
+    /// Rule holding the relay at that time, the occurrence is skipped
+    pub superseded_by: Option<Uuid>,
+}
+
+/// Occurrences of the enabled `rules` after `after`, up to `until` included,
+/// unresolved.
+pub fn occurrences<'a>(
+    rules: impl IntoIterator<Item = &'a ScheduleRule>,
+    location: Location,
+    after: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<Occurrence> {
+    rules
+        .into_iter()
+        .filter(|rule| rule.enabled)
+        .flat_map(|rule| {
+            rule.occurrences(location, after, until)
+                .into_iter()
+                .map(|at| Occurrence {
+                    rule_id: rule.id,
+                    relay_id: rule.relay_id.clone(),
+                    at,
+                    action: rule.action,
+                    priority: rule.priority,
+                    superseded_by: None,
+                })
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+struct Hold {
+    rule_id: Uuid,
+    priority: i32,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+}
+
+/// Resolves overlaps, remembering the holds across calls so a pulse started
+/// in a previous batch still counts.
+#[derive(Clone, Debug, Default)]
+pub struct Planner {
+    holds: HashMap<String, Hold>,
+}
+
+impl Planner {
+    /// Sort `occurrences` by time and mark the superseded ones.
+    pub fn resolve(&mut self, mut occurrences: Vec<Occurrence>) -> Vec<Occurrence> {
+        occurrences.sort_by_key(|occurrence| {
+            (
+                occurrence.at,
+                Reverse(occurrence.priority),
+                occurrence.rule_id,
+            )
+        });
+
+        for occurrence in &mut occurrences {
+            if let Some(hold) = self.holds.get(&occurrence.relay_id) {
+                let held = occurrence.at == hold.from || occurrence.at < hold.until;
+                if held && occurrence.priority <= hold.priority {
+                    occurrence.superseded_by = Some(hold.rule_id);
+                    continue;
+                }
+            }
+
+            let until = TimeDelta::from_std(occurrence.action.hold())
+                .ok()
+                .and_then(|hold| occurrence.at.checked_add_signed(hold))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC);
+            self.holds.insert(
+                occurrence.relay_id.clone(),
+                Hold {
+                    rule_id: occurrence.rule_id,
+                    priority: occurrence.priority,
+                    from: occurrence.at,
+                    until,
+                },
+            );
+        }
+
+        occurrences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Tz;
+    use std::time::Duration;
+
+    use crate::rule::Trigger;
+
+    const PARIS: Location = Location {
+        latitude: 48.8566,
+        longitude: 2.3522,
+    };
+
+    fn rule(id: u128, expression: &str, action: RuleAction, priority: i32) -> ScheduleRule {
+        ScheduleRule {
+            id: Uuid::from_u128(id),
+            name: format!("rule {id}"),
+            relay_id: "mist_relay".to_string(),
+            trigger: Trigger::Cron {
+                expression: expression.to_string(),
+            },
+            action,
+            time_zone: Tz::UTC,
+            priority,
+            enabled: true,
+        }
+    }
+
+    fn pulse(secs: u64) -> RuleAction {
+        RuleAction::Pulse {
+            duration: Duration::from_secs(secs),
+        }
+    }
+
+    fn utc(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().to_utc()
+    }
+
+    #[test]
+    fn higher_priorities_win_overlaps() {
+        let rules = [
+            // Ten minutes of mist every hour
+            rule(1, "0 * * * *", pulse(600), 0),
+            // Quick burst every five minutes, lower priority
+            rule(2, "*/5 * * * *", pulse(30), -1),
+            // Top of the hour override
+            rule(3, "0 12 * * *", RuleAction::TurnOff, 10),
+        ];
+
+        let resolved = Planner::default().resolve(occurrences(
+            &rules,
+            PARIS,
+            utc("2024-06-21T11:59:00Z"),
+            utc("2024-06-21T12:15:00Z"),
+        ));
+        let outcome: Vec<_> = resolved
+            .iter()
+            .map(|occurrence| {
+                (
+                    occurrence.rule_id.as_u128(),
+                    occurrence.superseded_by.map(|rule| rule.as_u128()),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            outcome,
+            [
+                // Same instant, the override wins
+                (3, None),
+                (1, Some(3)),
+                (2, Some(3)),
+                // The hourly pulse didn't start, nothing holds the relay
+                (2, None),
+                (2, None),
+                (2, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn holds_span_batches() {
+        let rules = [
+            rule(1, "0 * * * *", pulse(600), 1),
+            rule(2, "*/5 * * * *", pulse(30), 0),
+        ];
+        let mut planner = Planner::default();
+
+        let first = planner.resolve(occurrences(
+            &rules,
+            PARIS,
+            utc("2024-06-21T11:59:00Z"),
+            utc("2024-06-21T12:00:00Z"),
+        ));
+        let second = planner.resolve(occurrences(
+            &rules,
+            PARIS,
+            utc("2024-06-21T12:00:00Z"),
+            utc("2024-06-21T12:10:00Z"),
+        ));
+
+        assert!(first[0].superseded_by.is_none());
+        assert!(first[1].superseded_by.is_some());
+        // 12:05 falls in the pulse, 12:10 right after it
+        assert_eq!(second[0].superseded_by, Some(Uuid::from_u128(1)));
+        assert_eq!(second[1].superseded_by, None);
+    }
+}
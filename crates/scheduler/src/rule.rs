@@ -0,0 +1,375 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::{DateTime, Days, LocalResult, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::error::{Result, SchedulerError};
+use crate::solar::{solar_event, Location, SolarEvent};
+
+/// When a rule fires, in the time zone of the rule.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Trigger {
+    /// Cron expression, `min hour day month weekday` with an optional leading
+    /// seconds field.
+    Cron { expression: String },
+    /// Every `every` from `start` to `end` included, e.g. every 2 hours
+    /// between 08:00 and 20:00. The window runs past midnight when `end` is
+    /// before `start` and lasts the whole day when they are equal.
+    Interval {
+        #[serde(rename = "every_secs", with = "seconds")]
+        every: Duration,
+        start: NaiveTime,
+        end: NaiveTime,
+    },
+    /// Sunrise or sunset at the station, shifted by `offset_minutes`.
+    Solar {
+        event: SolarEvent,
+        #[serde(default)]
+        offset_minutes: i32,
+    },
+}
+
+/// What a rule does to its relay when it fires.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    TurnOn,
+    TurnOff,
+    /// Energize the relay, then release it after `duration`
+    Pulse {
+        #[serde(rename = "duration_secs", with = "seconds")]
+        duration: Duration,
+    },
+}
+
+impl RuleAction {
+    /// How long the relay is held by the action.
+    pub fn hold(self) -> Duration {
+        match self {
+            RuleAction::Pulse { duration } => duration,
+            RuleAction::TurnOn | RuleAction::TurnOff => Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRule {
+    pub id: Uuid,
+    pub name: String,
+    pub relay_id: String,
+    pub trigger: Trigger,
+    pub action: RuleAction,
+    /// Zone of the cron and interval times, and of the solar dates
+    pub time_zone: Tz,
+    /// Wins over lower priorities on the same relay, see [`crate::plan`]
+    pub priority: i32,
+    pub enabled: bool,
+}
+
+impl ScheduleRule {
+    /// Check the rule can be planned.
+    pub fn validate(&self) -> Result<()> {
+        match &self.trigger {
+            Trigger::Cron { expression } => {
+                cron_schedule(expression)?;
+            }
+            Trigger::Interval { every, .. } if every.is_zero() => {
+                return Err(SchedulerError::InvalidRule(
+                    "the interval must be positive".to_string(),
+                ));
+            }
+            Trigger::Interval { .. } | Trigger::Solar { .. } => {}
+        }
+
+        if matches!(self.action, RuleAction::Pulse { duration } if duration.is_zero()) {
+            return Err(SchedulerError::InvalidRule(
+                "the pulse duration must be positive".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Times the rule fires after `after`, up to `until` included.
+    ///
+    /// Local times falling in a daylight saving gap are skipped, those
+    /// repeated when the clocks go back fire once.
+    pub fn occurrences(
+        &self,
+        location: Location,
+        after: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Vec<DateTime<Utc>> {
+        if until <= after {
+            return Vec::new();
+        }
+
+        let tz = self.time_zone;
+        let mut times = match &self.trigger {
+            Trigger::Cron { expression } => {
+                let Ok(schedule) = cron_schedule(expression) else {
+                    return Vec::new();
+                };
+                return schedule
+                    .after(&after.with_timezone(&tz))
+                    .map(|time| time.with_timezone(&Utc))
+                    .take_while(|time| *time <= until)
+                    .collect();
+            }
+            Trigger::Interval { every, start, end } => {
+                let Ok(every) = TimeDelta::from_std(*every) else {
+                    return Vec::new();
+                };
+                // Windows open the day before may still run after midnight
+                local_dates(tz, after, until, 1)
+                    .flat_map(|date| {
+                        let open = date.and_time(*start);
+                        let close = window_close(open, *start, *end);
+                        std::iter::successors(Some(open), move |time| Some(*time + every))
+                            .take_while(move |time| *time <= close)
+                    })
+                    .filter_map(|local| earliest(tz, local))
+                    .collect::<Vec<_>>()
+            }
+            Trigger::Solar {
+                event,
+                offset_minutes,
+            } => {
+                let offset = TimeDelta::minutes((*offset_minutes).into());
+                local_dates(tz, after, until, 0)
+                    .filter_map(|date| solar_event(date, location, *event))
+                    .map(|time| time + offset)
+                    .collect()
+            }
+        };
+
+        times.retain(|time| after < *time && *time <= until);
+        times.sort_unstable();
+        times.dedup();
+        times
+    }
+}
+
+fn cron_schedule(expression: &str) -> Result<cron::Schedule> {
+    // The cron crate expects the seconds field
+    let expression = match expression.split_whitespace().count() {
+        5 => format!("0 {expression}"),
+        _ => expression.to_string(),
+    };
+
+    cron::Schedule::from_str(&expression)
+        .map_err(|err| SchedulerError::InvalidRule(format!("'{expression}': {err}")))
+}
+
+/// Local dates covering `after` to `until`, starting `lookback` days earlier.
+fn local_dates(
+    tz: Tz,
+    after: DateTime<Utc>,
+    until: DateTime<Utc>,
+    lookback: u64,
+) -> impl Iterator<Item = chrono::NaiveDate> {
+    let first = after.with_timezone(&tz).date_naive() - Days::new(lookback);
+    let last = until.with_timezone(&tz).date_naive();
+
+    first.iter_days().take_while(move |date| *date <= last)
+}
+
+/// Last local time of the window opened at `open`, itself excluded for a
+/// whole day window.
+fn window_close(open: NaiveDateTime, start: NaiveTime, end: NaiveTime) -> NaiveDateTime {
+    let length = end - start;
+    if length > TimeDelta::zero() {
+        open + length
+    } else if length < TimeDelta::zero() {
+        open + length + TimeDelta::days(1)
+    } else {
+        open + TimeDelta::days(1) - TimeDelta::nanoseconds(1)
+    }
+}
+
+fn earliest(tz: Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => {
+            Some(time.with_timezone(&Utc))
+        }
+        LocalResult::None => None,
+    }
+}
+
+/// Durations as whole seconds in the stored rules.
+mod seconds {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PARIS: Location = Location {
+        latitude: 48.8566,
+        longitude: 2.3522,
+    };
+
+    fn rule(trigger: Trigger, time_zone: Tz) -> ScheduleRule {
+        ScheduleRule {
+            id: Uuid::nil(),
+            name: "mist".to_string(),
+            relay_id: "mist_relay".to_string(),
+            trigger,
+            action: RuleAction::Pulse {
+                duration: Duration::from_secs(30),
+            },
+            time_zone,
+            priority: 0,
+            enabled: true,
+        }
+    }
+
+    fn utc(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().to_utc()
+    }
+
+    #[test]
+    fn intervals_follow_the_local_window() {
+        let rule = rule(
+            Trigger::Interval {
+                every: Duration::from_secs(2 * 3600),
+                start: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+            },
+            Tz::Europe__Paris,
+        );
+
+        let times = rule.occurrences(
+            PARIS,
+            utc("2024-06-21T00:00:00Z"),
+            utc("2024-06-22T00:00:00Z"),
+        );
+
+        // 08:00 to 20:00 CEST
+        assert_eq!(times.len(), 7);
+        assert_eq!(times[0], utc("2024-06-21T06:00:00Z"));
+        assert_eq!(times[6], utc("2024-06-21T18:00:00Z"));
+    }
+
+    #[test]
+    fn windows_can_run_past_midnight() {
+        let rule = rule(
+            Trigger::Interval {
+                every: Duration::from_secs(3600),
+                start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+            },
+            Tz::UTC,
+        );
+
+        let times = rule.occurrences(
+            PARIS,
+            utc("2024-06-21T00:00:00Z"),
+            utc("2024-06-21T23:59:59Z"),
+        );
+
+        // Midnight is excluded as the start of the range
+        assert_eq!(
+            times,
+            [
+                utc("2024-06-21T01:00:00Z"),
+                utc("2024-06-21T22:00:00Z"),
+                utc("2024-06-21T23:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn cron_rules_use_their_time_zone() {
+        let rule = rule(
+            Trigger::Cron {
+                expression: "30 7 * * Mon-Fri".to_string(),
+            },
+            Tz::America__New_York,
+        );
+        rule.validate().unwrap();
+
+        // Friday then Monday, 07:30 EST
+        let times = rule.occurrences(
+            PARIS,
+            utc("2024-01-05T00:00:00Z"),
+            utc("2024-01-09T00:00:00Z"),
+        );
+
+        assert_eq!(
+            times,
+            [utc("2024-01-05T12:30:00Z"), utc("2024-01-08T12:30:00Z")]
+        );
+    }
+
+    #[test]
+    fn solar_rules_are_offset() {
+        let rule = rule(
+            Trigger::Solar {
+                event: SolarEvent::Sunset,
+                offset_minutes: -30,
+            },
+            Tz::Europe__Paris,
+        );
+
+        let times = rule.occurrences(
+            PARIS,
+            utc("2024-06-21T00:00:00Z"),
+            utc("2024-06-22T00:00:00Z"),
+        );
+        let sunset = solar_event(times[0].date_naive(), PARIS, SolarEvent::Sunset).unwrap();
+
+        assert_eq!(times, [sunset - TimeDelta::minutes(30)]);
+    }
+
+    #[test]
+    fn rules_are_stored_as_tagged_json() {
+        let rule = rule(
+            Trigger::Interval {
+                every: Duration::from_secs(7200),
+                start: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+            },
+            Tz::Europe__Paris,
+        );
+
+        assert_eq!(
+            serde_json::to_value(&rule.trigger).unwrap(),
+            json!({ "type": "interval", "every_secs": 7200, "start": "08:00:00", "end": "20:00:00" })
+        );
+        assert_eq!(
+            serde_json::to_value(rule.action).unwrap(),
+            json!({ "type": "pulse", "duration_secs": 30 })
+        );
+
+        let invalid = ScheduleRule {
+            trigger: Trigger::Cron {
+                expression: "not a cron".to_string(),
+            },
+            ..rule
+        };
+        assert!(matches!(
+            invalid.validate(),
+            Err(SchedulerError::InvalidRule(_))
+        ));
+    }
+}
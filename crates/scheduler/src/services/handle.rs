@@ -0,0 +1,66 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Duration;
+use uuid::Uuid;
+
+use crate::error::{Result, SchedulerError};
+use crate::plan::Occurrence;
+use crate::rule::ScheduleRule;
+use crate::services::scheduler_service::SchedulerServiceCmd;
+
+#[derive(Clone)]
+pub struct SchedulerServiceHandle {
+    cmd_tx: mpsc::Sender<SchedulerServiceCmd>,
+}
+
+impl SchedulerServiceHandle {
+    pub(crate) fn new(cmd_tx: mpsc::Sender<SchedulerServiceCmd>) -> Self {
+        Self { cmd_tx }
+    }
+
+    /// Every rule, by name.
+    pub async fn rules(&self) -> Result<Vec<ScheduleRule>> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(SchedulerServiceCmd::Rules { respond_to }, rx)
+            .await
+    }
+
+    /// Insert a rule, or replace the one with the same id.
+    pub async fn save(&self, rule: ScheduleRule) -> Result<ScheduleRule> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(SchedulerServiceCmd::Save { rule, respond_to }, rx)
+            .await?
+    }
+
+    pub async fn remove(&self, id: Uuid) -> Result<()> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(SchedulerServiceCmd::Remove { id, respond_to }, rx)
+            .await?
+    }
+
+    /// What the rules will do over the next `horizon`, superseded
+    /// occurrences included.
+    pub async fn preview(&self, horizon: Duration) -> Result<Vec<Occurrence>> {
+        let (respond_to, rx) = oneshot::channel();
+        self.request(
+            SchedulerServiceCmd::Preview {
+                horizon,
+                respond_to,
+            },
+            rx,
+        )
+        .await
+    }
+
+    async fn request<T>(&self, cmd: SchedulerServiceCmd, rx: oneshot::Receiver<T>) -> Result<T> {
+        self.cmd_tx
+            .send(cmd)
+            .await
+            .map_err(|_| SchedulerError::ServiceStopped)?;
+
+        rx.await.map_err(|_| SchedulerError::ServiceStopped)
+    }
+}
@@ -0,0 +1,472 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::error::ActuatorError;
+use arksync_actuator::services::ActuatorServiceHandle;
+use arksync_db::stores::ScheduleRuleRecord;
+use arksync_db::ScheduleStore;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{sleep, Duration};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::error::{Result, SchedulerError};
+use crate::plan::{occurrences, Occurrence, Planner};
+use crate::rule::{RuleAction, ScheduleRule};
+use crate::services::SchedulerServiceHandle;
+use crate::solar::Location;
+
+/// Longest sleep between two checks, wall clock jumps are caught up after it.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+pub(crate) enum SchedulerServiceCmd {
+    Rules {
+        respond_to: oneshot::Sender<Vec<ScheduleRule>>,
+    },
+    /// Insert a rule or replace the one with the same id
+    Save {
+        rule: ScheduleRule,
+        respond_to: oneshot::Sender<Result<ScheduleRule>>,
+    },
+    Remove {
+        id: Uuid,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Resolved occurrences from now on, over `horizon`
+    Preview {
+        horizon: Duration,
+        respond_to: oneshot::Sender<Vec<Occurrence>>,
+    },
+}
+
+/// Fires the schedule rules through the actuator service.
+///
+/// Occurrences missed while the service was stopped are not caught up, and
+/// an occurrence never fires twice: the last firing of each rule is kept,
+/// in Postgres when a store is attached.
+pub struct SchedulerService {
+    relays: ActuatorServiceHandle,
+    location: Location,
+    store: Option<ScheduleStore<'static>>,
+    rules: HashMap<Uuid, ScheduleRule>,
+    last_fired: HashMap<Uuid, DateTime<Utc>>,
+    planner: Planner,
+    cmd_tx: mpsc::Sender<SchedulerServiceCmd>,
+    cmd_rx: mpsc::Receiver<SchedulerServiceCmd>,
+}
+
+impl SchedulerService {
+    pub fn new(relays: ActuatorServiceHandle, location: Location) -> Self {
+        let (cmd_tx, cmd_rx) = mpsc::channel(100);
+
+        Self {
+            relays,
+            location,
+            store: None,
+            rules: HashMap::new(),
+            last_fired: HashMap::new(),
+            planner: Planner::default(),
+            cmd_tx,
+            cmd_rx,
+        }
+    }
+
+    /// Handle to talk to the service once it runs.
+    pub fn handle(&self) -> SchedulerServiceHandle {
+        SchedulerServiceHandle::new(self.cmd_tx.clone())
+    }
+
+    /// Load the rules from Postgres when the service starts and keep them
+    /// there.
+    pub fn with_store(mut self, store: ScheduleStore<'static>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Rules to start with, on top of the stored ones.
+    ///
+    /// With a store they are saved there when the service starts, their
+    /// firings are claimed on their row.
+    pub fn with_rules(mut self, rules: impl IntoIterator<Item = ScheduleRule>) -> Self {
+        self.rules
+            .extend(rules.into_iter().map(|rule| (rule.id, rule)));
+        self
+    }
+
+    /// Fire the rules until `shutdown` is cancelled.
+    pub async fn run(mut self, shutdown: CancellationToken) {
+        self.load().await;
+        tracing::info!(rules = self.rules.len(), "Scheduler service started.");
+
+        let mut cursor = Utc::now();
+        loop {
+            let now = Utc::now();
+            self.fire_due(cursor, now).await;
+            cursor = now;
+
+            let wait = self.next_wake(now);
+            tokio::select! {
+                Some(cmd) = self.cmd_rx.recv() => self.handle_cmd(cmd, now).await,
+                _ = sleep(wait) => {}
+                _ = shutdown.cancelled() => break,
+            }
+        }
+
+        tracing::info!("Scheduler service stopped.");
+    }
+
+    async fn load(&mut self) {
+        let Some(store) = self.store else {
+            return;
+        };
+
+        let records = match store.rules().await {
+            Ok(records) => records,
+            Err(err) => {
+                tracing::error!(
+                    error = format_args!("{err:#}"),
+                    "Failed to load the schedule rules."
+                );
+                return;
+            }
+        };
+
+        let stored: HashSet<Uuid> = records.iter().map(|record| record.id).collect();
+        for rule in self
+            .rules
+            .values()
+            .filter(|rule| !stored.contains(&rule.id))
+        {
+            if let Err(err) = store.upsert(&rule_record(rule)).await {
+                tracing::error!(
+                    rule = rule.name,
+                    error = format_args!("{err:#}"),
+                    "Failed to save a schedule rule, it won't fire."
+                );
+            }
+        }
+
+        for record in records {
+            if let Some(last_fired_at) = record.last_fired_at {
+                self.last_fired.insert(record.id, last_fired_at);
+            }
+            match rule_from_record(record) {
+                Ok(rule) => {
+                    self.rules.insert(rule.id, rule);
+                }
+                Err(err) => tracing::error!(error = %err, "Skipping a schedule rule."),
+            }
+        }
+    }
+
+    async fn handle_cmd(&mut self, cmd: SchedulerServiceCmd, now: DateTime<Utc>) {
+        match cmd {
+            SchedulerServiceCmd::Rules { respond_to } => {
+                let mut rules: Vec<_> = self.rules.values().cloned().collect();
+                rules.sort_by(|a, b| a.name.cmp(&b.name));
+                let _ = respond_to.send(rules);
+            }
+
+            SchedulerServiceCmd::Save { rule, respond_to } => {
+                let _ = respond_to.send(self.save(rule).await);
+            }
+
+            SchedulerServiceCmd::Remove { id, respond_to } => {
+                let _ = respond_to.send(self.remove(id).await);
+            }
+
+            SchedulerServiceCmd::Preview {
+                horizon,
+                respond_to,
+            } => {
+                let until = now + TimeDelta::from_std(horizon).unwrap_or(TimeDelta::days(1));
+                let mut planner = self.planner.clone();
+                let _ = respond_to.send(planner.resolve(occurrences(
+                    self.rules.values(),
+                    self.location,
+                    now,
+                    until,
+                )));
+            }
+        }
+    }
+
+    async fn save(&mut self, rule: ScheduleRule) -> Result<ScheduleRule> {
+        rule.validate()?;
+
+        let states = self
+            .relays
+            .states()
+            .await
+            .ok_or(SchedulerError::RelaysUnavailable)?;
+        if !states.iter().any(|(spec, _)| spec.id == rule.relay_id) {
+            return Err(SchedulerError::UnknownRelay(rule.relay_id));
+        }
+
+        if let Some(store) = self.store {
+            store
+                .upsert(&rule_record(&rule))
+                .await
+                .map_err(|err| SchedulerError::Store(format!("{err:#}")))?;
+        }
+
+        tracing::info!(rule = rule.name, "Schedule rule saved.");
+        self.rules.insert(rule.id, rule.clone());
+        Ok(rule)
+    }
+
+    async fn remove(&mut self, id: Uuid) -> Result<()> {
+        if !self.rules.contains_key(&id) {
+            return Err(SchedulerError::UnknownRule(id));
+        }
+
+        if let Some(store) = self.store {
+            store
+                .delete(id)
+                .await
+                .map_err(|err| SchedulerError::Store(format!("{err:#}")))?;
+        }
+
+        if let Some(rule) = self.rules.remove(&id) {
+            tracing::info!(rule = rule.name, "Schedule rule removed.");
+        }
+        Ok(())
+    }
+
+    /// Fire the occurrences after `after`, up to `now` included.
+    async fn fire_due(&mut self, after: DateTime<Utc>, now: DateTime<Utc>) {
+        let mut due = occurrences(self.rules.values(), self.location, after, now);
+        due.retain(|occurrence| {
+            self.last_fired
+                .get(&occurrence.rule_id)
+                .is_none_or(|last| occurrence.at > *last)
+        });
+
+        for occurrence in self.planner.resolve(due) {
+            let name = self
+                .rules
+                .get(&occurrence.rule_id)
+                .map(|rule| rule.name.clone())
+                .unwrap_or_default();
+
+            if let Some(holder) = occurrence.superseded_by {
+                tracing::info!(
+                    rule = name,
+                    superseded_by = %holder,
+                    relay_id = occurrence.relay_id,
+                    "Schedule rule superseded."
+                );
+                continue;
+            }
+
+            if !self.claim(&occurrence).await {
+                tracing::warn!(rule = name, at = %occurrence.at, "Schedule rule already fired.");
+                continue;
+            }
+
+            tracing::info!(
+                rule = name,
+                relay_id = occurrence.relay_id,
+                "Schedule rule fires."
+            );
+            if let Err(err) = self.execute(&occurrence).await {
+                match err {
+                    ActuatorError::InterlockRefused { .. } => {
+                        tracing::warn!(rule = name, error = %err, "Schedule rule refused.")
+                    }
+                    _ => tracing::error!(rule = name, error = %err, "Schedule rule failed."),
+                }
+            }
+        }
+    }
+
+    /// Whether the occurrence is the first to fire at its time.
+    async fn claim(&mut self, occurrence: &Occurrence) -> bool {
+        if let Some(store) = self.store {
+            match store.claim_firing(occurrence.rule_id, occurrence.at).await {
+                Ok(true) => {}
+                Ok(false) => return false,
+                // Still fired, the in-memory firing keeps it unique while
+                // the service runs
+                Err(err) => tracing::error!(
+                    error = format_args!("{err:#}"),
+                    "Failed to record the schedule firing."
+                ),
+            }
+        }
+
+        self.last_fired.insert(occurrence.rule_id, occurrence.at);
+        true
+    }
+
+    async fn execute(&self, occurrence: &Occurrence) -> arksync_actuator::error::Result<()> {
+        let relay_id = &occurrence.relay_id;
+        match occurrence.action {
+            RuleAction::TurnOn => self.relays.turn_on(relay_id).await?,
+            RuleAction::TurnOff => self.relays.turn_off(relay_id).await?,
+            RuleAction::Pulse { duration } => self.relays.pulse(relay_id, duration).await?,
+        };
+
+        Ok(())
+    }
+
+    /// Time until the next occurrence, at most [`MAX_SLEEP`].
+    fn next_wake(&self, now: DateTime<Utc>) -> Duration {
+        let horizon = now + TimeDelta::from_std(MAX_SLEEP).unwrap_or(TimeDelta::minutes(1));
+
+        occurrences(self.rules.values(), self.location, now, horizon)
+            .iter()
+            .map(|occurrence| occurrence.at)
+            .min()
+            .and_then(|next| (next - now).to_std().ok())
+            .map_or(MAX_SLEEP, |wait| wait.min(MAX_SLEEP))
+    }
+}
+
+fn rule_record(rule: &ScheduleRule) -> ScheduleRuleRecord {
+    ScheduleRuleRecord {
+        id: rule.id,
+        name: rule.name.clone(),
+        relay_id: rule.relay_id.clone(),
+        trigger: serde_json::to_value(&rule.trigger).unwrap_or_default(),
+        action: serde_json::to_value(rule.action).unwrap_or_default(),
+        time_zone: rule.time_zone.name().to_string(),
+        priority: rule.priority,
+        enabled: rule.enabled,
+        last_fired_at: None,
+    }
+}
+
+fn rule_from_record(record: ScheduleRuleRecord) -> Result<ScheduleRule> {
+    let invalid =
+        |details: String| SchedulerError::InvalidRule(format!("rule '{}' {details}", record.name));
+
+    let rule = ScheduleRule {
+        id: record.id,
+        trigger: serde_json::from_value(record.trigger.clone())
+            .map_err(|err| invalid(format!("has an invalid trigger: {err}")))?,
+        action: serde_json::from_value(record.action.clone())
+            .map_err(|err| invalid(format!("has an invalid action: {err}")))?,
+        time_zone: chrono_tz::Tz::from_str(&record.time_zone)
+            .map_err(|err| invalid(format!("has an invalid time zone: {err}")))?,
+        name: record.name,
+        relay_id: record.relay_id,
+        priority: record.priority,
+        enabled: record.enabled,
+    };
+    rule.validate()?;
+
+    Ok(rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arksync_actuator::event::{ActuatorEvent, SwitchReason};
+    use arksync_actuator::gpio::{GpioBackend, SimulatedGpio};
+    use arksync_actuator::relay::{RelayBank, MIST_BOARD, MIST_RELAY};
+    use arksync_actuator::services::ActuatorService;
+    use chrono_tz::Tz;
+
+    use crate::rule::Trigger;
+
+    fn relays() -> ActuatorServiceHandle {
+        let gpio = SimulatedGpio::default();
+        let bank = RelayBank::open(&[MIST_BOARD], &GpioBackend::Simulated(gpio)).unwrap();
+        let service = ActuatorService::new(bank);
+        let handle = service.handle();
+        tokio::spawn(service.run(CancellationToken::new()));
+        handle
+    }
+
+    fn rule(expression: &str, action: RuleAction) -> ScheduleRule {
+        ScheduleRule {
+            id: Uuid::new_v4(),
+            name: "mist".to_string(),
+            relay_id: MIST_RELAY.id.to_string(),
+            trigger: Trigger::Cron {
+                expression: expression.to_string(),
+            },
+            action,
+            time_zone: Tz::UTC,
+            priority: 0,
+            enabled: true,
+        }
+    }
+
+    const PARIS: Location = Location {
+        latitude: 48.8566,
+        longitude: 2.3522,
+    };
+
+    fn utc(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().to_utc()
+    }
+
+    #[tokio::test]
+    async fn occurrences_fire_once() {
+        let relays = relays();
+        let mut events = relays.subscribe();
+        let mut scheduler = SchedulerService::new(relays, PARIS)
+            .with_rules([rule("0 8 * * *", RuleAction::TurnOn)]);
+
+        scheduler
+            .fire_due(utc("2024-06-21T07:59:00Z"), utc("2024-06-21T08:00:00Z"))
+            .await;
+        // Overlapping check, e.g. after the clock went back
+        scheduler
+            .fire_due(utc("2024-06-21T07:30:00Z"), utc("2024-06-21T08:01:00Z"))
+            .await;
+
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            ActuatorEvent::StateChanged { state, reason: SwitchReason::Command } if state.active
+        ));
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn rules_are_checked_before_being_saved() {
+        let scheduler = SchedulerService::new(relays(), PARIS);
+        let handle = scheduler.handle();
+        let shutdown = CancellationToken::new();
+        tokio::spawn(scheduler.run(shutdown.clone()));
+
+        let mut unknown = rule("0 8 * * *", RuleAction::TurnOn);
+        unknown.relay_id = "unknown".to_string();
+        assert_eq!(
+            handle.save(unknown).await,
+            Err(SchedulerError::UnknownRelay("unknown".to_string()))
+        );
+
+        let rule = rule("*/5 * * * *", RuleAction::TurnOff);
+        handle.save(rule.clone()).await.unwrap();
+        assert_eq!(handle.rules().await.unwrap(), std::slice::from_ref(&rule));
+
+        // Every 5 minutes over a day
+        let preview = handle
+            .preview(Duration::from_secs(24 * 3600))
+            .await
+            .unwrap();
+        assert!((287..=288).contains(&preview.len()));
+
+        handle.remove(rule.id).await.unwrap();
+        assert_eq!(
+            handle.remove(rule.id).await,
+            Err(SchedulerError::UnknownRule(rule.id))
+        );
+
+        shutdown.cancel();
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let rule = rule("0 8 * * *", RuleAction::TurnOn);
+
+        assert_eq!(rule_from_record(rule_record(&rule)), Ok(rule));
+    }
+}
@@ -0,0 +1,133 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Sunrise and sunset times, computed locally with the NOAA sunrise equation.
+//!
+//! Accurate to a minute or two away from the polar circles, enough to drive
+//! lights.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Julian day of 1970-01-01 00:00 UTC.
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2440587.5;
+/// Julian day of 2000-01-01 12:00 UTC.
+const J2000: f64 = 2451545.0;
+/// Sun altitude at sunrise and sunset, refraction and solar disc included.
+const HORIZON_DEGREES: f64 = -0.833;
+const EARTH_TILT_DEGREES: f64 = 23.4397;
+
+/// Coordinates in decimal degrees, east and north positive.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SolarEvent {
+    Sunrise,
+    Sunset,
+}
+
+/// Time of `event` on `date` at `location`.
+///
+/// `None` when the sun doesn't cross the horizon that day, during the polar
+/// night or the midnight sun.
+pub fn solar_event(
+    date: NaiveDate,
+    location: Location,
+    event: SolarEvent,
+) -> Option<DateTime<Utc>> {
+    let days_since_epoch = (date - NaiveDate::default()).num_days() as f64;
+    let day = (days_since_epoch + UNIX_EPOCH_JULIAN_DAY - J2000 + 0.0008).ceil();
+
+    // Mean solar noon at the longitude
+    let mean_noon = day - location.longitude / 360.0;
+    let anomaly = (357.5291 + 0.98560028 * mean_noon).rem_euclid(360.0);
+    let m = anomaly.to_radians();
+    let center = 1.9148 * m.sin() + 0.02 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    let ecliptic_longitude = (anomaly + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = J2000 + mean_noon + 0.0053 * m.sin() - 0.0069 * (2.0 * ecliptic_longitude).sin();
+
+    let declination = (ecliptic_longitude.sin() * EARTH_TILT_DEGREES.to_radians().sin()).asin();
+    let latitude = location.latitude.to_radians();
+    let cos_hour_angle = (HORIZON_DEGREES.to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+
+    let half_day = cos_hour_angle.acos().to_degrees() / 360.0;
+    let julian_day = match event {
+        SolarEvent::Sunrise => transit - half_day,
+        SolarEvent::Sunset => transit + half_day,
+    };
+    let millis = ((julian_day - UNIX_EPOCH_JULIAN_DAY) * 86_400_000.0).round() as i64;
+
+    DateTime::from_timestamp_millis(millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const PARIS: Location = Location {
+        latitude: 48.8566,
+        longitude: 2.3522,
+    };
+
+    fn assert_near(actual: Option<DateTime<Utc>>, expected: DateTime<Utc>) {
+        let delta = (actual.unwrap() - expected).num_seconds().abs();
+        assert!(delta <= 120, "{actual:?} is {delta}s away from {expected}");
+    }
+
+    #[test]
+    fn paris_summer_solstice() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+
+        assert_near(
+            solar_event(date, PARIS, SolarEvent::Sunrise),
+            Utc.with_ymd_and_hms(2024, 6, 21, 3, 47, 0).unwrap(),
+        );
+        assert_near(
+            solar_event(date, PARIS, SolarEvent::Sunset),
+            Utc.with_ymd_and_hms(2024, 6, 21, 19, 58, 0).unwrap(),
+        );
+    }
+
+    #[test]
+    fn west_longitudes_keep_their_local_date() {
+        let los_angeles = Location {
+            latitude: 34.0522,
+            longitude: -118.2437,
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 12, 21).unwrap();
+
+        // 16:47 PST
+        assert_near(
+            solar_event(date, los_angeles, SolarEvent::Sunset),
+            Utc.with_ymd_and_hms(2024, 12, 22, 0, 47, 0).unwrap(),
+        );
+    }
+
+    #[test]
+    fn no_event_beyond_the_polar_circle() {
+        let tromso = Location {
+            latitude: 69.6492,
+            longitude: 18.9553,
+        };
+
+        for date in [
+            NaiveDate::from_ymd_opt(2024, 6, 21).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 12, 21).unwrap(),
+        ] {
+            assert_eq!(solar_event(date, tromso, SolarEvent::Sunrise), None);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_config::ConfigHandler;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use uuid::Uuid;
+
+pub static CONFIG: LazyLock<Config> = LazyLock::new(|| ConfigHandler::new(mpl).load());
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Sensor ids pinned by the operator, keyed by hardware uid (e.g.
+    /// `usb:DQ0042`), they win over any identity signal
+    pub identities: HashMap<String, Uuid>,
+}
+
+fn mpl() -> Config {
+    Config {
+        identities: HashMap::new(),
+    }
+}
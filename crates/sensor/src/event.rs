@@ -0,0 +1,67 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::sensor::{SensorInfo, SensorKind, SensorState};
+
+/// A valid value read from a sensor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    pub sensor_id: Uuid,
+    pub kind: SensorKind,
+    pub value: f64,
+    pub time: DateTime<Utc>,
+}
+
+/// Outcome of one read, valid or not.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReadReport {
+    pub sensor_id: Uuid,
+    pub kind: SensorKind,
+    /// Time spent on the connection
+    pub latency: Duration,
+    /// The board answered with a valid measurement
+    pub ok: bool,
+    pub consecutive_failures: u32,
+}
+
+/// Events published by the sensor service.
+///
+/// Subscribers get them through a broadcast channel, see
+/// [`crate::services::SensorServiceHandle::subscribe`]. A slow subscriber may
+/// lag and miss events, it must not block the service.
+#[derive(Debug, Clone)]
+pub enum SensorEvent {
+    /// A sensor joined the registry.
+    Discovered(SensorInfo),
+    /// A sensor moved from `previous` to `info.state`.
+    StateChanged {
+        previous: SensorState,
+        info: SensorInfo,
+    },
+    /// A sensor left the registry, `info` is its last known snapshot.
+    Removed(SensorInfo),
+    /// A sensor got a new name.
+    Renamed(SensorInfo),
+    /// A sensor produced a valid measurement.
+    Measurement(Measurement),
+    /// A read finished, published after its measurement and state change.
+    ReadCompleted(ReadReport),
+}
+
+impl SensorEvent {
+    pub fn sensor_id(&self) -> Uuid {
+        match self {
+            SensorEvent::Discovered(info)
+            | SensorEvent::StateChanged { info, .. }
+            | SensorEvent::Removed(info)
+            | SensorEvent::Renamed(info) => info.id,
+            SensorEvent::Measurement(measurement) => measurement.sensor_id,
+            SensorEvent::ReadCompleted(report) => report.sensor_id,
+        }
+    }
+}
@@ -55,5 +55,7 @@ pub trait CommandTransport {
 pub trait Driver: CommandTransport {
     fn connection_info(&self) -> SensorConnection;
     fn device_info(&mut self) -> Result<DeviceInfo>;
+    /// Name stored on the board, `None` when it was never set.
+    fn name(&mut self) -> Result<Option<String>>;
     fn status(&mut self) -> Result<Status>;
 }
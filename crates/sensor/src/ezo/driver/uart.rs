@@ -65,9 +65,12 @@ impl Driver for UartDriver {
             }
 
             // Got unexpected response (possibly temperature reading or stale data)
-            eprintln!(
-                "Attempt {}/{}: Unexpected response to 'i' command: '{}' - retrying...",
-                attempt, MAX_RETRIES, response
+            tracing::debug!(
+                port = %self.connection.metadata.port_name,
+                attempt,
+                max_attempts = MAX_RETRIES,
+                %response,
+                "Unexpected response to the 'i' command, retrying."
             );
 
             // Small delay before retry
@@ -79,6 +82,23 @@ impl Driver for UartDriver {
         ))
     }
 
+    fn name(&mut self) -> Result<Option<String>> {
+        let response = self.send_command(b"Name,?")?;
+
+        // Atlas Scientific name response format: ?Name,<name>
+        // The name is empty when it was never set on the board.
+        match response.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("?Name,") => {
+                let name = response[6..].trim();
+                Ok((!name.is_empty()).then(|| name.to_string()))
+            }
+            _ => Err(DriverError::Read(format!(
+                "Unexpected response to 'Name,?' command: '{}'",
+                response
+            ))),
+        }
+    }
+
     fn status(&mut self) -> Result<Status> {
         let response = self.send_command(b"Status")?;
 
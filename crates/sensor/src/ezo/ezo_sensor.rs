@@ -4,14 +4,37 @@
 
 use chrono::Utc;
 use std::sync::Mutex;
+use std::time::Duration;
 
 use crate::core::temperature::DynamicRange;
 use crate::error::{Result, SensorError};
 use crate::ezo::driver::DriverError;
 use crate::ezo::driver::{CommandTransport, Driver};
-use crate::sensor::{Sensor, SensorInfo, SensorState, SensorStateReason};
+use crate::identity::IdentitySignal;
+use crate::sensor::{Calibration, Sensor, SensorInfo, SensorName, SensorState, SensorStateReason};
 
 const UNREACHABLE_FAILURE_THRESHOLD: u32 = 3;
+/// Longest name an EZO circuit stores.
+const MAX_NAME_LEN: usize = 16;
+/// LED off and on cycles of an identification.
+const IDENTIFY_BLINKS: usize = 5;
+const IDENTIFY_BLINK_DELAY: Duration = Duration::from_millis(300);
+
+/// Send a command answered with `*OK`.
+///
+/// Boards with response codes disabled answer with an empty line.
+fn send_ok(driver: &mut impl CommandTransport, command: &str) -> Result<()> {
+    let response = driver
+        .send_command(command.as_bytes())
+        .map_err(SensorError::source)?;
+
+    match response.trim() {
+        "*OK" | "" => Ok(()),
+        other => Err(SensorError::message(format!(
+            "Unexpected response to '{command}' command: '{other}'"
+        ))),
+    }
+}
 
 pub trait EzoSensor: Send + Sync + 'static {
     type DriverType: Driver;
@@ -43,6 +66,86 @@ pub trait EzoSensor: Send + Sync + 'static {
     fn check_measurement(&self, _value: f64) -> Option<SensorStateReason> {
         None
     }
+
+    /// Disable continuous reading mode (`C,0`) so the board stops streaming
+    /// values on the wire once we release it.
+    fn shutdown(&self) -> Result<()> {
+        let mut driver = self
+            .driver()
+            .lock()
+            .map_err(|err| SensorError::source(DriverError::Write(err.to_string())))?;
+
+        send_ok(&mut *driver, "C,0")
+    }
+
+    /// Store the name on the circuit (`Name,<name>`).
+    fn rename(&self, name: &str) -> Result<()> {
+        let valid = !name.is_empty()
+            && name.len() <= MAX_NAME_LEN
+            && name.chars().all(|c| c.is_ascii_graphic() && c != ',');
+        if !valid {
+            return Err(SensorError::message(format!(
+                "Invalid sensor name '{name}': expected 1 to {MAX_NAME_LEN} ASCII characters without spaces or commas"
+            )));
+        }
+
+        let mut driver = self
+            .driver()
+            .lock()
+            .map_err(|err| SensorError::source(DriverError::Write(err.to_string())))?;
+
+        send_ok(&mut *driver, &format!("Name,{name}"))?;
+        drop(driver);
+
+        // The name is the strongest identity signal of the probe
+        let mut info = self.data().lock().expect("sensor info mutex poisoned");
+        info.name = SensorName::Named(name.to_string());
+        info.hardware_uid = IdentitySignal::EzoName(name.to_string()).hardware_uid();
+
+        Ok(())
+    }
+
+    /// Single point (`Cal,<value>`) or factory (`Cal,clear`) calibration.
+    fn calibrate(&self, calibration: Calibration) -> Result<()> {
+        let command = match calibration {
+            Calibration::Point(value) => {
+                if let Some(reason) = self.check_measurement(value) {
+                    return Err(SensorError::message(format!(
+                        "Invalid calibration point {value}: {}",
+                        reason.code()
+                    )));
+                }
+                format!("Cal,{value}")
+            }
+            Calibration::Clear => "Cal,clear".to_string(),
+        };
+
+        let mut driver = self
+            .driver()
+            .lock()
+            .map_err(|err| SensorError::source(DriverError::Write(err.to_string())))?;
+
+        send_ok(&mut *driver, &command)
+    }
+
+    /// Toggle the LED (`L,0` and `L,1`), the driver is released between two
+    /// toggles so the regular reads go on.
+    fn identify(&self) -> Result<()> {
+        for _ in 0..IDENTIFY_BLINKS {
+            for command in ["L,0", "L,1"] {
+                {
+                    let mut driver = self
+                        .driver()
+                        .lock()
+                        .map_err(|err| SensorError::source(DriverError::Write(err.to_string())))?;
+                    send_ok(&mut *driver, command)?;
+                }
+                std::thread::sleep(IDENTIFY_BLINK_DELAY);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl<T> Sensor for T
@@ -113,6 +216,22 @@ where
         data.state_reason = SensorStateReason::ReadError(err.to_string());
     }
 
+    fn shutdown(&self) -> Result<()> {
+        EzoSensor::shutdown(self)
+    }
+
+    fn rename(&self, name: &str) -> Result<()> {
+        EzoSensor::rename(self, name)
+    }
+
+    fn calibrate(&self, calibration: Calibration) -> Result<()> {
+        EzoSensor::calibrate(self, calibration)
+    }
+
+    fn identify(&self) -> Result<()> {
+        EzoSensor::identify(self)
+    }
+
     fn mark_unplugged(&self) {
         let mut data = self.data().lock().expect("sensor info mutex poisoned");
         if data.state != SensorState::Unplugged {
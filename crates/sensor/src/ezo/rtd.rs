@@ -10,7 +10,8 @@ use crate::core::temperature::DynamicRange;
 
 use crate::ezo::driver::{uart::UartDriver, Driver};
 use crate::ezo::ezo_sensor::EzoSensor;
-use crate::sensor::{SensorInfo, SensorName, SensorState, SensorStateReason};
+use crate::identity::ResolvedIdentity;
+use crate::sensor::{SensorInfo, SensorKind, SensorName, SensorState, SensorStateReason};
 
 const RTD_DISCONNECTED_VALUE: f64 = -1023.0;
 const RTD_DISCONNECTED_EPSILON: f64 = 0.001;
@@ -60,12 +61,15 @@ impl<D: Driver + Send + 'static> EzoSensor for Rtd<D> {
 }
 
 impl<D: Driver> Rtd<D> {
-    pub fn new(driver: D, firmware: f64) -> Self {
+    pub fn new(driver: D, firmware: f64, name: SensorName, identity: ResolvedIdentity) -> Self {
         let now = Utc::now();
         Self {
             data: Mutex::new(SensorInfo {
+                id: identity.id,
+                hardware_uid: identity.hardware_uid,
+                kind: SensorKind::Temperature,
                 firmware,
-                name: SensorName::Unnamed,
+                name,
                 state: SensorState::Initializing,
                 state_reason: SensorStateReason::Plugged,
                 state_since: now,
@@ -80,7 +84,12 @@ impl<D: Driver> Rtd<D> {
 }
 
 impl Rtd<UartDriver> {
-    pub fn from_uart(driver: UartDriver, firmware: f64) -> Self {
-        Self::new(driver, firmware)
+    pub fn from_uart(
+        driver: UartDriver,
+        firmware: f64,
+        name: SensorName,
+        identity: ResolvedIdentity,
+    ) -> Self {
+        Self::new(driver, firmware, name, identity)
     }
 }
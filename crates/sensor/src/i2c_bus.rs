@@ -5,5 +5,7 @@
 #[derive(Debug, Clone, Copy)]
 /// Active i2c connection for communication
 pub struct I2cConnection {
+    /// Bus number, as in `/dev/i2c-<bus>`
+    pub bus: u8,
     pub address: u8,
 }
@@ -0,0 +1,366 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::SensorStore;
+use std::collections::HashMap;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::i2c_bus::I2cConnection;
+use crate::CONFIG;
+
+/// A hint about which physical probe sits behind a connection.
+///
+/// Signals are ordered from the one that best follows the probe to the one
+/// that mostly follows the cable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum IdentitySignal {
+    /// Name stored on the EZO circuit (`Name,<name>`), it travels with the board.
+    EzoName(String),
+    /// Bus and address of an I2C board, stable as long as the wiring is.
+    I2cAddress { bus: u8, address: u8 },
+    /// Serial number of the USB adapter the board is plugged into.
+    AdapterSerial(String),
+}
+
+impl IdentitySignal {
+    /// Textual form stored in `sensors.hardware_uid`.
+    pub fn hardware_uid(&self) -> String {
+        match self {
+            IdentitySignal::EzoName(name) => format!("ezo:{name}"),
+            IdentitySignal::I2cAddress { bus, address } => format!("i2c:{bus}:{address:#04x}"),
+            IdentitySignal::AdapterSerial(serial) => format!("usb:{serial}"),
+        }
+    }
+}
+
+impl fmt::Display for IdentitySignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.hardware_uid())
+    }
+}
+
+/// Everything we could learn about a device while probing it.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceSignals {
+    pub adapter_serial: Option<String>,
+    pub ezo_name: Option<String>,
+    pub i2c: Option<I2cConnection>,
+}
+
+impl DeviceSignals {
+    /// Available signals, strongest first.
+    pub fn signals(&self) -> Vec<IdentitySignal> {
+        let mut signals = Vec::new();
+
+        if let Some(name) = self.ezo_name.as_ref().filter(|name| !name.is_empty()) {
+            signals.push(IdentitySignal::EzoName(name.clone()));
+        }
+        if let Some(connection) = self.i2c {
+            signals.push(IdentitySignal::I2cAddress {
+                bus: connection.bus,
+                address: connection.address,
+            });
+        }
+        if let Some(serial) = &self.adapter_serial {
+            signals.push(IdentitySignal::AdapterSerial(serial.clone()));
+        }
+
+        signals
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentitySource {
+    /// Mapped by the operator.
+    Operator,
+    /// Already seen, either during this run or in the database.
+    Known,
+    /// First time we see this probe.
+    New,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedIdentity {
+    pub id: Uuid,
+    /// The strongest signal of the device, as stored in `sensors.hardware_uid`.
+    pub hardware_uid: String,
+    pub source: IdentitySource,
+}
+
+/// Resolves devices to a persistent sensor id, whatever the transport.
+///
+/// Lookups go through the operator mapping of [`crate::CONFIG`] first, then
+/// the ids already seen during this run, then the `sensors` table. A device
+/// we know nothing about gets a fresh id.
+///
+/// A sensor is only known under its strongest signal, a device matches it
+/// on a signal at least as strong. Two probes sharing one adapter keep their
+/// own ids, a probe whose EZO name can't be read gets a new one.
+pub struct IdentityResolver {
+    operator: HashMap<String, Uuid>,
+    /// Id of each sensor seen during this run, under its strongest signal
+    known: HashMap<String, Uuid>,
+    store: Option<SensorStore<'static>>,
+}
+
+impl IdentityResolver {
+    pub fn new(store: Option<SensorStore<'static>>) -> Self {
+        Self {
+            operator: CONFIG.identities.clone(),
+            known: HashMap::new(),
+            store,
+        }
+    }
+
+    /// Pin a hardware uid to a sensor id on top of the configured ones, this
+    /// wins over any other signal.
+    pub fn assign(&mut self, hardware_uid: impl Into<String>, id: Uuid) {
+        self.operator.insert(hardware_uid.into(), id);
+    }
+
+    /// Drop what this run learned about a sensor, the probe gets a new id
+    /// the next time it is plugged unless the operator mapped it.
+    ///
+    /// The `sensors` row must be forgotten too, see
+    /// [`arksync_db::SensorStore::forget`].
+    pub fn forget(&mut self, id: Uuid) {
+        self.known.retain(|_, known| *known != id);
+    }
+
+    /// Follow a probe whose EZO name changed, it is now known under its new
+    /// name only.
+    ///
+    /// The `sensors` row must be renamed too, see
+    /// [`arksync_db::SensorStore::rename`].
+    pub fn renamed(&mut self, id: Uuid, name: &str) {
+        self.known.retain(|_, known| *known != id);
+        self.known
+            .insert(IdentitySignal::EzoName(name.to_string()).hardware_uid(), id);
+    }
+
+    pub async fn resolve(&mut self, device: &DeviceSignals) -> eyre::Result<ResolvedIdentity> {
+        let signals = device.signals();
+        let Some(strongest) = signals.first() else {
+            eyre::bail!("device exposes no identity signal");
+        };
+
+        let hardware_uids: Vec<String> = signals.iter().map(IdentitySignal::hardware_uid).collect();
+        let hardware_uid = strongest.hardware_uid();
+
+        let resolved = if let Some(id) = find_first(&self.operator, &hardware_uids) {
+            (id, IdentitySource::Operator)
+        } else if let Some(id) = find_first(&self.known, &hardware_uids) {
+            (id, IdentitySource::Known)
+        } else if let Some(id) = self.find_in_store(&hardware_uids, &hardware_uid).await? {
+            (id, IdentitySource::Known)
+        } else {
+            (Uuid::new_v4(), IdentitySource::New)
+        };
+
+        let (id, source) = resolved;
+        self.known.retain(|_, known| *known != id);
+        self.known.insert(hardware_uid.clone(), id);
+
+        Ok(ResolvedIdentity {
+            id,
+            hardware_uid,
+            source,
+        })
+    }
+
+    /// Reconcile with the `sensors` table, where a row is kept under the
+    /// strongest signal of its probe.
+    ///
+    /// When the probe was recorded under a weaker signal (e.g. its adapter
+    /// serial before it got an EZO name), the row is moved to the strongest one
+    /// so the history keeps following the probe.
+    async fn find_in_store(
+        &self,
+        hardware_uids: &[String],
+        strongest: &str,
+    ) -> eyre::Result<Option<Uuid>> {
+        let Some(store) = self.store else {
+            return Ok(None);
+        };
+
+        let records = store.find_by_hardware_uids(hardware_uids).await?;
+        let best = hardware_uids
+            .iter()
+            .find_map(|uid| records.iter().find(|record| &record.hardware_uid == uid));
+
+        let Some(record) = best else {
+            return Ok(None);
+        };
+
+        if record.hardware_uid != strongest {
+            store.update_hardware_uid(record.id, strongest).await?;
+        }
+
+        Ok(Some(record.id))
+    }
+}
+
+impl Default for IdentityResolver {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+fn find_first(mapping: &HashMap<String, Uuid>, hardware_uids: &[String]) -> Option<Uuid> {
+    hardware_uids
+        .iter()
+        .find_map(|uid| mapping.get(uid).copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usb(serial: &str) -> DeviceSignals {
+        DeviceSignals {
+            adapter_serial: Some(serial.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn named_probe_keeps_its_id_across_adapters() {
+        let mut resolver = IdentityResolver::default();
+        let first = resolver
+            .resolve(&DeviceSignals {
+                ezo_name: Some("tank".to_string()),
+                ..usb("A")
+            })
+            .await
+            .unwrap();
+        let moved = resolver
+            .resolve(&DeviceSignals {
+                ezo_name: Some("tank".to_string()),
+                ..usb("B")
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(first.source, IdentitySource::New);
+        assert_eq!(moved.source, IdentitySource::Known);
+        assert_eq!(first.id, moved.id);
+        assert_eq!(moved.hardware_uid, "ezo:tank");
+    }
+
+    #[tokio::test]
+    async fn probes_sharing_an_adapter_keep_their_ids() {
+        let mut resolver = IdentityResolver::default();
+        let tank = resolver
+            .resolve(&DeviceSignals {
+                ezo_name: Some("tank".to_string()),
+                ..usb("A")
+            })
+            .await
+            .unwrap();
+        let sump = resolver
+            .resolve(&DeviceSignals {
+                ezo_name: Some("sump".to_string()),
+                ..usb("A")
+            })
+            .await
+            .unwrap();
+        let unnamed = resolver.resolve(&usb("A")).await.unwrap();
+
+        assert_eq!(sump.source, IdentitySource::New);
+        assert_ne!(sump.id, tank.id);
+        assert_eq!(unnamed.source, IdentitySource::New);
+        assert_ne!(unnamed.id, sump.id);
+
+        let back = resolver
+            .resolve(&DeviceSignals {
+                ezo_name: Some("tank".to_string()),
+                ..usb("A")
+            })
+            .await
+            .unwrap();
+        assert_eq!(back.id, tank.id);
+    }
+
+    #[tokio::test]
+    async fn probe_named_later_keeps_its_id() {
+        let mut resolver = IdentityResolver::default();
+        let unnamed = resolver.resolve(&usb("A")).await.unwrap();
+        let named = resolver
+            .resolve(&DeviceSignals {
+                ezo_name: Some("tank".to_string()),
+                ..usb("A")
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(named.source, IdentitySource::Known);
+        assert_eq!(named.id, unnamed.id);
+    }
+
+    #[tokio::test]
+    async fn renamed_probe_keeps_its_id() {
+        let mut resolver = IdentityResolver::default();
+        let tank = resolver
+            .resolve(&DeviceSignals {
+                ezo_name: Some("tank".to_string()),
+                ..usb("A")
+            })
+            .await
+            .unwrap();
+
+        resolver.renamed(tank.id, "reef");
+        let renamed = resolver
+            .resolve(&DeviceSignals {
+                ezo_name: Some("reef".to_string()),
+                ..usb("A")
+            })
+            .await
+            .unwrap();
+        let other = resolver
+            .resolve(&DeviceSignals {
+                ezo_name: Some("tank".to_string()),
+                ..usb("B")
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(renamed.source, IdentitySource::Known);
+        assert_eq!(renamed.id, tank.id);
+        assert_eq!(renamed.hardware_uid, "ezo:reef");
+        assert_eq!(other.source, IdentitySource::New);
+        assert_ne!(other.id, tank.id);
+    }
+
+    #[tokio::test]
+    async fn operator_mapping_wins() {
+        let mut resolver = IdentityResolver::default();
+        let id = Uuid::new_v4();
+        resolver.assign("usb:A", id);
+
+        let resolved = resolver.resolve(&usb("A")).await.unwrap();
+
+        assert_eq!(resolved.id, id);
+        assert_eq!(resolved.source, IdentitySource::Operator);
+    }
+
+    #[tokio::test]
+    async fn forgotten_probe_gets_a_new_id() {
+        let mut resolver = IdentityResolver::default();
+        let first = resolver.resolve(&usb("A")).await.unwrap();
+
+        resolver.forget(first.id);
+        let again = resolver.resolve(&usb("A")).await.unwrap();
+
+        assert_eq!(again.source, IdentitySource::New);
+        assert_ne!(again.id, first.id);
+    }
+
+    #[tokio::test]
+    async fn device_without_signal_is_rejected() {
+        let mut resolver = IdentityResolver::default();
+
+        assert!(resolver.resolve(&DeviceSignals::default()).await.is_err());
+    }
+}
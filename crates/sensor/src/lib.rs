@@ -2,10 +2,15 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+mod config;
 pub mod core;
 pub mod error;
+pub mod event;
 pub mod ezo;
 pub mod i2c_bus;
+pub mod identity;
 pub mod sensor;
 pub mod serial_port;
 pub mod services;
+
+pub use config::{Config, CONFIG};
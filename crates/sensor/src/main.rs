@@ -3,9 +3,41 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use arksync_sensor::services::SensorService;
+use tokio_util::sync::CancellationToken;
+use tracing_subscriber::EnvFilter;
+
+/// Levels are set with `RUST_LOG`, e.g. `RUST_LOG=arksync_sensor=debug`, and
+/// `ARKSYNC_LOG_FORMAT=json` logs one JSON object per line.
+fn init_tracing() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+
+    if std::env::var("ARKSYNC_LOG_FORMAT").is_ok_and(|format| format == "json") {
+        subscriber.json().with_current_span(true).init();
+    } else {
+        subscriber.init();
+    }
+}
 
 #[tokio::main]
 async fn main() {
-    println!("Starting ArkSync Sensor Service...");
-    SensorService::new().run().await;
+    init_tracing();
+    tracing::info!("Starting ArkSync Sensor Service...");
+    let shutdown = CancellationToken::new();
+
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            let _ = tokio::signal::ctrl_c().await;
+            shutdown.cancel();
+        }
+    });
+
+    let summary = SensorService::new().run(shutdown).await;
+
+    if !summary.is_clean() {
+        for sensor in &summary.sensors {
+            tracing::warn!(sensor_id = %sensor.uuid, outcome = ?sensor.outcome, "Sensor did not shut down cleanly.");
+        }
+    }
 }
@@ -3,11 +3,17 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use chrono::{DateTime, Utc};
+use std::fmt;
 use std::sync::Arc;
+use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
 use tokio::time::{interval, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument as _;
+use uuid::Uuid;
 
 use crate::error::{Result, SensorError};
+use crate::event::{Measurement, ReadReport, SensorEvent};
 use crate::i2c_bus::I2cConnection;
 use crate::serial_port::SerialPortMetadata;
 
@@ -18,6 +24,31 @@ pub enum SensorName {
     Named(String),
 }
 
+/// What a sensor measures, mirrors the `sensor_kind` database type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SensorKind {
+    Temperature,
+    Ph,
+    Ec,
+    Humidity,
+    Co2,
+    Custom,
+}
+
+impl SensorKind {
+    /// Snake case name, as used by the `sensor_kind` type and exporters.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SensorKind::Temperature => "temperature",
+            SensorKind::Ph => "ph",
+            SensorKind::Ec => "ec",
+            SensorKind::Humidity => "humidity",
+            SensorKind::Co2 => "co2",
+            SensorKind::Custom => "custom",
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SensorState {
     Active,
@@ -28,6 +59,19 @@ pub enum SensorState {
     Unreachable,
 }
 
+impl SensorState {
+    /// Snake case name, as used by the `sensor_status` type and exporters.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SensorState::Active => "active",
+            SensorState::Degraded => "degraded",
+            SensorState::Initializing => "initializing",
+            SensorState::Unplugged => "unplugged",
+            SensorState::Unreachable => "unreachable",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum SensorStateReason {
     Plugged,
@@ -39,14 +83,52 @@ pub enum SensorStateReason {
     NoRecentActivity,
 }
 
+impl SensorStateReason {
+    /// Stable code of the reason, without its details.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SensorStateReason::Plugged => "plugged",
+            SensorStateReason::Unplugged => "unplugged",
+            SensorStateReason::MeasurementOk => "measurement_ok",
+            SensorStateReason::InvalidMeasurement(_) => "invalid_measurement",
+            SensorStateReason::OutOfRange { .. } => "out_of_range",
+            SensorStateReason::ReadError(_) => "read_error",
+            SensorStateReason::NoRecentActivity => "no_recent_activity",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum SensorConnection {
     Uart(SerialPortMetadata),
     I2c(I2cConnection),
 }
 
+impl fmt::Display for SensorConnection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SensorConnection::Uart(port) => write!(f, "{}", port.port_name),
+            SensorConnection::I2c(i2c) => write!(f, "i2c-{}@{:#04x}", i2c.bus, i2c.address),
+        }
+    }
+}
+
+/// Calibration of a sensor board, in the unit of its measurements.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Calibration {
+    /// Single point at a known reference, e.g. the probe in a 100 °C bath.
+    Point(f64),
+    /// Back to the factory calibration.
+    Clear,
+}
+
 #[derive(Debug, Clone)]
 pub struct SensorInfo {
+    /// Persistent id, resolved by the [`crate::identity::IdentityResolver`]
+    pub id: Uuid,
+    /// Signal the sensor was identified with, see [`crate::identity::IdentitySignal`]
+    pub hardware_uid: String,
+    pub kind: SensorKind,
     pub firmware: f64,
     pub name: SensorName,
     pub state: SensorState,
@@ -65,43 +147,141 @@ pub trait Sensor: Send + Sync + 'static {
     fn record_error(&self, err: &SensorError);
     fn mark_unplugged(&self);
 
+    /// Put the board into a safe state before releasing it.
+    ///
+    /// Called by the service once the sensor task has stopped, so no read can
+    /// be in flight on the same connection.
+    fn shutdown(&self) -> Result<()>;
+
+    /// Store `name` on the board.
+    ///
+    /// The name travels with the probe, it becomes its strongest identity
+    /// signal from the next discovery.
+    fn rename(&self, name: &str) -> Result<()>;
+
+    /// Calibrate the board, the probe must sit in the reference already.
+    fn calibrate(&self, calibration: Calibration) -> Result<()>;
+
+    /// Blink the board LED for a few seconds so it can be found among others.
+    ///
+    /// Blocks on the connection, don't call it from the async runtime.
+    fn identify(&self) -> Result<()>;
+
+    /// Read a measurement, update the state machine and publish what changed
+    /// on `events`.
+    ///
+    /// Blocks on the connection, don't call it from the async runtime.
+    fn poll(&self, events: &broadcast::Sender<SensorEvent>) -> Result<f64> {
+        let info = self.info();
+
+        let started = std::time::Instant::now();
+        let result = self.read_measurement();
+        let latency = started.elapsed();
+        match &result {
+            Ok(value) => {
+                self.record_measurement(*value);
+                tracing::trace!(sensor_id = %info.id, value, "Sensor reading.");
+
+                // Invalid values only move the state machine
+                if self.check_measurement(*value).is_none() {
+                    let _ = events.send(SensorEvent::Measurement(Measurement {
+                        sensor_id: info.id,
+                        kind: info.kind,
+                        value: *value,
+                        time: Utc::now(),
+                    }));
+                }
+            }
+            Err(err) => self.record_error(err),
+        }
+
+        let current = self.info();
+        if current.state != info.state {
+            tracing::info!(
+                sensor_id = %info.id,
+                previous = info.state.as_str(),
+                state = current.state.as_str(),
+                reason = current.state_reason.code(),
+                "Sensor state changed."
+            );
+            // No receiver is fine, nobody is listening yet
+            let _ = events.send(SensorEvent::StateChanged {
+                previous: info.state,
+                info: current.clone(),
+            });
+        }
+
+        let _ = events.send(SensorEvent::ReadCompleted(ReadReport {
+            sensor_id: info.id,
+            kind: info.kind,
+            latency,
+            ok: current.consecutive_failures == 0,
+            consecutive_failures: current.consecutive_failures,
+        }));
+
+        result
+    }
+
     /// Spawn the main background task for this sensor.
-    fn run(self: Arc<Self>) -> JoinHandle<()> {
-        tokio::spawn(async move {
-            // This is based on Atlas Scientific read time, plus some time to not
-            // be at the edge of the value disponibility
-            let mut ticker = interval(Duration::from_millis(1200));
-            let unreachable_retry_interval = Duration::from_secs(30);
-            let mut last_unreachable_retry = Instant::now() - unreachable_retry_interval;
-
-            loop {
-                ticker.tick().await;
-
-                let info = self.info();
-                match info.state {
-                    SensorState::Unplugged => continue,
-                    SensorState::Unreachable => {
-                        if last_unreachable_retry.elapsed() < unreachable_retry_interval {
-                            continue;
-                        }
-                        last_unreachable_retry = Instant::now();
+    ///
+    /// The task observes `shutdown` between two reads only: a read that
+    /// already started is always completed so we never leave a half-written
+    /// command on the wire.
+    ///
+    /// Valid measurements and the state transitions caused by a read are
+    /// published on `events`.
+    fn run(
+        self: Arc<Self>,
+        events: broadcast::Sender<SensorEvent>,
+        shutdown: CancellationToken,
+    ) -> JoinHandle<()> {
+        let info = self.info();
+        let span = tracing::info_span!(
+            "sensor",
+            sensor_id = %info.id,
+            kind = info.kind.as_str(),
+            port = %info.connection
+        );
+
+        tokio::spawn(
+            async move {
+                // This is based on Atlas Scientific read time, plus some time to not
+                // be at the edge of the value disponibility
+                let mut ticker = interval(Duration::from_millis(1200));
+                let unreachable_retry_interval = Duration::from_secs(30);
+                let mut last_unreachable_retry = Instant::now() - unreachable_retry_interval;
+
+                loop {
+                    tokio::select! {
+                        _ = ticker.tick() => {}
+                        _ = shutdown.cancelled() => break,
                     }
-                    _ => {}
-                }
 
-                // TODO: Retry with backoff strategy: we allow some I/O error but after a specific threshold we start to update
-                // the state of the sensor to Degraded then Unresponsive.
-                match self.read_measurement() {
-                    Ok(value) => {
-                        self.record_measurement(value);
-                        println!("Sensor reading: {value:.3}");
+                    match self.info().state {
+                        SensorState::Unplugged => continue,
+                        SensorState::Unreachable => {
+                            if last_unreachable_retry.elapsed() < unreachable_retry_interval {
+                                continue;
+                            }
+                            last_unreachable_retry = Instant::now();
+                        }
+                        _ => {}
                     }
-                    Err(err) => {
-                        self.record_error(&err);
-                        eprintln!("Sensor read error: {err:#?}");
+
+                    // TODO: Retry with backoff strategy: we allow some I/O error but after a specific threshold we start to update
+                    // the state of the sensor to Degraded then Unresponsive.
+                    if let Err(err) = self.poll(&events) {
+                        let info = self.info();
+                        tracing::warn!(
+                            sensor_id = %info.id,
+                            error = %err,
+                            failures = info.consecutive_failures,
+                            "Sensor read failed."
+                        );
                     }
                 }
             }
-        })
+            .instrument(span),
+        )
     }
 }
@@ -5,4 +5,8 @@
 mod calibration;
 mod sensor;
 
-pub use sensor::SensorService;
+pub use sensor::{
+    kind_record, sensor_record, state_record, status_record, MeasurementRecorder,
+    SensorPersistence, SensorService, SensorServiceHandle, SensorShutdown, ShutdownOutcome,
+    ShutdownSummary,
+};
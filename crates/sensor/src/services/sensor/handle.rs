@@ -0,0 +1,132 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use uuid::Uuid;
+
+use crate::error::{Result, SensorError};
+use crate::event::SensorEvent;
+use crate::sensor::{Calibration, Sensor, SensorInfo};
+use crate::services::sensor::{SensorList, SensorServiceCmd};
+
+/// Cheap, cloneable access to a running [`super::SensorService`].
+///
+/// Queries return `None` once the service stopped.
+#[derive(Clone)]
+pub struct SensorServiceHandle {
+    cmd_tx: mpsc::Sender<SensorServiceCmd>,
+    events: broadcast::Sender<SensorEvent>,
+}
+
+impl SensorServiceHandle {
+    pub(crate) fn new(
+        cmd_tx: mpsc::Sender<SensorServiceCmd>,
+        events: broadcast::Sender<SensorEvent>,
+    ) -> Self {
+        Self { cmd_tx, events }
+    }
+
+    /// Receive the events published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<SensorEvent> {
+        self.events.subscribe()
+    }
+
+    /// Snapshot of the registry.
+    pub async fn all_sensors(&self) -> Option<Arc<SensorList>> {
+        let (respond_to, rx) = oneshot::channel();
+        self.cmd_tx
+            .send(SensorServiceCmd::AllSensors { respond_to })
+            .await
+            .ok()?;
+
+        rx.await.ok()
+    }
+
+    pub async fn find_sensor(&self, uuid: Uuid) -> Option<Arc<dyn Sensor>> {
+        let (respond_to, rx) = oneshot::channel();
+        self.cmd_tx
+            .send(SensorServiceCmd::FindSensor { uuid, respond_to })
+            .await
+            .ok()?;
+
+        rx.await.ok().flatten()
+    }
+
+    /// Read a sensor now, outside of its regular ticks.
+    ///
+    /// The measurement and state changes are published like any other read.
+    pub async fn read_sensor(&self, uuid: Uuid) -> Result<f64> {
+        let sensor = self.require_sensor(uuid).await?;
+        let events = self.events.clone();
+
+        tokio::task::spawn_blocking(move || sensor.poll(&events))
+            .await
+            .map_err(SensorError::source)?
+    }
+
+    /// Store a new name on the sensor board.
+    pub async fn rename_sensor(&self, uuid: Uuid, name: String) -> Result<SensorInfo> {
+        let sensor = self.require_sensor(uuid).await?;
+
+        let info = tokio::task::spawn_blocking({
+            let name = name.clone();
+            move || {
+                sensor.rename(&name)?;
+                Ok::<_, SensorError>(sensor.info())
+            }
+        })
+        .await
+        .map_err(SensorError::source)??;
+
+        // The board now answers to its new name when detected again
+        let _ = self
+            .cmd_tx
+            .send(SensorServiceCmd::RenameSensor { uuid, name })
+            .await;
+
+        let _ = self.events.send(SensorEvent::Renamed(info.clone()));
+
+        Ok(info)
+    }
+
+    /// Calibrate a sensor, the probe must sit in the reference already.
+    pub async fn calibrate_sensor(&self, uuid: Uuid, calibration: Calibration) -> Result<()> {
+        let sensor = self.require_sensor(uuid).await?;
+
+        tokio::task::spawn_blocking(move || sensor.calibrate(calibration))
+            .await
+            .map_err(SensorError::source)?
+    }
+
+    /// Blink the LED of a sensor board, returns once it stopped blinking.
+    pub async fn identify_sensor(&self, uuid: Uuid) -> Result<()> {
+        let sensor = self.require_sensor(uuid).await?;
+
+        tokio::task::spawn_blocking(move || sensor.identify())
+            .await
+            .map_err(SensorError::source)?
+    }
+
+    /// Forget the identity of a sensor, it must be unplugged.
+    ///
+    /// Only the running service forgets it, see
+    /// [`crate::identity::IdentityResolver::forget`] for the database.
+    pub async fn forget_sensor(&self, uuid: Uuid) -> Result<()> {
+        let (respond_to, rx) = oneshot::channel();
+        self.cmd_tx
+            .send(SensorServiceCmd::ForgetSensor { uuid, respond_to })
+            .await
+            .map_err(|_| SensorError::message("Sensor service stopped"))?;
+
+        rx.await
+            .map_err(|_| SensorError::message("Sensor service stopped"))?
+    }
+
+    async fn require_sensor(&self, uuid: Uuid) -> Result<Arc<dyn Sensor>> {
+        self.find_sensor(uuid)
+            .await
+            .ok_or_else(|| SensorError::message(format!("Unknown sensor {uuid}")))
+    }
+}
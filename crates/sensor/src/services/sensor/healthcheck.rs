@@ -42,12 +42,12 @@ pub async fn healthcheck(cmd_tx: &Sender<SensorServiceCmd>, shutdown: Cancellati
                     .await
                     .is_err()
                 {
-                    eprintln!("Health check: sensor registry is unavailable");
+                    tracing::error!("Health check: the sensor registry is unavailable.");
                     continue;
                 }
 
                 let Ok(sensors) = rx.await else {
-                    eprintln!("Health check: failed to receive sensor snapshot");
+                    tracing::error!("Health check: failed to receive the sensor snapshot.");
                     continue;
                 };
 
@@ -59,25 +59,23 @@ pub async fn healthcheck(cmd_tx: &Sender<SensorServiceCmd>, shutdown: Cancellati
                     let state_age = now.signed_duration_since(info.state_since).num_seconds();
                     let inactivity = now.signed_duration_since(info.last_activity).num_seconds();
 
-                    println!(
-                        "Health check: sensor {uuid} state={:?} reason={:?} state_age={}s inactivity={}s failures={}",
-                        info.state,
-                        info.state_reason,
-                        state_age,
-                        inactivity,
-                        info.consecutive_failures
+                    tracing::debug!(
+                        sensor_id = %uuid,
+                        state = info.state.as_str(),
+                        reason = ?info.state_reason,
+                        state_age_secs = state_age,
+                        inactivity_secs = inactivity,
+                        failures = info.consecutive_failures,
+                        "Health check."
                     );
 
                     if info.state == SensorState::Unplugged {
-                        sensors_to_remove.push(uuid.clone())
+                        sensors_to_remove.push(*uuid)
                     }
                 }
 
                 if !sensors_to_remove.is_empty() {
-                    println!(
-                        "Health check: removing stale sensors {:?}",
-                        sensors_to_remove
-                    );
+                    tracing::info!(sensors = ?sensors_to_remove, "Health check: removing stale sensors.");
                     let _ = cmd_tx
                         .send(SensorServiceCmd::RemoveSensors {
                             uuids: sensors_to_remove,
@@ -86,7 +84,7 @@ pub async fn healthcheck(cmd_tx: &Sender<SensorServiceCmd>, shutdown: Cancellati
                 }
             }
             _ = shutdown.cancelled() => {
-                println!("Health check: stopping");
+                tracing::debug!("Health check stopped.");
                 break;
             }
         }
@@ -0,0 +1,144 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::MeasurementRecord;
+use arksync_db::MeasurementStore;
+use std::collections::VecDeque;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+use crate::event::{Measurement, SensorEvent};
+use crate::services::sensor::persistence::kind_record;
+
+/// Measurements written in one statement, a batch is flushed once full.
+const DEFAULT_BATCH_SIZE: usize = 500;
+/// A partial batch is flushed at least this often.
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+/// Measurements kept in memory while the database is unavailable, the oldest
+/// are dropped past this.
+const MAX_PENDING_MEASUREMENTS: usize = 50_000;
+
+/// Subscriber writing sensor measurements to the `measurements` table in
+/// batches.
+pub struct MeasurementRecorder {
+    store: MeasurementStore<'static>,
+    batch_size: usize,
+    flush_interval: Duration,
+    pending: VecDeque<MeasurementRecord>,
+}
+
+impl MeasurementRecorder {
+    pub fn new(store: MeasurementStore<'static>) -> Self {
+        Self {
+            store,
+            batch_size: DEFAULT_BATCH_SIZE,
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    pub async fn run(
+        mut self,
+        mut events: broadcast::Receiver<SensorEvent>,
+        shutdown: CancellationToken,
+    ) {
+        let mut ticker = interval(self.flush_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(SensorEvent::Measurement(measurement)) => {
+                        self.push(measurement);
+                        if self.pending.len() >= self.batch_size {
+                            self.flush().await;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Measurement recorder lagging behind, sensor events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = ticker.tick() => self.flush().await,
+                _ = shutdown.cancelled() => {
+                    while let Ok(event) = events.try_recv() {
+                        if let SensorEvent::Measurement(measurement) = event {
+                            self.push(measurement);
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+
+        self.flush().await;
+        tracing::debug!("Measurement recorder stopped.");
+    }
+
+    fn push(&mut self, measurement: Measurement) {
+        if self.pending.len() >= MAX_PENDING_MEASUREMENTS {
+            self.pending.pop_front();
+        }
+
+        self.pending.push_back(MeasurementRecord {
+            sensor_id: measurement.sensor_id,
+            kind: kind_record(measurement.kind),
+            time: measurement.time,
+            value: measurement.value,
+        });
+    }
+
+    /// Write pending measurements, they are kept for the next flush while the
+    /// database is unavailable.
+    ///
+    /// A batch the database refuses is split until the measurements it
+    /// refuses are found, those are dropped so they don't hold back the
+    /// others.
+    async fn flush(&mut self) {
+        let mut batch_size = self.batch_size;
+
+        while !self.pending.is_empty() {
+            let len = self.pending.len().min(batch_size);
+            let batch = &self.pending.make_contiguous()[..len];
+
+            match self.store.insert_batch(batch).await {
+                Ok(_) => {
+                    self.pending.drain(..len);
+                }
+                Err(err) if arksync_db::is_transient(&err) => {
+                    tracing::error!(
+                        pending = self.pending.len(),
+                        error = format_args!("{err:#}"),
+                        "Failed to store the measurements."
+                    );
+                    return;
+                }
+                Err(_) if len > 1 => batch_size = len / 2,
+                Err(err) => {
+                    if let Some(measurement) = self.pending.pop_front() {
+                        tracing::warn!(
+                            sensor_id = %measurement.sensor_id,
+                            time = %measurement.time,
+                            error = format_args!("{err:#}"),
+                            "Measurement refused by the database, dropped."
+                        );
+                    }
+                    batch_size = self.batch_size;
+                }
+            }
+        }
+    }
+}
@@ -2,12 +2,20 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+mod handle;
 mod healthcheck;
+mod measurement_recorder;
+mod persistence;
 mod plugged_sensors;
 mod sensor_service;
+mod shutdown;
 mod unplugged_sensors;
 
+pub use handle::SensorServiceHandle;
 pub use healthcheck::healthcheck;
+pub use measurement_recorder::MeasurementRecorder;
+pub use persistence::{kind_record, sensor_record, state_record, status_record, SensorPersistence};
 pub use plugged_sensors::detect_plugged_sensors_task;
 pub use sensor_service::*;
+pub use shutdown::*;
 pub use unplugged_sensors::detect_unplugged_sensors;
@@ -0,0 +1,231 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::{
+    SensorDriver, SensorEventRecord, SensorKind as SensorKindRecord, SensorProtocol, SensorRecord,
+    SensorStateRecord, SensorStatus,
+};
+use arksync_db::SensorStore;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::event::SensorEvent;
+use crate::sensor::{
+    SensorConnection, SensorInfo, SensorKind, SensorName, SensorState, SensorStateReason,
+};
+
+/// Subscriber writing the sensor registry to Postgres.
+///
+/// Sensors are upserted when discovered and their state is updated on every
+/// transition, each transition being appended to the `sensor_events` log.
+pub struct SensorPersistence {
+    store: SensorStore<'static>,
+    station_knot_id: Uuid,
+    /// Last status written per sensor, to skip no-op transitions
+    statuses: HashMap<Uuid, SensorStatus>,
+}
+
+impl SensorPersistence {
+    pub fn new(store: SensorStore<'static>, station_knot_id: Uuid) -> Self {
+        Self {
+            store,
+            station_knot_id,
+            statuses: HashMap::new(),
+        }
+    }
+
+    pub async fn run(
+        mut self,
+        mut events: broadcast::Receiver<SensorEvent>,
+        shutdown: CancellationToken,
+    ) {
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => self.persist(event).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Sensor persistence lagging behind, sensor events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = shutdown.cancelled() => {
+                    // Keep the last transitions, e.g. sensors stopped on shutdown
+                    while let Ok(event) = events.try_recv() {
+                        self.persist(event).await;
+                    }
+                    tracing::debug!("Sensor persistence stopped.");
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn persist(&mut self, event: SensorEvent) {
+        let result = match &event {
+            SensorEvent::Discovered(info) => self.discovered(info).await,
+            SensorEvent::StateChanged { info, .. } | SensorEvent::Removed(info) => {
+                self.transition(info).await
+            }
+            SensorEvent::Renamed(info) => self.renamed(info).await,
+            // Stored by the measurement recorder
+            SensorEvent::Measurement(_) | SensorEvent::ReadCompleted(_) => Ok(()),
+        };
+
+        if let Err(err) = result {
+            tracing::error!(
+                sensor_id = %event.sensor_id(),
+                error = format_args!("{err:#}"),
+                "Failed to persist the sensor event."
+            );
+        }
+    }
+
+    async fn discovered(&mut self, info: &SensorInfo) -> eyre::Result<()> {
+        let record = sensor_record(info, self.station_knot_id);
+        self.store.upsert(&record).await?;
+        self.store
+            .append_event(&SensorEventRecord::new(info.id, None, &record.state))
+            .await?;
+        self.statuses.insert(info.id, record.state.status);
+
+        Ok(())
+    }
+
+    async fn renamed(&mut self, info: &SensorInfo) -> eyre::Result<()> {
+        match &info.name {
+            SensorName::Named(name) => self.store.rename(info.id, name, &info.hardware_uid).await,
+            SensorName::Unnamed => Ok(()),
+        }
+    }
+
+    async fn transition(&mut self, info: &SensorInfo) -> eyre::Result<()> {
+        let state = state_record(info);
+        let previous = self.statuses.get(&info.id).copied();
+
+        // Only status changes go to the event log, the counters are kept
+        // current either way
+        if previous == Some(state.status) {
+            return self.store.update_state(info.id, &state).await;
+        }
+
+        self.store
+            .record_transition(info.id, previous, &state)
+            .await?;
+        self.statuses.insert(info.id, state.status);
+
+        Ok(())
+    }
+}
+
+/// Row of a sensor of the knot `station_knot_id`, as written in `sensors`.
+pub fn sensor_record(info: &SensorInfo, station_knot_id: Uuid) -> SensorRecord {
+    let (protocol, connection) = match &info.connection {
+        SensorConnection::Uart(port) => (
+            SensorProtocol::Uart,
+            json!({
+                "port_name": port.port_name,
+                "serial_number": port.serial_number,
+                "baud_rate": port.baud_rate,
+            }),
+        ),
+        SensorConnection::I2c(connection) => (
+            SensorProtocol::I2c,
+            json!({
+                "bus": connection.bus,
+                "address": connection.address,
+            }),
+        ),
+    };
+
+    let name = match &info.name {
+        SensorName::Named(name) => name.clone(),
+        SensorName::Unnamed => info.hardware_uid.clone(),
+    };
+
+    SensorRecord {
+        id: info.id,
+        station_knot_id,
+        hardware_uid: Some(info.hardware_uid.clone()),
+        name,
+        kind: kind_record(info.kind),
+        driver: SensorDriver::AtlasScientificEzo,
+        protocol,
+        connection,
+        firmware: Some(info.firmware),
+        state: state_record(info),
+    }
+}
+
+/// The columns of a sensor row that move with its state machine.
+pub fn state_record(info: &SensorInfo) -> SensorStateRecord {
+    let (state_reason, state_reason_details) = state_reason(&info.state_reason);
+
+    SensorStateRecord {
+        status: status_record(info.state),
+        state_reason: state_reason.to_string(),
+        state_reason_details,
+        state_since: info.state_since,
+        last_activity_at: info.last_activity,
+        consecutive_failures: i32::try_from(info.consecutive_failures).unwrap_or(i32::MAX),
+    }
+}
+
+pub fn status_record(state: SensorState) -> SensorStatus {
+    match state {
+        SensorState::Active => SensorStatus::Active,
+        SensorState::Degraded => SensorStatus::Degraded,
+        SensorState::Initializing => SensorStatus::Initializing,
+        SensorState::Unplugged => SensorStatus::Unplugged,
+        SensorState::Unreachable => SensorStatus::Unreachable,
+    }
+}
+
+pub fn kind_record(kind: SensorKind) -> SensorKindRecord {
+    match kind {
+        SensorKind::Temperature => SensorKindRecord::Temperature,
+        SensorKind::Ph => SensorKindRecord::Ph,
+        SensorKind::Ec => SensorKindRecord::Ec,
+        SensorKind::Humidity => SensorKindRecord::Humidity,
+        SensorKind::Co2 => SensorKindRecord::Co2,
+        SensorKind::Custom => SensorKindRecord::Custom,
+    }
+}
+
+/// Split a reason in the `state_reason` code and its `state_reason_details`.
+fn state_reason(reason: &SensorStateReason) -> (&'static str, Value) {
+    let details = match reason {
+        SensorStateReason::InvalidMeasurement(value) => json!({ "value": value }),
+        SensorStateReason::OutOfRange { value, min, max } => {
+            json!({ "value": value, "min": min, "max": max })
+        }
+        SensorStateReason::ReadError(error) => json!({ "error": error }),
+        SensorStateReason::Plugged
+        | SensorStateReason::Unplugged
+        | SensorStateReason::MeasurementOk
+        | SensorStateReason::NoRecentActivity => json!({}),
+    };
+
+    (reason.code(), details)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_reason_details_keep_the_values() {
+        let (reason, details) = state_reason(&SensorStateReason::OutOfRange {
+            value: 1300.0,
+            min: -126.0,
+            max: 1254.0,
+        });
+
+        assert_eq!(reason, "out_of_range");
+        assert_eq!(details["value"], 1300.0);
+        assert_eq!(details["max"], 1254.0);
+    }
+}
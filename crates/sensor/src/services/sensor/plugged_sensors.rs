@@ -5,21 +5,26 @@
 use crate::ezo::driver::uart::UartDriver;
 use crate::ezo::driver::{DeviceType, Driver};
 use crate::ezo::rtd::Rtd;
-use crate::sensor::Sensor;
+use crate::identity::{DeviceSignals, IdentityResolver};
+use crate::sensor::{Sensor, SensorConnection, SensorName};
 use crate::services::sensor::SensorServiceCmd;
+use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::mpsc::Sender;
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, Mutex};
 use tokio::time::{interval, Duration as TokioDuration};
 use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
 
 use crate::serial_port::{self, SerialPortMetadata};
 
 /// Listen for plugged sensors.
 ///
-/// Finds new USB sensors and adds them to registry.
+/// Finds new USB sensors, resolves their persistent identity and adds them to
+/// registry.
 pub async fn detect_plugged_sensors_task(
     cmd_tx: &Sender<SensorServiceCmd>,
+    identity: Arc<Mutex<IdentityResolver>>,
     shutdown: CancellationToken,
 ) {
     let mut interval = interval(TokioDuration::from_secs(2));
@@ -28,16 +33,16 @@ pub async fn detect_plugged_sensors_task(
         tokio::select! {
             _ = interval.tick() => {}
             _ = shutdown.cancelled() => {
-                println!("Detector: stopping plugged sensor scan");
+                tracing::debug!("Stopping the plugged sensor scan.");
                 break;
             }
         }
 
-        println!("Detector: Scanning for sensors...");
+        tracing::trace!("Scanning for sensors.");
         let asc_ports = serial_port::find_asc_port();
 
         if !asc_ports.is_empty() {
-            println!("Detector: Found {} ASC ports", asc_ports.len());
+            tracing::trace!(ports = asc_ports.len(), "Found Atlas Scientific ports.");
         }
 
         // Get current sensor list
@@ -48,29 +53,44 @@ pub async fn detect_plugged_sensors_task(
         let current_sensors = rx.await;
 
         if let Ok(current_sensors) = current_sensors {
-            let mut new_sensors: Vec<(String, Arc<dyn Sensor>)> = Vec::new();
+            // The registry is keyed by sensor id, ports in use are found from
+            // the connections instead.
+            let connected_ports: HashSet<String> = current_sensors
+                .values()
+                .filter_map(|sensor| match sensor.info().connection {
+                    SensorConnection::Uart(port) => Some(port.serial_number),
+                    SensorConnection::I2c(_) => None,
+                })
+                .collect();
+
+            let mut new_sensors: Vec<(Uuid, Arc<dyn Sensor>)> = Vec::new();
 
             for port in asc_ports.iter() {
-                if !current_sensors.contains_key(&port.serial_number) {
-                    let sensor = create_sensor_from_port(port);
-                    println!(
-                        "Detector: Created sensor {}: {:#?}",
-                        port.serial_number,
-                        sensor.as_ref().map(|s| s.info())
-                    );
-
-                    match sensor {
-                        Ok(sensor) => {
-                            let data = sensor.info();
-                            println!("Detector: Created sensor - firmware v{}", data.firmware);
-                            new_sensors.push((port.serial_number.clone(), sensor));
-                        }
-                        Err(e) => {
-                            eprintln!(
-                                "Detector: Failed to create sensor {}: {}",
-                                port.serial_number, e
-                            );
-                        }
+                if connected_ports.contains(&port.serial_number) {
+                    continue;
+                }
+
+                let mut identity = identity.lock().await;
+                match create_sensor_from_port(port, &mut identity).await {
+                    Ok(sensor) => {
+                        let data = sensor.info();
+                        tracing::info!(
+                            sensor_id = %data.id,
+                            hardware_uid = %data.hardware_uid,
+                            kind = data.kind.as_str(),
+                            port = %port.port_name,
+                            firmware = data.firmware,
+                            "Sensor detected."
+                        );
+                        new_sensors.push((data.id, sensor));
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            port = %port.port_name,
+                            serial_number = %port.serial_number,
+                            error = %e,
+                            "Failed to create a sensor."
+                        );
                     }
                 }
             }
@@ -91,24 +111,47 @@ pub async fn detect_plugged_sensors_task(
 /// This function:
 /// 1. Creates a temporary UART driver
 /// 2. Queries device info to determine sensor type
-/// 3. Creates the appropriate sensor with the correct driver
-/// 4. Returns it as Arc<dyn Sensor>
-fn create_sensor_from_port(
+/// 3. Resolves the sensor identity from the adapter serial and board name
+/// 4. Creates the appropriate sensor with the correct driver
+/// 5. Returns it as Arc<dyn Sensor>
+async fn create_sensor_from_port(
     port: &SerialPortMetadata,
+    identity: &mut IdentityResolver,
 ) -> Result<Arc<dyn Sensor>, Box<dyn std::error::Error>> {
     // Create temporary driver to query device type
     let mut uart_driver = UartDriver::new(port)?;
     let device_info = uart_driver.device_info()?;
 
-    println!(
-        "Factory: Detected {:?} sensor v{}",
-        device_info.device_type, device_info.firmware_version
+    tracing::debug!(
+        port = %port.port_name,
+        device_type = ?device_info.device_type,
+        firmware = device_info.firmware_version,
+        "EZO board found."
     );
 
+    // Older firmwares may not support naming, the adapter serial is enough then
+    let ezo_name = uart_driver.name().unwrap_or_else(|err| {
+        tracing::warn!(port = %port.port_name, error = %err, "Failed to read the board name.");
+        None
+    });
+    let resolved = identity
+        .resolve(&DeviceSignals {
+            adapter_serial: Some(port.serial_number.clone()),
+            ezo_name: ezo_name.clone(),
+            i2c: None,
+        })
+        .await?;
+    let name = ezo_name.map_or(SensorName::Unnamed, SensorName::Named);
+
     // Create appropriate sensor based on device type
     match device_info.device_type {
         DeviceType::Rtd => {
-            let rtd = Rtd::<UartDriver>::from_uart(uart_driver, device_info.firmware_version);
+            let rtd = Rtd::<UartDriver>::from_uart(
+                uart_driver,
+                device_info.firmware_version,
+                name,
+                resolved,
+            );
             Ok(Arc::new(rtd) as Arc<dyn Sensor>)
         }
     }
@@ -3,34 +3,60 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use super::detect_plugged_sensors_task;
-use crate::sensor::Sensor;
-use crate::services::sensor::{detect_unplugged_sensors, healthcheck};
+use crate::error::{Result, SensorError};
+use crate::event::SensorEvent;
+use crate::identity::IdentityResolver;
+use crate::sensor::{Sensor, SensorState};
+use crate::services::sensor::{
+    detect_unplugged_sensors, healthcheck, MeasurementRecorder, SensorPersistence,
+    SensorServiceHandle, ShutdownOutcome, ShutdownSummary,
+};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
 use tokio::task::JoinHandle;
+use tokio::time::{timeout_at, Duration, Instant};
 use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// How long the sensor tasks get to finish their in-flight read on shutdown.
+///
+/// A read is bounded by the serial port timeout, so this leaves room for a
+/// couple of them before giving up on the tasks. The tasks stop concurrently,
+/// they all share this deadline.
+const SENSOR_TASK_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Events a subscriber can lag behind before missing some.
+const SENSOR_EVENTS_CAPACITY: usize = 1024;
 
 /// A sensor list compatible with both UART and I2C protocols.
-pub type SensorList = HashMap<String, Arc<dyn Sensor>>;
+///
+/// Sensors are keyed by their persistent id, not by their connection.
+pub type SensorList = HashMap<Uuid, Arc<dyn Sensor>>;
 
 pub enum SensorServiceCmd {
     /// Add sensors in the registry (no replacement)
     AddSensors {
-        sensors: Vec<(String, Arc<dyn Sensor>)>,
+        sensors: Vec<(Uuid, Arc<dyn Sensor>)>,
     },
     /// Remove sensors from the registry
-    RemoveSensors { uuids: Vec<String> },
-    #[expect(unused)]
-    /// Get a specific sensor by serial number
+    RemoveSensors { uuids: Vec<Uuid> },
+    /// Get a specific sensor by id
     FindSensor {
-        serial_number: String,
+        uuid: Uuid,
         respond_to: oneshot::Sender<Option<Arc<dyn Sensor>>>,
     },
     /// Get all sensors (snapshot)
     AllSensors {
         respond_to: oneshot::Sender<Arc<SensorList>>,
     },
+    /// Follow a sensor whose board was given a new name
+    RenameSensor { uuid: Uuid, name: String },
+    /// Forget the identity of a sensor no longer plugged
+    ForgetSensor {
+        uuid: Uuid,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
 }
 
 pub struct CommandChannel {
@@ -38,11 +64,23 @@ pub struct CommandChannel {
     rx: mpsc::Receiver<SensorServiceCmd>,
 }
 
+/// Background task of a registered sensor.
+struct SensorTask {
+    handle: JoinHandle<()>,
+    /// Child of the service token, cancelled alone when the sensor is removed.
+    cancel: CancellationToken,
+}
+
 /// Supervisor service that maintains the list of sensors
 pub struct SensorService {
     sensors: SensorList,
-    sensor_tasks: HashMap<String, JoinHandle<()>>,
+    sensor_tasks: HashMap<Uuid, SensorTask>,
     cmd_channel: CommandChannel,
+    events: broadcast::Sender<SensorEvent>,
+    /// Shared with the detection of plugged sensors
+    identity: Arc<Mutex<IdentityResolver>>,
+    persistence: Option<SensorPersistence>,
+    recorder: Option<MeasurementRecorder>,
 }
 
 impl Default for SensorService {
@@ -54,19 +92,63 @@ impl Default for SensorService {
 impl SensorService {
     pub fn new() -> Self {
         let (tx, rx) = mpsc::channel(100);
+        let (events, _) = broadcast::channel(SENSOR_EVENTS_CAPACITY);
 
         Self {
             sensors: HashMap::new(),
             sensor_tasks: HashMap::new(),
             cmd_channel: CommandChannel { tx, rx },
+            events,
+            identity: Arc::default(),
+            persistence: None,
+            recorder: None,
         }
     }
 
+    /// Handle to talk to the service once it runs.
+    pub fn handle(&self) -> SensorServiceHandle {
+        SensorServiceHandle::new(self.cmd_channel.tx.clone(), self.events.clone())
+    }
+
+    /// Use a custom identity resolver, e.g. one backed by the database or
+    /// loaded with operator mappings.
+    pub fn with_identity_resolver(mut self, identity: IdentityResolver) -> Self {
+        self.identity = Arc::new(Mutex::new(identity));
+        self
+    }
+
+    /// Persist the registry and its state transitions while the service runs.
+    pub fn with_persistence(mut self, persistence: SensorPersistence) -> Self {
+        self.persistence = Some(persistence);
+        self
+    }
+
+    /// Store the measurements while the service runs.
+    pub fn with_measurement_recorder(mut self, recorder: MeasurementRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
     /// Main supervisor loop - maintains sensor registry
-    pub async fn run(mut self) {
+    ///
+    /// Runs until `shutdown` is cancelled by the caller. Sensor tasks are then
+    /// stopped cooperatively, boards are put in a safe state and a summary of
+    /// the whole shutdown is returned.
+    pub async fn run(mut self, shutdown: CancellationToken) -> ShutdownSummary {
         let cmd_tx = self.cmd_channel.tx.clone();
-        let shutdown = CancellationToken::new();
-        println!("Sensor service started - maintaining sensor registry");
+        let identity = Arc::clone(&self.identity);
+        // Stopped after the sensor tasks so their last transitions are kept
+        let persisted = CancellationToken::new();
+        let persistence = self.persistence.take().map(|persistence| {
+            // Subscribe before any sensor is added so nothing is missed
+            let events = self.events.subscribe();
+            persistence.run(events, persisted.clone())
+        });
+        let recorder = self.recorder.take().map(|recorder| {
+            let events = self.events.subscribe();
+            recorder.run(events, persisted.clone())
+        });
+        tracing::info!("Sensor service started.");
 
         let main_loop = {
             let shutdown = shutdown.clone();
@@ -75,80 +157,276 @@ impl SensorService {
                 loop {
                     tokio::select! {
                         Some(cmd) = self.cmd_channel.rx.recv() => {
-                            self.handle_cmd(cmd);
-                        }
-
-                        _ = tokio::signal::ctrl_c() => {
-                            println!("Shutting down sensor registry...");
-                            shutdown.cancel();
-                            self.abort_all_sensor_tasks();
-                            break;
+                            self.handle_cmd(cmd, &shutdown);
                         }
 
                         _ = shutdown.cancelled() => {
-                            self.abort_all_sensor_tasks();
+                            tracing::info!("Shutting down the sensor registry.");
                             break;
                         }
                     }
                 }
+
+                let summary = self.stop_all_sensor_tasks().await;
+                persisted.cancel();
+                summary
             }
         };
 
         // TODO: check for mutex contention across awaits
-        tokio::join!(
+        let (summary, ..) = tokio::join!(
             main_loop,
             healthcheck(&cmd_tx, shutdown.clone()),
-            detect_plugged_sensors_task(&cmd_tx, shutdown.clone()),
-            detect_unplugged_sensors(&cmd_tx, shutdown)
+            detect_plugged_sensors_task(&cmd_tx, identity, shutdown.clone()),
+            detect_unplugged_sensors(&cmd_tx, shutdown),
+            async move {
+                if let Some(persistence) = persistence {
+                    persistence.await;
+                }
+            },
+            async move {
+                if let Some(recorder) = recorder {
+                    recorder.await;
+                }
+            }
         );
+
+        tracing::info!(%summary, "Sensor service stopped.");
+        summary
     }
 
     /// Handle commands to maintain sensor list
-    fn handle_cmd(&mut self, cmd: SensorServiceCmd) {
+    fn handle_cmd(&mut self, cmd: SensorServiceCmd, shutdown: &CancellationToken) {
         match cmd {
             SensorServiceCmd::AddSensors { sensors } => {
-                println!("Registry: Adding up to {} sensors", sensors.len());
                 for (uuid, sensor) in sensors {
                     if self.sensors.contains_key(&uuid) {
-                        println!("Registry: Sensor {uuid} already exists, skipping add");
+                        tracing::debug!(sensor_id = %uuid, "Sensor already registered.");
                         continue;
                     }
 
-                    let task = Arc::clone(&sensor).run();
-                    self.sensor_tasks.insert(uuid.clone(), task);
+                    let _ = self.events.send(SensorEvent::Discovered(sensor.info()));
+
+                    let cancel = shutdown.child_token();
+                    let handle = Arc::clone(&sensor).run(self.events.clone(), cancel.clone());
+                    self.sensor_tasks
+                        .insert(uuid, SensorTask { handle, cancel });
                     self.sensors.insert(uuid, sensor);
+                    tracing::info!(sensor_id = %uuid, "Sensor registered.");
                 }
-                println!("Registry: Total sensors = {}", self.sensors.len());
+                tracing::debug!(sensors = self.sensors.len(), "Sensor registry updated.");
             }
 
             SensorServiceCmd::RemoveSensors { uuids } => {
-                println!("Registry: Removing {} sensors", uuids.len());
                 for uuid in &uuids {
+                    // The task ends on its own after its current tick
                     if let Some(task) = self.sensor_tasks.remove(uuid) {
-                        task.abort();
+                        task.cancel.cancel();
+                    }
+                    if let Some(sensor) = self.sensors.remove(uuid) {
+                        let _ = self.events.send(SensorEvent::Removed(sensor.info()));
+                        tracing::info!(sensor_id = %uuid, "Sensor removed.");
                     }
-                    self.sensors.remove(uuid);
                 }
-                println!("Registry: Total sensors = {}", self.sensors.len());
+                tracing::debug!(sensors = self.sensors.len(), "Sensor registry updated.");
             }
 
-            SensorServiceCmd::FindSensor {
-                serial_number,
-                respond_to,
-            } => {
-                let sensor = self.sensors.get(&serial_number).cloned();
+            SensorServiceCmd::FindSensor { uuid, respond_to } => {
+                let sensor = self.sensors.get(&uuid).cloned();
                 let _ = respond_to.send(sensor);
             }
 
             SensorServiceCmd::AllSensors { respond_to } => {
                 let _ = respond_to.send(Arc::new(self.sensors.clone()));
             }
+
+            SensorServiceCmd::RenameSensor { uuid, name } => {
+                // The detection may hold the resolver while probing a port
+                let identity = Arc::clone(&self.identity);
+                tokio::spawn(async move {
+                    identity.lock().await.renamed(uuid, &name);
+                });
+            }
+
+            SensorServiceCmd::ForgetSensor { uuid, respond_to } => {
+                // A plugged sensor would be detected again right away
+                if self.sensors.contains_key(&uuid) {
+                    let _ = respond_to.send(Err(SensorError::message(format!(
+                        "Sensor {uuid} is plugged, unplug it before forgetting it"
+                    ))));
+                    return;
+                }
+
+                // The detection may hold the resolver while probing a port
+                let identity = Arc::clone(&self.identity);
+                tokio::spawn(async move {
+                    identity.lock().await.forget(uuid);
+                    tracing::info!(sensor_id = %uuid, "Sensor forgotten.");
+                    let _ = respond_to.send(Ok(()));
+                });
+            }
+        }
+    }
+
+    /// Wait for every sensor task to finish its in-flight read, then put the
+    /// boards into a safe state.
+    ///
+    /// Tasks are expected to be cancelled already through the service token.
+    async fn stop_all_sensor_tasks(&mut self) -> ShutdownSummary {
+        let mut summary = ShutdownSummary::default();
+        let tasks: Vec<_> = self.sensor_tasks.drain().collect();
+        for (_, task) in &tasks {
+            task.cancel.cancel();
+        }
+
+        // Tasks run on their own, waiting on them one by one against the
+        // same deadline bounds the whole shutdown to a single timeout
+        let deadline = Instant::now() + SENSOR_TASK_SHUTDOWN_TIMEOUT;
+        for (uuid, mut task) in tasks {
+            if timeout_at(deadline, &mut task.handle).await.is_err() {
+                tracing::warn!(sensor_id = %uuid, "Sensor did not stop in time, aborting it.");
+                task.handle.abort();
+                summary.push(uuid, ShutdownOutcome::TimedOut);
+                continue;
+            }
+
+            let Some(sensor) = self.sensors.get(&uuid) else {
+                summary.push(uuid, ShutdownOutcome::Skipped);
+                continue;
+            };
+
+            if sensor.info().state == SensorState::Unplugged {
+                summary.push(uuid, ShutdownOutcome::Skipped);
+                continue;
+            }
+
+            match sensor.shutdown() {
+                Ok(()) => summary.push(uuid, ShutdownOutcome::Stopped),
+                Err(err) => {
+                    tracing::error!(sensor_id = %uuid, error = %err, "Sensor refused its safe state.");
+                    summary.push(uuid, ShutdownOutcome::SafeStateFailed(err.to_string()));
+                }
+            }
+        }
+
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use crate::sensor::{Calibration, SensorConnection, SensorInfo, SensorKind, SensorStateReason};
+    use crate::serial_port::SerialPortMetadata;
+    use chrono::Utc;
+
+    /// Sensor whose task either stops on cancellation or hangs on a read.
+    struct FakeSensor {
+        id: Uuid,
+        hung: bool,
+    }
+
+    impl Sensor for FakeSensor {
+        fn info(&self) -> SensorInfo {
+            SensorInfo {
+                id: self.id,
+                hardware_uid: format!("usb:{}", self.id),
+                kind: SensorKind::Temperature,
+                firmware: 2.1,
+                name: Default::default(),
+                state: SensorState::Active,
+                state_reason: SensorStateReason::MeasurementOk,
+                state_since: Utc::now(),
+                last_activity: Utc::now(),
+                consecutive_failures: 0,
+                connection: SensorConnection::Uart(SerialPortMetadata {
+                    port_name: "/dev/ttyUSB0".to_string(),
+                    serial_number: self.id.to_string(),
+                    baud_rate: 9600,
+                }),
+            }
+        }
+
+        fn read_measurement(&self) -> Result<f64> {
+            Ok(21.5)
+        }
+
+        fn check_measurement(&self, _: f64) -> Option<SensorStateReason> {
+            None
+        }
+
+        fn record_measurement(&self, _: f64) {}
+
+        fn record_error(&self, _: &SensorError) {}
+
+        fn mark_unplugged(&self) {}
+
+        fn shutdown(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn rename(&self, _: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn calibrate(&self, _: Calibration) -> Result<()> {
+            Ok(())
+        }
+
+        fn identify(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn run(
+            self: Arc<Self>,
+            _: broadcast::Sender<SensorEvent>,
+            shutdown: CancellationToken,
+        ) -> JoinHandle<()> {
+            let hung = self.hung;
+
+            tokio::spawn(async move {
+                if hung {
+                    std::future::pending::<()>().await;
+                }
+                shutdown.cancelled().await;
+            })
         }
     }
 
-    fn abort_all_sensor_tasks(&mut self) {
-        for (_, task) in self.sensor_tasks.drain() {
-            task.abort();
+    #[tokio::test(start_paused = true)]
+    async fn hung_sensors_share_a_single_shutdown_deadline() {
+        let shutdown = CancellationToken::new();
+        let mut service = SensorService::new();
+        let sensors: Vec<(Uuid, Arc<dyn Sensor>)> = [true, true, false]
+            .into_iter()
+            .map(|hung| {
+                let id = Uuid::new_v4();
+                (id, Arc::new(FakeSensor { id, hung }) as Arc<dyn Sensor>)
+            })
+            .collect();
+        let stopped = sensors[2].0;
+        service.handle_cmd(SensorServiceCmd::AddSensors { sensors }, &shutdown);
+
+        // Stopped before the scans get to see the fake ports
+        shutdown.cancel();
+        let started = Instant::now();
+        let summary = service.run(shutdown).await;
+
+        assert_eq!(started.elapsed(), SENSOR_TASK_SHUTDOWN_TIMEOUT);
+        assert!(!summary.is_clean());
+        assert_eq!(summary.sensors.len(), 3);
+        for sensor in &summary.sensors {
+            let expected = if sensor.uuid == stopped {
+                ShutdownOutcome::Stopped
+            } else {
+                ShutdownOutcome::TimedOut
+            };
+            assert_eq!(sensor.outcome, expected);
         }
+        assert_eq!(
+            summary.to_string(),
+            "3 sensors: 1 stopped, 0 safe state failures, 2 timed out, 0 skipped"
+        );
     }
 }
@@ -0,0 +1,68 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fmt;
+use uuid::Uuid;
+
+/// How a single sensor went through the service shutdown.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShutdownOutcome {
+    /// The task stopped and the board was put in a safe state.
+    Stopped,
+    /// The task stopped but the board refused the safe state command.
+    SafeStateFailed(String),
+    /// The task didn't stop in time (stuck read) and was aborted.
+    TimedOut,
+    /// The sensor is unplugged, there is nothing to talk to.
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct SensorShutdown {
+    pub uuid: Uuid,
+    pub outcome: ShutdownOutcome,
+}
+
+/// Report returned by [`super::SensorService::run`] once the registry stopped.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownSummary {
+    pub sensors: Vec<SensorShutdown>,
+}
+
+impl ShutdownSummary {
+    pub fn push(&mut self, uuid: Uuid, outcome: ShutdownOutcome) {
+        self.sensors.push(SensorShutdown { uuid, outcome });
+    }
+
+    /// True when every sensor stopped cleanly or had nothing to stop.
+    pub fn is_clean(&self) -> bool {
+        self.sensors.iter().all(|sensor| {
+            matches!(
+                sensor.outcome,
+                ShutdownOutcome::Stopped | ShutdownOutcome::Skipped
+            )
+        })
+    }
+
+    fn count(&self, predicate: impl Fn(&ShutdownOutcome) -> bool) -> usize {
+        self.sensors
+            .iter()
+            .filter(|sensor| predicate(&sensor.outcome))
+            .count()
+    }
+}
+
+impl fmt::Display for ShutdownSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} sensors: {} stopped, {} safe state failures, {} timed out, {} skipped",
+            self.sensors.len(),
+            self.count(|outcome| matches!(outcome, ShutdownOutcome::Stopped)),
+            self.count(|outcome| matches!(outcome, ShutdownOutcome::SafeStateFailed(_))),
+            self.count(|outcome| matches!(outcome, ShutdownOutcome::TimedOut)),
+            self.count(|outcome| matches!(outcome, ShutdownOutcome::Skipped)),
+        )
+    }
+}
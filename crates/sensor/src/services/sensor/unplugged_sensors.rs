@@ -26,7 +26,7 @@ pub async fn detect_unplugged_sensors(
         tokio::select! {
             _ = interval.tick() => {}
             _ = shutdown.cancelled() => {
-                println!("Detector: stopping unplugged sensor scan");
+                tracing::debug!("Stopping the unplugged sensor scan.");
                 break;
             }
         }
@@ -54,12 +54,14 @@ pub async fn detect_unplugged_sensors(
                 match connection_info {
                     SensorConnection::Uart(port_metadata) => {
                         if !available_port_serials.contains(&port_metadata.serial_number) {
-                            println!(
-                                "Detector: Sensor {} is unplugged, removing from registry",
-                                port_metadata.serial_number
+                            tracing::info!(
+                                sensor_id = %info.id,
+                                port = %port_metadata.port_name,
+                                serial_number = %port_metadata.serial_number,
+                                "Sensor unplugged, removing it from the registry."
                             );
                             sensor.mark_unplugged();
-                            unplugged_sensors.push(port_metadata.serial_number.clone());
+                            unplugged_sensors.push(info.id);
                         }
                     }
                     SensorConnection::I2c(_) => {
@@ -0,0 +1,221 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Mutual challenge-response over the pairing key.
+//!
+//! The hub sends a nonce, the knot answers with its own nonce and a MAC of
+//! the hub nonce, then the hub answers with a MAC of the knot nonce. The key
+//! never goes over the wire and a recorded exchange can't be replayed.
+
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::error::{Result, SyncError};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const MIN_KEY_LEN: usize = 16;
+
+#[derive(Clone)]
+pub struct PairingKey(Vec<u8>);
+
+impl PairingKey {
+    pub fn new(key: &str) -> Result<Self> {
+        if key.len() < MIN_KEY_LEN {
+            return Err(SyncError::InvalidKey);
+        }
+
+        Ok(Self(key.as_bytes().to_vec()))
+    }
+
+    /// A fresh challenge.
+    pub fn nonce() -> String {
+        BASE64_STANDARD.encode(rand::random::<[u8; 32]>())
+    }
+
+    /// Proof of the knot `hardware_uid` for the challenge of the hub.
+    pub fn knot_proof(&self, hub_nonce: &str, hardware_uid: &str) -> String {
+        BASE64_STANDARD.encode(
+            self.mac(&["knot", hub_nonce, hardware_uid])
+                .finalize()
+                .into_bytes(),
+        )
+    }
+
+    pub fn verify_knot(&self, hub_nonce: &str, hardware_uid: &str, proof: &str) -> bool {
+        self.verify(&["knot", hub_nonce, hardware_uid], proof)
+    }
+
+    /// Proof of the hub for the challenge of the knot it registered as
+    /// `knot_id`.
+    pub fn hub_proof(&self, knot_nonce: &str, knot_id: Uuid) -> String {
+        BASE64_STANDARD.encode(
+            self.mac(&["hub", knot_nonce, &knot_id.to_string()])
+                .finalize()
+                .into_bytes(),
+        )
+    }
+
+    pub fn verify_hub(&self, knot_nonce: &str, knot_id: Uuid, proof: &str) -> bool {
+        self.verify(&["hub", knot_nonce, &knot_id.to_string()], proof)
+    }
+
+    /// Key of the connection the hub challenged with `hub_nonce`, once it
+    /// welcomed the knot `knot_id` that challenged it with `knot_nonce`.
+    pub fn session_key(&self, hub_nonce: &str, knot_nonce: &str, knot_id: Uuid) -> SessionKey {
+        let key = self
+            .mac(&["session", hub_nonce, knot_nonce, &knot_id.to_string()])
+            .finalize()
+            .into_bytes();
+
+        SessionKey(key.to_vec())
+    }
+
+    fn verify(&self, parts: &[&str], proof: &str) -> bool {
+        verify(&self.0, parts, proof)
+    }
+
+    fn mac(&self, parts: &[&str]) -> HmacSha256 {
+        mac(&self.0, parts)
+    }
+}
+
+/// End of a connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Hub,
+    Knot,
+}
+
+impl Side {
+    pub fn peer(self) -> Self {
+        match self {
+            Side::Hub => Side::Knot,
+            Side::Knot => Side::Hub,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Side::Hub => "hub",
+            Side::Knot => "knot",
+        }
+    }
+}
+
+/// Secret of one connection, every message sent after the handshake
+/// carries its MAC.
+///
+/// The MAC covers the sender and the position of the message, a message
+/// can't be replayed, reordered or sent back to its sender.
+#[derive(Clone)]
+pub struct SessionKey(Vec<u8>);
+
+impl SessionKey {
+    /// MAC of the `seq`th message sent by `sender`.
+    pub fn sign(&self, sender: Side, seq: u64, message: &str) -> String {
+        BASE64_STANDARD.encode(
+            mac(&self.0, &[sender.label(), &seq.to_string(), message])
+                .finalize()
+                .into_bytes(),
+        )
+    }
+
+    pub fn verify(&self, sender: Side, seq: u64, message: &str, signature: &str) -> bool {
+        verify(
+            &self.0,
+            &[sender.label(), &seq.to_string(), message],
+            signature,
+        )
+    }
+}
+
+impl std::fmt::Debug for SessionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SessionKey(..)")
+    }
+}
+
+fn verify(key: &[u8], parts: &[&str], proof: &str) -> bool {
+    BASE64_STANDARD
+        .decode(proof)
+        .is_ok_and(|proof| mac(key, parts).verify_slice(&proof).is_ok())
+}
+
+/// The role comes first so a proof of one side is never valid for the
+/// other.
+fn mac(key: &[u8], parts: &[&str]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC takes keys of any size");
+    for part in parts {
+        mac.update(part.as_bytes());
+        mac.update(&[0]);
+    }
+
+    mac
+}
+
+impl std::fmt::Debug for PairingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PairingKey(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "correct horse battery staple";
+
+    #[test]
+    fn short_keys_are_refused() {
+        assert_eq!(
+            PairingKey::new("hunter2").unwrap_err(),
+            SyncError::InvalidKey
+        );
+    }
+
+    #[test]
+    fn a_knot_proves_it_knows_the_key() {
+        let key = PairingKey::new(KEY).unwrap();
+        let nonce = PairingKey::nonce();
+        let proof = key.knot_proof(&nonce, "rpi:1234");
+
+        assert!(key.verify_knot(&nonce, "rpi:1234", &proof));
+        assert!(!key.verify_knot(&nonce, "rpi:5678", &proof));
+        assert!(!key.verify_knot(&PairingKey::nonce(), "rpi:1234", &proof));
+
+        let other = PairingKey::new("another key of the station").unwrap();
+        assert!(!other.verify_knot(&nonce, "rpi:1234", &proof));
+    }
+
+    #[test]
+    fn proofs_are_bound_to_a_side() {
+        let key = PairingKey::new(KEY).unwrap();
+        let nonce = PairingKey::nonce();
+        let knot_id = Uuid::new_v4();
+        let proof = key.hub_proof(&nonce, knot_id);
+
+        assert!(key.verify_hub(&nonce, knot_id, &proof));
+        assert!(!key.verify_knot(&nonce, &knot_id.to_string(), &proof));
+        assert!(!key.verify_hub(&nonce, knot_id, "not base64!"));
+    }
+
+    #[test]
+    fn messages_are_signed_for_one_position_and_sender() {
+        let key = PairingKey::new(KEY).unwrap();
+        let knot_id = Uuid::new_v4();
+        let session = key.session_key("hub nonce", "knot nonce", knot_id);
+        let signature = session.sign(Side::Hub, 1, "{\"type\":\"heartbeat\"}");
+
+        assert!(session.verify(Side::Hub, 1, "{\"type\":\"heartbeat\"}", &signature));
+        assert!(!session.verify(Side::Hub, 2, "{\"type\":\"heartbeat\"}", &signature));
+        assert!(!session.verify(Side::Knot, 1, "{\"type\":\"heartbeat\"}", &signature));
+        assert!(!session.verify(Side::Hub, 1, "{\"type\":\"goodbye\"}", &signature));
+
+        let other = key.session_key("hub nonce", "another knot nonce", knot_id);
+        assert!(!other.verify(Side::Hub, 1, "{\"type\":\"heartbeat\"}", &signature));
+    }
+}
@@ -0,0 +1,94 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_config::ConfigHandler;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use crate::protocol::KnotSettings;
+
+pub static CONFIG: LazyLock<Config> = LazyLock::new(|| ConfigHandler::new(mpl).load());
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Secret shared by a hub and its knots, at least 16 bytes. Neither side
+    /// starts without it.
+    pub pairing_key: String,
+    pub hub: HubConfig,
+    pub knot: KnotConfig,
+}
+
+#[derive(Clone, Debug)]
+pub struct HubConfig {
+    /// The app accepts remote knots, `sk hub` always does
+    pub enabled: bool,
+    pub listen: SocketAddr,
+    /// Without it the knots are authenticated but the traffic isn't
+    /// encrypted
+    pub tls: Option<TlsIdentity>,
+    /// A knot not heard of for this long is unreachable
+    pub unreachable_after: Duration,
+    /// Sent to the knots when they connect
+    pub settings: KnotSettings,
+}
+
+/// Certificate chain and private key of the hub, in PEM.
+#[derive(Clone, Debug)]
+pub struct TlsIdentity {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+#[derive(Clone, Debug)]
+pub struct KnotConfig {
+    /// `host:port` of the hub
+    pub hub_address: String,
+    /// Must be set when the hub has a certificate
+    pub tls: Option<TlsTrust>,
+    /// Name of the knot when first registered
+    pub name: String,
+    /// Replaces the hardware uid of the Pi, e.g. to run a knot next to its
+    /// hub on one machine
+    pub hardware_uid: Option<String>,
+    /// Messages kept while the hub can't be reached, the oldest are dropped
+    /// first
+    pub buffer_capacity: usize,
+    /// First wait before connecting again, doubled on each failure
+    pub reconnect_delay: Duration,
+    pub max_reconnect_delay: Duration,
+}
+
+/// Certificates trusted to sign the one of the hub, in PEM, and the name it
+/// is issued for.
+#[derive(Clone, Debug)]
+pub struct TlsTrust {
+    pub ca_path: PathBuf,
+    pub server_name: String,
+}
+
+fn mpl() -> Config {
+    Config {
+        pairing_key: String::new(),
+        hub: HubConfig {
+            enabled: false,
+            listen: SocketAddr::from(([0, 0, 0, 0], 7840)),
+            tls: None,
+            unreachable_after: Duration::from_secs(30),
+            settings: KnotSettings {
+                heartbeat_interval: Duration::from_secs(10),
+            },
+        },
+        knot: KnotConfig {
+            hub_address: "arksync.local:7840".to_string(),
+            tls: None,
+            name: "Knot".to_string(),
+            hardware_uid: None,
+            buffer_capacity: 10_000,
+            reconnect_delay: Duration::from_secs(1),
+            max_reconnect_delay: Duration::from_secs(60),
+        },
+    }
+}
@@ -0,0 +1,60 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncError {
+    /// The pairing key is missing or too short.
+    InvalidKey,
+    /// TLS can't be set up, e.g. an unreadable certificate.
+    Tls(String),
+    Io(String),
+    /// The peer sent something out of the protocol.
+    Protocol(String),
+    /// The peer failed to prove it knows the pairing key.
+    Unauthorized,
+    /// A message failed its integrity check, it was forged or altered.
+    Tampered,
+    /// The hub refused the knot.
+    Rejected(String),
+    /// No knot connected with this id.
+    KnotNotConnected(Uuid),
+    /// The knot didn't answer in time.
+    Timeout,
+    /// The knot failed to apply a command.
+    CommandFailed(String),
+    ServiceStopped,
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::InvalidKey => write!(f, "The pairing key must be at least 16 bytes long"),
+            SyncError::Tls(details) => write!(f, "TLS setup failed: {details}"),
+            SyncError::Io(details) => write!(f, "Connection failed: {details}"),
+            SyncError::Protocol(details) => write!(f, "Protocol error: {details}"),
+            SyncError::Unauthorized => write!(f, "The peer doesn't know the pairing key"),
+            SyncError::Tampered => write!(f, "A message failed its integrity check"),
+            SyncError::Rejected(reason) => write!(f, "Rejected by the hub: {reason}"),
+            SyncError::KnotNotConnected(id) => write!(f, "Knot {id} is not connected"),
+            SyncError::Timeout => write!(f, "The knot didn't answer in time"),
+            SyncError::CommandFailed(details) => write!(f, "Command failed on the knot: {details}"),
+            SyncError::ServiceStopped => write!(f, "Sync service stopped"),
+        }
+    }
+}
+
+impl Error for SyncError {}
+
+impl From<io::Error> for SyncError {
+    fn from(err: io::Error) -> Self {
+        SyncError::Io(err.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, SyncError>;
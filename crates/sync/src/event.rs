@@ -0,0 +1,32 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::StationKnotStatus;
+use uuid::Uuid;
+
+use crate::protocol::KnotMessage;
+
+/// Events published by the hub service.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HubEvent {
+    /// A knot connected, went to sleep or was lost.
+    KnotStatusChanged {
+        knot_id: Uuid,
+        name: String,
+        status: StationKnotStatus,
+    },
+    /// A knot reported its sensors, relays or measurements, after they were
+    /// stored.
+    Reported { knot_id: Uuid, message: KnotMessage },
+}
+
+impl HubEvent {
+    pub fn knot_id(&self) -> Uuid {
+        match self {
+            HubEvent::KnotStatusChanged { knot_id, .. } | HubEvent::Reported { knot_id, .. } => {
+                *knot_id
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Sync between a station hub and its remote knots.
+//!
+//! A knot connects to its hub over TCP, TLS when configured, and both prove
+//! they know the pairing key. The knot then streams its sensors, relays and
+//! measurements while the hub sends it relay commands and settings, one JSON
+//! message per line. Every message after the handshake carries a MAC from a
+//! key of the session, forged or replayed ones end the connection.
+
+pub mod auth;
+mod config;
+pub mod error;
+pub mod event;
+pub mod protocol;
+pub mod services;
+pub mod tls;
+
+pub use config::{Config, HubConfig, KnotConfig, TlsIdentity, TlsTrust, CONFIG};
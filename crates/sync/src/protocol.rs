@@ -0,0 +1,356 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::{
+    ActuatorRecord, ActuatorStateRecord, MeasurementRecord, SensorRecord, SensorStateRecord,
+    SensorStatus,
+};
+use chrono::{DateTime, Utc};
+use futures_util::{SinkExt as _, StreamExt as _};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::{Framed, LinesCodec, LinesCodecError};
+use uuid::Uuid;
+
+use crate::auth::{SessionKey, Side};
+use crate::error::{Result, SyncError};
+
+/// Longest line accepted, a batch of measurements included.
+const MAX_LINE: usize = 1024 * 1024;
+
+/// Settings of a knot decided by its hub.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KnotSettings {
+    /// The hub expects a message at least this often
+    pub heartbeat_interval: Duration,
+}
+
+/// What a hub asks a knot to do.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum KnotCommand {
+    SwitchRelay {
+        relay_id: String,
+        on: bool,
+    },
+    PulseRelay {
+        relay_id: String,
+        duration: Duration,
+    },
+}
+
+/// Messages sent by a knot to its hub.
+///
+/// Sensors and relays are sent as the rows the hub stores, with the ids and
+/// hardware uids of the knot.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KnotMessage {
+    /// Answer to the challenge of the hub.
+    Hello {
+        hardware_uid: String,
+        name: String,
+        /// Challenge of the knot, for the hub to prove itself
+        nonce: String,
+        proof: String,
+    },
+    Heartbeat,
+    /// The sensors and relays of the knot, sent on every connection.
+    Snapshot {
+        sensors: Vec<SensorRecord>,
+        actuators: Vec<ActuatorRecord>,
+    },
+    SensorDiscovered {
+        sensor: SensorRecord,
+    },
+    SensorTransition {
+        sensor_id: Uuid,
+        previous: Option<SensorStatus>,
+        state: SensorStateRecord,
+    },
+    SensorRenamed {
+        sensor_id: Uuid,
+        name: String,
+        hardware_uid: String,
+    },
+    Measurements {
+        measurements: Vec<MeasurementRecord>,
+    },
+    RelaySwitched {
+        relay_id: String,
+        state: ActuatorStateRecord,
+        /// Code of the switch reason, e.g. `command`
+        reason: String,
+    },
+    RelayRefused {
+        relay_id: String,
+        /// Code of the interlock, e.g. `min_off_time`
+        reason: String,
+        details: Value,
+        occurred_at: DateTime<Utc>,
+    },
+    CommandResult {
+        request_id: u64,
+        error: Option<String>,
+    },
+    /// The knot goes to sleep, it is not lost.
+    Goodbye,
+}
+
+/// Messages sent by a hub to a knot.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HubMessage {
+    /// First message of a connection.
+    Challenge {
+        nonce: String,
+    },
+    Welcome {
+        knot_id: Uuid,
+        proof: String,
+        settings: KnotSettings,
+    },
+    /// The knot is refused, the connection is closed.
+    Rejected {
+        reason: String,
+    },
+    Heartbeat,
+    Configure {
+        settings: KnotSettings,
+    },
+    Command {
+        request_id: u64,
+        command: KnotCommand,
+    },
+}
+
+/// Byte stream a connection runs over, TCP or TLS.
+pub trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Transport for T {}
+
+/// Messages sent as one JSON object per line.
+///
+/// Once sealed, each line starts with the MAC of its message followed by a
+/// space.
+pub struct Connection {
+    lines: Framed<Box<dyn Transport>, LinesCodec>,
+    seal: Option<Seal>,
+}
+
+struct Seal {
+    key: SessionKey,
+    /// This end of the connection
+    side: Side,
+    sent: u64,
+    received: u64,
+}
+
+impl Connection {
+    pub fn new(transport: impl Transport + 'static) -> Self {
+        let transport: Box<dyn Transport> = Box::new(transport);
+
+        Self {
+            lines: Framed::new(transport, LinesCodec::new_with_max_length(MAX_LINE)),
+            seal: None,
+        }
+    }
+
+    /// Sign the messages sent from now on and refuse those received without
+    /// a valid MAC, `side` being this end of the connection.
+    pub fn seal(&mut self, key: SessionKey, side: Side) {
+        self.seal = Some(Seal {
+            key,
+            side,
+            sent: 0,
+            received: 0,
+        });
+    }
+
+    pub async fn send(&mut self, message: &impl Serialize) -> Result<()> {
+        let mut line = serde_json::to_string(message)
+            .map_err(|err| SyncError::Protocol(format!("Unserializable message: {err}")))?;
+        if let Some(seal) = &mut self.seal {
+            seal.sent += 1;
+            line = format!("{} {line}", seal.key.sign(seal.side, seal.sent, &line));
+        }
+
+        self.lines.send(line).await.map_err(codec_error)
+    }
+
+    /// Next message, `None` once the peer closed the connection.
+    pub async fn recv<M: DeserializeOwned>(&mut self) -> Result<Option<M>> {
+        let Some(line) = self.lines.next().await else {
+            return Ok(None);
+        };
+        let line = line.map_err(codec_error)?;
+        let message = match &mut self.seal {
+            Some(seal) => {
+                let (signature, message) = line.split_once(' ').ok_or(SyncError::Tampered)?;
+                seal.received += 1;
+                if !seal
+                    .key
+                    .verify(seal.side.peer(), seal.received, message, signature)
+                {
+                    return Err(SyncError::Tampered);
+                }
+                message
+            }
+            None => &line,
+        };
+
+        serde_json::from_str(message)
+            .map(Some)
+            .map_err(|err| SyncError::Protocol(format!("Invalid message: {err}")))
+    }
+}
+
+fn codec_error(err: LinesCodecError) -> SyncError {
+    match err {
+        LinesCodecError::MaxLineLengthExceeded => {
+            SyncError::Protocol(format!("Message longer than {MAX_LINE} bytes"))
+        }
+        LinesCodecError::Io(err) => err.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::PairingKey;
+
+    #[tokio::test]
+    async fn messages_go_through_a_connection() {
+        let (knot, hub) = tokio::io::duplex(1024);
+        let mut knot = Connection::new(knot);
+        let mut hub = Connection::new(hub);
+        let message = KnotMessage::RelaySwitched {
+            relay_id: "mist".to_string(),
+            state: ActuatorStateRecord {
+                active: true,
+                state_since: Utc::now(),
+            },
+            reason: "command".to_string(),
+        };
+
+        knot.send(&message).await.unwrap();
+        drop(knot);
+
+        assert_eq!(hub.recv::<KnotMessage>().await.unwrap(), Some(message));
+        assert_eq!(hub.recv::<KnotMessage>().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn garbage_is_a_protocol_error() {
+        let (knot, hub) = tokio::io::duplex(1024);
+        let mut knot = Connection::new(knot);
+        let mut hub = Connection::new(hub);
+
+        knot.lines
+            .send("{\"type\":\"unknown\"}".to_string())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            hub.recv::<KnotMessage>().await,
+            Err(SyncError::Protocol(_))
+        ));
+    }
+
+    fn sealed() -> (Connection, Connection) {
+        let (knot, hub) = tokio::io::duplex(1024);
+        let key = PairingKey::new("correct horse battery staple")
+            .unwrap()
+            .session_key("hub nonce", "knot nonce", Uuid::nil());
+        let mut knot = Connection::new(knot);
+        let mut hub = Connection::new(hub);
+        knot.seal(key.clone(), Side::Knot);
+        hub.seal(key, Side::Hub);
+
+        (knot, hub)
+    }
+
+    #[tokio::test]
+    async fn sealed_messages_go_through() {
+        let (mut knot, mut hub) = sealed();
+
+        knot.send(&KnotMessage::Heartbeat).await.unwrap();
+        knot.send(&KnotMessage::Goodbye).await.unwrap();
+        hub.send(&HubMessage::Heartbeat).await.unwrap();
+
+        assert_eq!(hub.recv().await.unwrap(), Some(KnotMessage::Heartbeat));
+        assert_eq!(hub.recv().await.unwrap(), Some(KnotMessage::Goodbye));
+        assert_eq!(knot.recv().await.unwrap(), Some(HubMessage::Heartbeat));
+    }
+
+    #[tokio::test]
+    async fn tampered_messages_are_refused() {
+        let (mut knot, mut hub) = sealed();
+        let command = serde_json::to_string(&HubMessage::Command {
+            request_id: 1,
+            command: KnotCommand::SwitchRelay {
+                relay_id: "mist".to_string(),
+                on: false,
+            },
+        })
+        .unwrap();
+        let signature = hub.seal.as_ref().unwrap().key.sign(Side::Hub, 1, &command);
+
+        // Injected without a MAC, then altered after signing
+        hub.lines.send(command.clone()).await.unwrap();
+        hub.lines
+            .send(format!("{signature} {}", command.replace("false", "true")))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            knot.recv::<HubMessage>().await.unwrap_err(),
+            SyncError::Tampered
+        );
+        assert_eq!(
+            knot.recv::<HubMessage>().await.unwrap_err(),
+            SyncError::Tampered
+        );
+    }
+
+    #[tokio::test]
+    async fn replayed_messages_are_refused() {
+        let (mut knot, mut hub) = sealed();
+        let goodbye = serde_json::to_string(&KnotMessage::Goodbye).unwrap();
+        let signature = knot
+            .seal
+            .as_ref()
+            .unwrap()
+            .key
+            .sign(Side::Knot, 1, &goodbye);
+
+        knot.send(&KnotMessage::Goodbye).await.unwrap();
+        knot.lines
+            .send(format!("{signature} {goodbye}"))
+            .await
+            .unwrap();
+
+        assert_eq!(hub.recv().await.unwrap(), Some(KnotMessage::Goodbye));
+        assert_eq!(
+            hub.recv::<KnotMessage>().await.unwrap_err(),
+            SyncError::Tampered
+        );
+    }
+
+    #[test]
+    fn commands_are_tagged_with_their_action() {
+        let command = KnotCommand::SwitchRelay {
+            relay_id: "mist".to_string(),
+            on: true,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&command).unwrap(),
+            serde_json::json!({ "action": "switch_relay", "relay_id": "mist", "on": true })
+        );
+    }
+}
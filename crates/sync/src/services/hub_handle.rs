@@ -0,0 +1,90 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::{timeout, Duration};
+use uuid::Uuid;
+
+use crate::error::{Result, SyncError};
+use crate::event::HubEvent;
+use crate::protocol::{KnotCommand, KnotSettings};
+use crate::services::hub_service::{HubServiceCmd, SessionCmd};
+use crate::services::ConnectedKnot;
+
+/// A knot must apply a command within this delay.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Cheap, cloneable access to a running [`super::HubService`].
+#[derive(Clone)]
+pub struct HubServiceHandle {
+    cmd_tx: mpsc::Sender<HubServiceCmd>,
+    events: broadcast::Sender<HubEvent>,
+}
+
+impl HubServiceHandle {
+    pub(crate) fn new(
+        cmd_tx: mpsc::Sender<HubServiceCmd>,
+        events: broadcast::Sender<HubEvent>,
+    ) -> Self {
+        Self { cmd_tx, events }
+    }
+
+    /// Receive the knot statuses and reports published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<HubEvent> {
+        self.events.subscribe()
+    }
+
+    /// Knots connected right now, `None` once the service stopped.
+    pub async fn knots(&self) -> Option<Vec<ConnectedKnot>> {
+        let (respond_to, rx) = oneshot::channel();
+        self.cmd_tx
+            .send(HubServiceCmd::Knots { respond_to })
+            .await
+            .ok()?;
+
+        rx.await.ok()
+    }
+
+    /// Have a knot apply `command`, once it did.
+    pub async fn command(&self, knot_id: Uuid, command: KnotCommand) -> Result<()> {
+        let session = self.session(knot_id).await?;
+        let (respond_to, rx) = oneshot::channel();
+        session
+            .send(SessionCmd::Command {
+                command,
+                respond_to,
+            })
+            .await
+            .map_err(|_| SyncError::KnotNotConnected(knot_id))?;
+
+        match timeout(COMMAND_TIMEOUT, rx).await {
+            Ok(result) => result.map_err(|_| SyncError::KnotNotConnected(knot_id))?,
+            Err(_) => Err(SyncError::Timeout),
+        }
+    }
+
+    /// Send new settings to a knot, kept until it connects again.
+    pub async fn configure(&self, knot_id: Uuid, settings: KnotSettings) -> Result<()> {
+        self.session(knot_id)
+            .await?
+            .send(SessionCmd::Configure { settings })
+            .await
+            .map_err(|_| SyncError::KnotNotConnected(knot_id))
+    }
+
+    async fn session(&self, knot_id: Uuid) -> Result<mpsc::Sender<SessionCmd>> {
+        let (respond_to, rx) = oneshot::channel();
+        self.cmd_tx
+            .send(HubServiceCmd::Session {
+                knot_id,
+                respond_to,
+            })
+            .await
+            .map_err(|_| SyncError::ServiceStopped)?;
+
+        rx.await
+            .map_err(|_| SyncError::ServiceStopped)?
+            .ok_or(SyncError::KnotNotConnected(knot_id))
+    }
+}
@@ -0,0 +1,521 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::StationKnotStatus;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::task::JoinSet;
+use tokio::time::{sleep_until, timeout, Duration, Instant};
+use tokio_rustls::TlsAcceptor;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::auth::{PairingKey, Side};
+use crate::error::{Result, SyncError};
+use crate::event::HubEvent;
+use crate::protocol::{Connection, HubMessage, KnotCommand, KnotMessage, KnotSettings};
+use crate::services::knot_persistence::KnotRecorder;
+use crate::services::{HubServiceHandle, KnotPersistence};
+use crate::{tls, HubConfig};
+
+/// Events a subscriber can lag behind before missing some.
+const HUB_EVENTS_CAPACITY: usize = 1024;
+/// A connection must be authenticated within this delay.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(crate) enum HubServiceCmd {
+    /// Knots connected right now
+    Knots {
+        respond_to: oneshot::Sender<Vec<ConnectedKnot>>,
+    },
+    /// Channel to the session of a connected knot
+    Session {
+        knot_id: Uuid,
+        respond_to: oneshot::Sender<Option<mpsc::Sender<SessionCmd>>>,
+    },
+    /// An authenticated knot asks to join, `None` when refused
+    Register {
+        name: String,
+        hardware_uid: String,
+        session: u64,
+        cmd_tx: mpsc::Sender<SessionCmd>,
+        respond_to: oneshot::Sender<Option<ConnectedKnot>>,
+    },
+    /// A session ended, leaving its knot in `status`
+    Disconnected {
+        knot_id: Uuid,
+        session: u64,
+        status: StationKnotStatus,
+    },
+}
+
+pub(crate) enum SessionCmd {
+    Command {
+        command: KnotCommand,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    Configure {
+        settings: KnotSettings,
+    },
+}
+
+/// A remote knot connected to the hub.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectedKnot {
+    pub id: Uuid,
+    pub name: String,
+    pub hardware_uid: String,
+    pub connected_at: DateTime<Utc>,
+}
+
+struct Session {
+    id: u64,
+    knot: ConnectedKnot,
+    cmd_tx: mpsc::Sender<SessionCmd>,
+}
+
+/// Accepts the remote knots of a station hub.
+///
+/// Each knot is authenticated with the pairing key, then its reports are
+/// stored and published as [`HubEvent`]s. A knot is `awake` while
+/// connected, `sleeping` once it said goodbye and `unreachable` when the
+/// connection is lost or it stays silent too long.
+pub struct HubService {
+    listener: TcpListener,
+    acceptor: Option<TlsAcceptor>,
+    key: PairingKey,
+    settings: KnotSettings,
+    unreachable_after: Duration,
+    cmd_tx: mpsc::Sender<HubServiceCmd>,
+    cmd_rx: mpsc::Receiver<HubServiceCmd>,
+    events: broadcast::Sender<HubEvent>,
+    persistence: Option<KnotPersistence>,
+    sessions: HashMap<Uuid, Session>,
+    /// Knot ids by hardware uid, without persistence
+    knot_ids: HashMap<String, Uuid>,
+    next_session: u64,
+}
+
+impl HubService {
+    /// Listen on `config.listen`, with TLS when `config.tls` is set.
+    pub async fn bind(config: &HubConfig, key: PairingKey) -> Result<Self> {
+        let acceptor = config.tls.as_ref().map(tls::acceptor).transpose()?;
+        let listener = TcpListener::bind(config.listen).await?;
+        let (cmd_tx, cmd_rx) = mpsc::channel(100);
+        let (events, _) = broadcast::channel(HUB_EVENTS_CAPACITY);
+
+        Ok(Self {
+            listener,
+            acceptor,
+            key,
+            settings: config.settings,
+            unreachable_after: config.unreachable_after,
+            cmd_tx,
+            cmd_rx,
+            events,
+            persistence: None,
+            sessions: HashMap::new(),
+            knot_ids: HashMap::new(),
+            next_session: 0,
+        })
+    }
+
+    /// Address the knots connect to, the actual port when bound to `0`.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Handle to talk to the service once it runs.
+    pub fn handle(&self) -> HubServiceHandle {
+        HubServiceHandle::new(self.cmd_tx.clone(), self.events.clone())
+    }
+
+    /// Register the knots and store their reports while the service runs.
+    pub fn with_persistence(mut self, persistence: KnotPersistence) -> Self {
+        self.persistence = Some(persistence);
+        self
+    }
+
+    /// Accept knots until `shutdown` is cancelled, their connections are
+    /// then closed.
+    pub async fn run(mut self, shutdown: CancellationToken) {
+        if let Some(persistence) = &self.persistence {
+            if let Err(err) = persistence.reset().await {
+                tracing::error!(
+                    error = format_args!("{err:#}"),
+                    "Failed to mark the remote knots unreachable."
+                );
+            }
+        }
+        tracing::info!(
+            address = ?self.listener.local_addr().ok(),
+            tls = self.acceptor.is_some(),
+            "Hub service started."
+        );
+
+        let mut connections = JoinSet::new();
+        loop {
+            tokio::select! {
+                accepted = self.listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        connections.spawn(self.session(stream, peer).run(shutdown.clone()));
+                    }
+                    Err(error) => tracing::warn!(%error, "Failed to accept a knot."),
+                },
+                Some(cmd) = self.cmd_rx.recv() => self.handle_cmd(cmd).await,
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
+                _ = shutdown.cancelled() => break,
+            }
+        }
+
+        while connections.join_next().await.is_some() {}
+        tracing::info!("Hub service stopped.");
+    }
+
+    fn session(&mut self, stream: TcpStream, peer: SocketAddr) -> KnotSession {
+        self.next_session += 1;
+
+        KnotSession {
+            id: self.next_session,
+            peer,
+            stream: Some(stream),
+            acceptor: self.acceptor.clone(),
+            key: self.key.clone(),
+            settings: self.settings,
+            unreachable_after: self.unreachable_after,
+            hub_tx: self.cmd_tx.clone(),
+            events: self.events.clone(),
+            recorder: None,
+            persistence: self.persistence,
+        }
+    }
+
+    async fn handle_cmd(&mut self, cmd: HubServiceCmd) {
+        match cmd {
+            HubServiceCmd::Knots { respond_to } => {
+                let knots = self
+                    .sessions
+                    .values()
+                    .map(|session| session.knot.clone())
+                    .collect();
+                let _ = respond_to.send(knots);
+            }
+
+            HubServiceCmd::Session {
+                knot_id,
+                respond_to,
+            } => {
+                let cmd_tx = self
+                    .sessions
+                    .get(&knot_id)
+                    .map(|session| session.cmd_tx.clone());
+                let _ = respond_to.send(cmd_tx);
+            }
+
+            HubServiceCmd::Register {
+                name,
+                hardware_uid,
+                session,
+                cmd_tx,
+                respond_to,
+            } => {
+                let knot = self.register(name, hardware_uid).await;
+                if let Some(knot) = &knot {
+                    // A previous session of the knot ends with its channel
+                    self.sessions.insert(
+                        knot.id,
+                        Session {
+                            id: session,
+                            knot: knot.clone(),
+                            cmd_tx,
+                        },
+                    );
+                    self.publish_status(knot.id, &knot.name, StationKnotStatus::Awake);
+                }
+                let _ = respond_to.send(knot);
+            }
+
+            HubServiceCmd::Disconnected {
+                knot_id,
+                session,
+                status,
+            } => {
+                if self
+                    .sessions
+                    .get(&knot_id)
+                    .is_none_or(|current| current.id != session)
+                {
+                    return;
+                }
+                let Some(session) = self.sessions.remove(&knot_id) else {
+                    return;
+                };
+
+                if let Some(persistence) = &self.persistence {
+                    if let Err(err) = persistence.set_status(knot_id, status).await {
+                        tracing::error!(
+                            %knot_id,
+                            error = format_args!("{err:#}"),
+                            "Failed to persist the status of the knot."
+                        );
+                    }
+                }
+                self.publish_status(knot_id, &session.knot.name, status);
+            }
+        }
+    }
+
+    async fn register(&mut self, name: String, hardware_uid: String) -> Option<ConnectedKnot> {
+        let (id, name) = match &self.persistence {
+            Some(persistence) => match persistence.register(&name, &hardware_uid).await {
+                Ok(Some(record)) => (record.id, record.name),
+                Ok(None) => {
+                    tracing::warn!(hardware_uid, "A knot uses the hardware uid of a hub.");
+                    return None;
+                }
+                Err(err) => {
+                    tracing::error!(
+                        hardware_uid,
+                        error = format_args!("{err:#}"),
+                        "Failed to register the knot."
+                    );
+                    return None;
+                }
+            },
+            None => {
+                let id = *self
+                    .knot_ids
+                    .entry(hardware_uid.clone())
+                    .or_insert_with(Uuid::new_v4);
+                (id, name)
+            }
+        };
+
+        Some(ConnectedKnot {
+            id,
+            name,
+            hardware_uid,
+            connected_at: Utc::now(),
+        })
+    }
+
+    fn publish_status(&self, knot_id: Uuid, name: &str, status: StationKnotStatus) {
+        tracing::info!(%knot_id, name, ?status, "Knot status changed.");
+        // No receiver is fine, nobody is listening yet
+        let _ = self.events.send(HubEvent::KnotStatusChanged {
+            knot_id,
+            name: name.to_string(),
+            status,
+        });
+    }
+}
+
+/// One connection of a knot, from its handshake to its end.
+struct KnotSession {
+    id: u64,
+    peer: SocketAddr,
+    stream: Option<TcpStream>,
+    acceptor: Option<TlsAcceptor>,
+    key: PairingKey,
+    settings: KnotSettings,
+    unreachable_after: Duration,
+    hub_tx: mpsc::Sender<HubServiceCmd>,
+    events: broadcast::Sender<HubEvent>,
+    persistence: Option<KnotPersistence>,
+    recorder: Option<KnotRecorder>,
+}
+
+impl KnotSession {
+    async fn run(mut self, shutdown: CancellationToken) {
+        let (cmd_tx, cmd_rx) = mpsc::channel(32);
+        let handshake = timeout(HANDSHAKE_TIMEOUT, self.handshake(cmd_tx));
+        let (mut connection, knot) = tokio::select! {
+            result = handshake => match result {
+                Ok(Ok(joined)) => joined,
+                Ok(Err(error)) => {
+                    tracing::warn!(peer = %self.peer, %error, "Knot refused.");
+                    return;
+                }
+                Err(_) => {
+                    tracing::warn!(peer = %self.peer, "Knot handshake timed out.");
+                    return;
+                }
+            },
+            _ = shutdown.cancelled() => return,
+        };
+
+        let status = match self.serve(&mut connection, &knot, cmd_rx, shutdown).await {
+            Ok(status) => status,
+            Err(error) => {
+                tracing::warn!(knot_id = %knot.id, %error, "Knot connection lost.");
+                StationKnotStatus::Unreachable
+            }
+        };
+
+        let _ = self
+            .hub_tx
+            .send(HubServiceCmd::Disconnected {
+                knot_id: knot.id,
+                session: self.id,
+                status,
+            })
+            .await;
+    }
+
+    /// Authenticate the knot, then the hub to the knot.
+    async fn handshake(
+        &mut self,
+        cmd_tx: mpsc::Sender<SessionCmd>,
+    ) -> Result<(Connection, ConnectedKnot)> {
+        let stream = self.stream.take().ok_or(SyncError::ServiceStopped)?;
+        let mut connection = match &self.acceptor {
+            Some(acceptor) => Connection::new(acceptor.accept(stream).await?),
+            None => Connection::new(stream),
+        };
+
+        let nonce = PairingKey::nonce();
+        connection
+            .send(&HubMessage::Challenge {
+                nonce: nonce.clone(),
+            })
+            .await?;
+        let Some(KnotMessage::Hello {
+            hardware_uid,
+            name,
+            nonce: knot_nonce,
+            proof,
+        }) = connection.recv().await?
+        else {
+            return Err(SyncError::Protocol("Expected a hello".to_string()));
+        };
+
+        if !self.key.verify_knot(&nonce, &hardware_uid, &proof) {
+            reject(&mut connection, "Invalid proof of the pairing key").await;
+            return Err(SyncError::Unauthorized);
+        }
+
+        let (respond_to, rx) = oneshot::channel();
+        self.hub_tx
+            .send(HubServiceCmd::Register {
+                name,
+                hardware_uid,
+                session: self.id,
+                cmd_tx,
+                respond_to,
+            })
+            .await
+            .map_err(|_| SyncError::ServiceStopped)?;
+        let Some(knot) = rx.await.map_err(|_| SyncError::ServiceStopped)? else {
+            reject(&mut connection, "The knot can't be registered").await;
+            return Err(SyncError::Rejected(
+                "The knot can't be registered".to_string(),
+            ));
+        };
+
+        connection
+            .send(&HubMessage::Welcome {
+                knot_id: knot.id,
+                proof: self.key.hub_proof(&knot_nonce, knot.id),
+                settings: self.settings,
+            })
+            .await?;
+        connection.seal(
+            self.key.session_key(&nonce, &knot_nonce, knot.id),
+            Side::Hub,
+        );
+        self.recorder = self
+            .persistence
+            .map(|persistence| persistence.recorder(knot.id, &knot.hardware_uid));
+
+        Ok((connection, knot))
+    }
+
+    /// Exchange with the knot until it leaves, the status it is left in.
+    async fn serve(
+        &mut self,
+        connection: &mut Connection,
+        knot: &ConnectedKnot,
+        mut cmd_rx: mpsc::Receiver<SessionCmd>,
+        shutdown: CancellationToken,
+    ) -> Result<StationKnotStatus> {
+        let mut last_heard = Instant::now();
+        let mut pending = HashMap::<u64, oneshot::Sender<Result<()>>>::new();
+        let mut next_request = 0;
+
+        loop {
+            tokio::select! {
+                message = connection.recv::<KnotMessage>() => {
+                    last_heard = Instant::now();
+                    match message? {
+                        None => return Ok(StationKnotStatus::Unreachable),
+                        Some(KnotMessage::Goodbye) => return Ok(StationKnotStatus::Sleeping),
+                        Some(KnotMessage::Heartbeat) => {
+                            connection.send(&HubMessage::Heartbeat).await?;
+                        }
+                        Some(KnotMessage::CommandResult { request_id, error }) => {
+                            if let Some(respond_to) = pending.remove(&request_id) {
+                                let _ = respond_to.send(match error {
+                                    Some(error) => Err(SyncError::CommandFailed(error)),
+                                    None => Ok(()),
+                                });
+                            }
+                        }
+                        Some(KnotMessage::Hello { .. }) => {
+                            return Err(SyncError::Protocol("Unexpected hello".to_string()));
+                        }
+                        Some(message) => self.report(knot, message).await,
+                    }
+                }
+                cmd = cmd_rx.recv() => match cmd {
+                    Some(SessionCmd::Command { command, respond_to }) => {
+                        next_request += 1;
+                        pending.insert(next_request, respond_to);
+                        connection
+                            .send(&HubMessage::Command { request_id: next_request, command })
+                            .await?;
+                    }
+                    Some(SessionCmd::Configure { settings }) => {
+                        connection.send(&HubMessage::Configure { settings }).await?;
+                    }
+                    // The knot connected again
+                    None => return Ok(StationKnotStatus::Awake),
+                },
+                _ = sleep_until(last_heard + self.unreachable_after) => {
+                    tracing::warn!(knot_id = %knot.id, "Knot silent for too long.");
+                    return Ok(StationKnotStatus::Unreachable);
+                }
+                _ = shutdown.cancelled() => return Ok(StationKnotStatus::Unreachable),
+            }
+        }
+    }
+
+    async fn report(&mut self, knot: &ConnectedKnot, message: KnotMessage) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(err) = recorder.record(&message).await {
+                tracing::error!(
+                    knot_id = %knot.id,
+                    error = format_args!("{err:#}"),
+                    "Failed to persist the report of the knot."
+                );
+            }
+        }
+
+        // No receiver is fine, nobody is listening yet
+        let _ = self.events.send(HubEvent::Reported {
+            knot_id: knot.id,
+            message,
+        });
+    }
+}
+
+async fn reject(connection: &mut Connection, reason: &str) {
+    let _ = connection
+        .send(&HubMessage::Rejected {
+            reason: reason.to_string(),
+        })
+        .await;
+}
@@ -0,0 +1,459 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::event::ActuatorEvent;
+use arksync_actuator::services::{actuator_record, refusal_details, ActuatorServiceHandle};
+use arksync_db::stores::{ActuatorStateRecord, MeasurementRecord, SensorRecord};
+use arksync_sensor::event::SensorEvent;
+use arksync_sensor::sensor::SensorName;
+use arksync_sensor::services::{kind_record, sensor_record, state_record, status_record};
+use chrono::Utc;
+use std::collections::BTreeMap;
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::{interval_at, sleep, sleep_until, Duration, Instant, MissedTickBehavior};
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::TlsConnector;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::auth::{PairingKey, Side};
+use crate::error::{Result, SyncError};
+use crate::protocol::{Connection, HubMessage, KnotCommand, KnotMessage, KnotSettings};
+use crate::services::outbox::Outbox;
+use crate::{tls, KnotConfig};
+
+/// Heartbeats of the hub missed before the connection is considered lost.
+const MISSED_HEARTBEATS: u32 = 3;
+
+/// Runs a station knot headless, reporting to its hub.
+///
+/// The knot streams the events of its sensor and actuator services to the
+/// hub and applies the relay commands it receives. While the hub can't be
+/// reached the reports are kept in a bounded outbox and the knot connects
+/// again with an exponential backoff, the outbox is flushed first.
+pub struct KnotAgent {
+    link: Link,
+    reconnect_delay: Duration,
+    max_reconnect_delay: Duration,
+    reports: Reports,
+    relays: Option<ActuatorServiceHandle>,
+    outbox: Outbox,
+}
+
+/// What the knot needs to reach its hub.
+struct Link {
+    address: String,
+    tls: Option<(TlsConnector, ServerName<'static>)>,
+    key: PairingKey,
+    name: String,
+    hardware_uid: String,
+}
+
+/// Sources of the reports of the knot.
+struct Reports {
+    sensors: Option<broadcast::Receiver<SensorEvent>>,
+    relays: Option<broadcast::Receiver<ActuatorEvent>>,
+    /// Last known row of each sensor, sent on every connection
+    known_sensors: BTreeMap<Uuid, SensorRecord>,
+}
+
+impl KnotAgent {
+    /// The hardware uid of the Pi is used unless `config.hardware_uid` is
+    /// set.
+    pub fn new(config: &KnotConfig, key: PairingKey) -> Result<Self> {
+        let hardware_uid = match &config.hardware_uid {
+            Some(hardware_uid) => hardware_uid.clone(),
+            None => {
+                arksync_db::local_hardware_uid().map_err(|err| SyncError::Io(format!("{err:#}")))?
+            }
+        };
+        let tls = config
+            .tls
+            .as_ref()
+            .map(|trust| {
+                let server_name = ServerName::try_from(trust.server_name.clone())
+                    .map_err(|err| SyncError::Tls(err.to_string()))?;
+                Ok::<_, SyncError>((tls::connector(trust)?, server_name))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            link: Link {
+                address: config.hub_address.clone(),
+                tls,
+                key,
+                name: config.name.clone(),
+                hardware_uid,
+            },
+            reconnect_delay: config.reconnect_delay,
+            max_reconnect_delay: config.max_reconnect_delay,
+            reports: Reports {
+                sensors: None,
+                relays: None,
+                known_sensors: BTreeMap::new(),
+            },
+            relays: None,
+            outbox: Outbox::new(config.buffer_capacity),
+        })
+    }
+
+    /// Report the sensors and measurements of `events`, subscribed before
+    /// the sensor service runs.
+    pub fn with_sensors(mut self, events: broadcast::Receiver<SensorEvent>) -> Self {
+        self.reports.sensors = Some(events);
+        self
+    }
+
+    /// Report the relays and apply the commands of the hub to them.
+    pub fn with_relays(mut self, relays: ActuatorServiceHandle) -> Self {
+        self.reports.relays = Some(relays.subscribe());
+        self.relays = Some(relays);
+        self
+    }
+
+    /// Report to the hub until `shutdown` is cancelled, the hub is then
+    /// told the knot goes to sleep.
+    pub async fn run(mut self, shutdown: CancellationToken) {
+        tracing::info!(
+            hub = self.link.address,
+            hardware_uid = self.link.hardware_uid,
+            tls = self.link.tls.is_some(),
+            "Knot agent started."
+        );
+        let mut delay = self.reconnect_delay;
+
+        loop {
+            let joined = {
+                let connecting = self.link.connect();
+                tokio::pin!(connecting);
+                loop {
+                    tokio::select! {
+                        joined = &mut connecting => break Some(joined),
+                        Some(message) = self.reports.next() => self.outbox.push(message),
+                        _ = shutdown.cancelled() => break None,
+                    }
+                }
+            };
+
+            match joined {
+                Some(Ok((mut connection, knot_id, settings))) => {
+                    tracing::info!(%knot_id, "Connected to the hub.");
+                    delay = self.reconnect_delay;
+                    match self.serve(&mut connection, settings, &shutdown).await {
+                        Ok(()) => break,
+                        Err(error) => tracing::warn!(%error, "Connection to the hub lost."),
+                    }
+                }
+                Some(Err(error)) => {
+                    tracing::warn!(%error, retry_in = ?delay, "Failed to connect to the hub.");
+                }
+                None => break,
+            }
+
+            let waiting = sleep(delay);
+            tokio::pin!(waiting);
+            loop {
+                tokio::select! {
+                    _ = &mut waiting => break,
+                    Some(message) = self.reports.next() => self.outbox.push(message),
+                    _ = shutdown.cancelled() => {
+                        tracing::info!(buffered = self.outbox.len(), "Knot agent stopped.");
+                        return;
+                    }
+                }
+            }
+            delay = (delay * 2).min(self.max_reconnect_delay);
+        }
+
+        tracing::info!(buffered = self.outbox.len(), "Knot agent stopped.");
+    }
+
+    /// Report to the hub until `shutdown` is cancelled, or the connection
+    /// is lost.
+    async fn serve(
+        &mut self,
+        connection: &mut Connection,
+        mut settings: KnotSettings,
+        shutdown: &CancellationToken,
+    ) -> Result<()> {
+        connection.send(&self.snapshot().await).await?;
+        self.flush(connection).await?;
+
+        let mut heartbeat = heartbeat_timer(settings);
+        let mut last_heard = Instant::now();
+
+        loop {
+            tokio::select! {
+                message = connection.recv::<HubMessage>() => {
+                    last_heard = Instant::now();
+                    match message? {
+                        None => return Err(SyncError::Io("Closed by the hub".to_string())),
+                        Some(HubMessage::Heartbeat) => {}
+                        Some(HubMessage::Configure { settings: configured }) => {
+                            tracing::info!(?configured, "Knot settings changed by the hub.");
+                            settings = configured;
+                            heartbeat = heartbeat_timer(settings);
+                        }
+                        Some(HubMessage::Command { request_id, command }) => {
+                            let error = self.apply(command).await.err().map(|err| err.to_string());
+                            connection
+                                .send(&KnotMessage::CommandResult { request_id, error })
+                                .await?;
+                        }
+                        Some(HubMessage::Rejected { reason }) => {
+                            return Err(SyncError::Rejected(reason));
+                        }
+                        Some(HubMessage::Challenge { .. } | HubMessage::Welcome { .. }) => {
+                            return Err(SyncError::Protocol("Unexpected handshake".to_string()));
+                        }
+                    }
+                }
+                Some(message) = self.reports.next() => {
+                    self.outbox.push(message);
+                    self.flush(connection).await?;
+                }
+                _ = heartbeat.tick() => connection.send(&KnotMessage::Heartbeat).await?,
+                _ = sleep_until(last_heard + settings.heartbeat_interval * MISSED_HEARTBEATS) => {
+                    return Err(SyncError::Timeout);
+                }
+                _ = shutdown.cancelled() => {
+                    let _ = connection.send(&KnotMessage::Goodbye).await;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Send the buffered reports, those not sent are kept.
+    async fn flush(&mut self, connection: &mut Connection) -> Result<()> {
+        let dropped = self.outbox.take_dropped();
+        if dropped > 0 {
+            tracing::warn!(dropped, "Reports lost while the hub was unreachable.");
+        }
+
+        while let Some(message) = self.outbox.front() {
+            connection.send(message).await?;
+            self.outbox.pop_front();
+        }
+
+        Ok(())
+    }
+
+    /// The sensors and relays of the knot, with the nil knot id.
+    async fn snapshot(&self) -> KnotMessage {
+        let actuators = match &self.relays {
+            Some(relays) => relays
+                .states()
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(spec, state)| {
+                    let mut record = actuator_record(spec, Uuid::nil());
+                    record.state.active = state.active;
+                    record
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        KnotMessage::Snapshot {
+            sensors: self.reports.known_sensors.values().cloned().collect(),
+            actuators,
+        }
+    }
+
+    async fn apply(&self, command: KnotCommand) -> Result<()> {
+        let relays = self
+            .relays
+            .as_ref()
+            .ok_or_else(|| SyncError::CommandFailed("The knot has no relays".to_string()))?;
+        let result = match command {
+            KnotCommand::SwitchRelay { relay_id, on } => relays.switch(&relay_id, on).await,
+            KnotCommand::PulseRelay { relay_id, duration } => {
+                relays.pulse(&relay_id, duration).await
+            }
+        };
+
+        result
+            .map(|_| ())
+            .map_err(|err| SyncError::CommandFailed(err.to_string()))
+    }
+}
+
+impl Link {
+    /// Connect and authenticate both sides, the id and settings given by the
+    /// hub.
+    async fn connect(&self) -> Result<(Connection, Uuid, KnotSettings)> {
+        let stream = TcpStream::connect(&self.address).await?;
+        let mut connection = match &self.tls {
+            Some((connector, server_name)) => {
+                Connection::new(connector.connect(server_name.clone(), stream).await?)
+            }
+            None => Connection::new(stream),
+        };
+
+        let Some(HubMessage::Challenge { nonce: hub_nonce }) = connection.recv().await? else {
+            return Err(SyncError::Protocol("Expected a challenge".to_string()));
+        };
+        let nonce = PairingKey::nonce();
+        connection
+            .send(&KnotMessage::Hello {
+                hardware_uid: self.hardware_uid.clone(),
+                name: self.name.clone(),
+                nonce: nonce.clone(),
+                proof: self.key.knot_proof(&hub_nonce, &self.hardware_uid),
+            })
+            .await?;
+
+        match connection.recv().await? {
+            Some(HubMessage::Welcome {
+                knot_id,
+                proof,
+                settings,
+            }) => {
+                if !self.key.verify_hub(&nonce, knot_id, &proof) {
+                    return Err(SyncError::Unauthorized);
+                }
+                connection.seal(
+                    self.key.session_key(&hub_nonce, &nonce, knot_id),
+                    Side::Knot,
+                );
+                Ok((connection, knot_id, settings))
+            }
+            Some(HubMessage::Rejected { reason }) => Err(SyncError::Rejected(reason)),
+            _ => Err(SyncError::Protocol("Expected a welcome".to_string())),
+        }
+    }
+}
+
+impl Reports {
+    /// Next report, `None` once every source is closed.
+    async fn next(&mut self) -> Option<KnotMessage> {
+        loop {
+            if self.sensors.is_none() && self.relays.is_none() {
+                return None;
+            }
+
+            let message = tokio::select! {
+                event = recv(&mut self.sensors) => match event {
+                    Ok(event) => self.sensor_report(event),
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Knot agent lagging behind, sensor events lost.");
+                        None
+                    }
+                    Err(RecvError::Closed) => {
+                        self.sensors = None;
+                        None
+                    }
+                },
+                event = recv(&mut self.relays) => match event {
+                    Ok(event) => Some(relay_report(event)),
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Knot agent lagging behind, actuator events lost.");
+                        None
+                    }
+                    Err(RecvError::Closed) => {
+                        self.relays = None;
+                        None
+                    }
+                },
+            };
+
+            if message.is_some() {
+                return message;
+            }
+        }
+    }
+
+    fn sensor_report(&mut self, event: SensorEvent) -> Option<KnotMessage> {
+        match event {
+            SensorEvent::Discovered(info) => {
+                let sensor = sensor_record(&info, Uuid::nil());
+                self.known_sensors.insert(info.id, sensor.clone());
+                Some(KnotMessage::SensorDiscovered { sensor })
+            }
+            SensorEvent::StateChanged { previous, info } => {
+                let state = state_record(&info);
+                self.known_sensors
+                    .insert(info.id, sensor_record(&info, Uuid::nil()));
+                Some(KnotMessage::SensorTransition {
+                    sensor_id: info.id,
+                    previous: Some(status_record(previous)),
+                    state,
+                })
+            }
+            SensorEvent::Removed(info) => {
+                let previous = self
+                    .known_sensors
+                    .insert(info.id, sensor_record(&info, Uuid::nil()))
+                    .map(|sensor| sensor.state.status);
+                Some(KnotMessage::SensorTransition {
+                    sensor_id: info.id,
+                    previous,
+                    state: state_record(&info),
+                })
+            }
+            SensorEvent::Renamed(info) => {
+                self.known_sensors
+                    .insert(info.id, sensor_record(&info, Uuid::nil()));
+                match info.name {
+                    SensorName::Named(name) => Some(KnotMessage::SensorRenamed {
+                        sensor_id: info.id,
+                        name,
+                        hardware_uid: info.hardware_uid,
+                    }),
+                    SensorName::Unnamed => None,
+                }
+            }
+            SensorEvent::Measurement(measurement) => Some(KnotMessage::Measurements {
+                measurements: vec![MeasurementRecord {
+                    sensor_id: measurement.sensor_id,
+                    kind: kind_record(measurement.kind),
+                    time: measurement.time,
+                    value: measurement.value,
+                }],
+            }),
+            SensorEvent::ReadCompleted(_) => None,
+        }
+    }
+}
+
+fn relay_report(event: ActuatorEvent) -> KnotMessage {
+    match event {
+        ActuatorEvent::StateChanged { state, reason } => KnotMessage::RelaySwitched {
+            relay_id: state.id.to_string(),
+            state: ActuatorStateRecord {
+                active: state.active,
+                state_since: Utc::now(),
+            },
+            reason: reason.code().to_string(),
+        },
+        ActuatorEvent::Refused { relay_id, reason } => KnotMessage::RelayRefused {
+            relay_id: relay_id.to_string(),
+            reason: reason.code().to_string(),
+            details: refusal_details(&reason),
+            occurred_at: Utc::now(),
+        },
+    }
+}
+
+/// Next event of `events`, never when there is none.
+async fn recv<E: Clone>(
+    events: &mut Option<broadcast::Receiver<E>>,
+) -> std::result::Result<E, RecvError> {
+    match events {
+        Some(events) => events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn heartbeat_timer(settings: KnotSettings) -> tokio::time::Interval {
+    let mut heartbeat = interval_at(
+        Instant::now() + settings.heartbeat_interval,
+        settings.heartbeat_interval,
+    );
+    heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    heartbeat
+}
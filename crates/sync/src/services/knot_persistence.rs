@@ -0,0 +1,295 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::{
+    ActuatorEventRecord, ActuatorRecord, MeasurementRecord, SensorEventRecord, SensorRecord,
+    StationKnotRecord, StationKnotStatus,
+};
+use arksync_db::{ActuatorStore, MeasurementStore, SensorStore, StationStore};
+use eyre::eyre;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::protocol::KnotMessage;
+
+/// Writes the remote knots of a hub and what they report to Postgres.
+///
+/// Hardware uids of the knots are prefixed with the one of the knot, e.g.
+/// `rpi:1000000012345678/i2c:1:0x63`, the same probe address may be used on
+/// several knots.
+#[derive(Clone, Copy)]
+pub struct KnotPersistence {
+    station_hub_id: Uuid,
+    stations: StationStore<'static>,
+    sensors: SensorStore<'static>,
+    actuators: ActuatorStore<'static>,
+    measurements: MeasurementStore<'static>,
+}
+
+impl KnotPersistence {
+    pub fn new(pool: &'static PgPool, station_hub_id: Uuid) -> Self {
+        Self {
+            station_hub_id,
+            stations: StationStore::new(pool),
+            sensors: SensorStore::new(pool),
+            actuators: ActuatorStore::new(pool),
+            measurements: MeasurementStore::new(pool),
+        }
+    }
+
+    /// Knots are unreachable until they connect to the hub again.
+    pub(crate) async fn reset(&self) -> eyre::Result<()> {
+        self.stations
+            .mark_remote_knots_unreachable(self.station_hub_id)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The knot, marked awake, `None` when the hardware uid is the one of a
+    /// hub.
+    pub(crate) async fn register(
+        &self,
+        name: &str,
+        hardware_uid: &str,
+    ) -> eyre::Result<Option<StationKnotRecord>> {
+        self.stations
+            .register_remote_knot(self.station_hub_id, name, hardware_uid)
+            .await
+    }
+
+    pub(crate) async fn set_status(
+        &self,
+        knot_id: Uuid,
+        status: StationKnotStatus,
+    ) -> eyre::Result<()> {
+        self.stations.set_knot_status(knot_id, status).await
+    }
+
+    pub(crate) fn recorder(&self, knot_id: Uuid, knot_hardware_uid: &str) -> KnotRecorder {
+        KnotRecorder {
+            persistence: *self,
+            knot_id,
+            knot_hardware_uid: knot_hardware_uid.to_string(),
+            sensor_ids: HashMap::new(),
+            relay_ids: HashMap::new(),
+        }
+    }
+}
+
+/// Stores the reports of one connected knot.
+pub(crate) struct KnotRecorder {
+    persistence: KnotPersistence,
+    knot_id: Uuid,
+    knot_hardware_uid: String,
+    /// Row id of the sensors the knot reported, by their id on the knot
+    sensor_ids: HashMap<Uuid, Uuid>,
+    /// Row id of the relays by their id on the knot
+    relay_ids: HashMap<String, Uuid>,
+}
+
+impl KnotRecorder {
+    pub(crate) async fn record(&mut self, message: &KnotMessage) -> eyre::Result<()> {
+        let persistence = self.persistence;
+
+        match message {
+            KnotMessage::Snapshot { sensors, actuators } => {
+                for sensor in sensors {
+                    self.adopt_sensor(sensor).await?;
+                }
+                for actuator in actuators {
+                    self.adopt_actuator(actuator).await?;
+                }
+            }
+            KnotMessage::SensorDiscovered { sensor } => {
+                let record = self.adopt_sensor(sensor).await?;
+                persistence
+                    .sensors
+                    .append_event(&SensorEventRecord::new(record.id, None, &record.state))
+                    .await?;
+            }
+            KnotMessage::SensorTransition {
+                sensor_id,
+                previous,
+                state,
+            } => {
+                persistence
+                    .sensors
+                    .record_transition(self.sensor_id(*sensor_id)?, *previous, state)
+                    .await?;
+            }
+            KnotMessage::SensorRenamed {
+                sensor_id,
+                name,
+                hardware_uid,
+            } => {
+                persistence
+                    .sensors
+                    .rename(
+                        self.sensor_id(*sensor_id)?,
+                        name,
+                        &self.hardware_uid(hardware_uid),
+                    )
+                    .await?;
+            }
+            KnotMessage::Measurements { measurements } => {
+                let mapped: Vec<_> = measurements
+                    .iter()
+                    .filter_map(|measurement| {
+                        let sensor_id = self.sensor_ids.get(&measurement.sensor_id)?;
+                        Some(MeasurementRecord {
+                            sensor_id: *sensor_id,
+                            ..measurement.clone()
+                        })
+                    })
+                    .collect();
+                if mapped.len() < measurements.len() {
+                    tracing::warn!(
+                        knot_id = %self.knot_id,
+                        dropped = measurements.len() - mapped.len(),
+                        "Measurements of sensors the knot never reported dropped."
+                    );
+                }
+                persistence.measurements.insert_batch(&mapped).await?;
+            }
+            KnotMessage::RelaySwitched {
+                relay_id,
+                state,
+                reason,
+            } => {
+                if let Some(id) = self.relay_ids.get(relay_id) {
+                    persistence
+                        .actuators
+                        .record_state(*id, state, reason)
+                        .await?;
+                }
+            }
+            KnotMessage::RelayRefused {
+                relay_id,
+                reason,
+                details,
+                occurred_at,
+            } => {
+                if let Some(id) = self.relay_ids.get(relay_id) {
+                    let event = ActuatorEventRecord {
+                        actuator_id: *id,
+                        // Only switching on is ever refused
+                        active: true,
+                        refused: true,
+                        reason: reason.clone(),
+                        details: details.clone(),
+                        occurred_at: *occurred_at,
+                    };
+                    persistence.actuators.record_refusal(&event).await?;
+                }
+            }
+            KnotMessage::Hello { .. }
+            | KnotMessage::Heartbeat
+            | KnotMessage::CommandResult { .. }
+            | KnotMessage::Goodbye => {}
+        }
+
+        Ok(())
+    }
+
+    /// Row id of a sensor the knot reported during this session, its ids
+    /// never reach the rows of other knots.
+    fn sensor_id(&self, knot_sensor_id: Uuid) -> eyre::Result<Uuid> {
+        self.sensor_ids
+            .get(&knot_sensor_id)
+            .copied()
+            .ok_or_else(|| eyre!("Sensor {knot_sensor_id} was never reported by the knot"))
+    }
+
+    fn hardware_uid(&self, hardware_uid: &str) -> String {
+        format!("{}/{hardware_uid}", self.knot_hardware_uid)
+    }
+
+    /// Upsert a sensor of the knot, under the id of its row when the probe
+    /// is already known, under a new id otherwise.
+    async fn adopt_sensor(&mut self, sensor: &SensorRecord) -> eyre::Result<SensorRecord> {
+        let mut record = sensor.clone();
+        record.id = Uuid::new_v4();
+        record.station_knot_id = self.knot_id;
+        record.hardware_uid = sensor
+            .hardware_uid
+            .as_deref()
+            .map(|hardware_uid| self.hardware_uid(hardware_uid));
+
+        if let Some(hardware_uid) = &record.hardware_uid {
+            let known = self
+                .persistence
+                .sensors
+                .find_by_hardware_uids(std::slice::from_ref(hardware_uid))
+                .await?;
+            if let Some(known) = known.first() {
+                record.id = known.id;
+            }
+        }
+
+        self.persistence.sensors.upsert(&record).await?;
+        self.sensor_ids.insert(sensor.id, record.id);
+
+        Ok(record)
+    }
+
+    async fn adopt_actuator(&mut self, actuator: &ActuatorRecord) -> eyre::Result<()> {
+        let mut record = actuator.clone();
+        record.station_knot_id = self.knot_id;
+        record.hardware_uid = actuator
+            .hardware_uid
+            .as_deref()
+            .map(|hardware_uid| self.hardware_uid(hardware_uid));
+
+        let id = self.persistence.actuators.upsert(&record).await?;
+        // Relays are named after their id
+        self.relay_ids.insert(actuator.name.clone(), id);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arksync_db::stores::SensorKind;
+    use chrono::Utc;
+
+    /// Never connected, the records refused never reach the database.
+    fn recorder() -> KnotRecorder {
+        let pool = Box::leak(Box::new(
+            PgPool::connect_lazy("postgres://arksync@localhost/arksync").unwrap(),
+        ));
+        KnotPersistence::new(pool, Uuid::new_v4()).recorder(Uuid::new_v4(), "test:knot")
+    }
+
+    #[tokio::test]
+    async fn sensors_never_reported_are_refused() {
+        let mut recorder = recorder();
+        let sensor_id = Uuid::new_v4();
+
+        let renamed = recorder
+            .record(&KnotMessage::SensorRenamed {
+                sensor_id,
+                name: "water".to_string(),
+                hardware_uid: "ezo:water".to_string(),
+            })
+            .await;
+        assert!(renamed.is_err());
+
+        // Nothing left to insert once the unknown sensor is dropped
+        let measured = recorder
+            .record(&KnotMessage::Measurements {
+                measurements: vec![MeasurementRecord {
+                    sensor_id,
+                    kind: SensorKind::Temperature,
+                    time: Utc::now(),
+                    value: 24.5,
+                }],
+            })
+            .await;
+        assert!(measured.is_ok());
+    }
+}
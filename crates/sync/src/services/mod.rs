@@ -0,0 +1,14 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+mod hub_handle;
+mod hub_service;
+mod knot_agent;
+mod knot_persistence;
+mod outbox;
+
+pub use hub_handle::HubServiceHandle;
+pub use hub_service::{ConnectedKnot, HubService};
+pub use knot_agent::KnotAgent;
+pub use knot_persistence::KnotPersistence;
@@ -0,0 +1,135 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+
+use crate::protocol::KnotMessage;
+
+/// Measurements sent in one message when they pile up.
+const MEASUREMENTS_BATCH: usize = 500;
+
+/// Messages of a knot waiting for its hub, the oldest are dropped once full.
+pub(crate) struct Outbox {
+    messages: VecDeque<KnotMessage>,
+    capacity: usize,
+    /// Dropped since the last flush
+    dropped: u64,
+}
+
+impl Outbox {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            messages: VecDeque::new(),
+            capacity: capacity.max(1),
+            dropped: 0,
+        }
+    }
+
+    /// Queue a message, measurements are appended to the last batch when
+    /// there is room.
+    pub(crate) fn push(&mut self, message: KnotMessage) {
+        if let (
+            KnotMessage::Measurements { measurements },
+            Some(KnotMessage::Measurements {
+                measurements: batch,
+            }),
+        ) = (&message, self.messages.back_mut())
+        {
+            if batch.len() + measurements.len() <= MEASUREMENTS_BATCH {
+                batch.extend(measurements.iter().cloned());
+                return;
+            }
+        }
+
+        if self.messages.len() == self.capacity {
+            if self.dropped == 0 {
+                tracing::warn!(
+                    capacity = self.capacity,
+                    "Outbox full, dropping the oldest messages."
+                );
+            }
+            self.messages.pop_front();
+            self.dropped += 1;
+        }
+        self.messages.push_back(message);
+    }
+
+    pub(crate) fn front(&self) -> Option<&KnotMessage> {
+        self.messages.front()
+    }
+
+    pub(crate) fn pop_front(&mut self) -> Option<KnotMessage> {
+        self.messages.pop_front()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Number of messages dropped since the last call.
+    pub(crate) fn take_dropped(&mut self) -> u64 {
+        std::mem::take(&mut self.dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arksync_db::stores::{MeasurementRecord, SensorKind};
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn measurements(count: usize) -> KnotMessage {
+        KnotMessage::Measurements {
+            measurements: (0..count)
+                .map(|value| MeasurementRecord {
+                    sensor_id: Uuid::nil(),
+                    kind: SensorKind::Temperature,
+                    time: Utc::now(),
+                    value: value as f64,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn measurements_are_batched() {
+        let mut outbox = Outbox::new(10);
+
+        outbox.push(measurements(1));
+        outbox.push(measurements(1));
+        outbox.push(KnotMessage::Heartbeat);
+        outbox.push(measurements(1));
+
+        assert_eq!(outbox.len(), 3);
+        assert!(matches!(
+            outbox.front(),
+            Some(KnotMessage::Measurements { measurements }) if measurements.len() == 2
+        ));
+    }
+
+    #[test]
+    fn full_batches_start_a_new_message() {
+        let mut outbox = Outbox::new(10);
+
+        outbox.push(measurements(MEASUREMENTS_BATCH));
+        outbox.push(measurements(1));
+
+        assert_eq!(outbox.len(), 2);
+    }
+
+    #[test]
+    fn the_oldest_messages_are_dropped_once_full() {
+        let mut outbox = Outbox::new(2);
+
+        outbox.push(KnotMessage::Heartbeat);
+        outbox.push(KnotMessage::Goodbye);
+        outbox.push(measurements(1));
+
+        assert_eq!(outbox.len(), 2);
+        assert_eq!(outbox.take_dropped(), 1);
+        assert_eq!(outbox.take_dropped(), 0);
+        assert_eq!(outbox.pop_front(), Some(KnotMessage::Goodbye));
+    }
+}
@@ -0,0 +1,69 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, ServerConfig};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+use crate::error::{Result, SyncError};
+use crate::{TlsIdentity, TlsTrust};
+
+/// TLS of a hub presenting `identity`.
+pub fn acceptor(identity: &TlsIdentity) -> Result<TlsAcceptor> {
+    let certs = certificates(&identity.cert_path)?;
+    let key = PrivateKeyDer::from_pem_slice(&read(&identity.key_path)?).map_err(|err| {
+        SyncError::Tls(format!(
+            "No private key in {}: {err}",
+            identity.key_path.display()
+        ))
+    })?;
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|err| SyncError::Tls(err.to_string()))?;
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// TLS of a knot, trusting the hub certificates signed by `trust`.
+///
+/// Hubs are reached on the local network, usually with a private CA, so the
+/// platform roots are not trusted.
+pub fn connector(trust: &TlsTrust) -> Result<TlsConnector> {
+    let mut roots = RootCertStore::empty();
+    for cert in certificates(&trust.ca_path)? {
+        roots
+            .add(cert)
+            .map_err(|err| SyncError::Tls(err.to_string()))?;
+    }
+    let config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+fn certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_slice_iter(&read(path)?)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|err| {
+            SyncError::Tls(format!("Invalid certificate in {}: {err}", path.display()))
+        })?;
+    if certs.is_empty() {
+        return Err(SyncError::Tls(format!(
+            "No certificate in {}",
+            path.display()
+        )));
+    }
+
+    Ok(certs)
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|err| SyncError::Tls(format!("Can't read {}: {err}", path.display())))
+}
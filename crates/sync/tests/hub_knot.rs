@@ -0,0 +1,421 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::gpio::{GpioBackend, SimulatedGpio};
+use arksync_actuator::relay::{RelayBank, MIST_BOARD, MIST_RELAY};
+use arksync_actuator::services::ActuatorService;
+use arksync_db::stores::StationKnotStatus;
+use arksync_sensor::event::{Measurement, SensorEvent};
+use arksync_sensor::i2c_bus::I2cConnection;
+use arksync_sensor::sensor::{
+    SensorConnection, SensorInfo, SensorKind, SensorName, SensorState, SensorStateReason,
+};
+use arksync_sync::auth::PairingKey;
+use arksync_sync::error::SyncError;
+use arksync_sync::event::HubEvent;
+use arksync_sync::protocol::{Connection, HubMessage, KnotCommand, KnotMessage, KnotSettings};
+use arksync_sync::services::{HubService, HubServiceHandle, KnotAgent};
+use arksync_sync::{HubConfig, KnotConfig, TlsIdentity, TlsTrust};
+use chrono::Utc;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::time::{sleep, timeout, Duration};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+const KEY: &str = "correct horse battery staple";
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn hub_config(listen: SocketAddr, tls: Option<TlsIdentity>) -> HubConfig {
+    HubConfig {
+        enabled: true,
+        listen,
+        tls,
+        unreachable_after: Duration::from_millis(500),
+        settings: KnotSettings {
+            heartbeat_interval: Duration::from_millis(100),
+        },
+    }
+}
+
+fn knot_config(hub: SocketAddr, tls: Option<TlsTrust>) -> KnotConfig {
+    KnotConfig {
+        hub_address: hub.to_string(),
+        tls,
+        name: "Greenhouse".to_string(),
+        hardware_uid: Some("test:knot".to_string()),
+        buffer_capacity: 100,
+        reconnect_delay: Duration::from_millis(50),
+        max_reconnect_delay: Duration::from_millis(200),
+    }
+}
+
+async fn start_hub(
+    config: &HubConfig,
+    shutdown: &CancellationToken,
+) -> (HubServiceHandle, SocketAddr) {
+    let hub = HubService::bind(config, PairingKey::new(KEY).unwrap())
+        .await
+        .unwrap();
+    let handle = hub.handle();
+    let address = hub.local_addr().unwrap();
+    tokio::spawn(hub.run(shutdown.clone()));
+
+    (handle, address)
+}
+
+fn water_sensor() -> SensorInfo {
+    SensorInfo {
+        id: Uuid::new_v4(),
+        hardware_uid: "i2c:1:0x66".to_string(),
+        kind: SensorKind::Temperature,
+        firmware: 2.12,
+        name: SensorName::Named("water".to_string()),
+        state: SensorState::Active,
+        state_reason: SensorStateReason::MeasurementOk,
+        state_since: Utc::now(),
+        last_activity: Utc::now(),
+        consecutive_failures: 0,
+        connection: SensorConnection::I2c(I2cConnection {
+            bus: 1,
+            address: 0x66,
+        }),
+    }
+}
+
+fn measurement(sensor_id: Uuid, value: f64) -> SensorEvent {
+    SensorEvent::Measurement(Measurement {
+        sensor_id,
+        kind: SensorKind::Temperature,
+        value,
+        time: Utc::now(),
+    })
+}
+
+/// First event matching `found`, or fail after a few seconds.
+async fn next_event<T>(
+    events: &mut broadcast::Receiver<HubEvent>,
+    mut found: impl FnMut(HubEvent) -> Option<T>,
+) -> T {
+    timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(found) = found(events.recv().await.unwrap()) {
+                return found;
+            }
+        }
+    })
+    .await
+    .expect("no matching hub event")
+}
+
+async fn status_change(events: &mut broadcast::Receiver<HubEvent>) -> (Uuid, StationKnotStatus) {
+    next_event(events, |event| match event {
+        HubEvent::KnotStatusChanged {
+            knot_id, status, ..
+        } => Some((knot_id, status)),
+        HubEvent::Reported { .. } => None,
+    })
+    .await
+}
+
+async fn measured(events: &mut broadcast::Receiver<HubEvent>) -> Vec<f64> {
+    next_event(events, |event| match event {
+        HubEvent::Reported {
+            message: KnotMessage::Measurements { measurements },
+            ..
+        } => Some(measurements.iter().map(|m| m.value).collect()),
+        _ => None,
+    })
+    .await
+}
+
+/// Go through the handshake of a knot, the connection left unsealed.
+async fn join(hub: SocketAddr, hardware_uid: &str, name: &str) -> (Connection, Uuid) {
+    let key = PairingKey::new(KEY).unwrap();
+    let mut knot = Connection::new(TcpStream::connect(hub).await.unwrap());
+    let Some(HubMessage::Challenge { nonce }) = knot.recv().await.unwrap() else {
+        panic!("expected a challenge");
+    };
+    let knot_nonce = PairingKey::nonce();
+    knot.send(&KnotMessage::Hello {
+        hardware_uid: hardware_uid.to_string(),
+        name: name.to_string(),
+        nonce: knot_nonce.clone(),
+        proof: key.knot_proof(&nonce, hardware_uid),
+    })
+    .await
+    .unwrap();
+    let Some(HubMessage::Welcome { knot_id, proof, .. }) = knot.recv().await.unwrap() else {
+        panic!("expected a welcome");
+    };
+    assert!(key.verify_hub(&knot_nonce, knot_id, &proof));
+
+    (knot, knot_id)
+}
+
+#[tokio::test]
+async fn a_knot_reports_to_its_hub_and_applies_its_commands() {
+    let shutdown = CancellationToken::new();
+    let (hub, address) = start_hub(
+        &hub_config(SocketAddr::from(([127, 0, 0, 1], 0)), None),
+        &shutdown,
+    )
+    .await;
+    let mut events = hub.subscribe();
+
+    let relays = RelayBank::open(
+        &[MIST_BOARD],
+        &GpioBackend::Simulated(SimulatedGpio::default()),
+    )
+    .unwrap();
+    let actuators = ActuatorService::new(relays);
+    let relays = actuators.handle();
+    tokio::spawn(actuators.run(shutdown.clone()));
+
+    let (sensors, sensor_events) = broadcast::channel(16);
+    let sensor = water_sensor();
+    sensors
+        .send(SensorEvent::Discovered(sensor.clone()))
+        .unwrap();
+
+    let knot_shutdown = CancellationToken::new();
+    let knot = KnotAgent::new(&knot_config(address, None), PairingKey::new(KEY).unwrap())
+        .unwrap()
+        .with_sensors(sensor_events)
+        .with_relays(relays);
+    let knot = tokio::spawn(knot.run(knot_shutdown.clone()));
+
+    let (knot_id, status) = status_change(&mut events).await;
+    assert_eq!(status, StationKnotStatus::Awake);
+    let actuators = next_event(&mut events, |event| match event {
+        HubEvent::Reported {
+            message: KnotMessage::Snapshot { actuators, .. },
+            ..
+        } => Some(actuators),
+        _ => None,
+    })
+    .await;
+    assert_eq!(actuators[0].name, MIST_RELAY.id);
+    let discovered = next_event(&mut events, |event| match event {
+        HubEvent::Reported {
+            message: KnotMessage::SensorDiscovered { sensor },
+            ..
+        } => Some(sensor),
+        _ => None,
+    })
+    .await;
+    assert_eq!(discovered.id, sensor.id);
+    assert_eq!(discovered.hardware_uid.as_deref(), Some("i2c:1:0x66"));
+    assert_eq!(hub.knots().await.unwrap()[0].name, "Greenhouse");
+
+    sensors.send(measurement(sensor.id, 24.5)).unwrap();
+    assert_eq!(measured(&mut events).await, vec![24.5]);
+
+    hub.command(
+        knot_id,
+        KnotCommand::SwitchRelay {
+            relay_id: MIST_RELAY.id.to_string(),
+            on: true,
+        },
+    )
+    .await
+    .unwrap();
+    let switched = next_event(&mut events, |event| match event {
+        HubEvent::Reported {
+            message: KnotMessage::RelaySwitched {
+                relay_id, state, ..
+            },
+            ..
+        } => Some((relay_id, state.active)),
+        _ => None,
+    })
+    .await;
+    assert_eq!(switched, (MIST_RELAY.id.to_string(), true));
+    assert!(matches!(
+        hub.command(
+            knot_id,
+            KnotCommand::SwitchRelay {
+                relay_id: "unknown".to_string(),
+                on: true,
+            },
+        )
+        .await,
+        Err(SyncError::CommandFailed(_))
+    ));
+
+    knot_shutdown.cancel();
+    knot.await.unwrap();
+    assert_eq!(
+        status_change(&mut events).await,
+        (knot_id, StationKnotStatus::Sleeping)
+    );
+    assert_eq!(
+        hub.command(
+            knot_id,
+            KnotCommand::SwitchRelay {
+                relay_id: MIST_RELAY.id.to_string(),
+                on: false,
+            },
+        )
+        .await,
+        Err(SyncError::KnotNotConnected(knot_id))
+    );
+
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn a_knot_without_the_pairing_key_is_refused() {
+    let shutdown = CancellationToken::new();
+    let (hub, address) = start_hub(
+        &hub_config(SocketAddr::from(([127, 0, 0, 1], 0)), None),
+        &shutdown,
+    )
+    .await;
+
+    let key = PairingKey::new("not the key of this station").unwrap();
+    let knot = KnotAgent::new(&knot_config(address, None), key).unwrap();
+    tokio::spawn(knot.run(shutdown.clone()));
+    sleep(Duration::from_millis(300)).await;
+
+    assert_eq!(hub.knots().await, Some(Vec::new()));
+
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn reports_are_buffered_until_the_hub_is_reachable() {
+    // Reserve a port for the hub, started later
+    let address = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let shutdown = CancellationToken::new();
+
+    let (sensors, sensor_events) = broadcast::channel(16);
+    let sensor = water_sensor();
+    let knot = KnotAgent::new(&knot_config(address, None), PairingKey::new(KEY).unwrap())
+        .unwrap()
+        .with_sensors(sensor_events);
+    tokio::spawn(knot.run(shutdown.clone()));
+
+    sensors
+        .send(SensorEvent::Discovered(sensor.clone()))
+        .unwrap();
+    sensors.send(measurement(sensor.id, 24.5)).unwrap();
+    sensors.send(measurement(sensor.id, 24.7)).unwrap();
+    sleep(Duration::from_millis(200)).await;
+
+    let (hub, _) = start_hub(&hub_config(address, None), &shutdown).await;
+    let mut events = hub.subscribe();
+
+    assert_eq!(measured(&mut events).await, vec![24.5, 24.7]);
+
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn a_silent_knot_becomes_unreachable() {
+    let shutdown = CancellationToken::new();
+    let (hub, address) = start_hub(
+        &hub_config(SocketAddr::from(([127, 0, 0, 1], 0)), None),
+        &shutdown,
+    )
+    .await;
+    let mut events = hub.subscribe();
+
+    // A knot that joins, then never sends its heartbeats
+    let (_knot, knot_id) = join(address, "test:silent", "Silent").await;
+
+    assert_eq!(
+        status_change(&mut events).await,
+        (knot_id, StationKnotStatus::Awake)
+    );
+    assert_eq!(
+        status_change(&mut events).await,
+        (knot_id, StationKnotStatus::Unreachable)
+    );
+
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn unsigned_messages_end_the_connection() {
+    let shutdown = CancellationToken::new();
+    let (hub, address) = start_hub(
+        &hub_config(SocketAddr::from(([127, 0, 0, 1], 0)), None),
+        &shutdown,
+    )
+    .await;
+    let mut events = hub.subscribe();
+
+    // Sent as if injected after the handshake, the connection isn't sealed
+    let (mut knot, knot_id) = join(address, "test:forged", "Forged").await;
+    knot.send(&KnotMessage::Measurements {
+        measurements: Vec::new(),
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(
+        next_event(&mut events, Some).await,
+        HubEvent::KnotStatusChanged {
+            knot_id,
+            name: "Forged".to_string(),
+            status: StationKnotStatus::Awake,
+        }
+    );
+    // Lost right away, before it could be silent for too long
+    assert_eq!(
+        timeout(Duration::from_millis(300), events.recv())
+            .await
+            .unwrap()
+            .unwrap(),
+        HubEvent::KnotStatusChanged {
+            knot_id,
+            name: "Forged".to_string(),
+            status: StationKnotStatus::Unreachable,
+        }
+    );
+
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn knots_connect_over_tls() {
+    let shutdown = CancellationToken::new();
+    let identity = TlsIdentity {
+        cert_path: fixture("hub.pem"),
+        key_path: fixture("hub.key"),
+    };
+    let (hub, address) = start_hub(
+        &hub_config(SocketAddr::from(([127, 0, 0, 1], 0)), Some(identity)),
+        &shutdown,
+    )
+    .await;
+    let mut events = hub.subscribe();
+
+    let trust = TlsTrust {
+        ca_path: fixture("ca.pem"),
+        server_name: "localhost".to_string(),
+    };
+    let knot = KnotAgent::new(
+        &knot_config(address, Some(trust)),
+        PairingKey::new(KEY).unwrap(),
+    )
+    .unwrap();
+    tokio::spawn(knot.run(shutdown.clone()));
+
+    assert_eq!(status_change(&mut events).await.1, StationKnotStatus::Awake);
+
+    shutdown.cancel();
+}
@@ -0,0 +1,100 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_config::ConfigHandler;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+pub static CONFIG: LazyLock<Config> = LazyLock::new(|| ConfigHandler::new(mpl).load());
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub queue: QueueConfig,
+    pub influx: InfluxConfig,
+    pub mqtt: MqttConfig,
+    pub metrics: MetricsConfig,
+}
+
+#[derive(Clone, Debug)]
+pub struct QueueConfig {
+    /// Where segments and consumer cursors are stored
+    pub dir: PathBuf,
+    /// Size of the queue past which the oldest segments are dropped
+    pub max_bytes: u64,
+    /// Age past which a segment is dropped, consumed or not
+    pub max_age: Duration,
+    /// Size at which the active segment is closed and a new one started
+    pub segment_max_bytes: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct InfluxConfig {
+    /// `sk knot` and `sk hub` export the sensor events to InfluxDB
+    pub enabled: bool,
+    /// Base url of the InfluxDB 3 server
+    pub url: String,
+    pub database: String,
+    /// Token sent as a bearer, optional when the server runs without auth
+    pub token: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MqttConfig {
+    /// `sk knot` and `sk hub` bridge their sensors and relays to the broker
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// First level of every topic
+    pub topic_prefix: String,
+    /// Second level of every topic, identifies this knot
+    pub knot: String,
+    /// Home Assistant discovery prefix, `None` disables discovery
+    pub discovery_prefix: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MetricsConfig {
+    /// `sk knot` and `sk hub` serve the metrics of their sensors, relays and
+    /// telemetry queue
+    pub enabled: bool,
+    /// Address of the `/metrics` endpoint
+    pub listen: SocketAddr,
+}
+
+fn mpl() -> Config {
+    Config {
+        queue: QueueConfig {
+            dir: PathBuf::from("/var/lib/arksync/telemetry"),
+            max_bytes: 256 * 1024 * 1024,
+            max_age: Duration::from_secs(7 * 24 * 3600),
+            segment_max_bytes: 8 * 1024 * 1024,
+        },
+        influx: InfluxConfig {
+            enabled: false,
+            url: "http://localhost:8181".to_string(),
+            database: "arksync_series".to_string(),
+            token: None,
+        },
+        mqtt: MqttConfig {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            client_id: "arksync-local".to_string(),
+            username: None,
+            password: None,
+            topic_prefix: "arksync".to_string(),
+            knot: "local".to_string(),
+            discovery_prefix: Some("homeassistant".to_string()),
+        },
+        metrics: MetricsConfig {
+            enabled: false,
+            listen: SocketAddr::from(([0, 0, 0, 0], 9184)),
+        },
+    }
+}
@@ -0,0 +1,272 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use reqwest::{Client, StatusCode};
+use std::fmt;
+use tokio::time::{sleep_until, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::config::InfluxConfig;
+use crate::influx::line_protocol::record_line;
+use crate::queue::QueueConsumer;
+
+/// Records written in one request, a batch is sent once full.
+const DEFAULT_BATCH_SIZE: usize = 1000;
+/// A partial batch is flushed at least this often.
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(300);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub enum WriteError {
+    /// The server refused the data itself, sending it again won't help.
+    Rejected { status: StatusCode, body: String },
+    /// The server is unreachable or failing, the batch can be retried.
+    Unavailable(String),
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::Rejected { status, body } => {
+                write!(f, "batch rejected with {status}: {body}")
+            }
+            WriteError::Unavailable(reason) => write!(f, "server unavailable: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+/// Exponential backoff between two write attempts after a failure.
+struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+    retry_at: Option<Instant>,
+}
+
+impl Backoff {
+    fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            current: initial,
+            retry_at: None,
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.retry_at
+            .is_none_or(|retry_at| Instant::now() >= retry_at)
+    }
+
+    fn failed(&mut self) {
+        self.retry_at = Some(Instant::now() + self.current);
+        self.current = (self.current * 2).min(self.max);
+    }
+
+    fn succeeded(&mut self) {
+        self.current = self.initial;
+        self.retry_at = None;
+    }
+}
+
+/// Exporter writing measurements and sensor states to InfluxDB 3 in line
+/// protocol.
+///
+/// Records come from the telemetry queue and are acknowledged once written,
+/// so whatever was recorded while the server was unreachable is sent when it
+/// answers again.
+pub struct InfluxExporter {
+    client: Client,
+    write_url: String,
+    database: String,
+    token: Option<String>,
+    backoff: Backoff,
+    batch_size: usize,
+    flush_interval: Duration,
+}
+
+impl InfluxExporter {
+    /// Name of the exporter cursor in the telemetry queue.
+    pub const CONSUMER: &'static str = "influx";
+
+    pub fn new(config: &InfluxConfig) -> eyre::Result<Self> {
+        let client = Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+
+        Ok(Self {
+            client,
+            write_url: format!("{}/api/v3/write_lp", config.url.trim_end_matches('/')),
+            database: config.database.clone(),
+            token: config.token.clone(),
+            backoff: Backoff::new(DEFAULT_INITIAL_BACKOFF, DEFAULT_MAX_BACKOFF),
+            batch_size: DEFAULT_BATCH_SIZE,
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+        })
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    /// Delay before the first retry, doubled on each failure up to `max`.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = Backoff::new(initial, max.max(initial));
+        self
+    }
+
+    pub async fn run(mut self, mut consumer: QueueConsumer, shutdown: CancellationToken) {
+        loop {
+            let retry_at = self.backoff.retry_at;
+            let ready = async {
+                match retry_at {
+                    Some(retry_at) => sleep_until(retry_at).await,
+                    None => {
+                        consumer.wait(self.batch_size, self.flush_interval).await;
+                    }
+                }
+            };
+
+            tokio::select! {
+                _ = ready => self.export(&mut consumer).await,
+                _ = shutdown.cancelled() => break,
+            }
+        }
+
+        // Anything left stays in the queue for the next run
+        tracing::info!("Influx exporter stopped.");
+    }
+
+    /// Write batches until the queue is drained or the server fails.
+    async fn export(&mut self, consumer: &mut QueueConsumer) {
+        while self.backoff.is_ready() {
+            let records = match consumer.read(self.batch_size).await {
+                Ok(records) => records,
+                Err(err) => {
+                    tracing::error!(error = %err, "Influx exporter failed to read the queue.");
+                    return;
+                }
+            };
+            let Some(last) = records.last().map(|record| record.offset) else {
+                return;
+            };
+
+            let lines: Vec<String> = records
+                .iter()
+                .filter_map(|record| record_line(&record.record))
+                .collect();
+
+            if !lines.is_empty() {
+                match self.write(&lines).await {
+                    Ok(0) => {}
+                    Ok(dropped) => tracing::warn!(
+                        dropped,
+                        written = lines.len() - dropped,
+                        "Influx rejected records, dropped."
+                    ),
+                    Err(_) => return,
+                }
+                self.backoff.succeeded();
+            }
+
+            if let Err(err) = consumer.ack(last).await {
+                tracing::error!(
+                    offset = last,
+                    error = %err,
+                    "Influx exporter failed to acknowledge records."
+                );
+                return;
+            }
+        }
+    }
+
+    /// Write `lines`, splitting a rejected batch in halves until only the
+    /// lines Influx refuses are left out. Returns how many were dropped.
+    ///
+    /// Halves written before the server failed are sent again on the retry,
+    /// Influx overwrites the same points.
+    async fn write(&mut self, lines: &[String]) -> Result<usize, WriteError> {
+        let mut dropped = 0;
+        let mut pending = vec![lines];
+
+        while let Some(chunk) = pending.pop() {
+            match self.send(&chunk.join("\n")).await {
+                Ok(()) => {}
+                Err(WriteError::Rejected { .. }) if chunk.len() > 1 => {
+                    let (head, tail) = chunk.split_at(chunk.len() / 2);
+                    pending.push(tail);
+                    pending.push(head);
+                }
+                Err(err @ WriteError::Rejected { .. }) => {
+                    dropped += 1;
+                    tracing::debug!(line = chunk[0], error = %err, "Influx rejected a line.");
+                }
+                Err(err @ WriteError::Unavailable(_)) => {
+                    self.backoff.failed();
+                    tracing::warn!(
+                        error = %err,
+                        retry_in = ?self.backoff.retry_at.map(|at| at - Instant::now()),
+                        "Influx write failed."
+                    );
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(dropped)
+    }
+
+    async fn send(&self, batch: &str) -> Result<(), WriteError> {
+        let mut request = self
+            .client
+            .post(&self.write_url)
+            // A rejected batch writes nothing, so that only the bad lines
+            // are left out when it is split
+            .query(&[
+                ("db", self.database.as_str()),
+                ("precision", "nanosecond"),
+                ("accept_partial", "false"),
+            ])
+            .body(batch.to_string());
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|err| WriteError::Unavailable(err.to_string()))?;
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        // Auth or a missing database are configuration issues, the data is
+        // kept until they are fixed
+        if status.is_client_error()
+            && !matches!(
+                status,
+                StatusCode::UNAUTHORIZED
+                    | StatusCode::FORBIDDEN
+                    | StatusCode::NOT_FOUND
+                    | StatusCode::REQUEST_TIMEOUT
+                    | StatusCode::TOO_MANY_REQUESTS
+            )
+        {
+            return Err(WriteError::Rejected { status, body });
+        }
+
+        Err(WriteError::Unavailable(format!("{status}: {body}")))
+    }
+}
@@ -0,0 +1,122 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fmt::Write;
+
+use crate::queue::TelemetryRecord;
+
+/// Table of the sensor measurements.
+pub const MEASUREMENTS_TABLE: &str = "measurements";
+/// Table of the sensor state snapshots, one line per transition.
+pub const SENSOR_STATES_TABLE: &str = "sensor_states";
+
+/// Line protocol of a record.
+///
+/// - `measurements,sensor_id=<id>,kind=<kind> value=<value> <ns>`
+/// - `sensor_states,sensor_id=<id>,kind=<kind>,state=<state> reason="<code>",consecutive_failures=<n>i <ns>`
+///
+/// Returns `None` for values line protocol can't carry (NaN, infinity) or
+/// times out of the nanosecond range.
+pub fn record_line(record: &TelemetryRecord) -> Option<String> {
+    match record {
+        TelemetryRecord::Measurement {
+            sensor_id,
+            kind,
+            value,
+            time,
+        } => {
+            if !value.is_finite() {
+                return None;
+            }
+            let timestamp = time.timestamp_nanos_opt()?;
+
+            let mut line = String::from(MEASUREMENTS_TABLE);
+            push_tag(&mut line, "sensor_id", &sensor_id.to_string());
+            push_tag(&mut line, "kind", kind);
+            let _ = write!(line, " value={value} {timestamp}");
+
+            Some(line)
+        }
+        TelemetryRecord::SensorState {
+            sensor_id,
+            kind,
+            state,
+            reason,
+            consecutive_failures,
+            since,
+        } => {
+            let timestamp = since.timestamp_nanos_opt()?;
+
+            let mut line = String::from(SENSOR_STATES_TABLE);
+            push_tag(&mut line, "sensor_id", &sensor_id.to_string());
+            push_tag(&mut line, "kind", kind);
+            push_tag(&mut line, "state", state);
+            let _ = write!(
+                line,
+                " reason=\"{}\",consecutive_failures={consecutive_failures}i {timestamp}",
+                escape_string_field(reason),
+            );
+
+            Some(line)
+        }
+    }
+}
+
+fn push_tag(line: &mut String, key: &str, value: &str) {
+    line.push(',');
+    line.push_str(key);
+    line.push('=');
+    for c in value.chars() {
+        if matches!(c, ',' | '=' | ' ') {
+            line.push('\\');
+        }
+        line.push(c);
+    }
+}
+
+fn escape_string_field(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    #[test]
+    fn measurement_is_written_with_nanoseconds() {
+        let measurement = TelemetryRecord::Measurement {
+            sensor_id: Uuid::nil(),
+            kind: "temperature".to_string(),
+            value: 21.5,
+            time: Utc.timestamp_opt(1_700_000_000, 42).unwrap(),
+        };
+
+        assert_eq!(
+            record_line(&measurement).unwrap(),
+            "measurements,sensor_id=00000000-0000-0000-0000-000000000000,kind=temperature value=21.5 1700000000000000042"
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_skipped() {
+        let measurement = TelemetryRecord::Measurement {
+            sensor_id: Uuid::nil(),
+            kind: "ph".to_string(),
+            value: f64::NAN,
+            time: Utc::now(),
+        };
+
+        assert!(record_line(&measurement).is_none());
+    }
+
+    #[test]
+    fn tag_values_are_escaped() {
+        let mut line = String::new();
+        push_tag(&mut line, "name", "tank 1,a=b");
+
+        assert_eq!(line, r",name=tank\ 1\,a\=b");
+    }
+}
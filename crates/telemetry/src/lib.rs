@@ -0,0 +1,15 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+mod config;
+pub mod influx;
+pub mod metrics;
+pub mod mqtt;
+pub mod queue;
+
+pub use config::{Config, InfluxConfig, MetricsConfig, MqttConfig, QueueConfig, CONFIG};
+pub use influx::InfluxExporter;
+pub use metrics::StationMetrics;
+pub use mqtt::MqttBridge;
+pub use queue::{QueueConsumer, QueueWriter, TelemetryQueue, TelemetryRecord};
@@ -0,0 +1,41 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::metrics::StationMetrics;
+
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Router exposing `GET /metrics`, to be mounted on an existing server.
+pub fn router(metrics: StationMetrics) -> Router {
+    Router::new()
+        .route("/metrics", get(scrape))
+        .with_state(metrics)
+}
+
+/// Serve the metrics on `listener` until `shutdown` is cancelled.
+pub async fn serve(
+    listener: TcpListener,
+    metrics: StationMetrics,
+    shutdown: CancellationToken,
+) -> std::io::Result<()> {
+    if let Ok(addr) = listener.local_addr() {
+        tracing::info!(%addr, "Metrics listening.");
+    }
+
+    axum::serve(listener, router(metrics))
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await
+}
+
+async fn scrape(State(metrics): State<StationMetrics>) -> impl IntoResponse {
+    ([(CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE)], metrics.encode())
+}
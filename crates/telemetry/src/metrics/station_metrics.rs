@@ -0,0 +1,556 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::event::ActuatorEvent;
+use arksync_actuator::relay::RelayState;
+use arksync_sensor::event::SensorEvent;
+use arksync_sensor::sensor::{SensorInfo, SensorKind, SensorState};
+use chrono::{DateTime, Utc};
+use prometheus_client::collector::Collector;
+use prometheus_client::encoding::{text, DescriptorEncoder, EncodeLabelSet, EncodeMetric};
+use prometheus_client::metrics::counter::{ConstCounter, Counter};
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::{ConstGauge, Gauge};
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+use prometheus_client::metrics::MetricType;
+use prometheus_client::registry::Registry;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::queue::TelemetryQueue;
+
+const SENSOR_STATES: [SensorState; 5] = [
+    SensorState::Active,
+    SensorState::Degraded,
+    SensorState::Initializing,
+    SensorState::Unplugged,
+    SensorState::Unreachable,
+];
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct SensorLabels {
+    sensor: String,
+    kind: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct StateLabels {
+    sensor: String,
+    kind: String,
+    state: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ReadLabels {
+    sensor: String,
+    kind: String,
+    outcome: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct RelayLabels {
+    relay: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct RefusalLabels {
+    relay: String,
+    reason: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ConsumerLabels {
+    consumer: String,
+}
+
+/// What is kept of a sensor between two scrapes.
+struct SensorSnapshot {
+    kind: SensorKind,
+    last_activity: DateTime<Utc>,
+}
+
+struct Inner {
+    registry: Registry,
+    sensors: Mutex<HashMap<Uuid, SensorSnapshot>>,
+    value: Family<SensorLabels, Gauge<f64, AtomicU64>>,
+    state: Family<StateLabels, Gauge>,
+    consecutive_failures: Family<SensorLabels, Gauge>,
+    since_last_activity: Family<SensorLabels, Gauge<f64, AtomicU64>>,
+    read_duration: Family<ReadLabels, Histogram, fn() -> Histogram>,
+    registered: Gauge,
+    relay_active: Family<RelayLabels, Gauge>,
+    relay_refusals: Family<RefusalLabels, Counter>,
+    queue: Arc<OnceLock<TelemetryQueue>>,
+}
+
+/// Backpressure of the telemetry queue, read from its stats on each scrape.
+struct QueueCollector(Arc<OnceLock<TelemetryQueue>>);
+
+impl fmt::Debug for QueueCollector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueueCollector").finish_non_exhaustive()
+    }
+}
+
+impl Collector for QueueCollector {
+    fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), fmt::Error> {
+        let Some(queue) = self.0.get() else {
+            return Ok(());
+        };
+        let stats = queue.stats();
+
+        let gauges = [
+            (
+                "queue_bytes",
+                "Size of the telemetry queue on disk",
+                stats.bytes,
+            ),
+            (
+                "queue_segments",
+                "Segment files of the telemetry queue",
+                stats.segments as u64,
+            ),
+            (
+                "queue_records",
+                "Records kept in the telemetry queue",
+                stats.next_offset - stats.oldest_offset,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            let gauge = ConstGauge::new(value as i64);
+            gauge.encode(encoder.encode_descriptor(name, help, None, gauge.metric_type())?)?;
+        }
+
+        let dropped = ConstCounter::new(stats.dropped_records);
+        dropped.encode(encoder.encode_descriptor(
+            "queue_dropped_records",
+            "Records dropped by the size or age caps before every exporter got them",
+            None,
+            dropped.metric_type(),
+        )?)?;
+
+        let mut lag = encoder.encode_descriptor(
+            "queue_consumer_lag",
+            "Records waiting for an exporter",
+            None,
+            MetricType::Gauge,
+        )?;
+        for consumer in &stats.consumers {
+            let labels = ConsumerLabels {
+                consumer: consumer.name.clone(),
+            };
+            ConstGauge::new(consumer.lag as i64).encode(lag.encode_family(&labels)?)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Prometheus metrics of the station.
+///
+/// Fed from the sensor events and the relay states, a scrape only reads this
+/// copy and never waits on a sensor.
+#[derive(Clone)]
+pub struct StationMetrics {
+    inner: Arc<Inner>,
+}
+
+impl Default for StationMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StationMetrics {
+    pub fn new() -> Self {
+        let mut registry = Registry::with_prefix("arksync");
+        let value = Family::<SensorLabels, Gauge<f64, AtomicU64>>::default();
+        let state = Family::<StateLabels, Gauge>::default();
+        let consecutive_failures = Family::<SensorLabels, Gauge>::default();
+        let since_last_activity = Family::<SensorLabels, Gauge<f64, AtomicU64>>::default();
+        let read_duration =
+            Family::<ReadLabels, Histogram, fn() -> Histogram>::new_with_constructor(|| {
+                // 5ms to 10s, an EZO read takes around 900ms
+                Histogram::new(exponential_buckets(0.005, 2.0, 12))
+            });
+        let registered = Gauge::default();
+        let relay_active = Family::<RelayLabels, Gauge>::default();
+        let relay_refusals = Family::<RefusalLabels, Counter>::default();
+        let queue = Arc::new(OnceLock::new());
+
+        registry.register(
+            "sensor_value",
+            "Last valid measurement of a sensor",
+            value.clone(),
+        );
+        registry.register(
+            "sensor_state",
+            "Current state of a sensor, 1 for the active label",
+            state.clone(),
+        );
+        registry.register(
+            "sensor_consecutive_failures",
+            "Failed reads since the last valid measurement",
+            consecutive_failures.clone(),
+        );
+        registry.register(
+            "sensor_seconds_since_last_activity",
+            "Time elapsed since the last valid measurement",
+            since_last_activity.clone(),
+        );
+        registry.register(
+            "sensor_read_duration_seconds",
+            "Time spent reading a sensor",
+            read_duration.clone(),
+        );
+        registry.register(
+            "sensors_registered",
+            "Sensors in the registry",
+            registered.clone(),
+        );
+        registry.register(
+            "relay_active",
+            "Relay state, 1 when energized",
+            relay_active.clone(),
+        );
+        registry.register(
+            "relay_refusals",
+            "Switches refused by the relay interlocks",
+            relay_refusals.clone(),
+        );
+        registry.register_collector(Box::new(QueueCollector(Arc::clone(&queue))));
+
+        Self {
+            inner: Arc::new(Inner {
+                registry,
+                sensors: Mutex::new(HashMap::new()),
+                value,
+                state,
+                consecutive_failures,
+                since_last_activity,
+                read_duration,
+                registered,
+                relay_active,
+                relay_refusals,
+                queue,
+            }),
+        }
+    }
+
+    /// Sensors already registered before the metrics subscribed to events.
+    pub fn with_sensors(self, sensors: impl IntoIterator<Item = SensorInfo>) -> Self {
+        for info in sensors {
+            self.upsert_sensor(&info);
+        }
+        self
+    }
+
+    /// Relay states known before the metrics subscribed to their changes.
+    pub fn with_relays(self, relays: impl IntoIterator<Item = RelayState>) -> Self {
+        for state in relays {
+            self.record_relay(&state);
+        }
+        self
+    }
+
+    /// Expose the stats of the telemetry queue the exporters read.
+    pub fn with_queue(self, queue: TelemetryQueue) -> Self {
+        // Only the first queue is exposed
+        let _ = self.inner.queue.set(queue);
+        self
+    }
+
+    pub async fn run(
+        self,
+        mut events: broadcast::Receiver<SensorEvent>,
+        shutdown: CancellationToken,
+    ) {
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => self.observe(&event),
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Metrics lagging behind, sensor events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = shutdown.cancelled() => break,
+            }
+        }
+    }
+
+    /// Follow the events published by the actuator service.
+    pub async fn run_relays(
+        self,
+        mut relays: broadcast::Receiver<ActuatorEvent>,
+        shutdown: CancellationToken,
+    ) {
+        loop {
+            tokio::select! {
+                event = relays.recv() => match event {
+                    Ok(event) => self.observe_relay(&event),
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Metrics lagging behind, actuator events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = shutdown.cancelled() => break,
+            }
+        }
+    }
+
+    pub fn observe(&self, event: &SensorEvent) {
+        let inner = &self.inner;
+
+        match event {
+            SensorEvent::Discovered(info)
+            | SensorEvent::StateChanged { info, .. }
+            | SensorEvent::Renamed(info) => self.upsert_sensor(info),
+            SensorEvent::Removed(info) => self.remove_sensor(info),
+            SensorEvent::Measurement(measurement) => {
+                let labels = sensor_labels(measurement.sensor_id, measurement.kind);
+                inner.value.get_or_create(&labels).set(measurement.value);
+
+                let mut sensors = inner.sensors.lock().expect("metrics mutex poisoned");
+                if let Some(snapshot) = sensors.get_mut(&measurement.sensor_id) {
+                    snapshot.last_activity = measurement.time;
+                }
+            }
+            SensorEvent::ReadCompleted(report) => {
+                let labels = sensor_labels(report.sensor_id, report.kind);
+                inner
+                    .consecutive_failures
+                    .get_or_create(&labels)
+                    .set(report.consecutive_failures.into());
+
+                let outcome = if report.ok { "ok" } else { "error" };
+                inner
+                    .read_duration
+                    .get_or_create(&read_labels(&labels, outcome))
+                    .observe(report.latency.as_secs_f64());
+            }
+        }
+    }
+
+    pub fn observe_relay(&self, event: &ActuatorEvent) {
+        match event {
+            ActuatorEvent::StateChanged { state, .. } => self.record_relay(state),
+            ActuatorEvent::Refused { relay_id, reason } => {
+                self.inner
+                    .relay_refusals
+                    .get_or_create(&RefusalLabels {
+                        relay: relay_id.to_string(),
+                        reason: reason.code().to_string(),
+                    })
+                    .inc();
+            }
+        }
+    }
+
+    pub fn record_relay(&self, state: &RelayState) {
+        self.inner
+            .relay_active
+            .get_or_create(&RelayLabels {
+                relay: state.id.to_string(),
+            })
+            .set(state.active.into());
+    }
+
+    /// Metrics in the OpenMetrics text format.
+    pub fn encode(&self) -> String {
+        let inner = &self.inner;
+        let now = Utc::now();
+
+        for (id, snapshot) in inner.sensors.lock().expect("metrics mutex poisoned").iter() {
+            let elapsed = (now - snapshot.last_activity).num_milliseconds().max(0);
+            inner
+                .since_last_activity
+                .get_or_create(&sensor_labels(*id, snapshot.kind))
+                .set(elapsed as f64 / 1000.0);
+        }
+
+        let mut buffer = String::new();
+        text::encode(&mut buffer, &inner.registry).expect("writing to a String can't fail");
+        buffer
+    }
+
+    fn upsert_sensor(&self, info: &SensorInfo) {
+        let inner = &self.inner;
+        let labels = sensor_labels(info.id, info.kind);
+
+        for state in SENSOR_STATES {
+            inner
+                .state
+                .get_or_create(&state_labels(&labels, state))
+                .set((state == info.state).into());
+        }
+        inner
+            .consecutive_failures
+            .get_or_create(&labels)
+            .set(info.consecutive_failures.into());
+
+        let mut sensors = inner.sensors.lock().expect("metrics mutex poisoned");
+        sensors.insert(
+            info.id,
+            SensorSnapshot {
+                kind: info.kind,
+                last_activity: info.last_activity,
+            },
+        );
+        inner.registered.set(sensors.len() as i64);
+    }
+
+    fn remove_sensor(&self, info: &SensorInfo) {
+        let inner = &self.inner;
+        let labels = sensor_labels(info.id, info.kind);
+
+        inner.value.remove(&labels);
+        inner.consecutive_failures.remove(&labels);
+        inner.since_last_activity.remove(&labels);
+        for state in SENSOR_STATES {
+            inner.state.remove(&state_labels(&labels, state));
+        }
+        for outcome in ["ok", "error"] {
+            inner.read_duration.remove(&read_labels(&labels, outcome));
+        }
+
+        let mut sensors = inner.sensors.lock().expect("metrics mutex poisoned");
+        sensors.remove(&info.id);
+        inner.registered.set(sensors.len() as i64);
+    }
+}
+
+fn sensor_labels(sensor_id: Uuid, kind: SensorKind) -> SensorLabels {
+    SensorLabels {
+        sensor: sensor_id.to_string(),
+        kind: kind.as_str().to_string(),
+    }
+}
+
+fn state_labels(labels: &SensorLabels, state: SensorState) -> StateLabels {
+    StateLabels {
+        sensor: labels.sensor.clone(),
+        kind: labels.kind.clone(),
+        state: state.as_str().to_string(),
+    }
+}
+
+fn read_labels(labels: &SensorLabels, outcome: &str) -> ReadLabels {
+    ReadLabels {
+        sensor: labels.sensor.clone(),
+        kind: labels.kind.clone(),
+        outcome: outcome.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arksync_actuator::relay::MIST_RELAY;
+    use arksync_actuator::safety::InterlockReason;
+    use arksync_sensor::event::{Measurement, ReadReport};
+    use arksync_sensor::i2c_bus::I2cConnection;
+    use arksync_sensor::sensor::{SensorConnection, SensorName, SensorStateReason};
+    use std::time::Duration;
+
+    use crate::config::QueueConfig;
+    use crate::queue::TelemetryRecord;
+
+    fn sensor(state: SensorState) -> SensorInfo {
+        SensorInfo {
+            id: Uuid::nil(),
+            hardware_uid: "i2c:1:0x66".to_string(),
+            kind: SensorKind::Temperature,
+            firmware: 2.1,
+            name: SensorName::Unnamed,
+            state,
+            state_reason: SensorStateReason::MeasurementOk,
+            state_since: Utc::now(),
+            last_activity: Utc::now(),
+            consecutive_failures: 0,
+            connection: SensorConnection::I2c(I2cConnection {
+                bus: 1,
+                address: 0x66,
+            }),
+        }
+    }
+
+    #[test]
+    fn events_are_exposed_until_the_sensor_is_removed() {
+        let metrics = StationMetrics::new().with_sensors([sensor(SensorState::Active)]);
+        let series = format!(r#"sensor="{}",kind="temperature""#, Uuid::nil());
+
+        metrics.observe(&SensorEvent::Measurement(Measurement {
+            sensor_id: Uuid::nil(),
+            kind: SensorKind::Temperature,
+            value: 21.5,
+            time: Utc::now(),
+        }));
+        metrics.observe(&SensorEvent::ReadCompleted(ReadReport {
+            sensor_id: Uuid::nil(),
+            kind: SensorKind::Temperature,
+            latency: Duration::from_millis(900),
+            ok: true,
+            consecutive_failures: 0,
+        }));
+        metrics.record_relay(&RelayState::new(MIST_RELAY, true));
+        metrics.observe_relay(&ActuatorEvent::Refused {
+            relay_id: MIST_RELAY.id,
+            reason: InterlockReason::SwitchRateExceeded { max_per_hour: 60 },
+        });
+
+        let encoded = metrics.encode();
+        assert!(encoded.contains(&format!("arksync_sensor_value{{{series}}} 21.5")));
+        assert!(encoded.contains(&format!(
+            r#"arksync_sensor_state{{{series},state="active"}} 1"#
+        )));
+        assert!(encoded.contains(&format!(
+            r#"arksync_sensor_read_duration_seconds_count{{{series},outcome="ok"}} 1"#
+        )));
+        assert!(encoded.contains("arksync_sensors_registered 1"));
+        assert!(encoded.contains(r#"arksync_relay_active{relay="mist_relay"} 1"#));
+        assert!(encoded.contains(
+            r#"arksync_relay_refusals_total{relay="mist_relay",reason="switch_rate_exceeded"} 1"#
+        ));
+
+        metrics.observe(&SensorEvent::Removed(sensor(SensorState::Unplugged)));
+
+        let encoded = metrics.encode();
+        assert!(!encoded.contains(&series));
+        assert!(encoded.contains("arksync_sensors_registered 0"));
+        assert!(!encoded.contains("arksync_queue"));
+    }
+
+    #[tokio::test]
+    async fn queue_backpressure_is_exposed() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = TelemetryQueue::open(&QueueConfig {
+            dir: dir.path().to_path_buf(),
+            max_bytes: 1024 * 1024,
+            max_age: Duration::from_secs(3600),
+            segment_max_bytes: 64 * 1024,
+        })
+        .unwrap();
+        queue.consumer("influx").await.unwrap();
+        let record = TelemetryRecord::Measurement {
+            sensor_id: Uuid::nil(),
+            kind: "temperature".to_string(),
+            value: 21.5,
+            time: Utc::now(),
+        };
+        queue.append(&[record.clone(), record]).await.unwrap();
+
+        let encoded = StationMetrics::new().with_queue(queue).encode();
+
+        assert!(encoded.contains("arksync_queue_records 2"));
+        assert!(encoded.contains("arksync_queue_segments 1"));
+        assert!(encoded.contains("arksync_queue_dropped_records_total 0"));
+        assert!(encoded.contains(r#"arksync_queue_consumer_lag{consumer="influx"} 2"#));
+    }
+}
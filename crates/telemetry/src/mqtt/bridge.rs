@@ -0,0 +1,436 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::event::ActuatorEvent;
+use arksync_actuator::relay::{RelaySpec, RelayState};
+use arksync_sensor::event::SensorEvent;
+use arksync_sensor::sensor::{SensorInfo, SensorName, SensorState};
+use serde_json::json;
+use std::collections::HashMap;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::mqtt::command::{CommandHandler, CommandRequest, CommandResult, MqttCommand};
+use crate::mqtt::discovery::{HomeAssistantDiscovery, RELAY_OFF, RELAY_ON};
+use crate::mqtt::transport::{MqttPublisher, Publication, TransportEvent, OFFLINE, ONLINE};
+use crate::mqtt::Topics;
+use crate::queue::{QueueConsumer, TelemetryRecord};
+
+const DEFAULT_BATCH_SIZE: usize = 100;
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Bridge between the station and an MQTT broker.
+///
+/// Measurements are forwarded from the telemetry queue and acknowledged once
+/// handed to the client, so nothing is lost while the broker is away. Sensor
+/// states and availability come from the live events and are retained, they
+/// are published again on every connection. Commands received on the command
+/// topic go to the [`CommandHandler`]. Relay states follow the actuator
+/// events, whoever switched the relay.
+///
+/// With [`HomeAssistantDiscovery`], sensors and relays are announced to Home
+/// Assistant. Hot-plugged sensors are announced when discovered and cleared
+/// once removed.
+pub struct MqttBridge<P, H> {
+    publisher: P,
+    handler: H,
+    topics: Topics,
+    discovery: Option<HomeAssistantDiscovery>,
+    sensors: HashMap<Uuid, SensorInfo>,
+    relays: Vec<RelaySpec>,
+    /// Last known state of each relay, `true` when energized
+    relay_states: HashMap<&'static str, bool>,
+    relay_events: Option<broadcast::Receiver<ActuatorEvent>>,
+    connected: bool,
+    batch_size: usize,
+    flush_interval: Duration,
+}
+
+impl<P: MqttPublisher, H: CommandHandler> MqttBridge<P, H> {
+    /// Name of the bridge cursor in the telemetry queue.
+    pub const CONSUMER: &'static str = "mqtt";
+
+    pub fn new(publisher: P, handler: H, topics: Topics) -> Self {
+        Self {
+            publisher,
+            handler,
+            topics,
+            discovery: None,
+            sensors: HashMap::new(),
+            relays: Vec::new(),
+            relay_states: HashMap::new(),
+            relay_events: None,
+            connected: false,
+            batch_size: DEFAULT_BATCH_SIZE,
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+        }
+    }
+
+    /// Sensors already registered before the bridge subscribed to events.
+    pub fn with_sensors(mut self, sensors: impl IntoIterator<Item = SensorInfo>) -> Self {
+        self.sensors
+            .extend(sensors.into_iter().map(|info| (info.id, info)));
+        self
+    }
+
+    /// Relays exposed as switches, set from `<base>/relay/<relay>/set`.
+    ///
+    /// Their retained state starts from `states` and follows `events`, which
+    /// must be subscribed before the states are taken.
+    pub fn with_relays(
+        mut self,
+        states: impl IntoIterator<Item = (RelaySpec, RelayState)>,
+        events: broadcast::Receiver<ActuatorEvent>,
+    ) -> Self {
+        for (spec, state) in states {
+            self.relays.push(spec);
+            self.relay_states.insert(state.id, state.active);
+        }
+        self.relay_events = Some(events);
+        self
+    }
+
+    pub fn with_discovery(mut self, discovery: HomeAssistantDiscovery) -> Self {
+        self.discovery = Some(discovery);
+        self
+    }
+
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    pub async fn run(
+        mut self,
+        mut transport: mpsc::Receiver<TransportEvent>,
+        mut events: broadcast::Receiver<SensorEvent>,
+        mut consumer: QueueConsumer,
+        shutdown: CancellationToken,
+    ) {
+        let mut relay_events = self.relay_events.take();
+
+        loop {
+            let connected = self.connected;
+            let flush_interval = self.flush_interval;
+            let measurements = async {
+                if connected {
+                    consumer.wait(1, flush_interval).await
+                } else {
+                    std::future::pending().await
+                }
+            };
+
+            tokio::select! {
+                event = transport.recv() => match event {
+                    Some(event) => self.on_transport_event(event).await,
+                    None => break,
+                },
+                event = events.recv() => match event {
+                    Ok(event) => self.on_sensor_event(event).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "MQTT bridge lagging behind, sensor events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                event = next_relay_event(&mut relay_events) => match event {
+                    Ok(ActuatorEvent::StateChanged { state, .. }) => {
+                        self.relay_states.insert(state.id, state.active);
+                        self.publish_relay_state(state.id, state.active).await;
+                    }
+                    Ok(ActuatorEvent::Refused { .. }) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "MQTT bridge lagging behind, relay events lost.");
+                    }
+                    Err(RecvError::Closed) => relay_events = None,
+                },
+                ready = measurements => if ready {
+                    self.forward_measurements(&mut consumer).await;
+                },
+                _ = shutdown.cancelled() => break,
+            }
+        }
+
+        if self.connected {
+            self.publish(Publication::new(self.topics.status(), OFFLINE, true))
+                .await;
+        }
+        tracing::info!("MQTT bridge stopped.");
+    }
+
+    async fn on_transport_event(&mut self, event: TransportEvent) {
+        match event {
+            TransportEvent::Connected => {
+                tracing::info!("MQTT connected.");
+                self.connected = true;
+
+                let mut filters = vec![self.topics.commands()];
+                if !self.relays.is_empty() {
+                    filters.push(self.topics.relay_set_filter());
+                }
+                if let Some(discovery) = &self.discovery {
+                    filters.push(discovery.status_topic());
+                }
+                for filter in &filters {
+                    if let Err(err) = self.publisher.subscribe(filter).await {
+                        tracing::error!(%filter, error = %err, "MQTT subscription failed.");
+                    }
+                }
+                self.publish(Publication::new(self.topics.status(), ONLINE, true))
+                    .await;
+
+                self.publish_discovery().await;
+                let sensors: Vec<SensorInfo> = self.sensors.values().cloned().collect();
+                for info in &sensors {
+                    self.publish_sensor(info).await;
+                }
+                for (relay, on) in &self.relay_states {
+                    self.publish_relay_state(relay, *on).await;
+                }
+            }
+            TransportEvent::Disconnected(reason) => {
+                if self.connected {
+                    tracing::warn!(%reason, "MQTT disconnected.");
+                }
+                self.connected = false;
+            }
+            TransportEvent::Message { topic, payload } if topic == self.topics.commands() => {
+                self.on_command(&payload).await;
+            }
+            TransportEvent::Message { topic, payload }
+                if self
+                    .discovery
+                    .as_ref()
+                    .is_some_and(|discovery| topic == discovery.status_topic()) =>
+            {
+                // Birth message of a restarted Home Assistant
+                if payload == ONLINE.as_bytes() {
+                    self.publish_discovery().await;
+                }
+            }
+            TransportEvent::Message { topic, payload } => {
+                if let Some(relay) = self.topics.relay_from_set(&topic) {
+                    let relay = relay.to_string();
+                    self.on_relay_set(&relay, &payload).await;
+                }
+            }
+        }
+    }
+
+    async fn on_sensor_event(&mut self, event: SensorEvent) {
+        match event {
+            SensorEvent::Discovered(info) | SensorEvent::Renamed(info) => {
+                self.publish_sensor_discovery(&info).await;
+                self.publish_sensor(&info).await;
+                self.sensors.insert(info.id, info);
+            }
+            SensorEvent::StateChanged { info, .. } => {
+                self.publish_sensor(&info).await;
+                self.sensors.insert(info.id, info);
+            }
+            SensorEvent::Removed(info) => {
+                self.publish_sensor(&info).await;
+                if let (true, Some(discovery)) = (self.connected, &self.discovery) {
+                    self.publish(discovery.clear_sensor(info.id, &self.topics))
+                        .await;
+                }
+                self.sensors.remove(&info.id);
+            }
+            // Forwarded from the queue
+            SensorEvent::Measurement(_) | SensorEvent::ReadCompleted(_) => {}
+        }
+    }
+
+    async fn on_command(&self, payload: &[u8]) {
+        let result = match serde_json::from_slice::<CommandRequest>(payload) {
+            Ok(request) => {
+                tracing::info!(command = ?request.command, "MQTT command received.");
+                let result = self.handler.handle(request.command).await;
+                CommandResult::new(request.id, result)
+            }
+            Err(err) => CommandResult::new(None, Err(format!("Invalid command: {err}"))),
+        };
+
+        if let Some(error) = &result.error {
+            tracing::warn!(%error, "MQTT command failed.");
+        }
+
+        match serde_json::to_vec(&result) {
+            Ok(payload) => {
+                self.publish(Publication::new(
+                    self.topics.command_results(),
+                    payload,
+                    false,
+                ))
+                .await;
+            }
+            Err(err) => tracing::error!(error = %err, "Failed to encode an MQTT command result."),
+        }
+    }
+
+    /// `ON` / `OFF` from a Home Assistant switch.
+    async fn on_relay_set(&self, relay: &str, payload: &[u8]) {
+        let on = match payload {
+            p if p == RELAY_ON.as_bytes() => true,
+            p if p == RELAY_OFF.as_bytes() => false,
+            _ => {
+                tracing::warn!(
+                    relay,
+                    payload = %String::from_utf8_lossy(payload),
+                    "Invalid MQTT payload for a relay."
+                );
+                return;
+            }
+        };
+
+        let command = MqttCommand::SetRelay {
+            relay: relay.to_string(),
+            on,
+        };
+        // The new state comes back as an actuator event
+        if let Err(err) = self.handler.handle(command).await {
+            tracing::error!(relay, error = %err, "MQTT failed to set a relay.");
+        }
+    }
+
+    /// Retained state of a relay, read by the Home Assistant switch.
+    async fn publish_relay_state(&self, relay: &str, on: bool) {
+        if !self.connected {
+            return;
+        }
+
+        let state = if on { RELAY_ON } else { RELAY_OFF };
+        self.publish(Publication::new(
+            self.topics.relay_state(relay),
+            state,
+            true,
+        ))
+        .await;
+    }
+
+    /// Discovery configs of the relays and every known sensor.
+    async fn publish_discovery(&self) {
+        let Some(discovery) = &self.discovery else {
+            return;
+        };
+
+        for relay in &self.relays {
+            self.publish(discovery.relay_config(relay, &self.topics))
+                .await;
+        }
+        for info in self.sensors.values() {
+            self.publish(discovery.sensor_config(info, &self.topics))
+                .await;
+        }
+    }
+
+    async fn publish_sensor_discovery(&self, info: &SensorInfo) {
+        if let (true, Some(discovery)) = (self.connected, &self.discovery) {
+            self.publish(discovery.sensor_config(info, &self.topics))
+                .await;
+        }
+    }
+
+    /// Retained state and availability of a sensor.
+    async fn publish_sensor(&self, info: &SensorInfo) {
+        if !self.connected {
+            return;
+        }
+
+        let name = match &info.name {
+            SensorName::Named(name) => name.clone(),
+            SensorName::Unnamed => info.hardware_uid.clone(),
+        };
+        let state = json!({
+            "name": name,
+            "kind": info.kind.as_str(),
+            "state": info.state.as_str(),
+            "reason": info.state_reason.code(),
+            "since": info.state_since,
+            "lastActivity": info.last_activity,
+            "consecutiveFailures": info.consecutive_failures,
+            "firmware": info.firmware,
+        });
+        let availability = match info.state {
+            SensorState::Active | SensorState::Degraded | SensorState::Initializing => ONLINE,
+            SensorState::Unplugged | SensorState::Unreachable => OFFLINE,
+        };
+
+        self.publish(Publication::new(
+            self.topics.sensor_state(info.id),
+            state.to_string(),
+            true,
+        ))
+        .await;
+        self.publish(Publication::new(
+            self.topics.sensor_availability(info.id),
+            availability,
+            true,
+        ))
+        .await;
+    }
+
+    async fn forward_measurements(&self, consumer: &mut QueueConsumer) {
+        let records = match consumer.read(self.batch_size).await {
+            Ok(records) => records,
+            Err(err) => {
+                tracing::error!(error = %err, "MQTT bridge failed to read the queue.");
+                return;
+            }
+        };
+        let Some(last) = records.last().map(|record| record.offset) else {
+            return;
+        };
+
+        for record in records {
+            let TelemetryRecord::Measurement {
+                sensor_id,
+                kind,
+                value,
+                time,
+            } = record.record
+            else {
+                continue;
+            };
+
+            let payload = json!({ "value": value, "time": time }).to_string();
+            let publication =
+                Publication::new(self.topics.measurement(sensor_id, &kind), payload, false);
+
+            // Not acknowledged, the batch is sent again on the next round
+            if let Err(err) = self.publisher.publish(publication).await {
+                tracing::warn!(error = %err, "MQTT failed to publish a measurement.");
+                return;
+            }
+        }
+
+        if let Err(err) = consumer.ack(last).await {
+            tracing::error!(
+                offset = last,
+                error = %err,
+                "MQTT bridge failed to acknowledge records."
+            );
+        }
+    }
+
+    async fn publish(&self, publication: Publication) {
+        let topic = publication.topic.clone();
+
+        if let Err(err) = self.publisher.publish(publication).await {
+            tracing::warn!(%topic, error = %err, "MQTT publication failed.");
+        }
+    }
+}
+
+/// Next relay event, never ready without relays.
+async fn next_relay_event(
+    events: &mut Option<broadcast::Receiver<ActuatorEvent>>,
+) -> Result<ActuatorEvent, RecvError> {
+    match events {
+        Some(events) => events.recv().await,
+        None => std::future::pending().await,
+    }
+}
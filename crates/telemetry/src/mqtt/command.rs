@@ -0,0 +1,127 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::error::ActuatorError;
+use arksync_actuator::services::ActuatorServiceHandle;
+use arksync_sensor::services::SensorServiceHandle;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use uuid::Uuid;
+
+/// Commands accepted on the command topic, e.g.
+/// `{"command": "set_relay", "relay": "mist_relay", "on": true}`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum MqttCommand {
+    SetRelay { relay: String, on: bool },
+    RenameSensor { sensor: Uuid, name: String },
+    ReadSensor { sensor: Uuid },
+}
+
+/// A command and the optional id echoed back in its result.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CommandRequest {
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub command: MqttCommand,
+}
+
+/// Published on the command result topic.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CommandResult {
+    pub fn new(id: Option<String>, result: Result<(), String>) -> Self {
+        Self {
+            id,
+            ok: result.is_ok(),
+            error: result.err(),
+        }
+    }
+}
+
+/// Executes the commands received over MQTT.
+pub trait CommandHandler: Send + Sync {
+    fn handle(&self, command: MqttCommand) -> impl Future<Output = Result<(), String>> + Send;
+}
+
+/// Commands executed against the sensors and relays of this station.
+pub struct StationCommands {
+    sensors: SensorServiceHandle,
+    relays: Option<ActuatorServiceHandle>,
+}
+
+impl StationCommands {
+    pub fn new(sensors: SensorServiceHandle) -> Self {
+        Self {
+            sensors,
+            relays: None,
+        }
+    }
+
+    /// Let the relays of the actuator service be switched over MQTT.
+    pub fn with_relays(mut self, relays: ActuatorServiceHandle) -> Self {
+        self.relays = Some(relays);
+        self
+    }
+}
+
+impl CommandHandler for StationCommands {
+    async fn handle(&self, command: MqttCommand) -> Result<(), String> {
+        match command {
+            MqttCommand::SetRelay { relay, on } => {
+                let relays = self
+                    .relays
+                    .as_ref()
+                    .ok_or_else(|| ActuatorError::UnknownRelay(relay.clone()).to_string())?;
+
+                relays
+                    .switch(&relay, on)
+                    .await
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())
+            }
+            MqttCommand::RenameSensor { sensor, name } => self
+                .sensors
+                .rename_sensor(sensor, name)
+                .await
+                .map(|_| ())
+                .map_err(|err| err.to_string()),
+            MqttCommand::ReadSensor { sensor } => self
+                .sensors
+                .read_sensor(sensor)
+                .await
+                .map(|_| ())
+                .map_err(|err| err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_parsed_with_their_id() {
+        let request: CommandRequest = serde_json::from_str(
+            r#"{"id": "42", "command": "set_relay", "relay": "mist_relay", "on": true}"#,
+        )
+        .unwrap();
+
+        assert_eq!(request.id.as_deref(), Some("42"));
+        assert_eq!(
+            request.command,
+            MqttCommand::SetRelay {
+                relay: "mist_relay".to_string(),
+                on: true
+            }
+        );
+    }
+}
@@ -0,0 +1,235 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::relay::RelaySpec;
+use arksync_sensor::sensor::{SensorInfo, SensorKind, SensorName};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::mqtt::transport::{Publication, OFFLINE, ONLINE};
+use crate::mqtt::Topics;
+
+pub const RELAY_ON: &str = "ON";
+pub const RELAY_OFF: &str = "OFF";
+
+/// Home Assistant MQTT discovery messages.
+///
+/// Every entry is a retained config on
+/// `<prefix>/<component>/arksync_<knot>/<object>/config`, an empty retained
+/// payload on the same topic removes it.
+#[derive(Clone, Debug)]
+pub struct HomeAssistantDiscovery {
+    prefix: String,
+}
+
+impl HomeAssistantDiscovery {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Topic where Home Assistant announces itself, the configs are published
+    /// again when it comes back `online`.
+    pub fn status_topic(&self) -> String {
+        format!("{}/status", self.prefix)
+    }
+
+    pub fn sensor_config(&self, info: &SensorInfo, topics: &Topics) -> Publication {
+        let (device_class, unit) = device_class(info.kind);
+        let name = match &info.name {
+            SensorName::Named(name) => name.clone(),
+            SensorName::Unnamed => info.hardware_uid.clone(),
+        };
+
+        let mut config = json!({
+            "name": kind_label(info.kind),
+            "unique_id": format!("arksync_{}", info.id),
+            "state_topic": topics.measurement(info.id, info.kind.as_str()),
+            "value_template": "{{ value_json.value }}",
+            "state_class": "measurement",
+            "json_attributes_topic": topics.sensor_state(info.id),
+            "availability": [
+                { "topic": topics.status() },
+                { "topic": topics.sensor_availability(info.id) },
+            ],
+            "availability_mode": "all",
+            "payload_available": ONLINE,
+            "payload_not_available": OFFLINE,
+            "device": {
+                "identifiers": [format!("arksync_{}", info.id)],
+                "name": name,
+                "manufacturer": "Atlas Scientific",
+                "model": model(info.kind),
+                "sw_version": info.firmware.to_string(),
+                "via_device": knot_device_id(topics),
+            },
+        });
+        insert_some(&mut config, "device_class", device_class);
+        insert_some(&mut config, "unit_of_measurement", unit);
+
+        Publication::new(self.sensor_topic(info.id, topics), config.to_string(), true)
+    }
+
+    pub fn clear_sensor(&self, sensor_id: Uuid, topics: &Topics) -> Publication {
+        Publication::new(self.sensor_topic(sensor_id, topics), Vec::new(), true)
+    }
+
+    /// A relay as a `switch` of the knot device.
+    pub fn relay_config(&self, spec: &RelaySpec, topics: &Topics) -> Publication {
+        let config = json!({
+            "name": spec.id,
+            "unique_id": format!("arksync_{}_{}", topics.knot(), spec.id),
+            "command_topic": topics.relay_set(spec.id),
+            "state_topic": topics.relay_state(spec.id),
+            "payload_on": RELAY_ON,
+            "payload_off": RELAY_OFF,
+            "availability_topic": topics.status(),
+            "payload_available": ONLINE,
+            "payload_not_available": OFFLINE,
+            "device": knot_device(topics),
+        });
+
+        Publication::new(
+            format!(
+                "{}/switch/{}/{}/config",
+                self.prefix,
+                knot_device_id(topics),
+                spec.id
+            ),
+            config.to_string(),
+            true,
+        )
+    }
+
+    fn sensor_topic(&self, sensor_id: Uuid, topics: &Topics) -> String {
+        format!(
+            "{}/sensor/{}/{sensor_id}/config",
+            self.prefix,
+            knot_device_id(topics)
+        )
+    }
+}
+
+fn knot_device_id(topics: &Topics) -> String {
+    format!("arksync_{}", topics.knot())
+}
+
+fn knot_device(topics: &Topics) -> Value {
+    json!({
+        "identifiers": [knot_device_id(topics)],
+        "name": format!("ArkSync {}", topics.knot()),
+        "manufacturer": "ArkSync",
+    })
+}
+
+/// Home Assistant device class and unit of a sensor kind.
+fn device_class(kind: SensorKind) -> (Option<&'static str>, Option<&'static str>) {
+    match kind {
+        SensorKind::Temperature => (Some("temperature"), Some("°C")),
+        SensorKind::Ph => (Some("ph"), None),
+        SensorKind::Ec => (Some("conductivity"), Some("µS/cm")),
+        SensorKind::Humidity => (Some("humidity"), Some("%")),
+        SensorKind::Co2 => (Some("carbon_dioxide"), Some("ppm")),
+        SensorKind::Custom => (None, None),
+    }
+}
+
+fn kind_label(kind: SensorKind) -> &'static str {
+    match kind {
+        SensorKind::Temperature => "Temperature",
+        SensorKind::Ph => "pH",
+        SensorKind::Ec => "Conductivity",
+        SensorKind::Humidity => "Humidity",
+        SensorKind::Co2 => "CO2",
+        SensorKind::Custom => "Value",
+    }
+}
+
+fn model(kind: SensorKind) -> &'static str {
+    match kind {
+        SensorKind::Temperature => "EZO-RTD",
+        SensorKind::Ph => "EZO-pH",
+        SensorKind::Ec => "EZO-EC",
+        SensorKind::Humidity => "EZO-HUM",
+        SensorKind::Co2 => "EZO-CO2",
+        SensorKind::Custom => "EZO",
+    }
+}
+
+fn insert_some(config: &mut Value, key: &str, value: Option<&str>) {
+    if let (Some(value), Some(config)) = (value, config.as_object_mut()) {
+        config.insert(key.to_string(), json!(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arksync_actuator::relay::MIST_RELAY;
+    use arksync_sensor::i2c_bus::I2cConnection;
+    use arksync_sensor::sensor::{SensorConnection, SensorState, SensorStateReason};
+    use chrono::Utc;
+
+    fn ph_probe() -> SensorInfo {
+        SensorInfo {
+            id: Uuid::nil(),
+            hardware_uid: "i2c:1:0x63".to_string(),
+            kind: SensorKind::Ph,
+            firmware: 2.16,
+            name: SensorName::Named("tank_ph".to_string()),
+            state: SensorState::Active,
+            state_reason: SensorStateReason::MeasurementOk,
+            state_since: Utc::now(),
+            last_activity: Utc::now(),
+            consecutive_failures: 0,
+            connection: SensorConnection::I2c(I2cConnection {
+                bus: 1,
+                address: 0x63,
+            }),
+        }
+    }
+
+    #[test]
+    fn sensor_config_follows_its_kind() {
+        let topics = Topics::new("arksync", "greenhouse");
+        let publication =
+            HomeAssistantDiscovery::new("homeassistant").sensor_config(&ph_probe(), &topics);
+        let config: Value = serde_json::from_slice(&publication.payload).unwrap();
+
+        assert_eq!(
+            publication.topic,
+            format!(
+                "homeassistant/sensor/arksync_greenhouse/{}/config",
+                Uuid::nil()
+            )
+        );
+        assert!(publication.retain);
+        assert_eq!(config["device_class"], "ph");
+        assert!(config.get("unit_of_measurement").is_none());
+        assert_eq!(config["device"]["sw_version"], "2.16");
+        assert_eq!(
+            config["availability"][1]["topic"],
+            topics.sensor_availability(Uuid::nil())
+        );
+    }
+
+    #[test]
+    fn relay_is_a_switch_of_the_knot() {
+        let topics = Topics::new("arksync", "greenhouse");
+        let publication =
+            HomeAssistantDiscovery::new("homeassistant").relay_config(&MIST_RELAY, &topics);
+        let config: Value = serde_json::from_slice(&publication.payload).unwrap();
+
+        assert_eq!(
+            publication.topic,
+            "homeassistant/switch/arksync_greenhouse/mist_relay/config"
+        );
+        assert_eq!(
+            config["command_topic"],
+            "arksync/greenhouse/relay/mist_relay/set"
+        );
+        assert_eq!(config["device"]["identifiers"][0], "arksync_greenhouse");
+    }
+}
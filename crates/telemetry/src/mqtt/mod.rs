@@ -0,0 +1,18 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+mod bridge;
+mod command;
+mod discovery;
+mod topics;
+mod transport;
+
+pub use bridge::MqttBridge;
+pub use command::{CommandHandler, CommandRequest, CommandResult, MqttCommand, StationCommands};
+pub use discovery::{HomeAssistantDiscovery, RELAY_OFF, RELAY_ON};
+pub use topics::Topics;
+pub use transport::{
+    connect, MqttError, MqttPublisher, Publication, RumqttPublisher, TransportEvent, OFFLINE,
+    ONLINE,
+};
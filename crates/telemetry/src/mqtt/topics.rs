@@ -0,0 +1,105 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use uuid::Uuid;
+
+/// Topic layout of a knot, everything lives under `<prefix>/<knot>`.
+///
+/// - `<prefix>/<knot>/status`: retained `online` / `offline` of the bridge
+/// - `<prefix>/<knot>/command`: commands, results on `command/result`
+/// - `<prefix>/<knot>/<sensor>/<kind>`: measurements
+/// - `<prefix>/<knot>/<sensor>/state`: retained sensor state
+/// - `<prefix>/<knot>/<sensor>/availability`: retained `online` / `offline`
+/// - `<prefix>/<knot>/relay/<relay>/set`: `ON` / `OFF`, state on `.../state`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Topics {
+    base: String,
+    knot: String,
+}
+
+impl Topics {
+    pub fn new(prefix: &str, knot: &str) -> Self {
+        Self {
+            base: format!("{}/{knot}", prefix.trim_end_matches('/')),
+            knot: knot.to_string(),
+        }
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub fn knot(&self) -> &str {
+        &self.knot
+    }
+
+    pub fn status(&self) -> String {
+        format!("{}/status", self.base)
+    }
+
+    pub fn commands(&self) -> String {
+        format!("{}/command", self.base)
+    }
+
+    pub fn command_results(&self) -> String {
+        format!("{}/command/result", self.base)
+    }
+
+    pub fn measurement(&self, sensor_id: Uuid, kind: &str) -> String {
+        format!("{}/{sensor_id}/{kind}", self.base)
+    }
+
+    pub fn sensor_state(&self, sensor_id: Uuid) -> String {
+        format!("{}/{sensor_id}/state", self.base)
+    }
+
+    pub fn sensor_availability(&self, sensor_id: Uuid) -> String {
+        format!("{}/{sensor_id}/availability", self.base)
+    }
+
+    pub fn relay_set(&self, relay_id: &str) -> String {
+        format!("{}/relay/{relay_id}/set", self.base)
+    }
+
+    pub fn relay_state(&self, relay_id: &str) -> String {
+        format!("{}/relay/{relay_id}/state", self.base)
+    }
+
+    /// Filter matching the set topic of every relay.
+    pub fn relay_set_filter(&self) -> String {
+        self.relay_set("+")
+    }
+
+    /// Relay targeted by a set topic.
+    pub fn relay_from_set<'a>(&self, topic: &'a str) -> Option<&'a str> {
+        topic
+            .strip_prefix(self.base.as_str())?
+            .strip_prefix("/relay/")?
+            .strip_suffix("/set")
+            .filter(|relay_id| !relay_id.is_empty() && !relay_id.contains('/'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relay_is_parsed_from_its_set_topic() {
+        let topics = Topics::new("arksync", "greenhouse");
+
+        assert_eq!(
+            topics.relay_from_set("arksync/greenhouse/relay/mist_relay/set"),
+            Some("mist_relay")
+        );
+        assert_eq!(
+            topics.relay_from_set("arksync/greenhouse/relay/a/b/set"),
+            None
+        );
+        assert_eq!(
+            topics.relay_from_set("arksync/other/relay/mist_relay/set"),
+            None
+        );
+    }
+}
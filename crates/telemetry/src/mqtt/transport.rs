@@ -0,0 +1,147 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use rumqttc::{AsyncClient, ConnectionError, Event, LastWill, MqttOptions, Packet, QoS};
+use std::fmt;
+use std::future::Future;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+
+use crate::config::MqttConfig;
+use crate::mqtt::Topics;
+
+/// Requests the client keeps while the broker is unreachable.
+const CLIENT_CAPACITY: usize = 256;
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+pub const ONLINE: &str = "online";
+pub const OFFLINE: &str = "offline";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Publication {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub retain: bool,
+}
+
+impl Publication {
+    pub fn new(topic: String, payload: impl Into<Vec<u8>>, retain: bool) -> Self {
+        Self {
+            topic,
+            payload: payload.into(),
+            retain,
+        }
+    }
+}
+
+/// What the connection reports to the bridge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransportEvent {
+    /// Connected, or connected again, to the broker.
+    Connected,
+    Disconnected(String),
+    Message {
+        topic: String,
+        payload: Vec<u8>,
+    },
+}
+
+#[derive(Debug)]
+pub struct MqttError(pub String);
+
+impl fmt::Display for MqttError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MQTT client error: {}", self.0)
+    }
+}
+
+impl std::error::Error for MqttError {}
+
+/// Outgoing side of an MQTT connection, faked in tests.
+pub trait MqttPublisher: Send + Sync {
+    fn publish(
+        &self,
+        publication: Publication,
+    ) -> impl Future<Output = Result<(), MqttError>> + Send;
+    fn subscribe(&self, filter: &str) -> impl Future<Output = Result<(), MqttError>> + Send;
+}
+
+#[derive(Clone)]
+pub struct RumqttPublisher {
+    client: AsyncClient,
+}
+
+impl MqttPublisher for RumqttPublisher {
+    async fn publish(&self, publication: Publication) -> Result<(), MqttError> {
+        self.client
+            .publish(
+                publication.topic,
+                QoS::AtLeastOnce,
+                publication.retain,
+                publication.payload,
+            )
+            .await
+            .map_err(|err| MqttError(err.to_string()))
+    }
+
+    async fn subscribe(&self, filter: &str) -> Result<(), MqttError> {
+        self.client
+            .subscribe(filter, QoS::AtLeastOnce)
+            .await
+            .map_err(|err| MqttError(err.to_string()))
+    }
+}
+
+/// Connect to the broker configured in `config`.
+///
+/// The connection is driven by a background task reporting on the returned
+/// channel, it reconnects on its own and ends once the publisher is dropped.
+/// The broker marks the knot `offline` on `topics.status()` if we vanish.
+pub fn connect(
+    config: &MqttConfig,
+    topics: &Topics,
+) -> (RumqttPublisher, mpsc::Receiver<TransportEvent>) {
+    let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+    options.set_keep_alive(KEEP_ALIVE);
+    options.set_last_will(LastWill::new(
+        topics.status(),
+        OFFLINE,
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.clone().unwrap_or_default());
+    }
+
+    let (client, mut eventloop) = AsyncClient::new(options, CLIENT_CAPACITY);
+    let (events_tx, events_rx) = mpsc::channel(CLIENT_CAPACITY);
+
+    tokio::spawn(async move {
+        loop {
+            let event = match eventloop.poll().await {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => TransportEvent::Connected,
+                Ok(Event::Incoming(Packet::Publish(publish))) => TransportEvent::Message {
+                    topic: publish.topic,
+                    payload: publish.payload.to_vec(),
+                },
+                Ok(_) => continue,
+                Err(ConnectionError::RequestsDone) => break,
+                Err(err) => {
+                    let _ = events_tx
+                        .send(TransportEvent::Disconnected(err.to_string()))
+                        .await;
+                    sleep(RECONNECT_DELAY).await;
+                    continue;
+                }
+            };
+
+            if events_tx.send(event).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    (RumqttPublisher { client }, events_rx)
+}
@@ -0,0 +1,112 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io;
+use tokio::time::{sleep_until, Duration, Instant};
+
+use crate::queue::segment::Position;
+use crate::queue::{QueuedRecord, TelemetryQueue};
+
+/// Reader of the queue on behalf of one exporter.
+///
+/// Reads always start from the cursor, which only moves on
+/// [`QueueConsumer::ack`]: records read but not acknowledged (e.g. the server
+/// was down) are read again on the next call.
+pub struct QueueConsumer {
+    queue: TelemetryQueue,
+    name: String,
+    cursor: u64,
+    /// Where the last read stopped, to skip scanning the segment again
+    resume: Option<Position>,
+}
+
+impl QueueConsumer {
+    pub(super) fn new(queue: TelemetryQueue, name: String, cursor: u64) -> Self {
+        Self {
+            queue,
+            name,
+            cursor,
+            resume: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Next offset to read.
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+
+    /// Records waiting for this consumer.
+    pub async fn lag(&self) -> u64 {
+        let stats = self.queue.inner().stats();
+
+        stats.next_offset - self.cursor.clamp(stats.oldest_offset, stats.next_offset)
+    }
+
+    /// Wait until at least `min_records` are waiting or `timeout` elapsed.
+    ///
+    /// Returns whether enough records are available.
+    pub async fn wait(&self, min_records: usize, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let appended = self.queue.inner().appended.notified();
+            tokio::pin!(appended);
+            appended.as_mut().enable();
+
+            if self.lag().await >= min_records as u64 {
+                return true;
+            }
+
+            tokio::select! {
+                _ = appended => {}
+                _ = sleep_until(deadline) => return false,
+            }
+        }
+    }
+
+    /// Up to `max` records from the cursor, oldest first.
+    pub async fn read(&mut self, max: usize) -> io::Result<Vec<QueuedRecord>> {
+        let (cursor, position) = (self.cursor, self.resume);
+        let (records, resume, oldest) = self
+            .queue
+            .blocking(move |state| {
+                let (records, resume) = state.read(cursor, position, max)?;
+                Ok((records, resume, state.oldest_offset()))
+            })
+            .await?;
+
+        if cursor < oldest {
+            tracing::warn!(
+                consumer = self.name,
+                from = cursor,
+                to = oldest,
+                "Queue consumer missed records dropped by the caps."
+            );
+        }
+        self.resume = resume;
+
+        Ok(records)
+    }
+
+    /// Acknowledge every record up to `offset` included.
+    pub async fn ack(&mut self, offset: u64) -> io::Result<()> {
+        let name = self.name.clone();
+        let cursor = offset + 1;
+
+        self.queue
+            .blocking(move |state| {
+                state.store_cursor(&name, cursor)?;
+                state.enforce_caps()
+            })
+            .await?;
+        self.cursor = cursor;
+        self.resume = self.resume.filter(|resume| resume.offset == cursor);
+
+        Ok(())
+    }
+}
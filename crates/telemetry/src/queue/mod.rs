@@ -0,0 +1,14 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+mod consumer;
+mod record;
+mod segment;
+mod telemetry_queue;
+mod writer;
+
+pub use consumer::QueueConsumer;
+pub use record::{QueuedRecord, TelemetryRecord};
+pub use telemetry_queue::{ConsumerStats, QueueStats, TelemetryQueue};
+pub use writer::QueueWriter;
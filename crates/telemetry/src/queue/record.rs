@@ -0,0 +1,75 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_sensor::event::SensorEvent;
+use arksync_sensor::sensor::SensorInfo;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// What exporters get out of the queue, a self-contained copy of the sensor
+/// events worth exporting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TelemetryRecord {
+    Measurement {
+        sensor_id: Uuid,
+        kind: String,
+        value: f64,
+        time: DateTime<Utc>,
+    },
+    SensorState {
+        sensor_id: Uuid,
+        kind: String,
+        state: String,
+        reason: String,
+        consecutive_failures: u32,
+        since: DateTime<Utc>,
+    },
+}
+
+impl TelemetryRecord {
+    /// Record of an event, `None` for the ones not worth exporting.
+    pub fn from_event(event: &SensorEvent) -> Option<Self> {
+        match event {
+            SensorEvent::Measurement(measurement) => Some(TelemetryRecord::Measurement {
+                sensor_id: measurement.sensor_id,
+                kind: measurement.kind.as_str().to_string(),
+                value: measurement.value,
+                time: measurement.time,
+            }),
+            SensorEvent::Discovered(info)
+            | SensorEvent::StateChanged { info, .. }
+            | SensorEvent::Removed(info)
+            | SensorEvent::Renamed(info) => Some(Self::sensor_state(info)),
+            // Read latencies are exposed as metrics
+            SensorEvent::ReadCompleted(_) => None,
+        }
+    }
+
+    fn sensor_state(info: &SensorInfo) -> Self {
+        TelemetryRecord::SensorState {
+            sensor_id: info.id,
+            kind: info.kind.as_str().to_string(),
+            state: info.state.as_str().to_string(),
+            reason: info.state_reason.code().to_string(),
+            consecutive_failures: info.consecutive_failures,
+            since: info.state_since,
+        }
+    }
+
+    pub fn sensor_id(&self) -> Uuid {
+        match self {
+            TelemetryRecord::Measurement { sensor_id, .. }
+            | TelemetryRecord::SensorState { sensor_id, .. } => *sensor_id,
+        }
+    }
+}
+
+/// A record and its position in the queue, also the on-disk line format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedRecord {
+    pub offset: u64,
+    pub record: TelemetryRecord,
+}
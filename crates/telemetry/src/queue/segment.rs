@@ -0,0 +1,160 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::queue::QueuedRecord;
+
+const SEGMENT_EXTENSION: &str = "log";
+
+/// A file of the log, named after the offset of its first record.
+///
+/// Records are JSON lines, the segment holds offsets in `[base, next)`.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Segment {
+    pub base: u64,
+    pub next: u64,
+    pub bytes: u64,
+    /// Time of the last append, used for the age cap
+    pub modified: SystemTime,
+}
+
+impl Segment {
+    pub fn empty(base: u64) -> Self {
+        Self {
+            base,
+            next: base,
+            bytes: 0,
+            modified: SystemTime::now(),
+        }
+    }
+
+    pub fn records(&self) -> u64 {
+        self.next - self.base
+    }
+}
+
+/// Where a reader stopped: the record `offset` starts at `byte` in the
+/// segment `segment_base`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Position {
+    pub offset: u64,
+    pub segment_base: u64,
+    pub byte: u64,
+}
+
+pub(super) fn segment_path(dir: &Path, base: u64) -> PathBuf {
+    dir.join(format!("{base:020}.{SEGMENT_EXTENSION}"))
+}
+
+/// Base offsets of the segments found in `dir`, oldest first.
+pub(super) fn list_segments(dir: &Path) -> io::Result<Vec<u64>> {
+    let mut bases = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(SEGMENT_EXTENSION) {
+            continue;
+        }
+        if let Some(base) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u64>().ok())
+        {
+            bases.push(base);
+        }
+    }
+    bases.sort_unstable();
+
+    Ok(bases)
+}
+
+/// Load a segment, cutting what a crash may have left after the last
+/// complete record.
+pub(super) fn recover_segment(dir: &Path, base: u64) -> io::Result<Segment> {
+    let path = segment_path(dir, base);
+    let content = fs::read(&path)?;
+
+    let mut end = 0;
+    let mut next = base;
+    let mut start = 0;
+    while let Some(newline) = content[start..].iter().position(|byte| *byte == b'\n') {
+        let line = &content[start..start + newline];
+        let Ok(entry) = serde_json::from_slice::<QueuedRecord>(line) else {
+            break;
+        };
+        next = entry.offset + 1;
+        start += newline + 1;
+        end = start;
+    }
+
+    if end < content.len() {
+        tracing::warn!(
+            bytes = content.len() - end,
+            path = %path.display(),
+            "Truncating an incomplete queue record."
+        );
+        OpenOptions::new()
+            .write(true)
+            .open(&path)?
+            .set_len(end as u64)?;
+    }
+
+    Ok(Segment {
+        base,
+        next,
+        bytes: end as u64,
+        modified: fs::metadata(&path)?.modified()?,
+    })
+}
+
+/// Read up to `max` records from `from`, starting at `position` when it
+/// points to `from` in this segment.
+pub(super) fn read_segment(
+    dir: &Path,
+    segment: &Segment,
+    from: u64,
+    position: Option<Position>,
+    max: usize,
+    records: &mut Vec<QueuedRecord>,
+) -> io::Result<Position> {
+    let mut file = File::open(segment_path(dir, segment.base))?;
+    let mut byte = 0;
+
+    if let Some(position) =
+        position.filter(|position| position.segment_base == segment.base && position.offset == from)
+    {
+        file.seek(SeekFrom::Start(position.byte))?;
+        byte = position.byte;
+    }
+
+    let mut reader = BufReader::new(file);
+    let mut line = Vec::new();
+    let mut next = from;
+
+    while records.len() < max {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 || line.last() != Some(&b'\n') {
+            break;
+        }
+        byte += read as u64;
+
+        let entry: QueuedRecord = serde_json::from_slice(&line).map_err(io::Error::other)?;
+        if entry.offset < from {
+            continue;
+        }
+        next = entry.offset + 1;
+        records.push(entry);
+    }
+
+    Ok(Position {
+        offset: next,
+        segment_base: segment.base,
+        byte,
+    })
+}
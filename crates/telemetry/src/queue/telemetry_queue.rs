@@ -0,0 +1,512 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+use tokio::sync::Notify;
+
+use crate::config::QueueConfig;
+use crate::queue::segment::{
+    list_segments, read_segment, recover_segment, segment_path, Position, Segment,
+};
+use crate::queue::{QueueConsumer, QueuedRecord, TelemetryRecord};
+
+const CURSORS_DIR: &str = "cursors";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumerStats {
+    pub name: String,
+    /// Next offset the consumer will read
+    pub cursor: u64,
+    /// Records appended but not acknowledged yet
+    pub lag: u64,
+}
+
+/// Backpressure metrics of the queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueStats {
+    pub oldest_offset: u64,
+    pub next_offset: u64,
+    pub bytes: u64,
+    pub segments: usize,
+    /// Records dropped by the size or age caps before every consumer got them
+    pub dropped_records: u64,
+    pub consumers: Vec<ConsumerStats>,
+}
+
+/// Durable store-and-forward queue between the sensor events and exporters.
+///
+/// An append-only log split in segments. Each consumer keeps its own cursor on
+/// disk, advanced when it acknowledges records, so an exporter that was
+/// offline replays everything it missed. Segments consumed by everyone are
+/// deleted, the oldest ones are dropped anyway past the size or age cap.
+#[derive(Clone)]
+pub struct TelemetryQueue {
+    inner: Arc<Inner>,
+}
+
+pub(super) struct Inner {
+    /// Held for the file work in [`TelemetryQueue::blocking`], never on the
+    /// runtime
+    state: Mutex<QueueState>,
+    /// Copy of the offsets and cursors refreshed after each operation, read
+    /// without waiting on the disk
+    stats: Mutex<QueueStats>,
+    pub appended: Notify,
+}
+
+pub(super) struct QueueState {
+    config: QueueConfig,
+    segments: VecDeque<Segment>,
+    active: File,
+    dropped_records: u64,
+    cursors: HashMap<String, u64>,
+}
+
+impl TelemetryQueue {
+    /// Open the queue in `config.dir`, recovering segments and cursors left by
+    /// a previous run.
+    pub fn open(config: &QueueConfig) -> io::Result<Self> {
+        fs::create_dir_all(config.dir.join(CURSORS_DIR))?;
+
+        let mut segments = VecDeque::new();
+        let bases = list_segments(&config.dir)?;
+        for (i, base) in bases.iter().enumerate() {
+            let segment = match bases.get(i + 1) {
+                // Only the last segment can hold a partial record
+                Some(next_base) => Segment {
+                    next: *next_base,
+                    ..recover_segment_metadata(config, *base)?
+                },
+                None => recover_segment(&config.dir, *base)?,
+            };
+            segments.push_back(segment);
+        }
+
+        if segments.is_empty() {
+            segments.push_back(Segment::empty(0));
+        }
+
+        let active_base = segments.back().map_or(0, |segment| segment.base);
+        let active = open_segment(config, active_base)?;
+        let cursors = load_cursors(config)?;
+        let state = QueueState {
+            config: config.clone(),
+            segments,
+            active,
+            dropped_records: 0,
+            cursors,
+        };
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                stats: Mutex::new(state.stats()),
+                state: Mutex::new(state),
+                appended: Notify::new(),
+            }),
+        })
+    }
+
+    /// Append records, returns the offset of the last one.
+    pub async fn append(&self, records: &[TelemetryRecord]) -> io::Result<u64> {
+        let records = records.to_vec();
+        let last = self.blocking(move |state| state.append(&records)).await?;
+        self.inner.appended.notify_waiters();
+
+        Ok(last)
+    }
+
+    /// Flush appended records to disk and apply the caps.
+    pub async fn sync(&self) -> io::Result<()> {
+        self.blocking(|state| {
+            state.active.sync_data()?;
+            state.enforce_caps()
+        })
+        .await
+    }
+
+    /// A consumer resuming from its last acknowledged record, or from the
+    /// oldest record for a new one.
+    pub async fn consumer(&self, name: &str) -> io::Result<QueueConsumer> {
+        let owned = name.to_string();
+        let cursor = self
+            .blocking(move |state| match state.cursors.get(&owned) {
+                Some(cursor) => Ok(*cursor),
+                None => {
+                    let cursor = state.oldest_offset();
+                    state.store_cursor(&owned, cursor)?;
+                    Ok(cursor)
+                }
+            })
+            .await?;
+
+        Ok(QueueConsumer::new(self.clone(), name.to_string(), cursor))
+    }
+
+    /// Stats as of the last operation, never waits on the disk.
+    pub fn stats(&self) -> QueueStats {
+        self.inner.stats().clone()
+    }
+
+    pub(super) fn inner(&self) -> &Inner {
+        &self.inner
+    }
+
+    /// Run `f` on the state from a blocking thread, so that the file work
+    /// never stalls a runtime worker, then refresh the stats.
+    pub(super) async fn blocking<T, F>(&self, f: F) -> io::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut QueueState) -> io::Result<T> + Send + 'static,
+    {
+        let inner = Arc::clone(&self.inner);
+
+        tokio::task::spawn_blocking(move || {
+            let mut state = inner.state.lock().expect("queue state mutex poisoned");
+            let result = f(&mut state);
+            *inner.stats() = state.stats();
+            result
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+}
+
+impl Inner {
+    pub fn stats(&self) -> MutexGuard<'_, QueueStats> {
+        self.stats.lock().expect("queue stats mutex poisoned")
+    }
+}
+
+impl QueueState {
+    pub fn oldest_offset(&self) -> u64 {
+        self.segments.front().map_or(0, |segment| segment.base)
+    }
+
+    pub fn next_offset(&self) -> u64 {
+        self.segments.back().map_or(0, |segment| segment.next)
+    }
+
+    fn append(&mut self, records: &[TelemetryRecord]) -> io::Result<u64> {
+        let mut buffer = Vec::new();
+        let mut next = self.next_offset();
+
+        for record in records {
+            let entry = QueuedRecord {
+                offset: next,
+                record: record.clone(),
+            };
+            let mut line = serde_json::to_vec(&entry).map_err(io::Error::other)?;
+            line.push(b'\n');
+
+            let active_bytes = self.segments.back().map_or(0, |segment| segment.bytes);
+            if active_bytes > 0
+                && active_bytes + (buffer.len() + line.len()) as u64 > self.config.segment_max_bytes
+            {
+                self.write_active(&buffer, next)?;
+                buffer.clear();
+                self.roll(next)?;
+            }
+
+            buffer.extend_from_slice(&line);
+            next += 1;
+        }
+
+        self.write_active(&buffer, next)?;
+
+        Ok(next.saturating_sub(1))
+    }
+
+    fn write_active(&mut self, buffer: &[u8], next: u64) -> io::Result<()> {
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        self.active.write_all(buffer)?;
+        if let Some(segment) = self.segments.back_mut() {
+            segment.next = next;
+            segment.bytes += buffer.len() as u64;
+            segment.modified = SystemTime::now();
+        }
+
+        Ok(())
+    }
+
+    /// Close the active segment and start a new one at `base`.
+    fn roll(&mut self, base: u64) -> io::Result<()> {
+        self.active.sync_data()?;
+        self.active = open_segment(&self.config, base)?;
+        self.segments.push_back(Segment::empty(base));
+
+        self.enforce_caps()
+    }
+
+    /// Delete the segments every consumer is done with, then the oldest ones
+    /// while the queue is over its size or age cap. The active segment is
+    /// always kept.
+    pub fn enforce_caps(&mut self) -> io::Result<()> {
+        let min_cursor = self.cursors.values().min().copied();
+        let now = SystemTime::now();
+
+        while self.segments.len() > 1 {
+            let Some(oldest) = self.segments.front().copied() else {
+                break;
+            };
+            let consumed = min_cursor.is_some_and(|cursor| cursor >= oldest.next);
+            let bytes: u64 = self.segments.iter().map(|segment| segment.bytes).sum();
+            let expired = now
+                .duration_since(oldest.modified)
+                .is_ok_and(|age| age > self.config.max_age);
+
+            if !consumed && !expired && bytes <= self.config.max_bytes {
+                break;
+            }
+
+            if !consumed {
+                let unread = oldest.next - min_cursor.unwrap_or(oldest.base).max(oldest.base);
+                self.dropped_records += unread.min(oldest.records());
+                tracing::warn!(
+                    from = oldest.base,
+                    to = oldest.next,
+                    "Queue over its caps, records dropped."
+                );
+            }
+
+            match fs::remove_file(segment_path(&self.config.dir, oldest.base)) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+            self.segments.pop_front();
+        }
+
+        Ok(())
+    }
+
+    /// Read up to `max` records from `cursor`, skipping the ones dropped by
+    /// the caps.
+    pub fn read(
+        &self,
+        cursor: u64,
+        position: Option<Position>,
+        max: usize,
+    ) -> io::Result<(Vec<QueuedRecord>, Option<Position>)> {
+        let from = cursor.max(self.oldest_offset());
+        let mut records: Vec<QueuedRecord> = Vec::new();
+        let mut resume = None;
+
+        for segment in self.segments.iter().filter(|segment| segment.next > from) {
+            if records.len() >= max {
+                break;
+            }
+
+            let read_from = records.last().map_or(from, |last| last.offset + 1);
+            let count = records.len();
+            let position = read_segment(
+                &self.config.dir,
+                segment,
+                read_from,
+                position,
+                max,
+                &mut records,
+            )?;
+
+            if records.len() > count {
+                resume = Some(position);
+            }
+        }
+
+        Ok((records, resume))
+    }
+
+    pub fn store_cursor(&mut self, name: &str, cursor: u64) -> io::Result<()> {
+        let path = cursor_path(&self.config, name);
+        let tmp = path.with_extension("tmp");
+
+        fs::write(&tmp, cursor.to_string())?;
+        fs::rename(&tmp, &path)?;
+        self.cursors.insert(name.to_string(), cursor);
+
+        Ok(())
+    }
+
+    fn stats(&self) -> QueueStats {
+        let oldest_offset = self.oldest_offset();
+        let next_offset = self.next_offset();
+        let mut consumers: Vec<ConsumerStats> = self
+            .cursors
+            .iter()
+            .map(|(name, cursor)| ConsumerStats {
+                name: name.clone(),
+                cursor: *cursor,
+                lag: next_offset - (*cursor).clamp(oldest_offset, next_offset),
+            })
+            .collect();
+        consumers.sort_by(|a, b| a.name.cmp(&b.name));
+
+        QueueStats {
+            oldest_offset,
+            next_offset,
+            bytes: self.segments.iter().map(|segment| segment.bytes).sum(),
+            segments: self.segments.len(),
+            dropped_records: self.dropped_records,
+            consumers,
+        }
+    }
+}
+
+fn recover_segment_metadata(config: &QueueConfig, base: u64) -> io::Result<Segment> {
+    let metadata = fs::metadata(segment_path(&config.dir, base))?;
+
+    Ok(Segment {
+        base,
+        next: base,
+        bytes: metadata.len(),
+        modified: metadata.modified()?,
+    })
+}
+
+fn open_segment(config: &QueueConfig, base: u64) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(segment_path(&config.dir, base))
+}
+
+fn cursor_path(config: &QueueConfig, name: &str) -> PathBuf {
+    config.dir.join(CURSORS_DIR).join(name)
+}
+
+fn load_cursors(config: &QueueConfig) -> io::Result<HashMap<String, u64>> {
+    let mut cursors = HashMap::new();
+
+    for entry in fs::read_dir(config.dir.join(CURSORS_DIR))? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if path.extension().is_some() {
+            continue;
+        }
+        if let Ok(cursor) = fs::read_to_string(&path)?.trim().parse::<u64>() {
+            cursors.insert(name.to_string(), cursor);
+        }
+    }
+
+    Ok(cursors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::path::Path;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    fn config(dir: &Path, max_bytes: u64) -> QueueConfig {
+        QueueConfig {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            max_age: Duration::from_secs(3600),
+            segment_max_bytes: 256,
+        }
+    }
+
+    fn records(count: usize) -> Vec<TelemetryRecord> {
+        (0..count)
+            .map(|i| TelemetryRecord::Measurement {
+                sensor_id: Uuid::nil(),
+                kind: "temperature".to_string(),
+                value: i as f64,
+                time: Utc::now(),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn consumers_resume_from_their_own_cursor_after_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = TelemetryQueue::open(&config(dir.path(), 1024 * 1024)).unwrap();
+        let mut influx = queue.consumer("influx").await.unwrap();
+        let mut mqtt = queue.consumer("mqtt").await.unwrap();
+        queue.append(&records(10)).await.unwrap();
+
+        let read = influx.read(4).await.unwrap();
+        influx.ack(read[3].offset).await.unwrap();
+        // Read but not acknowledged, mqtt gets it again
+        mqtt.read(4).await.unwrap();
+        queue.sync().await.unwrap();
+        drop(queue);
+
+        let queue = TelemetryQueue::open(&config(dir.path(), 1024 * 1024)).unwrap();
+        let mut influx = queue.consumer("influx").await.unwrap();
+        let mut mqtt = queue.consumer("mqtt").await.unwrap();
+
+        assert_eq!(influx.read(100).await.unwrap()[0].offset, 4);
+        assert_eq!(mqtt.read(100).await.unwrap().len(), 10);
+        assert_eq!(queue.append(&records(1)).await.unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn oldest_segments_are_dropped_past_the_size_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = TelemetryQueue::open(&config(dir.path(), 512)).unwrap();
+        let mut consumer = queue.consumer("influx").await.unwrap();
+
+        for _ in 0..20 {
+            queue.append(&records(1)).await.unwrap();
+        }
+        let stats = queue.stats();
+
+        assert!(stats.bytes <= 512 + 256);
+        assert!(stats.dropped_records > 0);
+        assert_eq!(stats.oldest_offset, stats.dropped_records);
+        assert_eq!(
+            consumer.read(100).await.unwrap()[0].offset,
+            stats.oldest_offset
+        );
+    }
+
+    #[tokio::test]
+    async fn consumed_segments_are_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = TelemetryQueue::open(&config(dir.path(), 1024 * 1024)).unwrap();
+        let mut consumer = queue.consumer("influx").await.unwrap();
+
+        for _ in 0..20 {
+            queue.append(&records(1)).await.unwrap();
+        }
+        let read = consumer.read(100).await.unwrap();
+        consumer.ack(read.last().unwrap().offset).await.unwrap();
+
+        let stats = queue.stats();
+        assert_eq!(stats.segments, 1);
+        assert_eq!(stats.dropped_records, 0);
+        assert_eq!(stats.consumers[0].lag, 0);
+    }
+
+    #[tokio::test]
+    async fn partial_record_is_cut_on_recovery() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = TelemetryQueue::open(&config(dir.path(), 1024 * 1024)).unwrap();
+        queue.append(&records(1)).await.unwrap();
+        drop(queue);
+
+        let mut segment = OpenOptions::new()
+            .append(true)
+            .open(segment_path(dir.path(), 0))
+            .unwrap();
+        segment.write_all(br#"{"offset":1,"rec"#).unwrap();
+
+        let queue = TelemetryQueue::open(&config(dir.path(), 1024 * 1024)).unwrap();
+        let mut consumer = queue.consumer("influx").await.unwrap();
+
+        assert_eq!(queue.append(&records(1)).await.unwrap(), 1);
+        assert_eq!(consumer.read(100).await.unwrap().len(), 2);
+    }
+}
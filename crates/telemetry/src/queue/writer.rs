@@ -0,0 +1,80 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_sensor::event::SensorEvent;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+use crate::queue::{TelemetryQueue, TelemetryRecord};
+
+/// Appended records are flushed to disk at least this often.
+const DEFAULT_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Subscriber appending the sensor events to the telemetry queue.
+pub struct QueueWriter {
+    queue: TelemetryQueue,
+    sync_interval: Duration,
+}
+
+impl QueueWriter {
+    pub fn new(queue: TelemetryQueue) -> Self {
+        Self {
+            queue,
+            sync_interval: DEFAULT_SYNC_INTERVAL,
+        }
+    }
+
+    pub fn with_sync_interval(mut self, sync_interval: Duration) -> Self {
+        self.sync_interval = sync_interval;
+        self
+    }
+
+    pub async fn run(
+        self,
+        mut events: broadcast::Receiver<SensorEvent>,
+        shutdown: CancellationToken,
+    ) {
+        let mut ticker = interval(self.sync_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => self.append(&event).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Queue writer lagging behind, sensor events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = ticker.tick() => self.sync().await,
+                _ = shutdown.cancelled() => {
+                    while let Ok(event) = events.try_recv() {
+                        self.append(&event).await;
+                    }
+                    break;
+                }
+            }
+        }
+
+        self.sync().await;
+        tracing::info!("Queue writer stopped.");
+    }
+
+    async fn append(&self, event: &SensorEvent) {
+        let Some(record) = TelemetryRecord::from_event(event) else {
+            return;
+        };
+
+        if let Err(err) = self.queue.append(&[record]).await {
+            tracing::error!(error = %err, "Failed to append a sensor event to the queue.");
+        }
+    }
+
+    async fn sync(&self) {
+        if let Err(err) = self.queue.sync().await {
+            tracing::error!(error = %err, "Failed to sync the queue.");
+        }
+    }
+}
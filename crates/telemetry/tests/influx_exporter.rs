@@ -0,0 +1,169 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_telemetry::{
+    InfluxConfig, InfluxExporter, QueueConfig, TelemetryQueue, TelemetryRecord,
+};
+use chrono::Utc;
+use std::path::Path;
+use tokio::time::{sleep, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+use wiremock::matchers::{body_string_contains, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn influx_config(server: &MockServer) -> InfluxConfig {
+    InfluxConfig {
+        enabled: true,
+        url: server.uri(),
+        database: "arksync_series".to_string(),
+        token: Some("secret".to_string()),
+    }
+}
+
+fn queue(dir: &Path) -> TelemetryQueue {
+    TelemetryQueue::open(&QueueConfig {
+        dir: dir.to_path_buf(),
+        max_bytes: 1024 * 1024,
+        max_age: Duration::from_secs(3600),
+        segment_max_bytes: 64 * 1024,
+    })
+    .unwrap()
+}
+
+fn measurement(value: f64) -> TelemetryRecord {
+    TelemetryRecord::Measurement {
+        sensor_id: Uuid::new_v4(),
+        kind: "temperature".to_string(),
+        value,
+        time: Utc::now(),
+    }
+}
+
+/// Run the exporter until the queue is drained, or fail after a few seconds.
+async fn export_until_drained(exporter: InfluxExporter, queue: &TelemetryQueue) {
+    let consumer = queue.consumer(InfluxExporter::CONSUMER).await.unwrap();
+    let shutdown = CancellationToken::new();
+    let task = tokio::spawn(exporter.run(consumer, shutdown.clone()));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while queue.stats().consumers[0].lag > 0 {
+        assert!(Instant::now() < deadline, "queue not drained");
+        sleep(Duration::from_millis(20)).await;
+    }
+
+    shutdown.cancel();
+    task.await.unwrap();
+}
+
+#[tokio::test]
+async fn measurements_are_written_as_line_protocol() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v3/write_lp"))
+        .and(query_param("db", "arksync_series"))
+        .and(header("authorization", "Bearer secret"))
+        .and(body_string_contains("kind=temperature value=21.5"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let queue = queue(dir.path());
+    queue.append(&[measurement(21.5)]).await.unwrap();
+
+    let exporter = InfluxExporter::new(&influx_config(&server))
+        .unwrap()
+        .with_flush_interval(Duration::from_millis(20));
+    export_until_drained(exporter, &queue).await;
+}
+
+#[tokio::test]
+async fn database_name_is_url_encoded() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(query_param("db", "reef & sump"))
+        .and(query_param("precision", "nanosecond"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let queue = queue(dir.path());
+    queue.append(&[measurement(21.5)]).await.unwrap();
+
+    let exporter = InfluxExporter::new(&InfluxConfig {
+        database: "reef & sump".to_string(),
+        ..influx_config(&server)
+    })
+    .unwrap()
+    .with_flush_interval(Duration::from_millis(20));
+    export_until_drained(exporter, &queue).await;
+}
+
+#[tokio::test]
+async fn records_are_replayed_once_the_server_is_back() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("value=7"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let queue = queue(dir.path());
+    queue.append(&[measurement(7.0)]).await.unwrap();
+
+    let exporter = InfluxExporter::new(&influx_config(&server))
+        .unwrap()
+        .with_flush_interval(Duration::from_millis(20))
+        .with_backoff(Duration::from_millis(50), Duration::from_millis(50));
+    export_until_drained(exporter, &queue).await;
+}
+
+#[tokio::test]
+async fn only_the_rejected_lines_are_dropped() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(query_param("accept_partial", "false"))
+        .and(body_string_contains("value=99"))
+        .respond_with(ResponseTemplate::new(400))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let queue = queue(dir.path());
+    let values = [10.0, 20.0, 99.0, 30.0, 40.0];
+    queue.append(&values.map(measurement)).await.unwrap();
+
+    let exporter = InfluxExporter::new(&influx_config(&server))
+        .unwrap()
+        .with_flush_interval(Duration::from_millis(20));
+    export_until_drained(exporter, &queue).await;
+
+    let written: Vec<String> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|request| String::from_utf8(request.body).unwrap())
+        .filter(|body| !body.contains("value=99"))
+        .collect();
+    for value in ["value=10", "value=20", "value=30", "value=40"] {
+        assert!(written.iter().any(|body| body.contains(value)), "{value}");
+    }
+}
@@ -0,0 +1,36 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::relay::{RelayState, MIST_RELAY};
+use arksync_telemetry::metrics;
+use arksync_telemetry::StationMetrics;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+#[tokio::test]
+async fn metrics_are_scraped_over_http() {
+    let station_metrics = StationMetrics::new();
+    station_metrics.record_relay(&RelayState::new(MIST_RELAY, false));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let shutdown = CancellationToken::new();
+    let server = tokio::spawn(metrics::serve(listener, station_metrics, shutdown.clone()));
+
+    let response = reqwest::get(format!("http://{addr}/metrics"))
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("application/openmetrics-text"));
+
+    let body = response.text().await.unwrap();
+    assert!(body.contains(r#"arksync_relay_active{relay="mist_relay"} 0"#));
+    assert!(body.ends_with("# EOF\n"));
+
+    shutdown.cancel();
+    server.await.unwrap().unwrap();
+}
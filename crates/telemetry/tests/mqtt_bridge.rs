@@ -0,0 +1,275 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::event::{ActuatorEvent, SwitchReason};
+use arksync_actuator::relay::{RelayState, MIST_RELAY};
+use arksync_sensor::event::SensorEvent;
+use arksync_sensor::i2c_bus::I2cConnection;
+use arksync_sensor::sensor::{
+    SensorConnection, SensorInfo, SensorKind, SensorName, SensorState, SensorStateReason,
+};
+use arksync_telemetry::mqtt::{
+    CommandHandler, HomeAssistantDiscovery, MqttBridge, MqttCommand, MqttError, MqttPublisher,
+    Publication, Topics, TransportEvent,
+};
+use arksync_telemetry::{QueueConfig, TelemetryQueue, TelemetryRecord};
+use chrono::Utc;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{sleep, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// In-process stand-in for the broker connection.
+#[derive(Clone, Default)]
+struct FakeBroker {
+    published: Arc<Mutex<Vec<Publication>>>,
+    subscriptions: Arc<Mutex<Vec<String>>>,
+}
+
+impl FakeBroker {
+    fn last_on(&self, topic: &str) -> Option<Publication> {
+        self.published
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|publication| publication.topic == topic)
+            .cloned()
+    }
+
+    async fn wait_for(&self, topic: &str, payload: &str) -> Publication {
+        self.wait_until(topic, |publication| {
+            String::from_utf8_lossy(&publication.payload).contains(payload)
+        })
+        .await
+    }
+
+    async fn wait_until(&self, topic: &str, matches: impl Fn(&Publication) -> bool) -> Publication {
+        let deadline = Instant::now() + Duration::from_secs(5);
+
+        loop {
+            if let Some(publication) = self.last_on(topic).filter(&matches) {
+                return publication;
+            }
+            assert!(Instant::now() < deadline, "nothing expected on {topic}");
+            sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
+
+impl MqttPublisher for FakeBroker {
+    async fn publish(&self, publication: Publication) -> Result<(), MqttError> {
+        self.published.lock().unwrap().push(publication);
+        Ok(())
+    }
+
+    async fn subscribe(&self, filter: &str) -> Result<(), MqttError> {
+        self.subscriptions.lock().unwrap().push(filter.to_string());
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+struct RecordedCommands(Arc<Mutex<Vec<MqttCommand>>>);
+
+impl CommandHandler for RecordedCommands {
+    async fn handle(&self, command: MqttCommand) -> Result<(), String> {
+        self.0.lock().unwrap().push(command);
+        Ok(())
+    }
+}
+
+fn sensor(state: SensorState) -> SensorInfo {
+    SensorInfo {
+        id: Uuid::nil(),
+        hardware_uid: "i2c:1:0x66".to_string(),
+        kind: SensorKind::Temperature,
+        firmware: 2.1,
+        name: SensorName::Named("tank".to_string()),
+        state,
+        state_reason: SensorStateReason::MeasurementOk,
+        state_since: Utc::now(),
+        last_activity: Utc::now(),
+        consecutive_failures: 0,
+        connection: SensorConnection::I2c(I2cConnection {
+            bus: 1,
+            address: 0x66,
+        }),
+    }
+}
+
+fn open_queue(dir: &tempfile::TempDir) -> TelemetryQueue {
+    TelemetryQueue::open(&QueueConfig {
+        dir: dir.path().to_path_buf(),
+        max_bytes: 1024 * 1024,
+        max_age: Duration::from_secs(3600),
+        segment_max_bytes: 64 * 1024,
+    })
+    .unwrap()
+}
+
+#[tokio::test]
+async fn bridge_publishes_states_measurements_and_handles_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    let queue = open_queue(&dir);
+    let consumer = queue.consumer("mqtt").await.unwrap();
+    // Recorded while the broker was away
+    queue
+        .append(&[TelemetryRecord::Measurement {
+            sensor_id: Uuid::nil(),
+            kind: "temperature".to_string(),
+            value: 21.5,
+            time: Utc::now(),
+        }])
+        .await
+        .unwrap();
+
+    let broker = FakeBroker::default();
+    let commands = RecordedCommands::default();
+    let topics = Topics::new("arksync", "test");
+    let (transport_tx, transport_rx) = mpsc::channel(8);
+    let (events_tx, events_rx) = broadcast::channel(8);
+    let shutdown = CancellationToken::new();
+
+    let bridge = MqttBridge::new(broker.clone(), commands.clone(), topics.clone())
+        .with_sensors([sensor(SensorState::Active)])
+        .with_flush_interval(Duration::from_millis(20));
+    let task = tokio::spawn(bridge.run(transport_rx, events_rx, consumer, shutdown.clone()));
+
+    transport_tx.send(TransportEvent::Connected).await.unwrap();
+    broker.wait_for(&topics.status(), "online").await;
+    broker
+        .wait_for(&topics.sensor_availability(Uuid::nil()), "online")
+        .await;
+    broker
+        .wait_for(&topics.measurement(Uuid::nil(), "temperature"), "21.5")
+        .await;
+    assert!(broker
+        .subscriptions
+        .lock()
+        .unwrap()
+        .contains(&topics.commands()));
+
+    events_tx
+        .send(SensorEvent::StateChanged {
+            previous: SensorState::Active,
+            info: sensor(SensorState::Unreachable),
+        })
+        .unwrap();
+    let availability = broker
+        .wait_for(&topics.sensor_availability(Uuid::nil()), "offline")
+        .await;
+    assert!(availability.retain);
+
+    transport_tx
+        .send(TransportEvent::Message {
+            topic: topics.commands(),
+            payload: format!(
+                r#"{{"id": "1", "command": "read_sensor", "sensor": "{}"}}"#,
+                Uuid::nil()
+            )
+            .into_bytes(),
+        })
+        .await
+        .unwrap();
+    broker
+        .wait_for(&topics.command_results(), r#"{"id":"1","ok":true}"#)
+        .await;
+    assert_eq!(
+        commands.0.lock().unwrap().as_slice(),
+        [MqttCommand::ReadSensor {
+            sensor: Uuid::nil()
+        }]
+    );
+
+    shutdown.cancel();
+    task.await.unwrap();
+    assert_eq!(
+        broker.last_on(&topics.status()).unwrap().payload,
+        b"offline"
+    );
+}
+
+#[tokio::test]
+async fn bridge_announces_hot_plugged_sensors_and_relays_to_home_assistant() {
+    let dir = tempfile::tempdir().unwrap();
+    let consumer = open_queue(&dir).consumer("mqtt").await.unwrap();
+
+    let broker = FakeBroker::default();
+    let commands = RecordedCommands::default();
+    let topics = Topics::new("arksync", "test");
+    let (transport_tx, transport_rx) = mpsc::channel(8);
+    let (events_tx, events_rx) = broadcast::channel(8);
+    let (relay_events_tx, relay_events_rx) = broadcast::channel(8);
+    let shutdown = CancellationToken::new();
+
+    let bridge = MqttBridge::new(broker.clone(), commands.clone(), topics.clone())
+        .with_relays(
+            [(MIST_RELAY, RelayState::new(MIST_RELAY, false))],
+            relay_events_rx,
+        )
+        .with_discovery(HomeAssistantDiscovery::new("homeassistant"));
+    let task = tokio::spawn(bridge.run(transport_rx, events_rx, consumer, shutdown.clone()));
+
+    transport_tx.send(TransportEvent::Connected).await.unwrap();
+    let switch = "homeassistant/switch/arksync_test/mist_relay/config";
+    broker
+        .wait_for(switch, &topics.relay_set("mist_relay"))
+        .await;
+    let state = broker
+        .wait_for(&topics.relay_state("mist_relay"), "OFF")
+        .await;
+    assert!(state.retain);
+
+    let sensor_config = format!("homeassistant/sensor/arksync_test/{}/config", Uuid::nil());
+    events_tx
+        .send(SensorEvent::Discovered(sensor(SensorState::Active)))
+        .unwrap();
+    let config = broker
+        .wait_for(&sensor_config, r#""device_class":"temperature""#)
+        .await;
+    assert!(config.retain);
+
+    events_tx
+        .send(SensorEvent::Removed(sensor(SensorState::Unplugged)))
+        .unwrap();
+    broker
+        .wait_until(&sensor_config, |publication| publication.payload.is_empty())
+        .await;
+
+    transport_tx
+        .send(TransportEvent::Message {
+            topic: topics.relay_set("mist_relay"),
+            payload: b"ON".to_vec(),
+        })
+        .await
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while commands.0.lock().unwrap().is_empty() {
+        assert!(Instant::now() < deadline, "relay command not handled");
+        sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(
+        commands.0.lock().unwrap().as_slice(),
+        [MqttCommand::SetRelay {
+            relay: "mist_relay".to_string(),
+            on: true
+        }]
+    );
+
+    // Switched by anyone, e.g. a schedule
+    relay_events_tx
+        .send(ActuatorEvent::StateChanged {
+            state: RelayState::new(MIST_RELAY, true),
+            reason: SwitchReason::Command,
+        })
+        .unwrap();
+    broker
+        .wait_for(&topics.relay_state("mist_relay"), "ON")
+        .await;
+
+    shutdown.cancel();
+    task.await.unwrap();
+}
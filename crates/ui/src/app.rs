@@ -2,16 +2,39 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use crate::components::alert_list::AlertList;
 use crate::components::charts::{AirTemperatureGauge, WaterTemperatureChart};
 use crate::components::grid::{GridItem, GridLayout};
+use crate::components::node_list::NodeList;
 use crate::components::page_layout::PageLayout;
+use crate::components::relay_switches::RelaySwitches;
+use crate::components::sensor_list::SensorList;
 use crate::components::sidebar::Sidebar;
 use crate::theme::ArkSyncTheme;
+use futures_util::StreamExt as _;
+use leptos::logging::log;
 use leptos::prelude::*;
 use leptos_router::{
     components::{Route, Router, Routes},
     path,
 };
+use serde::Deserialize;
+use tauri_sys::core::invoke_result;
+use tauri_sys::event::listen;
+use wasm_bindgen_futures::spawn_local;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Sensor {
+    id: String,
+    kind: String,
+    connection: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct SensorChanged {
+    sensor: Sensor,
+}
 
 #[component]
 pub fn App() -> impl IntoView {
@@ -24,6 +47,9 @@ pub fn App() -> impl IntoView {
                         <Routes fallback=|| "Not found.">
                             <Route path=path!("/") view=Home/>
                             <Route path=path!("/dashboards") view=Dashboards />
+                            <Route path=path!("/alerts") view=Alerts />
+                            <Route path=path!("/sensors") view=Sensors />
+                            <Route path=path!("/nodes") view=Nodes />
                         </Routes>
                     </section>
                 </div>
@@ -34,14 +60,61 @@ pub fn App() -> impl IntoView {
 
 #[component]
 pub fn Dashboards() -> impl IntoView {
+    // Temperature sensors sorted by connection, the first one shows the air
+    // and the last one the water until dashboards can be configured.
+    let temperatures = RwSignal::new(Vec::<Sensor>::new());
+    let add = move |sensor: Sensor| {
+        if sensor.kind != "temperature" {
+            return;
+        }
+        temperatures.update(|sensors| {
+            if sensors.iter().all(|known| known.id != sensor.id) {
+                sensors.push(sensor);
+                sensors.sort_by(|a, b| a.connection.cmp(&b.connection));
+            }
+        });
+    };
+
+    Effect::new(move |_| {
+        spawn_local(async move {
+            match invoke_result::<Vec<Sensor>, String>("sensors", &()).await {
+                Ok(sensors) => sensors.into_iter().for_each(add),
+                Err(error) => log!("Failed to load sensors: {}", error),
+            }
+        });
+
+        spawn_local(async move {
+            let mut stream = match listen::<SensorChanged>("sensor_changed").await {
+                Ok(s) => s,
+                Err(e) => {
+                    log!("Failed to subscribe to sensor_changed: {}", e);
+                    return;
+                }
+            };
+
+            while let Some(event) = stream.next().await {
+                add(event.payload.sensor);
+            }
+        });
+    });
+
+    let air =
+        Memo::new(move |_| temperatures.with(|sensors| sensors.first().map(|s| s.id.clone())));
+    let water =
+        Memo::new(move |_| temperatures.with(|sensors| sensors.last().map(|s| s.id.clone())));
+
     view! {
         <div class="h-full">
             <GridLayout columns=12 display_grid=false>
                 <GridItem id=1 col_start=0 col_span=4 row_start=0 row_span=2 label="Air temperature".to_string()>
-                    <AirTemperatureGauge theme=ArkSyncTheme::Walden />
+                    {move || air.get().map(|sensor_id| view! {
+                        <AirTemperatureGauge sensor_id=sensor_id theme=ArkSyncTheme::Walden />
+                    })}
                 </GridItem>
                 <GridItem id=2 col_start=2 col_span=5 row_start=4 row_span=4>
-                    <WaterTemperatureChart theme=ArkSyncTheme::Walden />
+                    {move || water.get().map(|sensor_id| view! {
+                        <WaterTemperatureChart sensor_id=sensor_id theme=ArkSyncTheme::Walden />
+                    })}
                 </GridItem>
                 // <GridItem id=3 col_start=0 col_span=3 row_start=0 row_span=4>
                 //     No data yet
@@ -51,6 +124,33 @@ pub fn Dashboards() -> impl IntoView {
     }
 }
 
+#[component]
+pub fn Alerts() -> impl IntoView {
+    view! {
+        <PageLayout eyebrow="Station" title="Alerts">
+            <AlertList />
+        </PageLayout>
+    }
+}
+
+#[component]
+pub fn Sensors() -> impl IntoView {
+    view! {
+        <PageLayout eyebrow="Station" title="Sensors">
+            <SensorList />
+        </PageLayout>
+    }
+}
+
+#[component]
+pub fn Nodes() -> impl IntoView {
+    view! {
+        <PageLayout eyebrow="Station" title="Nodes">
+            <NodeList />
+        </PageLayout>
+    }
+}
+
 #[component]
 pub fn Home() -> impl IntoView {
     view! {
@@ -58,6 +158,7 @@ pub fn Home() -> impl IntoView {
             <p class="mt-3 max-w-xl text-sk-carbon-300">
                 "Environmental monitoring and regulation system."
             </p>
+            <RelaySwitches />
         </PageLayout>
     }
 }
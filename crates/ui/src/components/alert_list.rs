@@ -0,0 +1,170 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use futures_util::StreamExt as _;
+use leptos::prelude::*;
+use leptos::{logging::log, IntoView};
+use serde::{Deserialize, Serialize};
+use tauri_sys::core::invoke_result;
+use tauri_sys::event::listen;
+use wasm_bindgen_futures::spawn_local;
+
+const HISTORY_LIMIT: u32 = 50;
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Alert {
+    id: String,
+    rule_name: String,
+    severity: String,
+    message: String,
+    raised_at: String,
+    acknowledged_by: Option<String>,
+    resolved_at: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct AlertChanged {
+    alert: Alert,
+}
+
+#[derive(Serialize)]
+struct AlertHistoryArgs {
+    limit: u32,
+}
+
+#[derive(Serialize)]
+struct AcknowledgeAlertArgs<'a> {
+    id: &'a str,
+}
+
+fn severity_class(severity: &str) -> &'static str {
+    match severity {
+        "critical" => "text-red-400",
+        "warning" => "text-amber-300",
+        _ => "text-sk-carbon-300",
+    }
+}
+
+/// Open alerts, to acknowledge, then the latest ones, kept up to date by the
+/// alert service.
+#[component]
+pub fn AlertList() -> impl IntoView {
+    let open = RwSignal::new(Vec::<Alert>::new());
+    let history = RwSignal::new(Vec::<Alert>::new());
+
+    Effect::new(move |_| {
+        spawn_local(async move {
+            match invoke_result::<Vec<Alert>, String>("open_alerts", &()).await {
+                Ok(alerts) => open.set(alerts),
+                Err(error) => log!("Failed to load open alerts: {}", error),
+            }
+            let args = AlertHistoryArgs {
+                limit: HISTORY_LIMIT,
+            };
+            match invoke_result::<Vec<Alert>, String>("alert_history", &args).await {
+                Ok(alerts) => history.set(alerts),
+                Err(error) => log!("Failed to load alert history: {}", error),
+            }
+        });
+
+        spawn_local(async move {
+            let mut stream = match listen::<AlertChanged>("alert_changed").await {
+                Ok(s) => s,
+                Err(e) => {
+                    log!("Failed to subscribe to alert_changed: {}", e);
+                    return;
+                }
+            };
+
+            while let Some(event) = stream.next().await {
+                let alert = event.payload.alert;
+                open.update(|open| {
+                    open.retain(|known| known.id != alert.id);
+                    if alert.resolved_at.is_none() {
+                        open.push(alert.clone());
+                    }
+                });
+                history.update(|history| {
+                    match history.iter_mut().find(|known| known.id == alert.id) {
+                        Some(known) => *known = alert,
+                        None => {
+                            history.insert(0, alert);
+                            history.truncate(HISTORY_LIMIT as usize);
+                        }
+                    }
+                });
+            }
+        });
+    });
+
+    let acknowledge = move |id: String| {
+        spawn_local(async move {
+            let args = AcknowledgeAlertArgs { id: &id };
+            if let Err(error) = invoke_result::<Alert, String>("acknowledge_alert", &args).await {
+                log!("Failed to acknowledge alert {}: {}", id, error);
+            }
+        });
+    };
+
+    view! {
+        <section class="max-w-3xl">
+            <h2 class="font-mono text-[10px] uppercase tracking-[0.2em] text-sk-carbon-450">"Open"</h2>
+            <ul class="mt-2 space-y-2">
+                <For
+                    each=move || open.get()
+                    key=|alert| (alert.id.clone(), alert.acknowledged_by.clone())
+                    children=move |alert| {
+                        let id = alert.id.clone();
+
+                        view! {
+                            <li class="flex items-center justify-between gap-4 rounded-md border border-sk-carbon-725 bg-sk-carbon-850 px-3 py-2">
+                                <div class="min-w-0">
+                                    <div class=format!("text-sm font-medium {}", severity_class(&alert.severity))>
+                                        {alert.rule_name.clone()}
+                                    </div>
+                                    <div class="truncate text-sm text-sk-carbon-300">{alert.message.clone()}</div>
+                                </div>
+                                {match alert.acknowledged_by.clone() {
+                                    Some(by) => view! {
+                                        <span class="shrink-0 font-mono text-xs text-sk-carbon-450">{format!("ack by {by}")}</span>
+                                    }.into_any(),
+                                    None => view! {
+                                        <button
+                                            class="shrink-0 rounded-md px-3 py-1 text-sm font-medium text-sk-carbon-100 transition-colors hover:bg-sk-carbon-800"
+                                            on:click=move |_| acknowledge(id.clone())
+                                        >
+                                            "Acknowledge"
+                                        </button>
+                                    }.into_any(),
+                                }}
+                            </li>
+                        }
+                    }
+                />
+            </ul>
+
+            <h2 class="mt-8 font-mono text-[10px] uppercase tracking-[0.2em] text-sk-carbon-450">"History"</h2>
+            <ul class="mt-2 divide-y divide-sk-carbon-725">
+                <For
+                    each=move || history.get()
+                    key=|alert| (alert.id.clone(), alert.acknowledged_by.clone(), alert.resolved_at.clone())
+                    children=move |alert| {
+                        view! {
+                            <li class="flex items-center justify-between gap-4 py-2 text-sm">
+                                <span class=severity_class(&alert.severity)>{alert.message.clone()}</span>
+                                <span class="shrink-0 font-mono text-xs text-sk-carbon-450">
+                                    {alert.resolved_at.clone().map_or_else(
+                                        || format!("raised {}", alert.raised_at),
+                                        |resolved_at| format!("resolved {resolved_at}"),
+                                    )}
+                                </span>
+                            </li>
+                        }
+                    }
+                />
+            </ul>
+        </section>
+    }
+}
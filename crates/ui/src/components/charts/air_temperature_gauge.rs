@@ -9,25 +9,20 @@ use charming::{
     series::{Gauge, GaugeDetail, GaugeProgress},
     Animation, Chart, ChartResize, Echarts, WasmRenderer,
 };
-use futures_util::StreamExt as _;
 use leptos::{html::Div, prelude::*};
 use leptos::{logging::log, IntoView};
 use leptos_use::use_element_size;
-use serde::Deserialize;
-use tauri_sys::event::listen;
-use wasm_bindgen_futures::spawn_local;
 
+use crate::components::sensor_feed::{follow_sensor, SensorUpdate};
 use crate::theme::ArkSyncTheme;
 
-#[derive(Clone, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct SensorData {
-    name: String,
-    value: f32,
-}
-
 #[component]
-pub fn AirTemperatureGauge(#[prop(optional)] theme: Option<ArkSyncTheme>) -> impl IntoView {
+pub fn AirTemperatureGauge(
+    /// Sensor to show, its measurements are pushed by the sensor service
+    #[prop(into)]
+    sensor_id: String,
+    #[prop(optional)] theme: Option<ArkSyncTheme>,
+) -> impl IntoView {
     let chart_container = NodeRef::<Div>::new();
     let chart_node = NodeRef::<Div>::new();
     let chart_container_size = use_element_size(chart_container);
@@ -35,14 +30,19 @@ pub fn AirTemperatureGauge(#[prop(optional)] theme: Option<ArkSyncTheme>) -> imp
         (chart_container_size.width, chart_container_size.height);
 
     let sensor_value = RwSignal::new(0.0);
+    let state = RwSignal::new(String::from("initializing"));
     let chart_instance: Rc<RefCell<Option<Echarts>>> = Rc::new(RefCell::new(None));
 
-    let render_responsive_chart = move |width: f64, height: f64, serie: f32| {
+    let render_responsive_chart = move |width: f64, height: f64, serie: f64, state: &str| {
         let chart_instance: Rc<RefCell<Option<Echarts>>> = Rc::clone(&chart_instance);
         let mut chart_ref = chart_instance.borrow_mut();
         let width = if width == 0.0 { 300 } else { width as u32 };
         let height = if height == 0.0 { 150 } else { height as u32 };
 
+        let label = match state {
+            "active" => "Air temperature C°".to_string(),
+            state => format!("Air temperature C°, {state}"),
+        };
         let chart_config = Chart::new()
             .tooltip(Tooltip::new().formatter("{a} <br/>{b} : {c}%"))
             .series(
@@ -54,7 +54,7 @@ pub fn AirTemperatureGauge(#[prop(optional)] theme: Option<ArkSyncTheme>) -> imp
                             .formatter("{value}")
                             .value_animation(true),
                     )
-                    .data(vec![(serie.round(), "Air temperature C°")]),
+                    .data(vec![(serie.round(), label.as_str())]),
             );
 
         if let Some(echarts) = chart_ref.as_ref() {
@@ -90,23 +90,9 @@ pub fn AirTemperatureGauge(#[prop(optional)] theme: Option<ArkSyncTheme>) -> imp
     };
 
     Effect::new(move |_| {
-        spawn_local(async move {
-            let _ = tauri_sys::core::invoke::<()>("air_temperature_sensor", &()).await;
-        });
-
-        spawn_local(async move {
-            let event_name = "air_temperature_sensor";
-            let mut stream = match listen::<SensorData>(event_name).await {
-                Ok(s) => s,
-                Err(e) => {
-                    log!("Failed to subscribe to air_temperature_sensor: {}", e);
-                    return;
-                }
-            };
-
-            while let Some(sensor_data) = stream.next().await {
-                sensor_value.set(sensor_data.payload.value);
-            }
+        follow_sensor(sensor_id.clone(), move |update| match update {
+            SensorUpdate::Measurement { value, .. } => sensor_value.set(value),
+            SensorUpdate::State { sensor } => state.set(sensor.state),
         });
     });
 
@@ -116,10 +102,11 @@ pub fn AirTemperatureGauge(#[prop(optional)] theme: Option<ArkSyncTheme>) -> imp
                 chart_container_w.get(),
                 chart_container_h.get(),
                 sensor_value.get(),
+                state.get(),
             )
         },
-        move |(width, height, sensor_value): &(f64, f64, f32), _prev, _| {
-            render_responsive_chart(*width, *height, *sensor_value);
+        move |(width, height, sensor_value, state): &(f64, f64, f64, String), _prev, _| {
+            render_responsive_chart(*width, *height, *sensor_value, state);
         },
         false,
     );
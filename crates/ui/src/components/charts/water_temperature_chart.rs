@@ -8,52 +8,65 @@ use charming::{
     series::Line,
     Animation, Chart, ChartResize, Echarts, WasmRenderer,
 };
-use futures_util::StreamExt;
-use leptos::{html::Div, logging::log, prelude::*};
+use leptos::{html::Div, prelude::*};
 use leptos_use::use_element_size;
-use serde::Deserialize;
 use std::cell::RefCell;
 use std::rc::Rc;
-use tauri_sys::event::listen;
-use wasm_bindgen_futures::spawn_local;
 
+use crate::components::sensor_feed::{follow_sensor, SensorUpdate};
 use crate::theme::ArkSyncTheme;
 
-#[derive(Clone, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct SensorData {
-    name: String,
-    value: [f32; 7],
+/// Latest measurements drawn on the chart.
+const POINTS: usize = 30;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Point {
+    /// `HH:MM:SS` of the measurement
+    label: String,
+    value: f64,
 }
 
 #[component]
-pub fn WaterTemperatureChart(#[prop(optional)] theme: Option<ArkSyncTheme>) -> impl IntoView {
+pub fn WaterTemperatureChart(
+    /// Sensor to draw, its measurements are pushed by the sensor service
+    #[prop(into)]
+    sensor_id: String,
+    #[prop(optional)] theme: Option<ArkSyncTheme>,
+) -> impl IntoView {
     let chart_container = NodeRef::<Div>::new();
     let chart_node = NodeRef::<Div>::new();
     let chart_container_size = use_element_size(chart_container);
     let (chart_container_w, chart_container_h) =
         (chart_container_size.width, chart_container_size.height);
 
-    let sensor_values = RwSignal::new(vec![0.0; 7]);
+    let points = RwSignal::new(Vec::<Point>::new());
+    let state = RwSignal::new(String::from("initializing"));
     let chart_instance: Rc<RefCell<Option<Echarts>>> = Rc::new(RefCell::new(None));
 
-    let render_responsive_chart = move |width: f64, height: f64, serie: Vec<f32>| {
+    let render_responsive_chart = move |width: f64, height: f64, points: &[Point], state: &str| {
         let chart_instance: Rc<RefCell<Option<Echarts>>> = Rc::clone(&chart_instance);
         let mut chart_ref = chart_instance.borrow_mut();
         let width = if width == 0.0 { 300 } else { width as u32 };
         let height = if height == 0.0 { 150 } else { height as u32 };
 
+        let title = match state {
+            "active" => "Water Temperature (C°)".to_string(),
+            state => format!("Water Temperature (C°), {state}"),
+        };
         let chart_config = Chart::new()
             .title(
                 Title::new()
-                    .text("Water Temperature (C°)".to_string())
+                    .text(title)
                     .text_style(TextStyle::new().color(Color::Value("#39344a".to_string()))),
             )
-            .series(Line::new().data(serie))
+            .series(Line::new().data(points.iter().map(|point| point.value).collect::<Vec<_>>()))
             .x_axis(
-                Axis::new()
-                    .type_(AxisType::Category)
-                    .data(vec!["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]),
+                Axis::new().type_(AxisType::Category).data(
+                    points
+                        .iter()
+                        .map(|point| point.label.clone())
+                        .collect::<Vec<_>>(),
+                ),
             )
             .y_axis(Axis::new().type_(AxisType::Value));
 
@@ -83,23 +96,15 @@ pub fn WaterTemperatureChart(#[prop(optional)] theme: Option<ArkSyncTheme>) -> i
     };
 
     Effect::new(move |_| {
-        spawn_local(async move {
-            let _ = tauri_sys::core::invoke::<()>("water_temperature_sensor", &()).await;
-        });
-
-        spawn_local(async move {
-            let event_name = "water_temperature_sensor";
-            let mut stream = match listen::<SensorData>(event_name).await {
-                Ok(s) => s,
-                Err(e) => {
-                    log!("Failed to subscribe to water_temperature_sensor: {}", e);
-                    return;
+        follow_sensor(sensor_id.clone(), move |update| match update {
+            SensorUpdate::Measurement { value, time } => points.update(|points| {
+                let label = time.get(11..19).unwrap_or(&time).to_string();
+                points.push(Point { label, value });
+                if points.len() > POINTS {
+                    points.remove(0);
                 }
-            };
-
-            while let Some(sensor_data) = stream.next().await {
-                sensor_values.set(sensor_data.payload.value.to_vec());
-            }
+            }),
+            SensorUpdate::State { sensor } => state.set(sensor.state),
         });
     });
 
@@ -108,11 +113,12 @@ pub fn WaterTemperatureChart(#[prop(optional)] theme: Option<ArkSyncTheme>) -> i
             (
                 chart_container_w.get(),
                 chart_container_h.get(),
-                sensor_values.get(),
+                points.get(),
+                state.get(),
             )
         },
-        move |(width, height, sensor_values): &(f64, f64, Vec<f32>), _prev, _| {
-            render_responsive_chart(*width, *height, sensor_values.to_vec());
+        move |(width, height, points, state): &(f64, f64, Vec<Point>, String), _prev, _| {
+            render_responsive_chart(*width, *height, points, state);
         },
         false,
     );
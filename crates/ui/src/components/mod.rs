@@ -2,9 +2,14 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+pub mod alert_list;
 pub mod charts;
 pub mod grid;
 pub mod heroicons;
+pub mod node_list;
 pub mod page_layout;
 pub mod page_title;
+pub mod relay_switches;
+pub mod sensor_feed;
+pub mod sensor_list;
 pub mod sidebar;
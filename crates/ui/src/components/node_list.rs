@@ -0,0 +1,268 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use futures_util::StreamExt as _;
+use leptos::prelude::*;
+use leptos::{logging::log, IntoView};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use tauri_sys::core::invoke_result;
+use tauri_sys::event::listen;
+use wasm_bindgen_futures::spawn_local;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Node {
+    id: String,
+    name: String,
+    hardware_uid: String,
+    /// `local_hub` or `remote_knot`
+    role: String,
+    /// `awake`, `sleeping` or `unreachable`
+    status: String,
+    sensors: Vec<NodeSensor>,
+    actuators: Vec<NodeActuator>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeSensor {
+    id: String,
+    kind: String,
+    name: Option<String>,
+    connection: String,
+    state: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeActuator {
+    id: String,
+    name: String,
+    kind: String,
+    active: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RenameNodeArgs<'a> {
+    node_id: &'a str,
+    name: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SwitchNodeRelayArgs<'a> {
+    node_id: &'a str,
+    relay_id: &'a str,
+    on: bool,
+}
+
+fn status_class(status: &str) -> &'static str {
+    match status {
+        "awake" | "active" => "text-sk-aqua-50",
+        "sleeping" | "degraded" | "initializing" => "text-amber-300",
+        "unreachable" => "text-red-400",
+        _ => "text-sk-carbon-450",
+    }
+}
+
+fn role_label(role: &str) -> &'static str {
+    match role {
+        "local_hub" => "Hub",
+        _ => "Remote knot",
+    }
+}
+
+/// Fetch the nodes again, with their sensors and actuators.
+fn load(nodes: RwSignal<Vec<Node>>) {
+    spawn_local(async move {
+        match invoke_result::<Vec<Node>, String>("nodes", &()).await {
+            Ok(listed) => nodes.set(listed),
+            Err(error) => log!("Failed to load nodes: {}", error),
+        }
+    });
+}
+
+/// Reload the nodes whenever `event` is emitted.
+fn reload_on(event: &'static str, nodes: RwSignal<Vec<Node>>) {
+    spawn_local(async move {
+        let mut stream = match listen::<IgnoredAny>(event).await {
+            Ok(s) => s,
+            Err(e) => {
+                log!("Failed to subscribe to {}: {}", event, e);
+                return;
+            }
+        };
+
+        while stream.next().await.is_some() {
+            load(nodes);
+        }
+    });
+}
+
+/// The knots of the station, the local hub first, with the sensors and
+/// actuators each one drives.
+#[component]
+pub fn NodeList() -> impl IntoView {
+    let nodes = RwSignal::new(Vec::<Node>::new());
+    let status = RwSignal::new(None::<String>);
+
+    Effect::new(move |_| {
+        load(nodes);
+        reload_on("sensor_changed", nodes);
+        reload_on("relay_state_changed", nodes);
+        reload_on("node_changed", nodes);
+    });
+
+    let rename = move |node_id: String, name: String| {
+        spawn_local(async move {
+            let args = RenameNodeArgs {
+                node_id: &node_id,
+                name: &name,
+            };
+            match invoke_result::<(), String>("rename_node", &args).await {
+                Ok(()) => {
+                    load(nodes);
+                    status.set(Some(format!("Node renamed to {name}.")));
+                }
+                Err(error) => status.set(Some(error)),
+            }
+        });
+    };
+
+    // Relays of the hub are switched from the Relays page
+    let switch_relay = move |node_id: String, relay_id: String, on: bool| {
+        spawn_local(async move {
+            let args = SwitchNodeRelayArgs {
+                node_id: &node_id,
+                relay_id: &relay_id,
+                on,
+            };
+            if let Err(error) = invoke_result::<(), String>("switch_node_relay", &args).await {
+                status.set(Some(error));
+            }
+        });
+    };
+
+    view! {
+        <section class="max-w-4xl">
+            {move || status.get().map(|message| view! {
+                <p class="mb-4 text-sm text-sk-carbon-300">{message}</p>
+            })}
+            <ul class="space-y-4">
+                <For
+                    each=move || nodes.with(|nodes| nodes.iter().map(|node| node.id.clone()).collect::<Vec<_>>())
+                    key=|node_id| node_id.clone()
+                    children=move |node_id| {
+                        let node = {
+                            let node_id = node_id.clone();
+                            Memo::new(move |_| nodes.with(|nodes| nodes.iter().find(|node| node.id == node_id).cloned()))
+                        };
+                        let field = move |read: fn(&Node) -> String| move || node.with(|node| node.as_ref().map(read).unwrap_or_default());
+                        let name = RwSignal::new(String::new());
+                        let actuators_of = node_id.clone();
+                        let on_rename = move |_| rename(node_id.clone(), name.get_untracked());
+
+                        view! {
+                            <li class="rounded-md border border-sk-carbon-725 bg-sk-carbon-850 px-3 py-2">
+                                <div class="flex items-center justify-between gap-4">
+                                    <div class="min-w-0">
+                                        <div class="text-sm font-medium text-sk-carbon-100">{field(|node| node.name.clone())}</div>
+                                        <div class="truncate font-mono text-xs text-sk-carbon-450">
+                                            {field(|node| format!("{} · {}", role_label(&node.role), node.hardware_uid))}
+                                        </div>
+                                    </div>
+                                    <div class=field(|node| format!("shrink-0 text-sm {}", status_class(&node.status)))>
+                                        {field(|node| node.status.clone())}
+                                    </div>
+                                </div>
+                                <div class="mt-3 grid grid-cols-2 gap-4 text-sm">
+                                    <div>
+                                        <h3 class="mb-1 text-xs uppercase text-sk-carbon-450">"Sensors"</h3>
+                                        {move || node.with(|node| {
+                                            let sensors = node.as_ref().map(|node| node.sensors.clone()).unwrap_or_default();
+                                            if sensors.is_empty() {
+                                                return view! { <p class="text-sk-carbon-450">"No sensor."</p> }.into_any();
+                                            }
+                                            view! {
+                                                <ul class="space-y-1">
+                                                    {sensors.into_iter().map(|sensor| view! {
+                                                        <li class="flex justify-between gap-2" title=sensor.id>
+                                                            <span class="truncate text-sk-carbon-100">
+                                                                {format!(
+                                                                    "{} · {} · {}",
+                                                                    sensor.name.unwrap_or_else(|| "Unnamed".to_string()),
+                                                                    sensor.kind,
+                                                                    sensor.connection,
+                                                                )}
+                                                            </span>
+                                                            <span class=status_class(&sensor.state)>{sensor.state.clone()}</span>
+                                                        </li>
+                                                    }).collect_view()}
+                                                </ul>
+                                            }.into_any()
+                                        })}
+                                    </div>
+                                    <div>
+                                        <h3 class="mb-1 text-xs uppercase text-sk-carbon-450">"Actuators"</h3>
+                                        {move || node.with(|node| {
+                                            let actuators = node.as_ref().map(|node| node.actuators.clone()).unwrap_or_default();
+                                            let remote = node.as_ref().is_some_and(|node| node.role == "remote_knot" && node.status == "awake");
+                                            let node_id = actuators_of.clone();
+                                            if actuators.is_empty() {
+                                                return view! { <p class="text-sk-carbon-450">"No actuator."</p> }.into_any();
+                                            }
+                                            view! {
+                                                <ul class="space-y-1">
+                                                    {actuators.into_iter().map(|actuator| {
+                                                        let node_id = node_id.clone();
+                                                        let relay_id = actuator.name.clone();
+                                                        let on = !actuator.active;
+                                                        view! {
+                                                            <li class="flex items-center justify-between gap-2" title=actuator.id>
+                                                                <span class="truncate text-sk-carbon-100">
+                                                                    {format!("{} · {}", actuator.name, actuator.kind)}
+                                                                </span>
+                                                                <span class="flex items-center gap-2 text-sk-carbon-300">
+                                                                    {if actuator.active { "on" } else { "off" }}
+                                                                    {remote.then(move || view! {
+                                                                        <button
+                                                                            class="rounded-md px-2 py-0.5 text-xs font-medium text-sk-carbon-100 transition-colors hover:bg-sk-carbon-800"
+                                                                            on:click=move |_| switch_relay(node_id.clone(), relay_id.clone(), on)
+                                                                        >
+                                                                            {if on { "Switch on" } else { "Switch off" }}
+                                                                        </button>
+                                                                    })}
+                                                                </span>
+                                                            </li>
+                                                        }
+                                                    }).collect_view()}
+                                                </ul>
+                                            }.into_any()
+                                        })}
+                                    </div>
+                                </div>
+                                <div class="mt-3 flex items-center gap-2 text-sm">
+                                    <input
+                                        class="w-48 rounded-md border border-sk-carbon-725 bg-sk-carbon-900 px-2 py-1 text-sk-carbon-100"
+                                        placeholder="New name"
+                                        prop:value=move || name.get()
+                                        on:input=move |ev| name.set(event_target_value(&ev))
+                                    />
+                                    <button
+                                        class="rounded-md px-3 py-1 font-medium text-sk-carbon-100 transition-colors hover:bg-sk-carbon-800"
+                                        on:click=on_rename
+                                    >
+                                        "Rename"
+                                    </button>
+                                </div>
+                            </li>
+                        }
+                    }
+                />
+            </ul>
+        </section>
+    }
+}
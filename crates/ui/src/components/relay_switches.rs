@@ -0,0 +1,97 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use futures_util::StreamExt as _;
+use leptos::prelude::*;
+use leptos::{logging::log, IntoView};
+use serde::{Deserialize, Serialize};
+use tauri_sys::core::invoke_result;
+use tauri_sys::event::listen;
+use wasm_bindgen_futures::spawn_local;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RelayState {
+    id: String,
+    active: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SwitchRelayArgs<'a> {
+    relay_id: &'a str,
+    on: bool,
+}
+
+/// Manual control of the station relays, kept in sync with the actuator
+/// service whoever switches them.
+#[component]
+pub fn RelaySwitches() -> impl IntoView {
+    let relays = RwSignal::new(Vec::<RelayState>::new());
+
+    Effect::new(move |_| {
+        spawn_local(async move {
+            match invoke_result::<Vec<RelayState>, String>("relay_states", &()).await {
+                Ok(states) => relays.set(states),
+                Err(error) => log!("Failed to load relay states: {}", error),
+            }
+        });
+
+        spawn_local(async move {
+            let mut stream = match listen::<RelayState>("relay_state_changed").await {
+                Ok(s) => s,
+                Err(e) => {
+                    log!("Failed to subscribe to relay_state_changed: {}", e);
+                    return;
+                }
+            };
+
+            while let Some(event) = stream.next().await {
+                let state = event.payload;
+                relays.update(|relays| {
+                    if let Some(relay) = relays.iter_mut().find(|relay| relay.id == state.id) {
+                        *relay = state;
+                    }
+                });
+            }
+        });
+    });
+
+    let switch_relay = move |relay_id: String, on: bool| {
+        spawn_local(async move {
+            let args = SwitchRelayArgs {
+                relay_id: &relay_id,
+                on,
+            };
+            if let Err(error) = invoke_result::<RelayState, String>("switch_relay", &args).await {
+                log!("Failed to switch relay {}: {}", relay_id, error);
+            }
+        });
+    };
+
+    view! {
+        <ul class="mt-6 max-w-xl space-y-2">
+            <For
+                each=move || relays.get()
+                key=|relay| (relay.id.clone(), relay.active)
+                children=move |relay| {
+                    let relay_id = relay.id.clone();
+                    let on = !relay.active;
+
+                    view! {
+                        <li class="flex items-center justify-between rounded-md border border-sk-carbon-725 bg-sk-carbon-850 px-3 py-2">
+                            <span class="font-mono text-sm text-sk-carbon-300">{relay.id.clone()}</span>
+                            <button
+                                class="rounded-md px-3 py-1 text-sm font-medium text-sk-carbon-100 transition-colors hover:bg-sk-carbon-800"
+                                on:click=move |_| switch_relay(relay_id.clone(), on)
+                            >
+                                {if relay.active { "ON" } else { "OFF" }}
+                            </button>
+                        </li>
+                    }
+                }
+            />
+        </ul>
+    }
+}
@@ -0,0 +1,63 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use futures_util::StreamExt as _;
+use leptos::logging::log;
+use serde::{Deserialize, Serialize};
+use tauri_sys::core::invoke_result;
+use tauri_sys::event::listen;
+use wasm_bindgen_futures::spawn_local;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SensorState {
+    /// `active`, `degraded`, `initializing`, `unplugged` or `unreachable`
+    pub state: String,
+}
+
+/// Payload of the `sensor:<id>` events.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum SensorUpdate {
+    Measurement { value: f64, time: String },
+    State { sensor: SensorState },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SubscribeSensorArgs<'a> {
+    sensor_id: &'a str,
+}
+
+/// Call `on_update` with the measurements and state changes of a sensor.
+pub fn follow_sensor(sensor_id: String, on_update: impl Fn(SensorUpdate) + 'static) {
+    let event_name = format!("sensor:{sensor_id}");
+
+    spawn_local(async move {
+        let args = SubscribeSensorArgs {
+            sensor_id: &sensor_id,
+        };
+        if let Err(error) = invoke_result::<(), String>("subscribe_sensor", &args).await {
+            log!("Failed to subscribe to sensor {}: {}", sensor_id, error);
+        }
+    });
+
+    spawn_local(async move {
+        let mut stream = match listen::<SensorUpdate>(&event_name).await {
+            Ok(s) => s,
+            Err(e) => {
+                log!("Failed to listen to {}: {}", event_name, e);
+                return;
+            }
+        };
+
+        while let Some(event) = stream.next().await {
+            on_update(event.payload);
+        }
+    });
+}
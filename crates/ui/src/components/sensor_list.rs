@@ -0,0 +1,315 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use futures_util::StreamExt as _;
+use leptos::prelude::*;
+use leptos::{logging::log, IntoView};
+use serde::{Deserialize, Serialize};
+use tauri_sys::core::invoke_result;
+use tauri_sys::event::listen;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::components::sensor_feed::{follow_sensor, SensorUpdate};
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Sensor {
+    id: String,
+    kind: String,
+    name: Option<String>,
+    connection: String,
+    firmware: Option<f64>,
+    state: String,
+    state_reason: String,
+    state_since: String,
+    consecutive_failures: u32,
+    /// Only listed sensors come with it, the live ones get it from their
+    /// measurements
+    #[serde(default)]
+    last_value: Option<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct SensorChanged {
+    sensor: Sensor,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SensorArgs<'a> {
+    sensor_id: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RenameSensorArgs<'a> {
+    sensor_id: &'a str,
+    name: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CalibrateSensorArgs<'a> {
+    sensor_id: &'a str,
+    /// `None` restores the factory calibration
+    point: Option<f64>,
+}
+
+fn state_class(state: &str) -> &'static str {
+    match state {
+        "active" => "text-sk-aqua-50",
+        "degraded" | "initializing" => "text-amber-300",
+        "unreachable" => "text-red-400",
+        _ => "text-sk-carbon-450",
+    }
+}
+
+/// `YYYY-MM-DD HH:MM:SS` of an RFC 3339 time.
+fn short_time(time: &str) -> String {
+    time.get(..19).unwrap_or(time).replace('T', " ")
+}
+
+/// Every sensor of the station, plugged or not, with the actions to manage
+/// them.
+#[component]
+pub fn SensorList() -> impl IntoView {
+    let sensors = RwSignal::new(Vec::<RwSignal<Sensor>>::new());
+    let status = RwSignal::new(None::<String>);
+
+    let upsert = move |sensor: Sensor| {
+        let known = sensors.with_untracked(|sensors| {
+            sensors
+                .iter()
+                .find(|entry| entry.with_untracked(|entry| entry.id == sensor.id))
+                .copied()
+        });
+        if let Some(entry) = known {
+            entry.update(|entry| {
+                let last_value = sensor.last_value.or(entry.last_value);
+                *entry = sensor;
+                entry.last_value = last_value;
+            });
+            return;
+        }
+
+        let sensor_id = sensor.id.clone();
+        let entry = RwSignal::new(sensor);
+        sensors.update(|sensors| {
+            sensors.push(entry);
+            sensors.sort_by_key(|entry| entry.with_untracked(|entry| entry.connection.clone()));
+        });
+        follow_sensor(sensor_id, move |update| {
+            if let SensorUpdate::Measurement { value, .. } = update {
+                entry.update(|entry| entry.last_value = Some(value));
+            }
+        });
+    };
+
+    Effect::new(move |_| {
+        spawn_local(async move {
+            match invoke_result::<Vec<Sensor>, String>("sensors", &()).await {
+                Ok(listed) => listed.into_iter().for_each(upsert),
+                Err(error) => log!("Failed to load sensors: {}", error),
+            }
+        });
+
+        spawn_local(async move {
+            let mut stream = match listen::<SensorChanged>("sensor_changed").await {
+                Ok(s) => s,
+                Err(e) => {
+                    log!("Failed to subscribe to sensor_changed: {}", e);
+                    return;
+                }
+            };
+
+            while let Some(event) = stream.next().await {
+                upsert(event.payload.sensor);
+            }
+        });
+    });
+
+    let rename = move |sensor_id: String, name: String| {
+        spawn_local(async move {
+            let args = RenameSensorArgs {
+                sensor_id: &sensor_id,
+                name: &name,
+            };
+            match invoke_result::<Sensor, String>("rename_sensor", &args).await {
+                Ok(sensor) => {
+                    upsert(sensor);
+                    status.set(Some(format!("Sensor renamed to {name}.")));
+                }
+                Err(error) => status.set(Some(error)),
+            }
+        });
+    };
+
+    let calibrate = move |sensor_id: String, point: Option<f64>| {
+        spawn_local(async move {
+            let args = CalibrateSensorArgs {
+                sensor_id: &sensor_id,
+                point,
+            };
+            match invoke_result::<(), String>("calibrate_sensor", &args).await {
+                Ok(()) => status.set(Some(match point {
+                    Some(point) => format!("Sensor calibrated at {point}."),
+                    None => "Sensor calibration cleared.".to_string(),
+                })),
+                Err(error) => status.set(Some(error)),
+            }
+        });
+    };
+
+    let identify = move |sensor_id: String| {
+        spawn_local(async move {
+            let args = SensorArgs {
+                sensor_id: &sensor_id,
+            };
+            if let Err(error) = invoke_result::<(), String>("identify_sensor", &args).await {
+                status.set(Some(error));
+            }
+        });
+    };
+
+    let forget = move |sensor_id: String| {
+        spawn_local(async move {
+            let args = SensorArgs {
+                sensor_id: &sensor_id,
+            };
+            match invoke_result::<(), String>("forget_sensor", &args).await {
+                Ok(()) => sensors.update(|sensors| {
+                    sensors.retain(|entry| entry.with_untracked(|entry| entry.id != sensor_id));
+                }),
+                Err(error) => status.set(Some(error)),
+            }
+        });
+    };
+
+    view! {
+        <section class="max-w-4xl">
+            {move || status.get().map(|message| view! {
+                <p class="mb-4 text-sm text-sk-carbon-300">{message}</p>
+            })}
+            <ul class="space-y-2">
+                <For
+                    each=move || sensors.get()
+                    key=|entry| entry.with_untracked(|entry| entry.id.clone())
+                    children=move |entry| {
+                        let sensor_id = entry.with_untracked(|entry| entry.id.clone());
+                        let name = RwSignal::new(String::new());
+                        let point = RwSignal::new(String::new());
+                        let unplugged = move || entry.with(|entry| entry.state == "unplugged");
+
+                        let on_rename = {
+                            let sensor_id = sensor_id.clone();
+                            move |_| rename(sensor_id.clone(), name.get_untracked())
+                        };
+                        let on_calibrate = {
+                            let sensor_id = sensor_id.clone();
+                            move |_| match point.get_untracked().trim().parse::<f64>() {
+                                Ok(point) => calibrate(sensor_id.clone(), Some(point)),
+                                Err(_) => status.set(Some("Invalid calibration point.".to_string())),
+                            }
+                        };
+                        let on_clear = {
+                            let sensor_id = sensor_id.clone();
+                            move |_| calibrate(sensor_id.clone(), None)
+                        };
+                        let on_identify = {
+                            let sensor_id = sensor_id.clone();
+                            move |_| identify(sensor_id.clone())
+                        };
+                        let on_forget = move |_| forget(sensor_id.clone());
+
+                        view! {
+                            <li class="rounded-md border border-sk-carbon-725 bg-sk-carbon-850 px-3 py-2">
+                                <div class="flex items-center justify-between gap-4">
+                                    <div class="min-w-0">
+                                        <div class="text-sm font-medium text-sk-carbon-100">
+                                            {move || entry.with(|entry| entry.name.clone().unwrap_or_else(|| "Unnamed".to_string()))}
+                                        </div>
+                                        <div class="truncate font-mono text-xs text-sk-carbon-450">
+                                            {move || entry.with(|entry| format!(
+                                                "{} · {} · firmware {}",
+                                                entry.kind,
+                                                entry.connection,
+                                                entry.firmware.map_or_else(|| "?".to_string(), |firmware| firmware.to_string()),
+                                            ))}
+                                        </div>
+                                    </div>
+                                    <div class="shrink-0 text-right">
+                                        <div class=move || entry.with(|entry| format!("text-sm {}", state_class(&entry.state)))>
+                                            {move || entry.with(|entry| format!("{} ({})", entry.state, entry.state_reason))}
+                                        </div>
+                                        <div class="font-mono text-xs text-sk-carbon-450">
+                                            {move || entry.with(|entry| format!("since {}", short_time(&entry.state_since)))}
+                                        </div>
+                                    </div>
+                                    <div class="w-32 shrink-0 text-right font-mono text-sm text-sk-carbon-300">
+                                        <div>{move || entry.with(|entry| entry.last_value.map_or_else(|| "-".to_string(), |value| format!("{value:.2}")))}</div>
+                                        <div class="text-xs text-sk-carbon-450">
+                                            {move || entry.with(|entry| format!("{} failures", entry.consecutive_failures))}
+                                        </div>
+                                    </div>
+                                </div>
+                                <div class="mt-2 flex flex-wrap items-center gap-2 text-sm">
+                                    <input
+                                        class="w-36 rounded-md border border-sk-carbon-725 bg-sk-carbon-900 px-2 py-1 text-sk-carbon-100"
+                                        placeholder="New name"
+                                        maxlength="16"
+                                        prop:value=move || name.get()
+                                        on:input=move |ev| name.set(event_target_value(&ev))
+                                    />
+                                    <button
+                                        class="rounded-md px-3 py-1 font-medium text-sk-carbon-100 transition-colors hover:bg-sk-carbon-800 disabled:text-sk-carbon-450"
+                                        disabled=unplugged
+                                        on:click=on_rename
+                                    >
+                                        "Rename"
+                                    </button>
+                                    <input
+                                        class="w-24 rounded-md border border-sk-carbon-725 bg-sk-carbon-900 px-2 py-1 text-sk-carbon-100"
+                                        type="number"
+                                        step="any"
+                                        placeholder="Reference"
+                                        prop:value=move || point.get()
+                                        on:input=move |ev| point.set(event_target_value(&ev))
+                                    />
+                                    <button
+                                        class="rounded-md px-3 py-1 font-medium text-sk-carbon-100 transition-colors hover:bg-sk-carbon-800 disabled:text-sk-carbon-450"
+                                        disabled=unplugged
+                                        on:click=on_calibrate
+                                    >
+                                        "Calibrate"
+                                    </button>
+                                    <button
+                                        class="rounded-md px-3 py-1 font-medium text-sk-carbon-100 transition-colors hover:bg-sk-carbon-800 disabled:text-sk-carbon-450"
+                                        disabled=unplugged
+                                        on:click=on_clear
+                                    >
+                                        "Clear calibration"
+                                    </button>
+                                    <button
+                                        class="rounded-md px-3 py-1 font-medium text-sk-carbon-100 transition-colors hover:bg-sk-carbon-800 disabled:text-sk-carbon-450"
+                                        disabled=unplugged
+                                        on:click=on_identify
+                                    >
+                                        "Identify"
+                                    </button>
+                                    <button
+                                        class="ml-auto rounded-md px-3 py-1 font-medium text-red-400 transition-colors hover:bg-sk-carbon-800 disabled:text-sk-carbon-450"
+                                        disabled=move || !unplugged()
+                                        on:click=on_forget
+                                    >
+                                        "Forget"
+                                    </button>
+                                </div>
+                            </li>
+                        }
+                    }
+                />
+            </ul>
+        </section>
+    }
+}
@@ -0,0 +1,39 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use futures_util::StreamExt as _;
+use leptos::logging::{error, log, warn};
+use serde::Deserialize;
+use tauri_sys::event::listen;
+use wasm_bindgen_futures::spawn_local;
+
+/// Record sent by the log plugin to the webview target.
+#[derive(Deserialize)]
+struct LogRecord {
+    message: String,
+    /// 1 for trace up to 5 for error
+    level: u16,
+}
+
+/// Print the logs of the backend, services included, in the webview console.
+pub fn attach() {
+    spawn_local(async move {
+        let mut stream = match listen::<LogRecord>("log://log").await {
+            Ok(s) => s,
+            Err(e) => {
+                error!("Failed to subscribe to the backend logs: {}", e);
+                return;
+            }
+        };
+
+        while let Some(event) = stream.next().await {
+            let record = event.payload;
+            match record.level {
+                5 => error!("{}", record.message),
+                4 => warn!("{}", record.message),
+                _ => log!("{}", record.message),
+            }
+        }
+    });
+}
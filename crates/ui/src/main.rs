@@ -4,6 +4,7 @@
 
 mod app;
 mod components;
+mod console;
 mod theme;
 
 use app::*;
@@ -13,6 +14,7 @@ use crate::theme::{register_theme, ArkSyncTheme};
 
 fn main() {
     console_error_panic_hook::set_once();
+    console::attach();
 
     let Ok(_) = register_theme(vec![
         ArkSyncTheme::Westeros,
@@ -0,0 +1,25 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use eyre::Result;
+use sqlx::PgPool;
+
+use crate::{domain::User, stores::SessionStore};
+
+pub struct AuthenticateUseCase<'a> {
+    sessions: SessionStore<'a>,
+}
+
+impl<'a> AuthenticateUseCase<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self {
+            sessions: SessionStore::new(pool),
+        }
+    }
+
+    /// User behind a session token, `None` when unknown or expired.
+    pub async fn execute(&self, token: &str) -> Result<Option<User>> {
+        self.sessions.find_user(token).await
+    }
+}
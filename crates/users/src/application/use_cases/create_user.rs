@@ -24,9 +24,18 @@ impl<'a> CreateUserUseCase<'a> {
     }
 
     pub async fn execute(&self, cmd: CreateUser) -> Result<User> {
-        let user = User::new(cmd.username, cmd.password);
+        let user = User::new(cmd.username, cmd.password)?;
         self.store.create(&user).await?;
 
         Ok(user)
     }
+
+    /// Create the first user of the station, `None` when there is one
+    /// already.
+    pub async fn execute_first(&self, cmd: CreateUser) -> Result<Option<User>> {
+        let user = User::new(cmd.username, cmd.password)?;
+        let created = self.store.create_first(&user).await?;
+
+        Ok(created.then_some(user))
+    }
 }
@@ -0,0 +1,31 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use eyre::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::stores::{SessionStore, UserStore};
+
+pub struct DeleteUserUseCase<'a> {
+    users: UserStore<'a>,
+    sessions: SessionStore<'a>,
+}
+
+impl<'a> DeleteUserUseCase<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self {
+            users: UserStore::new(pool),
+            sessions: SessionStore::new(pool),
+        }
+    }
+
+    /// Delete a user and close its sessions, returns whether it existed.
+    pub async fn execute(&self, id: Uuid) -> Result<bool> {
+        let deleted = self.users.delete(id).await?;
+        self.sessions.delete_for_user(id).await?;
+
+        Ok(deleted)
+    }
+}
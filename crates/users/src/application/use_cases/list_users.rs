@@ -0,0 +1,24 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use eyre::Result;
+use sqlx::PgPool;
+
+use crate::{domain::User, stores::UserStore};
+
+pub struct ListUsersUseCase<'a> {
+    store: UserStore<'a>,
+}
+
+impl<'a> ListUsersUseCase<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self {
+            store: UserStore::new(pool),
+        }
+    }
+
+    pub async fn execute(&self) -> Result<Vec<User>> {
+        self.store.list().await
+    }
+}
@@ -0,0 +1,57 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::TimeDelta;
+use eyre::Result;
+use sqlx::PgPool;
+
+use crate::{
+    domain::{Session, User},
+    stores::{SessionStore, UserStore},
+};
+
+pub struct LogIn {
+    pub username: String,
+    pub password: String,
+    /// How long the session stays valid
+    pub ttl: TimeDelta,
+}
+
+pub struct LogInUseCase<'a> {
+    users: UserStore<'a>,
+    sessions: SessionStore<'a>,
+}
+
+impl<'a> LogInUseCase<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self {
+            users: UserStore::new(pool),
+            sessions: SessionStore::new(pool),
+        }
+    }
+
+    /// Open a session, `None` when the credentials don't match.
+    ///
+    /// A password still stored in clear text is hashed once it matched. An
+    /// unknown username takes as long as a wrong password.
+    pub async fn execute(&self, cmd: LogIn) -> Result<Option<(User, Session)>> {
+        let Some(mut user) = self.users.find_by_username(&cmd.username).await? else {
+            User::verify_unknown_password(&cmd.password);
+            return Ok(None);
+        };
+        if !user.verify_password(&cmd.password) {
+            return Ok(None);
+        }
+        if user.has_legacy_password() {
+            user.set_password(&cmd.password)?;
+            self.users.update_password(&user).await?;
+        }
+
+        self.sessions.delete_expired().await?;
+        let session = Session::new(user.id(), cmd.ttl);
+        self.sessions.create(&session).await?;
+
+        Ok(Some((user, session)))
+    }
+}
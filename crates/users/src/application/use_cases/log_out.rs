@@ -0,0 +1,24 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use eyre::Result;
+use sqlx::PgPool;
+
+use crate::stores::SessionStore;
+
+pub struct LogOutUseCase<'a> {
+    sessions: SessionStore<'a>,
+}
+
+impl<'a> LogOutUseCase<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self {
+            sessions: SessionStore::new(pool),
+        }
+    }
+
+    pub async fn execute(&self, token: &str) -> Result<()> {
+        self.sessions.delete(token).await
+    }
+}
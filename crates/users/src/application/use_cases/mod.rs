@@ -2,6 +2,16 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+mod authenticate;
 mod create_user;
+mod delete_user;
+mod list_users;
+mod log_in;
+mod log_out;
 
+pub use authenticate::AuthenticateUseCase;
 pub use create_user::{CreateUser, CreateUserUseCase};
+pub use delete_user::DeleteUserUseCase;
+pub use list_users::ListUsersUseCase;
+pub use log_in::{LogIn, LogInUseCase};
+pub use log_out::LogOutUseCase;
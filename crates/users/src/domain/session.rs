@@ -0,0 +1,88 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use uuid::Uuid;
+
+/// Bearer token handed to a client once logged in.
+///
+/// Only its hash is stored, a leaked database doesn't leak sessions.
+#[derive(Clone)]
+pub struct SessionToken(String);
+
+impl SessionToken {
+    fn generate() -> Self {
+        let bytes: [u8; 32] = rand::rng().random();
+
+        Self(
+            bytes
+                .iter()
+                .fold(String::with_capacity(64), |mut hex, byte| {
+                    let _ = write!(hex, "{byte:02x}");
+                    hex
+                }),
+        )
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub(crate) fn hash(token: &str) -> Vec<u8> {
+        Sha256::digest(token.as_bytes()).to_vec()
+    }
+}
+
+impl std::fmt::Debug for SessionToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SessionToken(..)")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Session {
+    token: SessionToken,
+    user_id: Uuid,
+    expires_at: DateTime<Utc>,
+}
+
+impl Session {
+    pub fn new(user_id: Uuid, ttl: TimeDelta) -> Self {
+        Self {
+            token: SessionToken::generate(),
+            user_id,
+            expires_at: Utc::now() + ttl,
+        }
+    }
+
+    pub fn token(&self) -> &SessionToken {
+        &self.token
+    }
+
+    pub fn user_id(&self) -> Uuid {
+        self.user_id
+    }
+
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.expires_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_random_and_hashed() {
+        let first = Session::new(Uuid::nil(), TimeDelta::hours(1));
+        let second = Session::new(Uuid::nil(), TimeDelta::hours(1));
+
+        assert_eq!(first.token().as_str().len(), 64);
+        assert_ne!(first.token().as_str(), second.token().as_str());
+        assert_eq!(SessionToken::hash(first.token().as_str()).len(), 32);
+    }
+}
@@ -2,33 +2,145 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, SaltString};
+use argon2::{Argon2, PasswordHasher, PasswordVerifier};
+use eyre::{eyre, Result};
+use std::sync::LazyLock;
 use uuid::Uuid;
 
+/// Hash checked when no user matches, so that the time a log in takes
+/// doesn't tell which usernames exist.
+static DUMMY_PASSWORD: LazyLock<Option<String>> = LazyLock::new(|| hash_password("arksync").ok());
+
 #[derive(Clone, Debug)]
 pub struct User {
     id: Uuid,
     username: String,
+    /// Argon2id hash in the PHC string format
     password: String,
 }
 
 impl User {
-    pub fn new(username: String, password: String) -> Self {
-        Self {
+    /// A new user, `password` is hashed right away.
+    pub fn new(username: String, password: String) -> Result<Self> {
+        Ok(Self {
             id: Uuid::new_v4(),
             username,
+            password: hash_password(&password)?,
+        })
+    }
+
+    pub(crate) fn from_parts(id: Uuid, username: String, password: String) -> Self {
+        Self {
+            id,
+            username,
             password,
         }
     }
 
-    pub(crate) fn id(&self) -> Uuid {
+    pub fn id(&self) -> Uuid {
         self.id
     }
 
-    pub(crate) fn username(&self) -> &str {
+    pub fn username(&self) -> &str {
         &self.username
     }
 
     pub(crate) fn password(&self) -> &str {
         &self.password
     }
+
+    /// Whether `password` matches the stored hash.
+    ///
+    /// Passwords stored in clear text before hashing was introduced still
+    /// match, until they are hashed on the next log in.
+    pub fn verify_password(&self, password: &str) -> bool {
+        match PasswordHash::new(&self.password) {
+            Ok(hash) => Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok(),
+            Err(_) => !self.password.is_empty() && same_bytes(&self.password, password),
+        }
+    }
+
+    /// Spend the time of [`User::verify_password`] on a user that doesn't
+    /// exist, always `false`.
+    pub fn verify_unknown_password(password: &str) -> bool {
+        if let Some(hash) = DUMMY_PASSWORD
+            .as_deref()
+            .and_then(|hash| PasswordHash::new(hash).ok())
+        {
+            let _ = Argon2::default().verify_password(password.as_bytes(), &hash);
+        }
+
+        false
+    }
+
+    /// Whether the password is still stored in clear text.
+    pub fn has_legacy_password(&self) -> bool {
+        PasswordHash::new(&self.password).is_err()
+    }
+
+    /// Replace the password, hashed right away.
+    pub fn set_password(&mut self, password: &str) -> Result<()> {
+        self.password = hash_password(password)?;
+        Ok(())
+    }
+}
+
+fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|err| eyre!("Failed to hash the password: {err}"))?;
+
+    Ok(hash.to_string())
+}
+
+/// Compare in a time that doesn't depend on where the strings differ.
+fn same_bytes(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn password_is_hashed_and_verified() {
+        let user = User::new("grower".to_string(), "correct horse".to_string()).unwrap();
+
+        assert_ne!(user.password(), "correct horse");
+        assert!(user.verify_password("correct horse"));
+        assert!(!user.verify_password("battery staple"));
+    }
+
+    #[test]
+    fn legacy_password_matches_until_rehashed() {
+        let mut user = User::from_parts(
+            Uuid::new_v4(),
+            "grower".to_string(),
+            "correct horse".to_string(),
+        );
+
+        assert!(user.has_legacy_password());
+        assert!(user.verify_password("correct horse"));
+        assert!(!user.verify_password("battery staple"));
+
+        user.set_password("correct horse").unwrap();
+
+        assert!(!user.has_legacy_password());
+        assert_ne!(user.password(), "correct horse");
+        assert!(user.verify_password("correct horse"));
+    }
+
+    #[test]
+    fn unknown_user_never_matches() {
+        assert!(DUMMY_PASSWORD.is_some());
+        assert!(!User::verify_unknown_password("arksync"));
+    }
 }
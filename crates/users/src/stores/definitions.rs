@@ -2,10 +2,11 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use chrono::{DateTime, Utc};
 use sqlx::FromRow;
 use uuid::Uuid;
 
-use crate::domain::User;
+use crate::domain::{Session, SessionToken, User};
 
 #[derive(Clone, Debug, FromRow)]
 pub struct UserRecord {
@@ -23,3 +24,26 @@ impl From<&User> for UserRecord {
         }
     }
 }
+
+impl From<UserRecord> for User {
+    fn from(record: UserRecord) -> Self {
+        User::from_parts(record.id, record.username, record.password)
+    }
+}
+
+#[derive(Clone, Debug, FromRow)]
+pub struct SessionRecord {
+    pub token_hash: Vec<u8>,
+    pub user_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<&Session> for SessionRecord {
+    fn from(session: &Session) -> Self {
+        Self {
+            token_hash: SessionToken::hash(session.token().as_str()),
+            user_id: session.user_id(),
+            expires_at: session.expires_at(),
+        }
+    }
+}
@@ -3,7 +3,9 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 mod definitions;
+mod session_store;
 mod user_store;
 
-pub use definitions::UserRecord;
+pub use definitions::{SessionRecord, UserRecord};
+pub use session_store::SessionStore;
 pub use user_store::UserStore;
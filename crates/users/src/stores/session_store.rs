@@ -0,0 +1,83 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use eyre::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::{Session, SessionToken, User};
+use crate::stores::{SessionRecord, UserRecord};
+
+pub struct SessionStore<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> SessionStore<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, session: &Session) -> Result<()> {
+        let record = SessionRecord::from(session);
+
+        sqlx::query(
+            r#"
+            INSERT INTO user_sessions (token_hash, user_id, expires_at)
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(record.token_hash)
+        .bind(record.user_id)
+        .bind(record.expires_at)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Owner of an unexpired session.
+    pub async fn find_user(&self, token: &str) -> Result<Option<User>> {
+        let record = sqlx::query_as::<_, UserRecord>(
+            r#"
+            SELECT users.id, users.username, users.password
+            FROM user_sessions
+            JOIN users ON users.id = user_sessions.user_id
+            WHERE user_sessions.token_hash = $1
+              AND user_sessions.expires_at > now()
+              AND users.deleted_at IS NULL
+            "#,
+        )
+        .bind(SessionToken::hash(token))
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(record.map(User::from))
+    }
+
+    pub async fn delete(&self, token: &str) -> Result<()> {
+        sqlx::query("DELETE FROM user_sessions WHERE token_hash = $1")
+            .bind(SessionToken::hash(token))
+            .execute(self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_for_user(&self, user_id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM user_sessions WHERE user_id = $1")
+            .bind(user_id)
+            .execute(self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_expired(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM user_sessions WHERE expires_at <= now()")
+            .execute(self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -4,6 +4,7 @@
 
 use eyre::Result;
 use sqlx::PgPool;
+use uuid::Uuid;
 
 use crate::{domain::User, stores::UserRecord};
 
@@ -33,4 +34,119 @@ impl<'a> UserStore<'a> {
 
         Ok(())
     }
+
+    /// Create the first user of the station, returns whether it was created
+    /// because no user exists yet.
+    ///
+    /// The table is locked meanwhile so that two concurrent requests can't
+    /// both create one.
+    pub async fn create_first(&self, user: &User) -> Result<bool> {
+        let user_record = UserRecord::from(user);
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("LOCK TABLE users IN SHARE ROW EXCLUSIVE MODE")
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query(
+            r#"
+            INSERT INTO users (id, username, password)
+            SELECT $1, $2, $3
+            WHERE NOT EXISTS (SELECT 1 FROM users WHERE deleted_at IS NULL)
+            "#,
+        )
+        .bind(user_record.id)
+        .bind(user_record.username)
+        .bind(user_record.password)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn update_password(&self, user: &User) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET password = $2
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(user.id())
+        .bind(user.password())
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn find(&self, id: Uuid) -> Result<Option<User>> {
+        let record = sqlx::query_as::<_, UserRecord>(
+            r#"
+            SELECT id, username, password
+            FROM users
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(record.map(User::from))
+    }
+
+    pub async fn find_by_username(&self, username: &str) -> Result<Option<User>> {
+        let record = sqlx::query_as::<_, UserRecord>(
+            r#"
+            SELECT id, username, password
+            FROM users
+            WHERE username = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(username)
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(record.map(User::from))
+    }
+
+    pub async fn list(&self) -> Result<Vec<User>> {
+        let records = sqlx::query_as::<_, UserRecord>(
+            r#"
+            SELECT id, username, password
+            FROM users
+            WHERE deleted_at IS NULL
+            ORDER BY username
+            "#,
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(records.into_iter().map(User::from).collect())
+    }
+
+    pub async fn count(&self) -> Result<i64> {
+        let count = sqlx::query_scalar("SELECT count(*) FROM users WHERE deleted_at IS NULL")
+            .fetch_one(self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Soft delete a user, returns whether it existed.
+    pub async fn delete(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET deleted_at = now()
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
@@ -0,0 +1,111 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_alert::alert::Alert;
+use arksync_alert::error::AlertError;
+use arksync_alert::services::{AlertService, AlertServiceHandle, NotificationService};
+use arksync_alert::CONFIG;
+use arksync_db::AlertStore;
+use arksync_sensor::services::SensorServiceHandle;
+use chrono::Utc;
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+const ALERT_EVENT: &str = "alert_changed";
+/// Who acknowledges from the app, it has no user session
+const LOCAL_USER: &str = "station";
+
+/// The alert service running for the app, alerts kept in Postgres.
+pub struct Alerts {
+    handle: AlertServiceHandle,
+    shutdown: CancellationToken,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Alerts {
+    /// Start the configured rules on the sensor events, push the alert
+    /// changes to the webview and send them on the notification channels.
+    pub fn start(app: AppHandle, sensors: &SensorServiceHandle) -> Result<Self, AlertError> {
+        let service = AlertService::new(CONFIG.rules.clone())?
+            .with_sensors(sensors.subscribe())
+            .with_store(AlertStore::new(arksync_db::pool()));
+        let handle = service.handle();
+        let notifications = NotificationService::new(CONFIG.channels.clone(), handle.subscribe())?;
+        let shutdown = CancellationToken::new();
+
+        let mut events = handle.subscribe();
+        let forwarding = shutdown.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    event = events.recv() => event,
+                    _ = forwarding.cancelled() => break,
+                };
+
+                match event {
+                    Ok(event) => {
+                        if let Err(error) = app.emit(ALERT_EVENT, event) {
+                            log::error!("Failed to emit alert event: {error}");
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        log::warn!("Alert events lagging behind, {missed} events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        let tasks = vec![
+            tauri::async_runtime::spawn(service.run(shutdown.clone())),
+            tauri::async_runtime::spawn(notifications.run(shutdown.clone())),
+        ];
+
+        Ok(Self {
+            handle,
+            shutdown,
+            tasks: Mutex::new(tasks),
+        })
+    }
+
+    pub fn stop(&self) {
+        self.shutdown.cancel();
+
+        let tasks = std::mem::take(&mut *self.tasks.lock().expect("alert tasks mutex poisoned"));
+        for task in tasks {
+            if let Err(error) = tauri::async_runtime::block_on(task) {
+                log::error!("Alert service failed to stop: {error}");
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn open_alerts(alerts: State<'_, Alerts>) -> Result<Vec<Alert>, String> {
+    alerts.handle.open().await.map_err(|err| err.to_string())
+}
+
+/// Latest alerts, resolved or not, newest first.
+#[tauri::command]
+pub async fn alert_history(limit: u32) -> Result<Vec<Alert>, String> {
+    let records = AlertStore::new(arksync_db::pool())
+        .history(Utc::now(), i64::from(limit.clamp(1, 1000)))
+        .await
+        .map_err(|err| err.to_string())?;
+
+    Ok(records.into_iter().map(Alert::from).collect())
+}
+
+#[tauri::command]
+pub async fn acknowledge_alert(alerts: State<'_, Alerts>, id: Uuid) -> Result<Alert, String> {
+    alerts
+        .handle
+        .acknowledge(id, LOCAL_USER)
+        .await
+        .map_err(|err| err.to_string())
+}
@@ -0,0 +1,174 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::services::ActuatorServiceHandle;
+use arksync_control::error::ControlError;
+use arksync_control::pid::{Mode, PidTerms};
+use arksync_control::rule::{ControlRule, Strategy};
+use arksync_control::services::{ControlService, ControlServiceHandle};
+use arksync_control::CONFIG;
+use arksync_sensor::services::SensorServiceHandle;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+const CONTROL_EVENT: &str = "control_event";
+
+/// A control rule as shown in the app.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlRuleSummary {
+    pub id: Uuid,
+    pub name: String,
+    pub sensor_id: Uuid,
+    pub relay_id: String,
+    /// Configured setpoint, `None` for a hysteresis rule
+    pub setpoint: Option<f64>,
+}
+
+impl From<&ControlRule> for ControlRuleSummary {
+    fn from(rule: &ControlRule) -> Self {
+        Self {
+            id: rule.id,
+            name: rule.name.clone(),
+            sensor_id: rule.sensor_id,
+            relay_id: rule.relay_id.clone(),
+            setpoint: match rule.strategy {
+                Strategy::Hysteresis(_) => None,
+                Strategy::Pid(settings) => Some(settings.setpoint),
+            },
+        }
+    }
+}
+
+/// The control service running for the app, driving the relays from the
+/// sensor measurements.
+pub struct Control {
+    handle: ControlServiceHandle,
+    shutdown: CancellationToken,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Control {
+    /// Start the configured rules on the sensor events and forward the PID
+    /// terms and switches to the webview.
+    pub fn start(
+        app: AppHandle,
+        relays: ActuatorServiceHandle,
+        sensors: &SensorServiceHandle,
+    ) -> Result<Self, ControlError> {
+        let service = ControlService::new(
+            relays,
+            CONFIG.rules.clone(),
+            &arksync_actuator::CONFIG.safety,
+        )?;
+        let handle = service.handle();
+        let shutdown = CancellationToken::new();
+
+        let mut events = handle.subscribe();
+        let forwarding = shutdown.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    event = events.recv() => event,
+                    _ = forwarding.cancelled() => break,
+                };
+
+                match event {
+                    Ok(event) => {
+                        if let Err(error) = app.emit(CONTROL_EVENT, event) {
+                            log::error!("Failed to emit control event: {error}");
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        log::warn!("Control events lagging behind, {missed} events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        let task = tauri::async_runtime::spawn(service.run(sensors.subscribe(), shutdown.clone()));
+
+        Ok(Self {
+            handle,
+            shutdown,
+            task: Mutex::new(Some(task)),
+        })
+    }
+
+    /// Stop driving the relays, before they are left in their fail-safe
+    /// state.
+    pub fn stop(&self) {
+        self.shutdown.cancel();
+
+        let task = self
+            .task
+            .lock()
+            .expect("control task mutex poisoned")
+            .take();
+        if let Some(task) = task {
+            if let Err(error) = tauri::async_runtime::block_on(task) {
+                log::error!("Control service failed to stop: {error}");
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn control_rules(control: State<'_, Control>) -> Result<Vec<ControlRuleSummary>, String> {
+    let rules = control
+        .handle
+        .rules()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    Ok(rules.iter().map(ControlRuleSummary::from).collect())
+}
+
+/// Terms of the last update of a PID rule, `None` before its first
+/// measurement. The following ones come as control events.
+#[tauri::command]
+pub async fn pid_terms(
+    control: State<'_, Control>,
+    rule_id: Uuid,
+) -> Result<Option<PidTerms>, String> {
+    control
+        .handle
+        .pid_terms(rule_id)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub async fn set_control_setpoint(
+    control: State<'_, Control>,
+    rule_id: Uuid,
+    setpoint: f64,
+) -> Result<(), String> {
+    control
+        .handle
+        .set_setpoint(rule_id, setpoint)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// Switch a PID rule to `{"type": "auto"}` or
+/// `{"type": "manual", "output": 0.4}`.
+#[tauri::command]
+pub async fn set_control_mode(
+    control: State<'_, Control>,
+    rule_id: Uuid,
+    mode: Mode,
+) -> Result<(), String> {
+    control
+        .handle
+        .set_mode(rule_id, mode)
+        .await
+        .map_err(|err| err.to_string())
+}
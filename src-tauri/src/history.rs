@@ -0,0 +1,29 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::{History, HistoryQuery};
+use arksync_db::{pool, MeasurementStore};
+
+/// History of a sensor over a window, downsampled to the point budget of the
+/// chart asking for it.
+#[tauri::command]
+pub async fn measurement_history(query: HistoryQuery) -> Result<History, String> {
+    if query.from >= query.to {
+        return Err(format!(
+            "Invalid history window: {} is not before {}.",
+            query.from, query.to
+        ));
+    }
+
+    MeasurementStore::new(pool())
+        .history(&query)
+        .await
+        .map_err(|err| {
+            log::error!(
+                "Failed to load the history of sensor {}: {err:#}",
+                query.sensor_id
+            );
+            err.to_string()
+        })
+}
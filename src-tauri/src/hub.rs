@@ -0,0 +1,119 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::pool;
+use arksync_sync::auth::PairingKey;
+use arksync_sync::error::SyncError;
+use arksync_sync::event::HubEvent;
+use arksync_sync::protocol::{KnotCommand, KnotMessage};
+use arksync_sync::services::{HubService, HubServiceHandle, KnotPersistence};
+use arksync_sync::CONFIG;
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+const NODE_EVENT: &str = "node_changed";
+
+/// The hub service running for the app, when it accepts remote knots.
+pub struct Hub {
+    handle: Option<HubServiceHandle>,
+    shutdown: CancellationToken,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Hub {
+    /// Accept the remote knots of the hub `station_hub_id` if enabled, and
+    /// tell the webview when one of them changes.
+    pub fn start(app: AppHandle, station_hub_id: Uuid) -> Result<Self, SyncError> {
+        let shutdown = CancellationToken::new();
+        if !CONFIG.hub.enabled {
+            return Ok(Self {
+                handle: None,
+                shutdown,
+                task: Mutex::new(None),
+            });
+        }
+
+        let key = PairingKey::new(&CONFIG.pairing_key)?;
+        let service = tauri::async_runtime::block_on(HubService::bind(&CONFIG.hub, key))?
+            .with_persistence(KnotPersistence::new(pool(), station_hub_id));
+        let handle = service.handle();
+
+        let mut events = handle.subscribe();
+        let forwarding = shutdown.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    event = events.recv() => event,
+                    _ = forwarding.cancelled() => break,
+                };
+
+                match event {
+                    Ok(event) if changes_node(&event) => {
+                        if let Err(error) = app.emit(NODE_EVENT, event.knot_id()) {
+                            log::error!("Failed to emit node event: {error}");
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        log::warn!("Hub events lagging behind, {missed} events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        let task = tauri::async_runtime::spawn(service.run(shutdown.clone()));
+
+        Ok(Self {
+            handle: Some(handle),
+            shutdown,
+            task: Mutex::new(Some(task)),
+        })
+    }
+
+    /// Close the connections of the remote knots.
+    pub fn stop(&self) {
+        self.shutdown.cancel();
+
+        let task = self.task.lock().expect("hub task mutex poisoned").take();
+        if let Some(task) = task {
+            if let Err(error) = tauri::async_runtime::block_on(task) {
+                log::error!("Hub service failed to stop: {error}");
+            }
+        }
+    }
+}
+
+/// Measurements don't show on the nodes page.
+fn changes_node(event: &HubEvent) -> bool {
+    !matches!(
+        event,
+        HubEvent::Reported {
+            message: KnotMessage::Measurements { .. },
+            ..
+        }
+    )
+}
+
+#[tauri::command]
+pub async fn switch_node_relay(
+    hub: State<'_, Hub>,
+    node_id: Uuid,
+    relay_id: String,
+    on: bool,
+) -> Result<(), String> {
+    let handle = hub
+        .handle
+        .as_ref()
+        .ok_or_else(|| "The hub doesn't accept remote knots.".to_string())?;
+
+    handle
+        .command(node_id, KnotCommand::SwitchRelay { relay_id, on })
+        .await
+        .map_err(|err| err.to_string())
+}
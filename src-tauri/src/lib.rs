@@ -2,136 +2,112 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+mod alert;
+mod control;
+mod history;
+mod hub;
+mod logging;
+mod node;
 mod relay;
+mod schedule;
+mod sensor;
+mod telemetry;
 
-use rand::{rngs::StdRng, Rng, SeedableRng};
-use serde::Serialize;
 use std::{
     collections::HashSet,
     sync::{LazyLock, Mutex},
 };
-use tauri::{AppHandle, Emitter};
-use tauri_plugin_log::{Builder as TauriLog, Target, TargetKind};
-use tokio::time::{interval, Duration};
+use tauri::{Manager, RunEvent};
 
+/// Sensors whose events are forwarded to the webview.
 pub static SENSORS: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
 
 pub fn builder() -> tauri::Builder<tauri::Wry> {
     tauri::Builder::<tauri::Wry>::default()
         .setup(|app| {
-            tauri::async_runtime::block_on(async { arksync_db::run().await })
-                .map_err(|err| -> Box<dyn std::error::Error> { err.into() })?;
+            let knot = tauri::async_runtime::block_on(async {
+                arksync_db::run().await?;
+                arksync_db::register_local_hub().await
+            })
+            .map_err(|err| -> Box<dyn std::error::Error> { err.into() })?;
 
-            relay::spawn_debug_loop(app.handle().clone());
+            let relays = relay::Relays::start(app.handle().clone(), knot.id)
+                .map_err(|err| -> Box<dyn std::error::Error> { err.into() })?;
+            let sensors = sensor::Sensors::start(app.handle().clone(), knot.id);
+            app.manage(schedule::Scheduler::start(relays.handle()));
+            let control =
+                control::Control::start(app.handle().clone(), relays.handle(), &sensors.handle())
+                    .map_err(|err| -> Box<dyn std::error::Error> { err.into() })?;
+            app.manage(control);
+            let telemetry = telemetry::Telemetry::start(&sensors.handle(), relays.handle())
+                .map_err(|err| -> Box<dyn std::error::Error> { err.into() })?;
+            app.manage(telemetry);
+            app.manage(relays);
+            let alerts = alert::Alerts::start(app.handle().clone(), &sensors.handle())
+                .map_err(|err| -> Box<dyn std::error::Error> { err.into() })?;
+            app.manage(alerts);
+            app.manage(sensors);
+            let hub = hub::Hub::start(app.handle().clone(), knot.station_hub_id)
+                .map_err(|err| -> Box<dyn std::error::Error> { err.into() })?;
+            app.manage(hub);
             Ok(())
         })
         .plugin(tauri_plugin_updater::Builder::new().build())
-        .plugin(
-            TauriLog::new()
-                .targets([
-                    Target::new(TargetKind::Stderr),
-                    Target::new(TargetKind::Webview),
-                ])
-                .level(log::LevelFilter::Debug)
-                .build(),
-        )
+        .plugin(logging::plugin())
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
-            air_temperature_sensor,
-            water_temperature_sensor
+            sensor::sensors,
+            sensor::subscribe_sensor,
+            sensor::rename_sensor,
+            sensor::calibrate_sensor,
+            sensor::identify_sensor,
+            sensor::forget_sensor,
+            node::nodes,
+            node::rename_node,
+            hub::switch_node_relay,
+            history::measurement_history,
+            relay::relay_states,
+            relay::switch_relay,
+            relay::pulse_relay,
+            control::control_rules,
+            control::pid_terms,
+            control::set_control_setpoint,
+            control::set_control_mode,
+            alert::open_alerts,
+            alert::alert_history,
+            alert::acknowledge_alert
         ])
 }
 
 pub fn run(context: tauri::Context) {
-    builder().run(context).expect("Failed to run ArkSync");
-}
-
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct VecSensorData<'a> {
-    name: &'a str,
-    value: [f32; 7],
-}
-
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct SensorData<'a> {
-    name: &'a str,
-    value: f32,
-}
-
-#[tauri::command]
-async fn water_temperature_sensor(app: AppHandle) {
-    let sensor_name = "water_temperature_sensor";
-    let mut sensors = SENSORS.lock().unwrap();
-
-    if sensors.contains(sensor_name) {
-        log::info!("Sensor '{sensor_name}' already registered.");
-        return;
-    }
-
-    sensors.insert(sensor_name.to_string());
-
-    tauri::async_runtime::spawn(async move {
-        log::info!("Spawning sensor '{sensor_name}'...");
-
-        let mut interval = interval(Duration::from_secs(5));
-        loop {
-            interval.tick().await;
-
-            let mut rng = StdRng::from_os_rng();
-            let mut air_temp_series: [f32; 7] = [0.0; 7];
-
-            for air_temp_metric in &mut air_temp_series {
-                *air_temp_metric = rng.random_range(8.02..80.0);
+    builder()
+        .build(context)
+        .expect("Failed to build ArkSync")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                if let Some(hub) = app.try_state::<hub::Hub>() {
+                    hub.stop();
+                }
+                if let Some(alerts) = app.try_state::<alert::Alerts>() {
+                    alerts.stop();
+                }
+                if let Some(telemetry) = app.try_state::<telemetry::Telemetry>() {
+                    telemetry.stop();
+                }
+                if let Some(control) = app.try_state::<control::Control>() {
+                    control.stop();
+                }
+                if let Some(sensors) = app.try_state::<sensor::Sensors>() {
+                    sensors.stop();
+                }
+                if let Some(scheduler) = app.try_state::<schedule::Scheduler>() {
+                    scheduler.stop();
+                }
+                if let Some(relays) = app.try_state::<relay::Relays>() {
+                    relays.stop();
+                }
             }
-
-            // TODO: retrieve the data from GPIO
-            let sensor_data = VecSensorData {
-                name: "Water Temperature (C°)",
-                value: air_temp_series,
-            };
-
-            log::debug!("{sensor_data:#?}");
-
-            app.emit("water_temperature_sensor", &sensor_data).unwrap();
-        }
-    });
-}
-
-#[tauri::command]
-async fn air_temperature_sensor(app: AppHandle) {
-    let sensor_name = "air_temperature_sensor";
-    let mut sensors = SENSORS.lock().unwrap();
-
-    if sensors.contains(sensor_name) {
-        log::info!("Sensor '{sensor_name}' is already registered.");
-        return;
-    }
-
-    sensors.insert(sensor_name.to_string());
-
-    tauri::async_runtime::spawn(async move {
-        log::info!("Spawning sensor '{sensor_name}'...");
-
-        let mut interval = interval(Duration::from_secs(5));
-        loop {
-            interval.tick().await;
-
-            let mut rng = StdRng::from_os_rng();
-            let water_temp_series = rng.random_range(8.02..80.0);
-
-            // TODO: retrieve the data from GPIO
-            let sensor_data = SensorData {
-                name: "Air Temperature (C°)",
-                value: water_temp_series,
-            };
-
-            log::debug!("{sensor_data:#?}");
-
-            app.emit("air_temperature_sensor", &sensor_data).unwrap();
-        }
-    });
+        });
 }
 
 #[cfg(test)]
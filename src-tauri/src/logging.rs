@@ -0,0 +1,46 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use chrono::Utc;
+use log::LevelFilter;
+use serde_json::json;
+use tauri::plugin::TauriPlugin;
+use tauri::Runtime;
+use tauri_plugin_log::{Builder as TauriLog, Target, TargetKind};
+
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Debug;
+
+/// Logs of the app and of the services it runs to stderr and the webview.
+///
+/// The `tracing` events of the services come through `log`, their fields
+/// appended to the message. `RUST_LOG` sets the level, e.g. `RUST_LOG=info`,
+/// and `ARKSYNC_LOG_FORMAT=json` writes one JSON object per line.
+pub fn plugin<R: Runtime>() -> TauriPlugin<R> {
+    let level = std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(DEFAULT_LEVEL);
+    let logger = TauriLog::new()
+        .targets([
+            Target::new(TargetKind::Stderr),
+            Target::new(TargetKind::Webview),
+        ])
+        .level(level);
+
+    if std::env::var("ARKSYNC_LOG_FORMAT").is_ok_and(|format| format == "json") {
+        logger
+            .format(|out, message, record| {
+                let line = json!({
+                    "timestamp": Utc::now().to_rfc3339(),
+                    "level": record.level().as_str(),
+                    "target": record.target(),
+                    "message": message.to_string(),
+                });
+                out.finish(format_args!("{line}"))
+            })
+            .build()
+    } else {
+        logger.build()
+    }
+}
@@ -0,0 +1,121 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::{ActuatorSummaryRecord, StationKnotRole, StationKnotStatus};
+use arksync_db::{pool, ActuatorStore, SensorStore, StationStore};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::sensor::{SensorView, Sensors};
+
+/// An actuator of a knot as shown by the webview.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActuatorView {
+    pub id: Uuid,
+    pub name: String,
+    /// What the actuator is, e.g. `relay`
+    pub kind: String,
+    pub active: bool,
+    pub state_since: DateTime<Utc>,
+}
+
+impl From<ActuatorSummaryRecord> for ActuatorView {
+    fn from(record: ActuatorSummaryRecord) -> Self {
+        Self {
+            id: record.id,
+            name: record.name,
+            kind: record.kind,
+            active: record.active,
+            state_since: record.state_since,
+        }
+    }
+}
+
+/// A knot of the station with its sensors and actuators.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeView {
+    pub id: Uuid,
+    pub hub_id: Uuid,
+    pub name: String,
+    pub hardware_uid: String,
+    pub role: StationKnotRole,
+    pub status: StationKnotStatus,
+    pub sensors: Vec<SensorView>,
+    pub actuators: Vec<ActuatorView>,
+}
+
+/// Knots of the station, the local hub first.
+#[tauri::command]
+pub async fn nodes(sensors: State<'_, Sensors>) -> Result<Vec<NodeView>, String> {
+    let knots = StationStore::new(pool())
+        .knots()
+        .await
+        .map_err(|err| err.to_string())?;
+    let records = SensorStore::new(pool())
+        .list()
+        .await
+        .map_err(|err| err.to_string())?;
+    let actuators = ActuatorStore::new(pool())
+        .list()
+        .await
+        .map_err(|err| err.to_string())?;
+    // The registry is fresher than the rows of the sensors plugged here
+    let live = sensors.handle().all_sensors().await.unwrap_or_default();
+
+    let mut knot_sensors: HashMap<Uuid, Vec<SensorView>> = HashMap::new();
+    for record in &records {
+        let sensor = live.get(&record.id).map_or_else(
+            || SensorView::from(record),
+            |live| SensorView::from(&live.info()),
+        );
+        knot_sensors
+            .entry(record.station_knot_id)
+            .or_default()
+            .push(sensor);
+    }
+    let mut knot_actuators: HashMap<Uuid, Vec<ActuatorView>> = HashMap::new();
+    for actuator in actuators {
+        knot_actuators
+            .entry(actuator.station_knot_id)
+            .or_default()
+            .push(actuator.into());
+    }
+
+    Ok(knots
+        .into_iter()
+        .map(|knot| NodeView {
+            sensors: knot_sensors.remove(&knot.id).unwrap_or_default(),
+            actuators: knot_actuators.remove(&knot.id).unwrap_or_default(),
+            id: knot.id,
+            hub_id: knot.station_hub_id,
+            name: knot.name,
+            hardware_uid: knot.hardware_uid,
+            role: knot.role,
+            status: knot.status,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn rename_node(node_id: Uuid, name: String) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("A node needs a name.".to_string());
+    }
+
+    let renamed = StationStore::new(pool())
+        .rename_knot(node_id, name)
+        .await
+        .map_err(|err| err.to_string())?;
+    if !renamed {
+        return Err(format!("Unknown node {node_id}."));
+    }
+
+    Ok(())
+}
@@ -2,51 +2,126 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use arksync_actuator::relay::{RelayDriver, RelayState, MIST_RELAY};
-use tauri::{AppHandle, Emitter};
-use tokio::time::{interval, Duration};
+use arksync_actuator::error::ActuatorError;
+use arksync_actuator::event::ActuatorEvent;
+use arksync_actuator::relay::{RelayBank, RelayState};
+use arksync_actuator::services::{ActuatorPersistence, ActuatorService, ActuatorServiceHandle};
+use arksync_actuator::CONFIG;
+use arksync_db::{pool, ActuatorStore};
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
 
 const RELAY_EVENT: &str = "relay_state_changed";
-const RELAY_TICK_SECONDS: u64 = 5;
-
-pub fn spawn_debug_loop(app: AppHandle) {
-    tauri::async_runtime::spawn(async move {
-        log::info!(
-            "Starting relay debug loop on GPIO{} (active-low={}), toggling every {}s.",
-            MIST_RELAY.gpio_bcm_pin,
-            MIST_RELAY.active_low,
-            RELAY_TICK_SECONDS
-        );
-
-        let driver = RelayDriver::new(MIST_RELAY);
-        let mut relay_is_active = false;
-        let mut ticker = interval(Duration::from_secs(RELAY_TICK_SECONDS));
-
-        loop {
-            ticker.tick().await;
-
-            relay_is_active = !relay_is_active;
-            let state = RelayState::new(MIST_RELAY, relay_is_active);
-
-            if let Err(error) = driver.apply(state) {
-                log::error!(
-                    "Failed to switch relay '{}' on GPIO{}: {error}",
-                    MIST_RELAY.id,
-                    MIST_RELAY.gpio_bcm_pin
-                );
-                continue;
+
+/// The actuator service running for the app.
+pub struct Relays {
+    handle: ActuatorServiceHandle,
+    shutdown: CancellationToken,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Relays {
+    /// Claim the relay boards configured on the knot and forward their state
+    /// changes to the webview.
+    pub fn start(app: AppHandle, station_knot_id: Uuid) -> Result<Self, ActuatorError> {
+        let relays = RelayBank::open(&CONFIG.boards, &CONFIG.gpio)?;
+        let service = ActuatorService::new(relays)
+            .with_safety_limits(CONFIG.safety.clone())
+            .with_persistence(ActuatorPersistence::new(
+                ActuatorStore::new(pool()),
+                station_knot_id,
+            ));
+        let handle = service.handle();
+        let shutdown = CancellationToken::new();
+
+        let mut events = handle.subscribe();
+        let forwarding = shutdown.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    event = events.recv() => event,
+                    _ = forwarding.cancelled() => break,
+                };
+
+                match event {
+                    Ok(ActuatorEvent::StateChanged { state, .. }) => {
+                        if let Err(error) = app.emit(RELAY_EVENT, state) {
+                            log::error!("Failed to emit relay state event: {error}");
+                        }
+                    }
+                    // Already answered to the command that was refused
+                    Ok(ActuatorEvent::Refused { .. }) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        log::warn!("Relay events lagging behind, {missed} events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
             }
+        });
+
+        let task = tauri::async_runtime::spawn(service.run(shutdown.clone()));
+
+        Ok(Self {
+            handle,
+            shutdown,
+            task: Mutex::new(Some(task)),
+        })
+    }
 
-            log::info!(
-                "Relay '{}' switched {} with {} level.",
-                MIST_RELAY.id,
-                if state.active { "ON" } else { "OFF" },
-                state.level
-            );
+    pub fn handle(&self) -> ActuatorServiceHandle {
+        self.handle.clone()
+    }
 
-            if let Err(error) = app.emit(RELAY_EVENT, state) {
-                log::error!("Failed to emit relay state event: {error}");
+    /// Stop the service, leaving every relay in its fail-safe state.
+    pub fn stop(&self) {
+        self.shutdown.cancel();
+
+        let task = self.task.lock().expect("relay task mutex poisoned").take();
+        if let Some(task) = task {
+            if let Err(error) = tauri::async_runtime::block_on(task) {
+                log::error!("Actuator service failed to stop: {error}");
             }
         }
-    });
+    }
+}
+
+#[tauri::command]
+pub async fn relay_states(relays: State<'_, Relays>) -> Result<Vec<RelayState>, String> {
+    relays
+        .handle
+        .states()
+        .await
+        .map(|states| states.into_iter().map(|(_, state)| state).collect())
+        .ok_or_else(|| ActuatorError::ServiceStopped.to_string())
+}
+
+#[tauri::command]
+pub async fn switch_relay(
+    relays: State<'_, Relays>,
+    relay_id: String,
+    on: bool,
+) -> Result<RelayState, String> {
+    relays
+        .handle
+        .switch(&relay_id, on)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub async fn pulse_relay(
+    relays: State<'_, Relays>,
+    relay_id: String,
+    duration_ms: u64,
+) -> Result<RelayState, String> {
+    relays
+        .handle
+        .pulse(&relay_id, Duration::from_millis(duration_ms))
+        .await
+        .map_err(|err| err.to_string())
 }
@@ -0,0 +1,48 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::services::ActuatorServiceHandle;
+use arksync_db::ScheduleStore;
+use arksync_scheduler::services::SchedulerService;
+use arksync_scheduler::CONFIG;
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// The scheduler service running for the app, rules kept in Postgres.
+pub struct Scheduler {
+    shutdown: CancellationToken,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Scheduler {
+    pub fn start(relays: ActuatorServiceHandle) -> Self {
+        let service = SchedulerService::new(relays, CONFIG.location)
+            .with_store(ScheduleStore::new(arksync_db::pool()));
+        let shutdown = CancellationToken::new();
+        let task = tauri::async_runtime::spawn(service.run(shutdown.clone()));
+
+        Self {
+            shutdown,
+            task: Mutex::new(Some(task)),
+        }
+    }
+
+    /// Stop firing the rules, before the relays are left in their fail-safe
+    /// state.
+    pub fn stop(&self) {
+        self.shutdown.cancel();
+
+        let task = self
+            .task
+            .lock()
+            .expect("scheduler task mutex poisoned")
+            .take();
+        if let Some(task) = task {
+            if let Err(error) = tauri::async_runtime::block_on(task) {
+                log::error!("Scheduler service failed to stop: {error}");
+            }
+        }
+    }
+}
@@ -0,0 +1,419 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_db::stores::SensorRecord;
+use arksync_db::{pool, MeasurementStore, SensorStore};
+use arksync_sensor::event::SensorEvent;
+use arksync_sensor::identity::IdentityResolver;
+use arksync_sensor::sensor::{Calibration, SensorInfo, SensorName};
+use arksync_sensor::services::{
+    MeasurementRecorder, SensorPersistence, SensorService, SensorServiceHandle, ShutdownSummary,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::SENSORS;
+
+const SENSOR_CHANGED_EVENT: &str = "sensor_changed";
+
+/// A sensor as shown by the webview.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SensorView {
+    pub id: Uuid,
+    /// `temperature`, `ph`, `ec`, `humidity`, `co2` or `custom`
+    pub kind: &'static str,
+    pub name: Option<String>,
+    /// Serial port or I2C bus and address
+    pub connection: String,
+    pub firmware: Option<f64>,
+    /// `active`, `degraded`, `initializing`, `unplugged` or `unreachable`
+    pub state: &'static str,
+    pub state_reason: String,
+    pub state_since: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    pub consecutive_failures: u32,
+}
+
+impl From<&SensorInfo> for SensorView {
+    fn from(info: &SensorInfo) -> Self {
+        Self {
+            id: info.id,
+            kind: info.kind.as_str(),
+            name: match &info.name {
+                SensorName::Named(name) => Some(name.clone()),
+                SensorName::Unnamed => None,
+            },
+            connection: info.connection.to_string(),
+            firmware: Some(info.firmware),
+            state: info.state.as_str(),
+            state_reason: info.state_reason.code().to_string(),
+            state_since: info.state_since,
+            last_activity: info.last_activity,
+            consecutive_failures: info.consecutive_failures,
+        }
+    }
+}
+
+/// A sensor known from the database only, e.g. unplugged since the start.
+impl From<&SensorRecord> for SensorView {
+    fn from(record: &SensorRecord) -> Self {
+        Self {
+            id: record.id,
+            kind: record.kind.as_str(),
+            name: Some(record.name.clone()),
+            connection: connection_label(&record.connection),
+            firmware: record.firmware,
+            state: record.state.status.as_str(),
+            state_reason: record.state.state_reason.clone(),
+            state_since: record.state.state_since,
+            last_activity: record.state.last_activity_at,
+            consecutive_failures: u32::try_from(record.state.consecutive_failures).unwrap_or(0),
+        }
+    }
+}
+
+/// Same label as the live connection, from its `sensors.connection` form.
+fn connection_label(connection: &Value) -> String {
+    if let Some(port) = connection["port_name"].as_str() {
+        return port.to_string();
+    }
+
+    match (connection["bus"].as_u64(), connection["address"].as_u64()) {
+        (Some(bus), Some(address)) => format!("i2c-{bus}@{address:#04x}"),
+        _ => "unknown".to_string(),
+    }
+}
+
+/// A row of the sensors page.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SensorEntry {
+    #[serde(flatten)]
+    pub sensor: SensorView,
+    pub last_value: Option<f64>,
+    pub last_value_at: Option<DateTime<Utc>>,
+}
+
+/// Payload of the `sensor:<id>` events.
+#[derive(Clone, Debug, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+enum SensorUpdate {
+    Measurement {
+        value: f64,
+        time: DateTime<Utc>,
+    },
+    /// The sensor was discovered, changed state, got renamed or was removed.
+    State {
+        sensor: SensorView,
+    },
+}
+
+impl SensorUpdate {
+    fn from_event(event: &SensorEvent) -> Option<Self> {
+        match event {
+            SensorEvent::Measurement(measurement) => Some(SensorUpdate::Measurement {
+                value: measurement.value,
+                time: measurement.time,
+            }),
+            SensorEvent::Discovered(info)
+            | SensorEvent::StateChanged { info, .. }
+            | SensorEvent::Renamed(info)
+            | SensorEvent::Removed(info) => Some(SensorUpdate::State {
+                sensor: SensorView::from(info),
+            }),
+            SensorEvent::ReadCompleted(_) => None,
+        }
+    }
+}
+
+/// Payload of the `sensor_changed` events, for views listing the registry.
+#[derive(Clone, Debug, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+enum SensorChanged {
+    Discovered {
+        sensor: SensorView,
+    },
+    /// The sensor changed state or got renamed.
+    Updated {
+        sensor: SensorView,
+    },
+    Removed {
+        sensor: SensorView,
+    },
+}
+
+impl SensorChanged {
+    fn from_event(event: &SensorEvent) -> Option<Self> {
+        match event {
+            SensorEvent::Discovered(info) => Some(SensorChanged::Discovered {
+                sensor: info.into(),
+            }),
+            SensorEvent::StateChanged { info, .. } | SensorEvent::Renamed(info) => {
+                Some(SensorChanged::Updated {
+                    sensor: info.into(),
+                })
+            }
+            SensorEvent::Removed(info) => Some(SensorChanged::Removed {
+                sensor: info.into(),
+            }),
+            SensorEvent::Measurement(_) | SensorEvent::ReadCompleted(_) => None,
+        }
+    }
+}
+
+fn sensor_event(sensor_id: Uuid) -> String {
+    format!("sensor:{sensor_id}")
+}
+
+/// The sensor service running for the app, the registry and measurements
+/// kept in Postgres.
+pub struct Sensors {
+    handle: SensorServiceHandle,
+    shutdown: CancellationToken,
+    task: Mutex<Option<JoinHandle<ShutdownSummary>>>,
+}
+
+impl Sensors {
+    /// Detect the boards plugged into the knot, start reading them and push
+    /// the registry changes to the webview.
+    pub fn start(app: AppHandle, station_knot_id: Uuid) -> Self {
+        let service = SensorService::new()
+            .with_identity_resolver(IdentityResolver::new(Some(SensorStore::new(pool()))))
+            .with_persistence(SensorPersistence::new(
+                SensorStore::new(pool()),
+                station_knot_id,
+            ))
+            .with_measurement_recorder(MeasurementRecorder::new(MeasurementStore::new(pool())));
+        let handle = service.handle();
+        let shutdown = CancellationToken::new();
+
+        let mut events = handle.subscribe();
+        let forwarding = shutdown.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    event = events.recv() => event,
+                    _ = forwarding.cancelled() => break,
+                };
+
+                match event {
+                    Ok(event) => {
+                        let Some(changed) = SensorChanged::from_event(&event) else {
+                            continue;
+                        };
+                        if let Err(error) = app.emit(SENSOR_CHANGED_EVENT, changed) {
+                            log::error!("Failed to emit sensor registry event: {error}");
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        log::warn!("Sensor registry events lagging behind, {missed} events lost.");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        let task = tauri::async_runtime::spawn(service.run(shutdown.clone()));
+
+        Self {
+            handle,
+            shutdown,
+            task: Mutex::new(Some(task)),
+        }
+    }
+
+    pub fn handle(&self) -> SensorServiceHandle {
+        self.handle.clone()
+    }
+
+    /// Stop reading, leaving the boards in a safe state.
+    pub fn stop(&self) {
+        self.shutdown.cancel();
+
+        let task = self.task.lock().expect("sensor task mutex poisoned").take();
+        if let Some(task) = task {
+            match tauri::async_runtime::block_on(task) {
+                Ok(summary) if !summary.is_clean() => {
+                    for sensor in &summary.sensors {
+                        log::warn!(
+                            "Sensor {} did not shut down cleanly: {:?}",
+                            sensor.uuid,
+                            sensor.outcome
+                        );
+                    }
+                }
+                Ok(_) => {}
+                Err(error) => log::error!("Sensor service failed to stop: {error}"),
+            }
+        }
+    }
+}
+
+/// Sensors plugged or known from the database, with their last measurement.
+#[tauri::command]
+pub async fn sensors(sensors: State<'_, Sensors>) -> Result<Vec<SensorEntry>, String> {
+    let live = sensors
+        .handle
+        .all_sensors()
+        .await
+        .ok_or_else(|| "The sensor service is stopped.".to_string())?;
+    let records = SensorStore::new(pool())
+        .list()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    // The registry is fresher than the rows, written as the events come
+    let mut views: HashMap<Uuid, SensorView> = records
+        .iter()
+        .map(|record| (record.id, SensorView::from(record)))
+        .collect();
+    for sensor in live.values() {
+        let info = sensor.info();
+        views.insert(info.id, SensorView::from(&info));
+    }
+
+    let ids: Vec<Uuid> = views.keys().copied().collect();
+    let latest: HashMap<Uuid, (f64, DateTime<Utc>)> = MeasurementStore::new(pool())
+        .latest(&ids)
+        .await
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .map(|record| (record.sensor_id, (record.value, record.time)))
+        .collect();
+
+    Ok(views
+        .into_values()
+        .map(|sensor| {
+            let last = latest.get(&sensor.id).copied();
+            SensorEntry {
+                sensor,
+                last_value: last.map(|(value, _)| value),
+                last_value_at: last.map(|(_, time)| time),
+            }
+        })
+        .collect())
+}
+
+/// Store a new name on a sensor board.
+#[tauri::command]
+pub async fn rename_sensor(
+    sensors: State<'_, Sensors>,
+    sensor_id: Uuid,
+    name: String,
+) -> Result<SensorView, String> {
+    sensors
+        .handle
+        .rename_sensor(sensor_id, name)
+        .await
+        .map(|info| SensorView::from(&info))
+        .map_err(|err| err.to_string())
+}
+
+/// Calibrate a sensor at `point`, in the unit of its measurements, or back to
+/// its factory calibration without one.
+#[tauri::command]
+pub async fn calibrate_sensor(
+    sensors: State<'_, Sensors>,
+    sensor_id: Uuid,
+    point: Option<f64>,
+) -> Result<(), String> {
+    let calibration = point.map_or(Calibration::Clear, Calibration::Point);
+    sensors
+        .handle
+        .calibrate_sensor(sensor_id, calibration)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// Blink the LED of a sensor board.
+#[tauri::command]
+pub async fn identify_sensor(sensors: State<'_, Sensors>, sensor_id: Uuid) -> Result<(), String> {
+    sensors
+        .handle
+        .identify_sensor(sensor_id)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// Forget an unplugged sensor, its history is kept but the probe gets a new
+/// id the next time it is plugged.
+#[tauri::command]
+pub async fn forget_sensor(sensors: State<'_, Sensors>, sensor_id: Uuid) -> Result<(), String> {
+    sensors
+        .handle
+        .forget_sensor(sensor_id)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    // Only the service may know it, when it was never persisted
+    SensorStore::new(pool())
+        .forget(sensor_id)
+        .await
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+/// Push the measurements and state changes of a sensor to the webview, on
+/// the `sensor:<id>` event.
+///
+/// A sensor is forwarded once, whatever the number of views subscribing to
+/// it. The id may be of a sensor not plugged yet.
+#[tauri::command]
+pub fn subscribe_sensor(app: AppHandle, sensors: State<'_, Sensors>, sensor_id: Uuid) {
+    if !SENSORS
+        .lock()
+        .expect("sensors mutex poisoned")
+        .insert(sensor_id.to_string())
+    {
+        log::info!("Sensor '{sensor_id}' already registered.");
+        return;
+    }
+
+    let mut events = sensors.handle.subscribe();
+    let forwarding = sensors.shutdown.clone();
+    let event_name = sensor_event(sensor_id);
+    log::info!("Forwarding sensor '{sensor_id}'...");
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => event,
+                _ = forwarding.cancelled() => break,
+            };
+
+            match event {
+                Ok(event) if event.sensor_id() == sensor_id => {
+                    let Some(update) = SensorUpdate::from_event(&event) else {
+                        continue;
+                    };
+                    if let Err(error) = app.emit(&event_name, update) {
+                        log::error!("Failed to emit sensor event: {error}");
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => {
+                    log::warn!("Sensor '{sensor_id}' events lagging behind, {missed} events lost.");
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
@@ -0,0 +1,90 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use arksync_actuator::services::ActuatorServiceHandle;
+use arksync_sensor::services::SensorServiceHandle;
+use arksync_telemetry::mqtt::{
+    self, HomeAssistantDiscovery, RumqttPublisher, StationCommands, Topics,
+};
+use arksync_telemetry::{InfluxExporter, MqttBridge, QueueWriter, TelemetryQueue, CONFIG};
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// The telemetry exporters running for the app, fed by the telemetry queue.
+pub struct Telemetry {
+    shutdown: CancellationToken,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Telemetry {
+    /// Append the sensor events to the queue and export them to the enabled
+    /// servers, the MQTT bridge also drives the relays. Nothing runs when no
+    /// exporter is enabled.
+    pub fn start(
+        sensors: &SensorServiceHandle,
+        relays: ActuatorServiceHandle,
+    ) -> eyre::Result<Self> {
+        let shutdown = CancellationToken::new();
+        let mut tasks = Vec::new();
+
+        if CONFIG.influx.enabled || CONFIG.mqtt.enabled {
+            let queue = TelemetryQueue::open(&CONFIG.queue)?;
+
+            if CONFIG.influx.enabled {
+                let exporter = InfluxExporter::new(&CONFIG.influx)?;
+                let consumer =
+                    tauri::async_runtime::block_on(queue.consumer(InfluxExporter::CONSUMER))?;
+                tasks.push(tauri::async_runtime::spawn(
+                    exporter.run(consumer, shutdown.clone()),
+                ));
+            }
+
+            if CONFIG.mqtt.enabled {
+                let consumer = tauri::async_runtime::block_on(
+                    queue.consumer(MqttBridge::<RumqttPublisher, StationCommands>::CONSUMER),
+                )?;
+                let commands = StationCommands::new(sensors.clone()).with_relays(relays.clone());
+                let events = sensors.subscribe();
+                let relay_events = relays.subscribe();
+                let bridging = shutdown.clone();
+                // The client is spawned on the runtime it connects from
+                tasks.push(tauri::async_runtime::spawn(async move {
+                    let topics = Topics::new(&CONFIG.mqtt.topic_prefix, &CONFIG.mqtt.knot);
+                    let (publisher, transport) = mqtt::connect(&CONFIG.mqtt, &topics);
+                    let states = relays.states().await.unwrap_or_default();
+                    let mut bridge = MqttBridge::new(publisher, commands, topics)
+                        .with_relays(states, relay_events);
+                    if let Some(prefix) = &CONFIG.mqtt.discovery_prefix {
+                        bridge = bridge.with_discovery(HomeAssistantDiscovery::new(prefix.clone()));
+                    }
+
+                    bridge.run(transport, events, consumer, bridging).await;
+                }));
+            }
+
+            tasks.push(tauri::async_runtime::spawn(
+                QueueWriter::new(queue).run(sensors.subscribe(), shutdown.clone()),
+            ));
+        }
+
+        Ok(Self {
+            shutdown,
+            tasks: Mutex::new(tasks),
+        })
+    }
+
+    /// Stop the exporters, what they didn't send stays in the queue.
+    pub fn stop(&self) {
+        self.shutdown.cancel();
+
+        let tasks =
+            std::mem::take(&mut *self.tasks.lock().expect("telemetry tasks mutex poisoned"));
+        for task in tasks {
+            if let Err(error) = tauri::async_runtime::block_on(task) {
+                log::error!("Telemetry exporter failed to stop: {error}");
+            }
+        }
+    }
+}